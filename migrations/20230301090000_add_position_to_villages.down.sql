@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE villages DROP COLUMN x;
+ALTER TABLE villages DROP COLUMN y;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN x INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE villages ADD COLUMN y INTEGER NOT NULL DEFAULT 0;
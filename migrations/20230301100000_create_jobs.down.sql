@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS jobs;
+DROP INDEX IF EXISTS idx_jobs_village_id;
@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS jobs (
+	id BLOB PRIMARY KEY,
+	player_id BLOB NOT NULL,
+	village_id INTEGER NOT NULL,
+	task TEXT NOT NULL,
+	duration INTEGER NOT NULL,
+	done INTEGER NOT NULL DEFAULT 0,
+	cancellable INTEGER NOT NULL DEFAULT 0,
+	started_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_village_id ON jobs (village_id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS heroes;
+DROP INDEX IF EXISTS idx_heroes_player_id;
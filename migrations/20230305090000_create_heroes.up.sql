@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS heroes (
+	id BLOB PRIMARY KEY,
+	player_id BLOB NOT NULL UNIQUE,
+	village_id INTEGER NOT NULL,
+	health INTEGER NOT NULL DEFAULT 100,
+	experience INTEGER NOT NULL DEFAULT 0,
+	strength INTEGER NOT NULL DEFAULT 100,
+	inventory TEXT NOT NULL,
+	equipment TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_heroes_player_id ON heroes (player_id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE heroes DROP COLUMN status;
+ALTER TABLE heroes DROP COLUMN regenerated_at;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE heroes ADD COLUMN status TEXT NOT NULL DEFAULT '"Home"';
+ALTER TABLE heroes ADD COLUMN regenerated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE villages DROP COLUMN resources;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN resources TEXT NOT NULL DEFAULT '[750,750,750,750]';
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN reinforcement_policy TEXT NOT NULL DEFAULT '"Anyone"';
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS world_config (
+	id INTEGER PRIMARY KEY CHECK (id = 1),
+	world_size INTEGER NOT NULL,
+	created_at TEXT NOT NULL
+);
@@ -0,0 +1,14 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS reports (
+	id BLOB PRIMARY KEY,
+	attacker_player_id BLOB NOT NULL,
+	attacker_village_id INTEGER NOT NULL,
+	defender_player_id BLOB NOT NULL,
+	defender_village_id INTEGER NOT NULL,
+	audience TEXT NOT NULL,
+	content TEXT NOT NULL,
+	created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_reports_attacker_player_id ON reports (attacker_player_id);
+CREATE INDEX IF NOT EXISTS idx_reports_defender_player_id ON reports (defender_player_id);
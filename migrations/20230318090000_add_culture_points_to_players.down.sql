@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE players DROP COLUMN culture_points;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE players ADD COLUMN culture_points INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE players ADD COLUMN protected_until TEXT;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS combat_points (
+	player_id BLOB PRIMARY KEY,
+	attack INTEGER NOT NULL DEFAULT 0,
+	defense INTEGER NOT NULL DEFAULT 0
+);
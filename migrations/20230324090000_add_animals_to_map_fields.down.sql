@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE map_fields DROP COLUMN animals;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE map_fields ADD COLUMN animals TEXT;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN parent_village_id INTEGER;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS culture_point_awards (
+	player_id BLOB NOT NULL,
+	day TEXT NOT NULL,
+	amount INTEGER NOT NULL,
+	PRIMARY KEY (player_id, day)
+);
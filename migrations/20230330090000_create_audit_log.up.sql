@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS audit_log (
+	id BLOB PRIMARY KEY,
+	admin_id BLOB NOT NULL,
+	village_id INTEGER NOT NULL,
+	action TEXT NOT NULL,
+	created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_village_id ON audit_log (village_id);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE jobs DROP COLUMN processing_since;
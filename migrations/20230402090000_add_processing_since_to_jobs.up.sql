@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE jobs ADD COLUMN processing_since TEXT;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN trapper TEXT NOT NULL DEFAULT '{"prisoners":[]}';
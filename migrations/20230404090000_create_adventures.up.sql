@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS adventures (
+	id BLOB PRIMARY KEY,
+	player_id BLOB NOT NULL,
+	x INTEGER NOT NULL,
+	y INTEGER NOT NULL,
+	difficulty TEXT NOT NULL,
+	reward TEXT NOT NULL,
+	created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_adventures_player_id ON adventures (player_id);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE heroes ADD COLUMN attributes TEXT NOT NULL DEFAULT '{"strength":0,"off_bonus":0,"def_bonus":0,"production":0}';
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN brewery_celebration_until TEXT;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE villages ADD COLUMN artifacts TEXT NOT NULL DEFAULT '[]';
+
+-- construction plans were stored as buildings, they're held as artifacts now
+UPDATE villages
+SET artifacts = '["ConstructionPlan"]',
+    buildings = (
+        SELECT json_group_object(b.key, json(b.value))
+        FROM json_each(villages.buildings) b
+        WHERE json_extract(b.value, '$.name') != 'AncientConstructionPlan'
+    )
+WHERE EXISTS (
+    SELECT 1 FROM json_each(villages.buildings) b
+    WHERE json_extract(b.value, '$.name') = 'AncientConstructionPlan'
+);
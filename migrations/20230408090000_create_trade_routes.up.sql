@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS trade_routes (
+	id BLOB PRIMARY KEY,
+	player_id BLOB NOT NULL,
+	village_id INTEGER NOT NULL,
+	target_village_id INTEGER NOT NULL,
+	resources TEXT NOT NULL,
+	interval_secs INTEGER NOT NULL,
+	created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_routes_village_id ON trade_routes (village_id);
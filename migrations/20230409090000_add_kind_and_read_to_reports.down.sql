@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE reports DROP COLUMN kind;
+ALTER TABLE reports DROP COLUMN read_by_attacker;
+ALTER TABLE reports DROP COLUMN read_by_defender;
@@ -0,0 +1,13 @@
+-- Add up migration script here
+ALTER TABLE reports ADD COLUMN kind TEXT NOT NULL DEFAULT '"Battle"';
+ALTER TABLE reports ADD COLUMN read_by_attacker BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE reports ADD COLUMN read_by_defender BOOLEAN NOT NULL DEFAULT 0;
+
+-- raids can't be told apart from attacks anymore, they stay battles
+UPDATE reports
+SET kind = CASE
+    WHEN json_extract(content, '$.Scouting') IS NOT NULL THEN '"Scouting"'
+    WHEN json_extract(content, '$.Delivery') IS NOT NULL THEN '"Trade"'
+    WHEN json_extract(content, '$.Adventure') IS NOT NULL THEN '"Adventure"'
+    ELSE '"Battle"'
+END;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE reports DROP COLUMN archived_by_attacker;
+ALTER TABLE reports DROP COLUMN archived_by_defender;
+ALTER TABLE reports DROP COLUMN deleted_by_attacker;
+ALTER TABLE reports DROP COLUMN deleted_by_defender;
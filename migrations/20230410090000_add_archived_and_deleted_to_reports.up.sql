@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE reports ADD COLUMN archived_by_attacker BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE reports ADD COLUMN archived_by_defender BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE reports ADD COLUMN deleted_by_attacker BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE reports ADD COLUMN deleted_by_defender BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS farm_lists (
+	id BLOB PRIMARY KEY,
+	player_id BLOB NOT NULL,
+	village_id INTEGER NOT NULL,
+	name TEXT NOT NULL,
+	units TEXT NOT NULL,
+	targets TEXT NOT NULL,
+	created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_farm_lists_village_id ON farm_lists (village_id);
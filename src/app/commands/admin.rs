@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::events::GameEvent,
+    game::models::{
+        audit::{AuditAction, AuditEntry},
+        ResourceGroup,
+    },
+    repository::Repository,
+};
+
+// Support commands fixing broken villages. Only admins can run them and every change
+// is recorded into the audit log.
+
+pub struct SetVillageResourcesCommand {
+    repo: Arc<dyn Repository>,
+    admins: Vec<Uuid>,
+    admin_id: Uuid,
+    village_id: u32,
+    resources: ResourceGroup,
+}
+
+impl SetVillageResourcesCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        admins: Vec<Uuid>,
+        admin_id: Uuid,
+        village_id: u32,
+        resources: ResourceGroup,
+    ) -> Self {
+        Self {
+            repo,
+            admins,
+            admin_id,
+            village_id,
+            resources,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SetVillageResourcesCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        check_admin(&self.admins, self.admin_id)?;
+
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let from = village.resources.clone();
+        village.set_resources(self.resources.clone())?;
+        self.repo.update_village(village).await?;
+
+        self.repo
+            .add_audit_entry(AuditEntry::new(
+                self.admin_id,
+                self.village_id,
+                AuditAction::SetVillageResources {
+                    from,
+                    to: self.resources.clone(),
+                },
+            ))
+            .await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+pub struct SetBuildingLevelCommand {
+    repo: Arc<dyn Repository>,
+    admins: Vec<Uuid>,
+    admin_id: Uuid,
+    village_id: u32,
+    slot_id: u8,
+    level: u8,
+}
+
+impl SetBuildingLevelCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        admins: Vec<Uuid>,
+        admin_id: Uuid,
+        village_id: u32,
+        slot_id: u8,
+        level: u8,
+    ) -> Self {
+        Self {
+            repo,
+            admins,
+            admin_id,
+            village_id,
+            slot_id,
+            level,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SetBuildingLevelCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        check_admin(&self.admins, self.admin_id)?;
+
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let building = village
+            .get_building_by_slot_id(self.slot_id)
+            .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+        village.set_building_level(self.slot_id, self.level)?;
+        self.repo.update_village(village).await?;
+
+        self.repo
+            .add_audit_entry(AuditEntry::new(
+                self.admin_id,
+                self.village_id,
+                AuditAction::SetBuildingLevel {
+                    slot_id: self.slot_id,
+                    building_name: building.name,
+                    from: building.level,
+                    to: self.level,
+                },
+            ))
+            .await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+fn check_admin(admins: &[Uuid], player_id: Uuid) -> Result<()> {
+    if !admins.contains(&player_id) {
+        return Err(Error::msg("Only admins can run this command."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::{SetBuildingLevelCommand, SetVillageResourcesCommand};
+    use crate::{
+        app::commands::Command,
+        db::test_utils::{insert_village, setup_repo},
+        game::models::{
+            audit::AuditAction, buildings::BuildingName, map::Position, village::Village,
+            ResourceGroup, Tribe,
+        },
+        repository::Repository,
+    };
+
+    async fn setup() -> (Arc<dyn Repository>, Village) {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+
+        (Arc::new(repo), village)
+    }
+
+    #[tokio::test]
+    async fn test_set_village_resources_within_capacity() {
+        let (repo, village) = setup().await;
+        let admin_id = Uuid::new_v4();
+        let set = |resources: ResourceGroup| {
+            SetVillageResourcesCommand::new(
+                repo.clone(),
+                vec![admin_id],
+                admin_id,
+                village.id,
+                resources,
+            )
+        };
+
+        let too_much = ResourceGroup::new(village.stocks.warehouse() + 1, 0, 0, 0);
+        assert!(set(too_much).run().await.is_err());
+
+        let resources = ResourceGroup::new(100, 200, 300, village.stocks.granary());
+        set(resources.clone()).run().await.unwrap();
+        let stored = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(stored.resources, resources);
+
+        let entries = repo
+            .get_audit_entries_by_village_id(village.id)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].admin_id, admin_id);
+        assert!(matches!(
+            &entries[0].action,
+            AuditAction::SetVillageResources { from, to }
+                if *from == village.resources && *to == resources
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_building_level_up_to_max_level() {
+        let (repo, village) = setup().await;
+        let admin_id = Uuid::new_v4();
+        let set = |level: u8| {
+            SetBuildingLevelCommand::new(
+                repo.clone(),
+                vec![admin_id],
+                admin_id,
+                village.id,
+                19,
+                level,
+            )
+        };
+
+        assert!(set(21).run().await.is_err());
+        set(20).run().await.unwrap();
+        let stored = repo.get_village_by_id(village.id).await.unwrap();
+        let main_building = stored.get_building_by_slot_id(19).unwrap();
+        assert_eq!(main_building.name, BuildingName::MainBuilding);
+        assert_eq!(main_building.level, 20);
+        assert!(stored.population > village.population);
+
+        let entries = repo
+            .get_audit_entries_by_village_id(village.id)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries[0].action,
+            AuditAction::SetBuildingLevel {
+                slot_id: 19,
+                from: 1,
+                to: 20,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_admin_commands_need_an_admin() {
+        let (repo, village) = setup().await;
+        let admin_id = Uuid::new_v4();
+
+        assert!(SetBuildingLevelCommand::new(
+            repo.clone(),
+            vec![admin_id],
+            village.player_id,
+            village.id,
+            19,
+            5,
+        )
+        .run()
+        .await
+        .is_err());
+        assert!(SetVillageResourcesCommand::new(
+            repo.clone(),
+            vec![],
+            admin_id,
+            village.id,
+            ResourceGroup::new(1, 1, 1, 1),
+        )
+        .run()
+        .await
+        .is_err());
+
+        assert!(repo
+            .get_audit_entries_by_village_id(village.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Command;
+use crate::{app::events::GameEvent, repository::Repository};
+
+pub struct AnnexOasisCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    oasis_id: u32,
+    world_size: i32,
+}
+
+impl AnnexOasisCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, oasis_id: u32, world_size: i32) -> Self {
+        Self {
+            repo,
+            village_id,
+            oasis_id,
+            world_size,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for AnnexOasisCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let mut oasis = self.repo.get_oasis_by_id(self.oasis_id).await?;
+
+        village.annex_oasis(&mut oasis, self.world_size)?;
+        self.repo.update_oasis(oasis).await?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::AnnexOasisCommand;
+    use crate::{
+        app::commands::Command,
+        db::test_utils::{insert_oasis, insert_valley, setup_repo},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_annex_oasis_near_village() {
+        let repo = setup_repo().await;
+        let home = Position { x: 3, y: 4 };
+        let near = Position { x: 5, y: 6 };
+        let far = Position { x: 8, y: 4 };
+        insert_valley(&repo, &home).await;
+        insert_oasis(&repo, &near, [0; 10]).await;
+        insert_oasis(&repo, &far, [0; 10]).await;
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new("Alice".to_string(), &valley, &alice, true);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(20)
+                .unwrap(),
+        );
+        repo.found_village(village.clone(), None).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let far_id = far.to_id(WORLD_MAX_SIZE);
+        assert!(
+            AnnexOasisCommand::new(repo.clone(), village.id, far_id, WORLD_MAX_SIZE)
+                .run()
+                .await
+                .is_err()
+        );
+
+        let near_id = near.to_id(WORLD_MAX_SIZE);
+        AnnexOasisCommand::new(repo.clone(), village.id, near_id, WORLD_MAX_SIZE)
+            .run()
+            .await
+            .unwrap();
+
+        let oasis = repo.get_oasis_by_id(near_id).await.unwrap();
+        assert_eq!(oasis.player_id, Some(alice.id));
+        assert_eq!(oasis.village_id, Some(village.id));
+        let village = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(village.oases.len(), 1);
+        assert_eq!(village.production.bonus.lumber, 25);
+        assert!(repo
+            .get_oasis_by_id(far_id)
+            .await
+            .unwrap()
+            .player_id
+            .is_none());
+    }
+}
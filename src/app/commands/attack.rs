@@ -1,12 +1,19 @@
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Error, Result};
+use chrono::Utc;
 
 use super::Command;
 use crate::{
     app::events::GameEvent,
     app::jobs::{Job, JobTask},
-    game::{battle::CataTargets, models::army::Army},
+    game::{
+        battle::CataTargets,
+        models::{
+            army::Army,
+            map::{travel_time_secs, TravelSettings},
+        },
+    },
     repository::Repository,
 };
 
@@ -16,6 +23,8 @@ pub struct AttackCommand {
     army: Army,
     cata_targets: CataTargets,
     defender_village_id: u32,
+    max_outgoing_movements: u32,
+    travel: TravelSettings,
 }
 
 impl AttackCommand {
@@ -25,6 +34,8 @@ impl AttackCommand {
         army: Army,
         cata_targets: CataTargets,
         defender_village_id: u32,
+        max_outgoing_movements: u32,
+        travel: TravelSettings,
     ) -> Self {
         Self {
             repo: repo.clone(),
@@ -32,41 +43,152 @@ impl AttackCommand {
             army,
             cata_targets,
             defender_village_id,
+            max_outgoing_movements,
+            travel,
         }
     }
 }
 
 #[async_trait::async_trait]
 impl Command for AttackCommand {
-    async fn run(&self) -> Result<Vec<GameEvent>> {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
         let attacker_village = self.repo.get_village_by_id(self.village_id).await?;
         let defender_village = self
             .repo
             .get_village_by_id(self.defender_village_id)
             .await?;
 
-        let speed = self.army.clone().speed();
-        let time_secs =
-            attacker_village.calculate_travel_time_secs(defender_village.position, speed) as u64;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+        // the Rally Point level limits the movements, up to the server-wide cap
+        let max = attacker_village.max_outgoing_movements();
+        if max == 0 {
+            return Err(Error::msg("A Rally Point is needed to send armies."));
+        }
+        check_outgoing_movements(&pending, max.min(self.max_outgoing_movements))?;
+
+        let sendable = attacker_village.sendable_troops();
+        if self
+            .army
+            .units
+            .iter()
+            .zip(sendable.iter())
+            .any(|(q, s)| q > s)
+        {
+            return Err(Error::msg("Not enough troops at home to send."));
+        }
+
+        let defender = self
+            .repo
+            .get_player_by_id(defender_village.player_id)
+            .await?;
+        if defender.is_protected_at(Utc::now()) {
+            return Err(Error::msg("Target player is under beginners' protection."));
+        }
+
+        let speed = attacker_village.army_speed(&self.army, Utc::now());
+        let time_secs = travel_time_secs(
+            &attacker_village.position,
+            &defender_village.position,
+            speed,
+            self.travel,
+        ) as u64;
 
         let job = Job::new(
-            attacker_village.player_id.clone(),
-            self.village_id.clone(),
+            attacker_village.player_id,
+            self.village_id,
             time_secs,
             JobTask::Attack {
                 army: self.army.clone(),
                 cata_targets: self.cata_targets.clone(),
-                village_id: self.defender_village_id.clone(),
-                player_id: defender_village.player_id.clone(),
+                village_id: self.defender_village_id,
+                player_id: defender_village.player_id,
             },
         );
 
-        Ok(vec![
-            GameEvent::JobEnqueued(job),
-            GameEvent::ArmyDeployed {
-                army: self.army.clone(),
-                village_id: self.village_id.clone(),
+        Ok((
+            (),
+            vec![
+                GameEvent::JobEnqueued(job),
+                GameEvent::ArmyDeployed {
+                    army: self.army.clone(),
+                    village_id: self.village_id,
+                },
+            ],
+        ))
+    }
+}
+
+// Fails when the village already has the maximum number of armies on the move.
+pub fn check_outgoing_movements(pending: &[Job], max: u32) -> Result<()> {
+    let outgoing = pending
+        .iter()
+        .filter(|j| !j.done && j.task.is_army_movement())
+        .count();
+
+    if outgoing as u32 >= max {
+        return Err(Error::msg("Too many armies on the move from this village."));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::check_outgoing_movements;
+    use crate::{
+        app::jobs::{Job, JobTask},
+        game::models::{army::Army, buildings::BuildingName, Tribe},
+    };
+
+    fn raid() -> Job {
+        let player_id = Uuid::new_v4();
+        let army = Army::new(
+            1,
+            player_id,
+            Tribe::Roman,
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        Job::new(
+            player_id,
+            1,
+            600,
+            JobTask::Raid {
+                army,
+                village_id: 2,
+                player_id: Uuid::new_v4(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_outgoing_movements_cap() {
+        let mut pending = vec![raid(), raid()];
+        assert!(check_outgoing_movements(&pending, 3).is_ok());
+
+        pending.push(raid());
+        assert!(check_outgoing_movements(&pending, 3).is_err());
+
+        // arrived armies and other jobs don't count
+        pending[0].done = true;
+        pending.push(Job::new(
+            Uuid::new_v4(),
+            1,
+            600,
+            JobTask::BuildingUpgrade {
+                slot_id: 19,
+                building_name: BuildingName::MainBuilding,
+                target_level: None,
             },
-        ])
+        ));
+        assert!(check_outgoing_movements(&pending, 3).is_ok());
     }
 }
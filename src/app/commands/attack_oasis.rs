@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+
+use super::{attack::check_outgoing_movements, Command};
+use crate::{
+    app::events::GameEvent,
+    app::jobs::{Job, JobTask},
+    game::models::{
+        army::Army,
+        map::{travel_time_secs, TravelSettings},
+    },
+    repository::Repository,
+};
+
+// Sends troops against the animals guarding an unoccupied oasis. A normal attack
+// fights to the death, a raid leaves when one side has had enough.
+pub struct AttackOasisCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    army: Army,
+    oasis_id: u32,
+    is_normal: bool,
+    max_outgoing_movements: u32,
+    travel: TravelSettings,
+}
+
+impl AttackOasisCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        army: Army,
+        oasis_id: u32,
+        is_normal: bool,
+        max_outgoing_movements: u32,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            army,
+            oasis_id,
+            is_normal,
+            max_outgoing_movements,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for AttackOasisCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let oasis = self.repo.get_oasis_by_id(self.oasis_id).await?;
+        if oasis.player_id.is_some() {
+            return Err(Error::msg("The oasis already belongs to someone."));
+        }
+
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+        let max = village.max_outgoing_movements();
+        if max == 0 {
+            return Err(Error::msg("A Rally Point is needed to send armies."));
+        }
+        check_outgoing_movements(&pending, max.min(self.max_outgoing_movements))?;
+
+        let sendable = village.sendable_troops();
+        if self
+            .army
+            .units
+            .iter()
+            .zip(sendable.iter())
+            .any(|(q, s)| q > s)
+        {
+            return Err(Error::msg("Not enough troops at home to send."));
+        }
+
+        let speed = village.army_speed(&self.army, Utc::now());
+        let time_secs =
+            travel_time_secs(&village.position, &oasis.position, speed, self.travel) as u64;
+
+        let job = Job::new(
+            village.player_id,
+            self.village_id,
+            time_secs,
+            JobTask::OasisAttack {
+                army: self.army.clone(),
+                oasis_id: self.oasis_id,
+                is_normal: self.is_normal,
+            },
+        );
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobEnqueued(job),
+                GameEvent::ArmyDeployed {
+                    army: self.army.clone(),
+                    village_id: self.village_id,
+                },
+            ],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::AttackOasisCommand;
+    use crate::{
+        app::{commands::annex_oasis::AnnexOasisCommand, jobs::JobTask, App},
+        config::Config,
+        db::test_utils::{insert_oasis, insert_valley, setup_repo},
+        game::models::{
+            army::Army,
+            buildings::{Building, BuildingName},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_cleared_oasis_can_be_annexed() {
+        let repo = setup_repo().await;
+        let home = Position { x: 3, y: 4 };
+        let oasis_position = Position { x: 5, y: 6 };
+        insert_valley(&repo, &home).await;
+        insert_oasis(&repo, &oasis_position, [10, 0, 0, 0, 0, 0, 0, 0, 0, 0]).await;
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new("Alice".to_string(), &valley, &alice, true);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(10)
+                .unwrap(),
+        );
+        village.buildings.insert(
+            39,
+            Building::new(BuildingName::RallyPoint).at_level(1).unwrap(),
+        );
+        village.army.units = [100, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.found_village(village.clone(), None).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let app = App::new(repo.clone(), Config::default());
+        let oasis_id = oasis_position.to_id(WORLD_MAX_SIZE);
+
+        // the animals are still there
+        let annex = || AnnexOasisCommand::new(repo.clone(), village.id, oasis_id, WORLD_MAX_SIZE);
+        assert!(app.execute(annex()).await.is_err());
+
+        let army = Army::new(
+            village.id,
+            alice.id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let config = Config::default();
+        app.execute(AttackOasisCommand::new(
+            repo.clone(),
+            village.id,
+            army,
+            oasis_id,
+            true,
+            config.max_outgoing_movements,
+            config.travel_settings(),
+        ))
+        .await
+        .unwrap();
+
+        let attack = repo
+            .get_pending_jobs_by_village_id(village.id)
+            .await
+            .unwrap()[0]
+            .clone();
+        assert!(matches!(attack.task, JobTask::OasisAttack { .. }));
+        app.process_job(attack).await.unwrap();
+
+        let oasis = repo.get_oasis_by_id(oasis_id).await.unwrap();
+        assert!(oasis.is_cleared());
+        let pending = repo
+            .get_pending_jobs_by_village_id(village.id)
+            .await
+            .unwrap();
+        assert!(matches!(pending[0].task, JobTask::ArmyReturn { .. }));
+
+        app.execute(annex()).await.unwrap();
+        let oasis = repo.get_oasis_by_id(oasis_id).await.unwrap();
+        assert_eq!(oasis.village_id, Some(village.id));
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+        queries::queue_completion::lane,
+    },
+    game::models::{village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+pub struct CancelBuildingUpgradeCommand {
+    repo: Arc<dyn Repository>,
+    job_id: Uuid,
+}
+
+impl CancelBuildingUpgradeCommand {
+    pub fn new(repo: Arc<dyn Repository>, job_id: Uuid) -> Self {
+        Self { repo, job_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for CancelBuildingUpgradeCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let job = self.repo.get_job_by_id(self.job_id).await?;
+        let mut village = self.repo.get_village_by_id(job.village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(job.village_id)
+            .await?;
+
+        let rescheduled = cancel_upgrade(&job, &mut village, &pending, Utc::now())?;
+        self.repo.update_village(village).await?;
+        for j in rescheduled {
+            self.repo.update_job(j).await?;
+        }
+
+        Ok(((), vec![GameEvent::JobCancelled { job_id: job.id }]))
+    }
+}
+
+// Gives back the cost of a building upgrade: all of it while the upgrade is still
+// waiting in the queue, the share of the time left once the builders are at work.
+// The upgrades queued after it on the same lane move forward by the time freed up,
+// they're returned to be saved.
+fn cancel_upgrade(
+    job: &Job,
+    village: &mut Village,
+    pending: &[Job],
+    now: DateTime<Utc>,
+) -> Result<Vec<Job>> {
+    let slot_id = match job.task {
+        JobTask::BuildingUpgrade { slot_id, .. } => slot_id,
+        _ => return Err(Error::msg("This job is not a building upgrade.")),
+    };
+    if job.done || job.ends_at() <= now {
+        return Err(Error::msg("This upgrade is already completed."));
+    }
+    let building = village
+        .get_building_by_slot_id(slot_id)
+        .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+
+    // the same slot can be queued more times, each job pays one level
+    let queued_before = pending
+        .iter()
+        .filter(|j| j.id != job.id && j.started_at < job.started_at)
+        .filter(|j| matches!(j.task, JobTask::BuildingUpgrade { slot_id: s, .. } if s == slot_id))
+        .count() as u8;
+    let cost = building
+        .at_level(building.level + queued_before + 1)?
+        .cost()
+        .resources;
+
+    let (refund, freed_secs) = match job.started_at > now {
+        true => (cost, job.duration as i64),
+        false => {
+            let left = (job.ends_at() - now).num_seconds();
+            let share = |amount: u32| (amount as u64 * left as u64 / job.duration.max(1)) as u32;
+            let refund = ResourceGroup::new(
+                share(cost.lumber()),
+                share(cost.clay()),
+                share(cost.iron()),
+                share(cost.crop()),
+            );
+            (refund, left)
+        }
+    };
+    village.deposit_resources(&refund);
+
+    let job_lane = lane(&job.task, &village.tribe);
+    let rescheduled = pending
+        .iter()
+        .filter(|j| j.id != job.id && j.started_at > now && j.started_at >= job.started_at)
+        .filter(|j| lane(&j.task, &village.tribe) == job_lane)
+        .map(|j| {
+            let mut j = j.clone();
+            j.started_at = (j.started_at - Duration::seconds(freed_secs)).max(now);
+            j
+        })
+        .collect();
+
+    Ok(rescheduled)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::cancel_upgrade;
+    use crate::{
+        app::jobs::{Job, JobTask},
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::BuildingName, map::Position, village::Village, ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        let mut village = test_village(&test_player(Tribe::Teuton), Position { x: 10, y: 20 });
+        village.set_resources(ResourceGroup::default()).unwrap();
+        village
+    }
+
+    fn upgrade(village: &Village, slot_id: u8, started_at: DateTime<Utc>) -> Job {
+        let mut job = Job::new(
+            village.player_id,
+            village.id,
+            100,
+            JobTask::BuildingUpgrade {
+                slot_id,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        job.started_at = started_at;
+        job
+    }
+
+    #[test]
+    fn test_cancel_queued_upgrade_refunds_everything() {
+        let mut village = village();
+        let now = Utc::now();
+        let running = upgrade(&village, 1, now + Duration::seconds(-10));
+        let queued = upgrade(&village, 1, now + Duration::seconds(90));
+        let next = upgrade(&village, 2, now + Duration::seconds(190));
+        let pending = vec![running.clone(), queued.clone(), next.clone()];
+
+        let rescheduled = cancel_upgrade(&queued, &mut village, &pending, now).unwrap();
+
+        // Woodcutter level 2, the running job already pays level 1
+        assert_eq!(village.resources, ResourceGroup::new(65, 165, 85, 100));
+        assert_eq!(rescheduled.len(), 1);
+        assert_eq!(rescheduled[0].id, next.id);
+        assert_eq!(
+            rescheduled[0].started_at,
+            next.started_at - Duration::seconds(100)
+        );
+    }
+
+    #[test]
+    fn test_cancel_processing_upgrade_refunds_time_left() {
+        let mut village = village();
+        let now = Utc::now();
+        let running = upgrade(&village, 1, now + Duration::seconds(-75));
+        let queued = upgrade(&village, 2, now + Duration::seconds(25));
+        let pending = vec![running.clone(), queued.clone()];
+
+        let rescheduled = cancel_upgrade(&running, &mut village, &pending, now).unwrap();
+
+        // a quarter of the Woodcutter level 1 cost
+        assert_eq!(village.resources, ResourceGroup::new(10, 25, 12, 15));
+        assert_eq!(rescheduled.len(), 1);
+        assert_eq!(rescheduled[0].started_at, now);
+    }
+
+    #[test]
+    fn test_cancel_completed_upgrade_is_rejected() {
+        let mut village = village();
+        let now = Utc::now();
+        let job = upgrade(&village, 1, now + Duration::seconds(-200));
+
+        assert!(cancel_upgrade(&job, &mut village, std::slice::from_ref(&job), now).is_err());
+        assert_eq!(village.resources, ResourceGroup::default());
+    }
+}
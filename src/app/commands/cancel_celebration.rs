@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        celebration::{celebration_cost, celebration_refund},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct CancelCelebrationCommand {
+    repo: Arc<dyn Repository>,
+    job_id: Uuid,
+}
+
+impl CancelCelebrationCommand {
+    pub fn new(repo: Arc<dyn Repository>, job_id: Uuid) -> Self {
+        Self { repo, job_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for CancelCelebrationCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let job = self.repo.get_job_by_id(self.job_id).await?;
+        let mut village = self.repo.get_village_by_id(job.village_id).await?;
+
+        cancel_celebration(&job, &mut village, Utc::now())?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobCancelled { job_id: job.id }]))
+    }
+}
+
+// Gives back part of the cost of a celebration still waiting to start. Celebrations
+// already going on can't be cancelled.
+fn cancel_celebration(job: &Job, village: &mut Village, now: DateTime<Utc>) -> Result<()> {
+    let big = match job.task {
+        JobTask::CelebrationTownHall { big } => big,
+        JobTask::CelebrationBrewery => false,
+        _ => return Err(Error::msg("This job is not a celebration.")),
+    };
+    if job.done || job.started_at <= now {
+        return Err(Error::msg("An active celebration can't be cancelled."));
+    }
+
+    village.deposit_resources(&celebration_refund(&celebration_cost(big)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::cancel_celebration;
+    use crate::{
+        app::jobs::{Job, JobTask},
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::BuildingName, map::Position, village::Village, ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        // big enough stocks to hold the refund
+        for (slot_id, name) in [(20, BuildingName::Warehouse), (21, BuildingName::Granary)] {
+            village.add_building(name, slot_id).unwrap();
+            village.set_building_level(slot_id, 10).unwrap();
+        }
+        village.set_resources(ResourceGroup::default()).unwrap();
+        village
+    }
+
+    fn celebration(village: &Village, starts_in: Duration) -> Job {
+        let mut job = Job::new(
+            village.player_id,
+            village.id,
+            86_400,
+            JobTask::CelebrationTownHall { big: false },
+        );
+        job.started_at = Utc::now() + starts_in;
+        job
+    }
+
+    #[test]
+    fn test_cancel_pending_celebration_refunds_part_of_the_cost() {
+        let mut village = village();
+        let job = celebration(&village, Duration::hours(1));
+
+        cancel_celebration(&job, &mut village, Utc::now()).unwrap();
+        // 75% of the small celebration cost
+        assert_eq!(
+            village.resources,
+            ResourceGroup::new(4800, 4987, 4455, 1005)
+        );
+    }
+
+    #[test]
+    fn test_cancel_active_celebration_is_rejected() {
+        let mut village = village();
+        let job = celebration(&village, Duration::hours(-1));
+
+        assert!(cancel_celebration(&job, &mut village, Utc::now()).is_err());
+        assert_eq!(village.resources, ResourceGroup::default());
+    }
+}
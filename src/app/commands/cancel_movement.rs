@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, repository::Repository};
+
+// Calls back an army movement of the player within the grace period after departure.
+pub struct CancelMovementCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    job_id: Uuid,
+    grace_secs: u64,
+}
+
+impl CancelMovementCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, job_id: Uuid, grace_secs: u64) -> Self {
+        Self {
+            repo,
+            player_id,
+            job_id,
+            grace_secs,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for CancelMovementCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let job = self.repo.get_job_by_id(self.job_id).await?;
+        if job.player_id != self.player_id {
+            return Err(Error::msg("These troops don't belong to the player."));
+        }
+        let return_job = job.cancel(self.grace_secs, Utc::now())?;
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobCancelled { job_id: job.id },
+                GameEvent::JobEnqueued(return_job),
+            ],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::CancelMovementCommand;
+    use crate::{
+        app::{
+            commands::Command,
+            events::GameEvent,
+            jobs::{Job, JobTask},
+        },
+        db::test_utils::setup_repo,
+        game::models::{army::Army, Tribe},
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_only_the_owner_cancels_a_movement() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let army = Army::new(
+            1,
+            alice,
+            Tribe::Roman,
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let job = Job::new(
+            alice,
+            1,
+            600,
+            JobTask::Raid {
+                army,
+                village_id: 2,
+                player_id: bob,
+            },
+        );
+        repo.add_job(job.clone()).await.unwrap();
+
+        let cancel = |player_id| CancelMovementCommand::new(repo.clone(), player_id, job.id, 90);
+        assert!(cancel(bob).run().await.is_err());
+
+        let (_, events) = cancel(alice).run().await.unwrap();
+        assert!(matches!(
+            events[0],
+            GameEvent::JobCancelled { job_id } if job_id == job.id
+        ));
+    }
+}
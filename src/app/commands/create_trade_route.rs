@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{trade_route::TradeRoute, ResourceGroup},
+    repository::Repository,
+};
+
+pub struct CreateTradeRouteCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    target_village_id: u32,
+    resources: ResourceGroup,
+    interval_secs: u64,
+}
+
+impl CreateTradeRouteCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+        interval_secs: u64,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            target_village_id,
+            resources,
+            interval_secs,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for CreateTradeRouteCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        // the target must exist when the route is created, it's loaded again on each run
+        self.repo.get_village_by_id(self.target_village_id).await?;
+        if village.merchants() == 0 {
+            return Err(Error::msg(
+                "A Marketplace is needed to create trade routes.",
+            ));
+        }
+
+        let route = TradeRoute::new(
+            village.player_id,
+            village.id,
+            self.target_village_id,
+            self.resources.clone(),
+            self.interval_secs,
+        )?;
+        self.repo.add_trade_route(route.clone()).await?;
+
+        // the first shipment leaves right away
+        let job = Job::new(
+            route.player_id,
+            route.village_id,
+            0,
+            JobTask::TradeRoute { route_id: route.id },
+        );
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::{events::GameEvent, jobs::JobTask},
+    repository::Repository,
+};
+
+pub struct DeleteTradeRouteCommand {
+    repo: Arc<dyn Repository>,
+    route_id: Uuid,
+}
+
+impl DeleteTradeRouteCommand {
+    pub fn new(repo: Arc<dyn Repository>, route_id: Uuid) -> Self {
+        Self { repo, route_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DeleteTradeRouteCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let route = self
+            .repo
+            .get_trade_route_by_id(self.route_id)
+            .await?
+            .ok_or_else(|| Error::msg("Trade route not found."))?;
+        self.repo.remove_trade_route(route.id).await?;
+
+        // merchants already on their way still deliver, only the next runs are cancelled
+        let events = self
+            .repo
+            .get_pending_jobs_by_village_id(route.village_id)
+            .await?
+            .into_iter()
+            .filter(|j| matches!(j.task, JobTask::TradeRoute { route_id } if route_id == route.id))
+            .map(|j| GameEvent::JobCancelled { job_id: j.id })
+            .collect();
+
+        Ok(((), events))
+    }
+}
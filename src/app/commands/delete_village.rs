@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{app::events::GameEvent, game::models::village::Village, repository::Repository};
+
+pub struct DeleteVillageCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    delete_account_with_last_village: bool,
+}
+
+impl DeleteVillageCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        delete_account_with_last_village: bool,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            delete_account_with_last_village,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DeleteVillageCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let villages = self
+            .repo
+            .get_villages_by_player_id(village.player_id)
+            .await?;
+        let delete_account =
+            check_deletion(&village, &villages, self.delete_account_with_last_village)?;
+
+        // queues are dropped without refunds
+        let jobs = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        self.repo.delete_village(self.village_id).await?;
+        if delete_account {
+            self.repo.delete_player(village.player_id).await?;
+        }
+
+        Ok((
+            (),
+            jobs.into_iter()
+                .map(|j| GameEvent::JobCancelled { job_id: j.id })
+                .collect(),
+        ))
+    }
+}
+
+// Checks the village can be deleted, returning true when the whole account has to go
+// with it.
+fn check_deletion(
+    village: &Village,
+    villages: &[Village],
+    delete_account_with_last_village: bool,
+) -> Result<bool> {
+    if villages.len() <= 1 {
+        return match delete_account_with_last_village {
+            true => Ok(true),
+            false => Err(Error::msg("The last village of a player can't be deleted")),
+        };
+    }
+    if village.is_capital {
+        return Err(Error::msg("The capital can't be deleted"));
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{check_deletion, DeleteVillageCommand};
+    use crate::{
+        app::{
+            commands::Command,
+            events::GameEvent,
+            jobs::{Job, JobTask},
+        },
+        db::test_utils::{insert_valley, insert_village, setup_repo, test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_delete_village_frees_slot_and_cancels_queues() {
+        let repo = setup_repo().await;
+        let (home, colony) = (Position { x: 3, y: 4 }, Position { x: 5, y: 6 });
+        insert_valley(&repo, &home).await;
+        insert_valley(&repo, &colony).await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        repo.award_culture_points(alice.id, "2023-04-01".to_string(), 2000)
+            .await
+            .unwrap();
+
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut capital = Village::new("Alice".to_string(), &valley, &alice, true);
+        capital.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(10).unwrap(),
+        );
+        repo.found_village(capital.clone(), None).await.unwrap();
+
+        let valley = repo
+            .get_valley_by_id(colony.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new("Colony".to_string(), &valley, &alice, false);
+        village.parent_village_id = Some(capital.id);
+        repo.found_village(village.clone(), None).await.unwrap();
+
+        let job = Job::new(
+            alice.id,
+            village.id,
+            600,
+            JobTask::BuildingUpgrade {
+                slot_id: 19,
+                building_name: BuildingName::MainBuilding,
+                target_level: None,
+            },
+        );
+        repo.add_job(job.clone()).await.unwrap();
+
+        let villages = repo.get_villages_by_player_id(alice.id).await.unwrap();
+        assert_eq!(capital.free_expansion_slots(&villages), 0);
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let (_, events) = DeleteVillageCommand::new(repo.clone(), village.id, false)
+            .run()
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            events.as_slice(),
+            [GameEvent::JobCancelled { job_id }] if *job_id == job.id
+        ));
+        assert!(repo.get_village_by_id(village.id).await.is_err());
+        let valley = repo.get_valley_by_id(village.id).await.unwrap();
+        assert_eq!(valley.player_id, None);
+        assert_eq!(valley.village_id, None);
+
+        let villages = repo.get_villages_by_player_id(alice.id).await.unwrap();
+        assert_eq!(capital.free_expansion_slots(&villages), 1);
+    }
+
+    #[tokio::test]
+    async fn test_last_village_deletes_account_only_if_enabled() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        assert!(DeleteVillageCommand::new(repo.clone(), village.id, false)
+            .run()
+            .await
+            .is_err());
+        assert!(repo.get_village_by_id(village.id).await.is_ok());
+
+        DeleteVillageCommand::new(repo.clone(), village.id, true)
+            .run()
+            .await
+            .unwrap();
+        assert!(repo.get_village_by_id(village.id).await.is_err());
+        assert!(repo.get_player_by_id(village.player_id).await.is_err());
+    }
+
+    #[test]
+    fn test_capital_cant_be_deleted() {
+        let alice = test_player(Tribe::Roman);
+        let villages: Vec<Village> = [
+            (Position { x: 3, y: 4 }, true),
+            (Position { x: 5, y: 6 }, false),
+        ]
+        .into_iter()
+        .map(|(position, is_capital)| Village {
+            is_capital,
+            ..test_village(&alice, position)
+        })
+        .collect();
+
+        assert!(check_deletion(&villages[0], &villages, true).is_err());
+        assert!(!check_deletion(&villages[1], &villages, true).unwrap());
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        scale_time,
+        village::{Village, DEMOLITION_TIME_SECS},
+    },
+    repository::Repository,
+};
+
+pub struct DemolishBuildingCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    server_speed: u8,
+}
+
+impl DemolishBuildingCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, slot_id: u8, server_speed: u8) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DemolishBuildingCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let job = start_demolition(&village, self.slot_id, self.server_speed)?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Returns the job to tear down one level of the building on the given slot. Demolitions
+// are free and always take the same time, whatever the building.
+pub fn start_demolition(village: &Village, slot_id: u8, server_speed: u8) -> Result<Job> {
+    let building = village.validate_demolition(slot_id)?;
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        scale_time(DEMOLITION_TIME_SECS, 1.0, server_speed) as u64,
+        JobTask::BuildingDowngrade {
+            slot_id,
+            building_name: building.name,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::start_demolition;
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{buildings::BuildingName, map::Position, village::Village, Tribe},
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Gaul), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_start_demolition() {
+        let mut village = village();
+        village.set_building_level(1, 3).unwrap();
+        assert!(
+            start_demolition(&village, 1, 1).is_err(),
+            "Main Building is too low"
+        );
+
+        // slot 19 is the main building
+        village.set_building_level(19, 10).unwrap();
+        let job = start_demolition(&village, 1, 2).unwrap();
+        assert_eq!(job.duration, 300);
+        assert!(matches!(
+            job.task,
+            JobTask::BuildingDowngrade {
+                slot_id: 1,
+                building_name: BuildingName::Woodcutter,
+            }
+        ));
+        assert!(start_demolition(&village, 30, 1).is_err(), "empty slot");
+    }
+}
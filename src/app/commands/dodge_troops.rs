@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        army::Army,
+        map::{travel_time_secs, TravelSettings},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct DodgeTroopsCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    safe_target: u32,
+    return_after: u64,
+    travel: TravelSettings,
+}
+
+impl DodgeTroopsCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        safe_target: u32,
+        return_after: u64,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            safe_target,
+            return_after,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DodgeTroopsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let target = self.repo.get_village_by_id(self.safe_target).await?;
+
+        let job = dodge(&mut village, &target, self.return_after, self.travel)?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Takes the whole sendable garrison (hero included) out of the village and returns the job
+// reinforcing the safe target with it. Once there, the army stays for `return_after`
+// seconds and then comes back home.
+fn dodge(
+    village: &mut Village,
+    target: &Village,
+    return_after: u64,
+    travel: TravelSettings,
+) -> Result<Job> {
+    if village.id == target.id {
+        return Err(Error::msg("Troops can't dodge to the same village"));
+    }
+    // TODO: allow allied villages once alliances exist
+    if village.player_id != target.player_id {
+        return Err(Error::msg("Troops can only dodge to own villages"));
+    }
+    let sendable = village.sendable_troops();
+    if sendable.iter().sum::<u32>() == 0 && village.army.hero.is_none() {
+        return Err(Error::msg("No troops to send"));
+    }
+
+    let units = village.army.deploy(sendable)?;
+    let mut army = Army::new(
+        village.id,
+        village.player_id,
+        village.tribe.clone(),
+        units,
+        village.smithy,
+    );
+    army.hero = village.army.hero.take();
+
+    let time_secs = travel_time_secs(&village.position, &target.position, army.speed(), travel);
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        time_secs as u64,
+        JobTask::Reinforcement {
+            army,
+            village_id: target.id,
+            player_id: target.player_id,
+            return_after: Some(return_after),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dodge;
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            map::{Position, TravelSettings},
+            village::Village,
+            Player, Tribe,
+        },
+    };
+
+    fn village(player: &Player, x: i32, y: i32) -> Village {
+        test_village(player, Position { x, y })
+    }
+
+    fn player() -> Player {
+        test_player(Tribe::Roman)
+    }
+
+    #[test]
+    fn test_dodge_sends_the_whole_garrison() {
+        let owner = player();
+        let mut home = village(&owner, 10, 20);
+        let target = village(&owner, 10, 30);
+        home.army.units = [10, 5, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let job = dodge(&mut home, &target, 3600, TravelSettings::default()).unwrap();
+
+        assert_eq!(home.army.immensity(), 0);
+        assert_eq!(job.village_id, home.id);
+        match job.task {
+            JobTask::Reinforcement {
+                army,
+                village_id,
+                return_after,
+                ..
+            } => {
+                assert_eq!(army.units, [10, 5, 0, 0, 0, 0, 0, 0, 0, 0]);
+                assert_eq!(village_id, target.id);
+                assert_eq!(return_after, Some(3600));
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_dodge_target_validation() {
+        let owner = player();
+        let mut home = village(&owner, 10, 20);
+        home.army.units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let same = home.clone();
+        assert!(dodge(&mut home, &same, 3600, TravelSettings::default()).is_err());
+
+        let foreign = village(&player(), 10, 30);
+        assert!(dodge(&mut home, &foreign, 3600, TravelSettings::default()).is_err());
+
+        assert_eq!(home.army.immensity(), 10);
+    }
+
+    #[test]
+    fn test_dodge_without_troops() {
+        let owner = player();
+        let mut home = village(&owner, 10, 20);
+        let target = village(&owner, 10, 30);
+
+        assert!(dodge(&mut home, &target, 3600, TravelSettings::default()).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::events::GameEvent,
+    game::models::{army::TroopSet, farm_list::FarmList, map::Position},
+    repository::Repository,
+};
+
+pub struct CreateFarmListCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    name: String,
+    units: TroopSet,
+}
+
+impl CreateFarmListCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, name: String, units: TroopSet) -> Self {
+        Self {
+            repo,
+            village_id,
+            name,
+            units,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for CreateFarmListCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        let list = FarmList::new(village.player_id, village.id, self.name.clone(), self.units)?;
+        self.repo.add_farm_list(list).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+// Renames the list and changes the troops sent to each of its targets.
+pub struct UpdateFarmListCommand {
+    repo: Arc<dyn Repository>,
+    list_id: Uuid,
+    name: String,
+    units: TroopSet,
+}
+
+impl UpdateFarmListCommand {
+    pub fn new(repo: Arc<dyn Repository>, list_id: Uuid, name: String, units: TroopSet) -> Self {
+        Self {
+            repo,
+            list_id,
+            name,
+            units,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for UpdateFarmListCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut list = get_farm_list(self.repo.as_ref(), self.list_id).await?;
+
+        list.update(self.name.clone(), self.units)?;
+        self.repo.update_farm_list(list).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+// Raids already sent from the list keep going.
+pub struct DeleteFarmListCommand {
+    repo: Arc<dyn Repository>,
+    list_id: Uuid,
+}
+
+impl DeleteFarmListCommand {
+    pub fn new(repo: Arc<dyn Repository>, list_id: Uuid) -> Self {
+        Self { repo, list_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DeleteFarmListCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let list = get_farm_list(self.repo.as_ref(), self.list_id).await?;
+        self.repo.remove_farm_list(list.id).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+pub struct AddFarmListTargetCommand {
+    repo: Arc<dyn Repository>,
+    list_id: Uuid,
+    position: Position,
+}
+
+impl AddFarmListTargetCommand {
+    pub fn new(repo: Arc<dyn Repository>, list_id: Uuid, position: Position) -> Self {
+        Self {
+            repo,
+            list_id,
+            position,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for AddFarmListTargetCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut list = get_farm_list(self.repo.as_ref(), self.list_id).await?;
+
+        // targets are checked when the list is sent, villages can be founded or
+        // destroyed meanwhile
+        list.add_target(self.position.clone())?;
+        self.repo.update_farm_list(list).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+pub struct RemoveFarmListTargetCommand {
+    repo: Arc<dyn Repository>,
+    list_id: Uuid,
+    position: Position,
+}
+
+impl RemoveFarmListTargetCommand {
+    pub fn new(repo: Arc<dyn Repository>, list_id: Uuid, position: Position) -> Self {
+        Self {
+            repo,
+            list_id,
+            position,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for RemoveFarmListTargetCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut list = get_farm_list(self.repo.as_ref(), self.list_id).await?;
+
+        list.remove_target(&self.position)?;
+        self.repo.update_farm_list(list).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+pub async fn get_farm_list(repo: &dyn Repository, list_id: Uuid) -> Result<FarmList> {
+    repo.get_farm_list_by_id(list_id)
+        .await?
+        .ok_or_else(|| Error::msg("Farm list not found."))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::{
+        AddFarmListTargetCommand, DeleteFarmListCommand, RemoveFarmListTargetCommand,
+        UpdateFarmListCommand,
+    };
+    use crate::{
+        app::commands::Command,
+        db::test_utils::setup_repo,
+        game::models::{farm_list::FarmList, map::Position},
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_farm_list_targets_are_stored() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let list = FarmList::new(Uuid::new_v4(), 1, "Farms".to_string(), [5; 10]).unwrap();
+        repo.add_farm_list(list.clone()).await.unwrap();
+
+        for x in [1, 2] {
+            AddFarmListTargetCommand::new(repo.clone(), list.id, Position { x, y: 0 })
+                .run()
+                .await
+                .unwrap();
+        }
+        RemoveFarmListTargetCommand::new(repo.clone(), list.id, Position { x: 1, y: 0 })
+            .run()
+            .await
+            .unwrap();
+
+        let stored = repo.get_farm_list_by_id(list.id).await.unwrap().unwrap();
+        assert_eq!(stored.targets, vec![Position { x: 2, y: 0 }]);
+        assert!(
+            RemoveFarmListTargetCommand::new(repo.clone(), list.id, Position { x: 1, y: 0 })
+                .run()
+                .await
+                .is_err()
+        );
+
+        UpdateFarmListCommand::new(repo.clone(), list.id, "Oases".to_string(), [1; 10])
+            .run()
+            .await
+            .unwrap();
+        let stored = repo.get_farm_list_by_id(list.id).await.unwrap().unwrap();
+        assert_eq!(stored.name, "Oases");
+        assert_eq!(stored.units, [1; 10]);
+        assert_eq!(stored.targets.len(), 1);
+
+        DeleteFarmListCommand::new(repo.clone(), list.id)
+            .run()
+            .await
+            .unwrap();
+        assert!(repo.get_farm_list_by_id(list.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,484 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::events::GameEvent,
+    game::models::{
+        map::Position,
+        village::{allowed_villages, Village, SETTLERS_NEEDED, SETTLER_IDX},
+    },
+    repository::Repository,
+};
+
+pub struct FoundVillageAtCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    position: Position,
+    world_size: i32,
+}
+
+impl FoundVillageAtCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        position: Position,
+        world_size: i32,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            position,
+            world_size,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for FoundVillageAtCommand {
+    // the id of the new village
+    type Output = u32;
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let position = self.position.normalize(self.world_size)?;
+        let player = self.repo.get_player_by_id(self.player_id).await?;
+        let valley = self
+            .repo
+            .get_valley_by_id(position.to_id(self.world_size))
+            .await?;
+        if valley.player_id.is_some() || valley.village_id.is_some() {
+            return Err(Error::msg("Valley already occupied."));
+        }
+
+        // the first village of a player is its capital, the others need enough culture
+        // points and settlers from a village with a free expansion slot.
+        let villages = self.repo.get_villages_by_player_id(self.player_id).await?;
+        if villages.len() as u32 >= allowed_villages(player.culture_points) {
+            return Err(Error::msg("Not enough culture points for a new village."));
+        }
+        let settlers_village = match villages.is_empty() {
+            true => None,
+            false => Some(settlers_village(&villages)?),
+        };
+
+        let mut village = Village::new(
+            "New village".to_string(),
+            &valley,
+            &player,
+            settlers_village.is_none(),
+        );
+        village.parent_village_id = settlers_village.map(|v| v.id);
+
+        // valley, settlers and culture points are checked again while founding, in case
+        // another command got there first
+        self.repo
+            .found_village(village.clone(), settlers_village.map(|v| v.id))
+            .await?;
+
+        Ok((
+            village.id,
+            vec![GameEvent::VillageFounded(Box::new(village))],
+        ))
+    }
+}
+
+// Returns the village sending the settlers: it needs enough of them and a free expansion
+// slot from its Residence or Palace.
+fn settlers_village(villages: &[Village]) -> Result<&Village> {
+    let mut with_settlers = villages
+        .iter()
+        .filter(|v| v.army.units[SETTLER_IDX] >= SETTLERS_NEEDED)
+        .peekable();
+    if with_settlers.peek().is_none() {
+        return Err(Error::msg(format!(
+            "{} settlers are needed to found a village.",
+            SETTLERS_NEEDED
+        )));
+    }
+
+    with_settlers
+        .find(|v| v.free_expansion_slots(villages) > 0)
+        .ok_or_else(|| Error::msg("No villages with settlers have free expansion slots."))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+
+    use super::{settlers_village, FoundVillageAtCommand};
+    use crate::{
+        app::commands::Command,
+        db::test_utils::{insert_valley, setup_repo},
+        db::test_utils::{test_player, test_village},
+        game::error::GameError,
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, Quadrant, WORLD_MAX_SIZE},
+            village::{Village, SETTLERS_NEEDED, SETTLER_IDX},
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_only_one_player_founds_on_the_same_valley() {
+        let repo = setup_repo().await;
+
+        let position = Position { x: 3, y: 4 };
+        insert_valley(&repo, &position).await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let bob = repo
+            .register_player("bob".to_string(), Tribe::Gaul)
+            .await
+            .unwrap();
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let first =
+            FoundVillageAtCommand::new(repo.clone(), alice.id, position.clone(), WORLD_MAX_SIZE);
+        let second =
+            FoundVillageAtCommand::new(repo.clone(), bob.id, position.clone(), WORLD_MAX_SIZE);
+
+        let (a, b) = tokio::join!(first.run(), second.run());
+        assert!(
+            a.is_ok() ^ b.is_ok(),
+            "exactly one player founds the village"
+        );
+
+        let winner = if a.is_ok() { alice.id } else { bob.id };
+        let village = repo
+            .get_village_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(village.player_id, winner);
+        assert!(village.is_capital);
+
+        let valley = repo
+            .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(valley.player_id, Some(winner));
+    }
+
+    #[tokio::test]
+    async fn test_settlers_found_only_one_village() {
+        let repo = setup_repo().await;
+
+        let home = Position { x: 3, y: 4 };
+        let first_target = Position { x: 5, y: 6 };
+        let second_target = Position { x: -5, y: -6 };
+        for position in [&home, &first_target, &second_target] {
+            insert_valley(&repo, position).await;
+        }
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new("Alice".to_string(), &valley, &alice, true);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(10).unwrap(),
+        );
+        village.army.units[SETTLER_IDX] = SETTLERS_NEEDED;
+        repo.found_village(village.clone(), None).await.unwrap();
+        repo.award_culture_points(alice.id, "2023-04-01".to_string(), 2000)
+            .await
+            .unwrap();
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let first = FoundVillageAtCommand::new(
+            repo.clone(),
+            alice.id,
+            first_target.clone(),
+            WORLD_MAX_SIZE,
+        );
+        let second = FoundVillageAtCommand::new(
+            repo.clone(),
+            alice.id,
+            second_target.clone(),
+            WORLD_MAX_SIZE,
+        );
+
+        let (a, b) = tokio::join!(first.run(), second.run());
+        assert!(
+            a.is_ok() ^ b.is_ok(),
+            "one set of settlers founds one village"
+        );
+
+        let home = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(home.army.units[SETTLER_IDX], 0);
+        assert_eq!(
+            repo.get_villages_by_player_id(alice.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let (claimed, free) = if a.is_ok() {
+            (first_target, second_target)
+        } else {
+            (second_target, first_target)
+        };
+        let claimed = repo
+            .get_valley_by_id(claimed.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(claimed.player_id, Some(alice.id));
+        let free = repo
+            .get_valley_by_id(free.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(free.player_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_expansion_needs_culture_points() {
+        let repo = setup_repo().await;
+        let home = Position { x: 3, y: 4 };
+        let target = Position { x: 5, y: 6 };
+        for position in [&home, &target] {
+            insert_valley(&repo, position).await;
+        }
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new("Alice".to_string(), &valley, &alice, true);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(10).unwrap(),
+        );
+        village.army.units[SETTLER_IDX] = SETTLERS_NEEDED;
+        repo.found_village(village.clone(), None).await.unwrap();
+        repo.award_culture_points(alice.id, "2023-04-01".to_string(), 1999)
+            .await
+            .unwrap();
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let command =
+            FoundVillageAtCommand::new(repo.clone(), alice.id, target.clone(), WORLD_MAX_SIZE);
+        assert!(command.run().await.is_err());
+        let valley = repo
+            .get_valley_by_id(target.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(valley.player_id, None);
+
+        repo.award_culture_points(alice.id, "2023-04-02".to_string(), 1)
+            .await
+            .unwrap();
+        command.run().await.unwrap();
+        let home = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(home.army.units[SETTLER_IDX], 0);
+    }
+
+    #[tokio::test]
+    async fn test_culture_points_found_only_one_village() {
+        let repo = setup_repo().await;
+
+        let home = Position { x: 3, y: 4 };
+        let first_target = Position { x: 5, y: 6 };
+        let second_target = Position { x: -5, y: -6 };
+        for position in [&home, &first_target, &second_target] {
+            insert_valley(&repo, position).await;
+        }
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(home.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        // two expansion slots and settlers for both, but culture points for one village
+        let mut village = Village::new("Alice".to_string(), &valley, &alice, true);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(20).unwrap(),
+        );
+        village.army.units[SETTLER_IDX] = SETTLERS_NEEDED * 2;
+        repo.found_village(village.clone(), None).await.unwrap();
+        repo.award_culture_points(alice.id, "2023-04-01".to_string(), 2000)
+            .await
+            .unwrap();
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let first = FoundVillageAtCommand::new(
+            repo.clone(),
+            alice.id,
+            first_target.clone(),
+            WORLD_MAX_SIZE,
+        );
+        let second = FoundVillageAtCommand::new(
+            repo.clone(),
+            alice.id,
+            second_target.clone(),
+            WORLD_MAX_SIZE,
+        );
+
+        let (a, b) = tokio::join!(first.run(), second.run());
+        assert!(
+            a.is_ok() ^ b.is_ok(),
+            "culture points allow only one more village"
+        );
+
+        let home = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(home.army.units[SETTLER_IDX], SETTLERS_NEEDED);
+        assert_eq!(
+            repo.get_villages_by_player_id(alice.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    fn home_village(residence: Option<(BuildingName, u8)>, settlers: u32) -> Village {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 3, y: 4 });
+        if let Some((name, level)) = residence {
+            village
+                .buildings
+                .insert(20, Building::new(name).at_level(level).unwrap());
+        }
+        village.army.units[SETTLER_IDX] = settlers;
+        village
+    }
+
+    #[test]
+    fn test_settlers_village_needs_settlers_and_slots() {
+        let residence = Some((BuildingName::Residence, 10));
+
+        let home = home_village(residence.clone(), SETTLERS_NEEDED - 1);
+        let err = settlers_village(std::slice::from_ref(&home)).unwrap_err();
+        assert!(err.to_string().contains("settlers are needed"));
+
+        let home = home_village(Some((BuildingName::Residence, 9)), SETTLERS_NEEDED);
+        let err = settlers_village(std::slice::from_ref(&home)).unwrap_err();
+        assert!(err.to_string().contains("expansion slots"));
+
+        let home = home_village(residence, SETTLERS_NEEDED);
+        let found = settlers_village(std::slice::from_ref(&home)).unwrap();
+        assert_eq!(found.id, home.id);
+
+        // the only slot is taken by a village founded before
+        let mut child = home_village(None, 0);
+        child.id = home.id + 1;
+        child.parent_village_id = Some(home.id);
+        assert!(settlers_village(&[home, child]).is_err());
+    }
+
+    #[test]
+    fn test_residence_and_palace_expansion_slots() {
+        for (level, residence, palace) in [
+            (1, 0, 0),
+            (10, 1, 1),
+            (14, 1, 1),
+            (15, 1, 2),
+            (19, 1, 2),
+            (20, 2, 3),
+        ] {
+            let home = home_village(Some((BuildingName::Residence, level)), 0);
+            assert_eq!(home.expansion_slots(), residence, "residence {}", level);
+            let home = home_village(Some((BuildingName::Palace, level)), 0);
+            assert_eq!(home.expansion_slots(), palace, "palace {}", level);
+        }
+        assert_eq!(home_village(None, 0).expansion_slots(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_target_out_of_range() {
+        let repo = setup_repo().await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let target = Position { x: 1000, y: 4 };
+        let err = FoundVillageAtCommand::new(repo, alice.id, target, WORLD_MAX_SIZE)
+            .run()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<GameError>(),
+            Some(&GameError::TargetOutOfRange { x: 1000, y: 4 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wraparound_target_is_accepted() {
+        let repo = setup_repo().await;
+        let wrapped = Position {
+            x: -WORLD_MAX_SIZE,
+            y: 4,
+        };
+        insert_valley(&repo, &wrapped).await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let target = Position {
+            x: WORLD_MAX_SIZE + 1,
+            y: 4,
+        };
+        FoundVillageAtCommand::new(repo.clone(), alice.id, target, WORLD_MAX_SIZE)
+            .run()
+            .await
+            .unwrap();
+
+        let village = repo
+            .get_village_by_id(wrapped.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(village.player_id, alice.id);
+    }
+
+    #[tokio::test]
+    async fn test_target_wraps_at_the_configured_world_edge() {
+        let repo = setup_repo().await;
+        repo.bootstrap_new_map(10, Utc::now()).await.unwrap();
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_unoccupied_valley(Some(Quadrant::WestNorth), Some(42))
+            .await
+            .unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        // a world of size 10 is 21 fields wide
+        let target = Position {
+            x: valley.position.x + 21,
+            y: valley.position.y,
+        };
+        let (village_id, _) = FoundVillageAtCommand::new(repo.clone(), alice.id, target, 10)
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(village_id, valley.id);
+        let village = repo.get_village_by_id(valley.id).await.unwrap();
+        assert_eq!(village.position, valley.position);
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, game::models::hero::HeroAttributes, repository::Repository};
+
+pub struct ReallocateHeroPointsCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    attributes: HeroAttributes,
+}
+
+impl ReallocateHeroPointsCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, attributes: HeroAttributes) -> Self {
+        Self {
+            repo,
+            player_id,
+            attributes,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for ReallocateHeroPointsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut hero = self.repo.get_hero_by_player_id(self.player_id).await?;
+        hero.reallocate_points(self.attributes.clone())?;
+        self.repo.update_hero(hero.clone()).await?;
+
+        Ok(((), vec![GameEvent::HeroUpdated(hero)]))
+    }
+}
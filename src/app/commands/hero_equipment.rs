@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, game::models::hero::ItemSlot, repository::Repository};
+
+pub struct EquipHeroItemCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    item_id: Uuid,
+}
+
+impl EquipHeroItemCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, item_id: Uuid) -> Self {
+        Self {
+            repo,
+            player_id,
+            item_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for EquipHeroItemCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut hero = self.repo.get_hero_by_player_id(self.player_id).await?;
+        hero.equip(self.item_id)?;
+        self.repo.update_hero(hero.clone()).await?;
+
+        Ok(((), vec![GameEvent::HeroUpdated(hero)]))
+    }
+}
+
+pub struct UnequipHeroItemCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    slot: ItemSlot,
+}
+
+impl UnequipHeroItemCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, slot: ItemSlot) -> Self {
+        Self {
+            repo,
+            player_id,
+            slot,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for UnequipHeroItemCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut hero = self.repo.get_hero_by_player_id(self.player_id).await?;
+        hero.unequip(self.slot.clone())?;
+        self.repo.update_hero(hero.clone()).await?;
+
+        Ok(((), vec![GameEvent::HeroUpdated(hero)]))
+    }
+}
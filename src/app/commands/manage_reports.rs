@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, repository::Repository};
+
+// Moves reports of the player out of the default list.
+pub struct ArchiveReportsCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    report_ids: Vec<Uuid>,
+}
+
+impl ArchiveReportsCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, report_ids: Vec<Uuid>) -> Self {
+        Self {
+            repo,
+            player_id,
+            report_ids,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for ArchiveReportsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        check_ownership(self.repo.as_ref(), self.player_id, &self.report_ids).await?;
+        self.repo
+            .archive_reports(&self.report_ids, self.player_id)
+            .await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+// Deletes reports for the player, the other side keeps seeing them.
+pub struct DeleteReportsCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    report_ids: Vec<Uuid>,
+}
+
+impl DeleteReportsCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, report_ids: Vec<Uuid>) -> Self {
+        Self {
+            repo,
+            player_id,
+            report_ids,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for DeleteReportsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        check_ownership(self.repo.as_ref(), self.player_id, &self.report_ids).await?;
+        self.repo
+            .delete_reports(&self.report_ids, self.player_id)
+            .await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+// Refuses the whole batch when any of the reports isn't visible to the player.
+async fn check_ownership(
+    repo: &dyn Repository,
+    player_id: Uuid,
+    report_ids: &[Uuid],
+) -> Result<()> {
+    if report_ids.is_empty() {
+        return Err(Error::msg("No reports selected."));
+    }
+
+    let reports = repo.get_reports_by_ids(report_ids).await?;
+    let owned = reports
+        .iter()
+        .filter(|r| r.view_for(player_id).is_some())
+        .count();
+    // hidden reports look the same as missing ones
+    if owned != report_ids.len() {
+        return Err(Error::msg("Report not found."));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::{ArchiveReportsCommand, DeleteReportsCommand};
+    use crate::{
+        app::{
+            commands::Command,
+            queries::{reports::ListReports, Query},
+        },
+        db::test_utils::setup_repo,
+        game::models::{
+            report::{
+                MerchantDelivery, Report, ReportAudience, ReportContent, ReportFilter, ReportSort,
+            },
+            ResourceGroup,
+        },
+        repository::Repository,
+    };
+
+    async fn delivery(repo: &Arc<dyn Repository>, sender: Uuid, receiver: Uuid) -> Report {
+        let report = Report::new(
+            sender,
+            1,
+            receiver,
+            2,
+            ReportAudience::Everyone,
+            ReportContent::Delivery(MerchantDelivery {
+                delivered: ResourceGroup::new(100, 0, 0, 0),
+                stored: ResourceGroup::new(100, 0, 0, 0),
+            }),
+        );
+        repo.add_report(report.clone()).await.unwrap();
+        report
+    }
+
+    async fn total(repo: &Arc<dyn Repository>, player_id: Uuid, archived: bool) -> u32 {
+        let filter = ReportFilter {
+            archived,
+            ..Default::default()
+        };
+        ListReports::new(
+            repo.clone(),
+            player_id,
+            filter,
+            1,
+            10,
+            ReportSort::NewestFirst,
+        )
+        .run()
+        .await
+        .unwrap()
+        .total
+    }
+
+    #[tokio::test]
+    async fn test_only_own_reports_can_be_changed() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let own = delivery(&repo, alice, bob).await;
+        let other = delivery(&repo, bob, carol).await;
+
+        let ids = vec![own.id, other.id];
+        assert!(ArchiveReportsCommand::new(repo.clone(), alice, ids.clone())
+            .run()
+            .await
+            .is_err());
+        assert!(DeleteReportsCommand::new(repo.clone(), alice, ids)
+            .run()
+            .await
+            .is_err());
+        assert!(
+            DeleteReportsCommand::new(repo.clone(), alice, vec![Uuid::new_v4()])
+                .run()
+                .await
+                .is_err()
+        );
+        assert!(DeleteReportsCommand::new(repo.clone(), alice, vec![])
+            .run()
+            .await
+            .is_err());
+
+        // nothing changed, not even the report alice owns
+        assert_eq!(total(&repo, alice, false).await, 1);
+        assert_eq!(total(&repo, carol, false).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_archived_reports_have_their_own_list() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let first = delivery(&repo, alice, bob).await;
+        delivery(&repo, alice, bob).await;
+
+        ArchiveReportsCommand::new(repo.clone(), alice, vec![first.id])
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(total(&repo, alice, false).await, 1);
+        assert_eq!(total(&repo, alice, true).await, 1);
+
+        // bob didn't archive anything
+        assert_eq!(total(&repo, bob, false).await, 2);
+        assert_eq!(total(&repo, bob, true).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_reports_stay_for_the_other_side() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let report = delivery(&repo, alice, bob).await;
+
+        DeleteReportsCommand::new(repo.clone(), alice, vec![report.id])
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(total(&repo, alice, false).await, 0);
+        assert_eq!(total(&repo, bob, false).await, 1);
+        assert!(
+            DeleteReportsCommand::new(repo.clone(), alice, vec![report.id])
+                .run()
+                .await
+                .is_err(),
+            "the report is gone for alice"
+        );
+
+        DeleteReportsCommand::new(repo.clone(), bob, vec![report.id])
+            .run()
+            .await
+            .unwrap();
+        let left = repo.get_reports_by_ids(&[report.id]).await.unwrap();
+        assert!(left.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, game::models::report::ReportView, repository::Repository};
+
+// Opens a report for the player, marking it as read for their side only.
+pub struct MarkReportReadCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    report_id: Uuid,
+}
+
+impl MarkReportReadCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, report_id: Uuid) -> Self {
+        Self {
+            repo,
+            player_id,
+            report_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for MarkReportReadCommand {
+    type Output = ReportView;
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut report = self.repo.get_report_by_id(self.report_id).await?;
+
+        // hidden reports can't be read, they look the same as missing ones
+        if report.view_for(self.player_id).is_none() {
+            return Err(Error::msg("Report not found."));
+        }
+        self.repo
+            .mark_report_read(report.id, self.player_id)
+            .await?;
+
+        report.read_by_attacker |= self.player_id == report.attacker_player_id;
+        report.read_by_defender |= self.player_id == report.defender_player_id;
+        let view = report
+            .view_for(self.player_id)
+            .ok_or_else(|| Error::msg("Report not found."))?;
+
+        Ok((view, vec![]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::MarkReportReadCommand;
+    use crate::{
+        app::commands::Command,
+        db::test_utils::setup_repo,
+        game::models::report::{Report, ReportAudience, ReportContent, ReportView, ScoutingIntel},
+        repository::Repository,
+    };
+
+    fn scouting(attacker: Uuid, defender: Uuid, detected: bool) -> Report {
+        Report::new(
+            attacker,
+            1,
+            defender,
+            2,
+            ReportAudience::Spy { detected },
+            ReportContent::Scouting(ScoutingIntel::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_viewing_a_report_marks_it_read() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+        let report = scouting(attacker, defender, true);
+        repo.add_report(report.clone()).await.unwrap();
+
+        let (view, events) = MarkReportReadCommand::new(repo.clone(), defender, report.id)
+            .run()
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+        assert!(matches!(
+            view,
+            ReportView::ScoutingNotice { read: true, .. }
+        ));
+
+        let stored = repo.get_report_by_id(report.id).await.unwrap();
+        assert!(stored.is_read_by(defender));
+        assert!(!stored.is_read_by(attacker), "each side reads on its own");
+
+        let (view, _) = MarkReportReadCommand::new(repo.clone(), attacker, report.id)
+            .run()
+            .await
+            .unwrap();
+        match view {
+            ReportView::Full(r) => assert!(r.read_by_attacker && r.read_by_defender),
+            v => panic!("unexpected report view {:?}", v),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hidden_reports_cant_be_read() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+        let report = scouting(attacker, defender, false);
+        repo.add_report(report.clone()).await.unwrap();
+
+        assert!(
+            MarkReportReadCommand::new(repo.clone(), defender, report.id)
+                .run()
+                .await
+                .is_err()
+        );
+        assert!(
+            MarkReportReadCommand::new(repo.clone(), Uuid::new_v4(), report.id)
+                .run()
+                .await
+                .is_err()
+        );
+
+        let stored = repo.get_report_by_id(report.id).await.unwrap();
+        assert!(!stored.read_by_attacker && !stored.read_by_defender);
+    }
+}
@@ -1,20 +1,73 @@
+pub mod admin;
+pub mod annex_oasis;
 pub mod attack;
+pub mod attack_oasis;
+pub mod cancel_building_upgrade;
+pub mod cancel_celebration;
+pub mod cancel_movement;
+pub mod create_trade_route;
+pub mod delete_trade_route;
+pub mod delete_village;
+pub mod demolish_building;
+pub mod dodge_troops;
+pub mod farm_lists;
+pub mod found_village;
+pub mod hero_attributes;
+pub mod hero_equipment;
+pub mod manage_reports;
+pub mod mark_report_read;
+pub mod npc_trade;
+pub mod recall_reinforcement;
 pub mod register_player;
+pub mod reinforce;
+pub mod reinforcement_policy;
+pub mod return_merchant;
+pub mod send_farm_list;
+pub mod send_hero_on_adventure;
+pub mod send_merchant;
+pub mod send_troops;
+pub mod start_celebration;
+pub mod train_units;
+pub mod upgrade_building;
 
 use anyhow::Result;
+use uuid::Uuid;
 
 use super::events::GameEvent;
 use crate::game::{
     battle::CataTargets,
-    models::{army::Army, Tribe},
+    models::{
+        army::{Army, TroopSet, UnitName},
+        hero::{HeroAttributes, ItemSlot},
+        map::Position,
+        village::ReinforcementPolicy,
+        ResourceGroup, Tribe,
+    },
 };
 
+// Commands change the game state, returning their result along with the events to
+// process.
 #[async_trait::async_trait]
 pub trait Command {
+    type Output: Send;
+
     fn validate(&self) -> Result<()> {
         Ok(())
     }
-    async fn run(&self) -> Result<Vec<GameEvent>>;
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)>;
+}
+
+#[async_trait::async_trait]
+impl<C: Command + Send + Sync + ?Sized> Command for Box<C> {
+    type Output = C::Output;
+
+    fn validate(&self) -> Result<()> {
+        (**self).validate()
+    }
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        (**self).run().await
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -25,25 +78,173 @@ pub enum Cmd {
     },
     Attack {
         village_id: u32,
-        army: Army,
+        army: Box<Army>,
         cata_targets: CataTargets,
         defender_map_id: u32,
     },
+    AttackOasis {
+        village_id: u32,
+        army: Box<Army>,
+        oasis_id: u32,
+        is_normal: bool,
+    },
+    CancelMovement {
+        player_id: Uuid,
+        job_id: Uuid,
+    },
+    RecallReinforcement {
+        player_id: Uuid,
+        job_id: Uuid,
+    },
+    CancelCelebration {
+        job_id: Uuid,
+    },
+    CancelBuildingUpgrade {
+        job_id: Uuid,
+    },
+    FoundVillageAt {
+        player_id: Uuid,
+        position: Position,
+    },
+    UpgradeBuilding {
+        village_id: u32,
+        slot_id: u8,
+        target_level: Option<u8>,
+    },
+    DemolishBuilding {
+        village_id: u32,
+        slot_id: u8,
+    },
+    SetReinforcementPolicy {
+        village_id: u32,
+        policy: ReinforcementPolicy,
+    },
+    EquipHeroItem {
+        player_id: Uuid,
+        item_id: Uuid,
+    },
+    UnequipHeroItem {
+        player_id: Uuid,
+        slot: ItemSlot,
+    },
+    ReallocateHeroPoints {
+        player_id: Uuid,
+        attributes: HeroAttributes,
+    },
+    SendHeroOnAdventure {
+        player_id: Uuid,
+        adventure_id: Uuid,
+    },
+    DeleteVillage {
+        village_id: u32,
+    },
+    AnnexOasis {
+        village_id: u32,
+        oasis_id: u32,
+    },
+    SetVillageResources {
+        admin_id: Uuid,
+        village_id: u32,
+        resources: ResourceGroup,
+    },
+    SetBuildingLevel {
+        admin_id: Uuid,
+        village_id: u32,
+        slot_id: u8,
+        level: u8,
+    },
+    DodgeTroops {
+        village_id: u32,
+        safe_target: u32,
+        return_after: u64,
+    },
     Raid,
-    Reinforce,
+    // Troops sent from the Rally Point, once the player confirmed them.
+    SendTroops {
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        units: TroopSet,
+        kind: send_troops::MovementKind,
+    },
+    Reinforce {
+        village_id: u32,
+        army: Box<Army>,
+        target_village_id: u32,
+    },
     ReturnArmy,
-    SendMerchant,
-    ReturnMerchant,
-    TrainBarracksUnit,
-    TrainStableUnit,
-    TrainWorkshopUnit,
+    CreateFarmList {
+        village_id: u32,
+        name: String,
+        units: TroopSet,
+    },
+    UpdateFarmList {
+        list_id: Uuid,
+        name: String,
+        units: TroopSet,
+    },
+    DeleteFarmList {
+        list_id: Uuid,
+    },
+    AddFarmListTarget {
+        list_id: Uuid,
+        position: Position,
+    },
+    RemoveFarmListTarget {
+        list_id: Uuid,
+        position: Position,
+    },
+    SendFarmList {
+        list_id: Uuid,
+    },
+    SendMerchant {
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+    },
+    ReturnMerchant {
+        job_id: Uuid,
+    },
+    CreateTradeRoute {
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+        interval_secs: u64,
+    },
+    DeleteTradeRoute {
+        route_id: Uuid,
+    },
+    MarkReportRead {
+        player_id: Uuid,
+        report_id: Uuid,
+    },
+    ArchiveReports {
+        player_id: Uuid,
+        report_ids: Vec<Uuid>,
+    },
+    DeleteReports {
+        player_id: Uuid,
+        report_ids: Vec<Uuid>,
+    },
+    NpcTrade {
+        village_id: u32,
+        resources: ResourceGroup,
+    },
+    TrainUnits {
+        village_id: u32,
+        slot_id: u8,
+        unit: UnitName,
+        quantity: u32,
+    },
     TrainExpansionUnit,
     TrainTrapperUnit,
-    TrainGreatBarracksUnit,
-    TrainGreatStableUnit,
-    TrainGreatWorkshopUnit,
     ResearchAcademy,
     ResearchSmithy,
-    StartTownHallCelebration,
-    StartBreweryCelebration,
+    StartTownHallCelebration {
+        village_id: u32,
+        big: bool,
+    },
+    StartBreweryCelebration {
+        village_id: u32,
+    },
 }
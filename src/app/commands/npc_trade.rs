@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::events::GameEvent,
+    game::models::{buildings::BuildingName, village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+pub struct NpcTradeCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    resources: ResourceGroup,
+    enabled: bool,
+}
+
+impl NpcTradeCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        resources: ResourceGroup,
+        enabled: bool,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            resources,
+            enabled,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for NpcTradeCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+
+        npc_trade(&mut village, &self.resources, self.enabled)?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![]))
+    }
+}
+
+// Swaps the village resources, on the spot, with a new split of the same total. The
+// new amounts must fit in the Warehouse and the Granary.
+fn npc_trade(village: &mut Village, resources: &ResourceGroup, enabled: bool) -> Result<()> {
+    if !enabled {
+        return Err(Error::msg(
+            "The NPC merchant is not available on this server.",
+        ));
+    }
+    if village
+        .get_building_by_name(BuildingName::Marketplace)
+        .is_none()
+    {
+        return Err(Error::msg(
+            "A Marketplace is needed to trade with the NPC merchant.",
+        ));
+    }
+    if resources.total() != village.resources.total() {
+        return Err(Error::msg(
+            "The new resources must add up to the ones in the village.",
+        ));
+    }
+
+    let warehouse = village.stocks.warehouse();
+    let fits = resources.lumber() <= warehouse
+        && resources.clay() <= warehouse
+        && resources.iron() <= warehouse
+        && resources.crop() <= village.stocks.granary();
+    if !fits {
+        return Err(Error::msg(
+            "The new resources don't fit in the Warehouse or the Granary.",
+        ));
+    }
+
+    village.resources = resources.clone();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::npc_trade;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        let mut v = test_village(&test_player(Tribe::Gaul), Position { x: 10, y: 20 });
+        v.buildings
+            .insert(20, Building::new(BuildingName::Marketplace));
+        v.resources = ResourceGroup::new(800, 400, 0, 0);
+        v
+    }
+
+    #[test]
+    fn test_npc_trade_even_split() {
+        let mut v = village();
+
+        npc_trade(&mut v, &ResourceGroup::new(300, 300, 300, 300), true).unwrap();
+        assert_eq!(v.resources, ResourceGroup::new(300, 300, 300, 300));
+    }
+
+    #[test]
+    fn test_npc_trade_totals_must_match() {
+        let mut v = village();
+
+        assert!(npc_trade(&mut v, &ResourceGroup::new(300, 300, 300, 301), true).is_err());
+        assert!(npc_trade(&mut v, &ResourceGroup::new(300, 300, 300, 299), true).is_err());
+        assert_eq!(v.resources, ResourceGroup::new(800, 400, 0, 0));
+    }
+
+    #[test]
+    fn test_npc_trade_limits() {
+        let mut v = village();
+        v.resources = ResourceGroup::new(800, 800, 0, 0);
+        let split = ResourceGroup::new(0, 0, 0, 1600);
+
+        assert!(
+            npc_trade(&mut v, &split, true).is_err(),
+            "the granary holds only 800 crop"
+        );
+        assert!(npc_trade(&mut v, &ResourceGroup::new(400, 400, 400, 400), false).is_err());
+
+        v.buildings.remove(&20);
+        assert!(npc_trade(&mut v, &ResourceGroup::new(400, 400, 400, 400), true).is_err());
+    }
+}
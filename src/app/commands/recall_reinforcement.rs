@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::Command;
+use crate::{app::events::GameEvent, repository::Repository};
+
+// Calls back reinforcements sent by the player before they reach their target.
+pub struct RecallReinforcementCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    job_id: Uuid,
+}
+
+impl RecallReinforcementCommand {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, job_id: Uuid) -> Self {
+        Self {
+            repo,
+            player_id,
+            job_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for RecallReinforcementCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let job = self.repo.get_job_by_id(self.job_id).await?;
+        if job.player_id != self.player_id {
+            return Err(Error::msg("These troops don't belong to the player."));
+        }
+        let return_job = job.recall(Utc::now())?;
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobCancelled { job_id: job.id },
+                GameEvent::JobEnqueued(return_job),
+            ],
+        ))
+    }
+}
@@ -5,7 +5,7 @@ use anyhow::Result;
 use super::Command;
 use crate::{
     app::events::GameEvent,
-    game::models::{village::Village, Tribe},
+    game::models::{village::Village, Player, Tribe},
     repository::Repository,
 };
 
@@ -27,19 +27,22 @@ impl RegisterPlayerCommand {
 
 #[async_trait::async_trait]
 impl Command for RegisterPlayerCommand {
-    async fn run(&self) -> Result<Vec<GameEvent>> {
+    type Output = Player;
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
         let player = self
             .repo
             .register_player(self.username.clone(), self.tribe.clone())
             .await?;
-        let valley = self.repo.get_unoccupied_valley(None).await?;
+        let valley = self.repo.get_unoccupied_valley(None, None).await?;
         let village = Village::new("New village".to_string(), &valley, &player, true);
 
-        println!("{}", serde_json::json!(village.clone()));
-
-        Ok(vec![
-            GameEvent::PlayerRegistered(player),
-            GameEvent::VillageFounded(village),
-        ])
+        Ok((
+            player.clone(),
+            vec![
+                GameEvent::PlayerRegistered(player),
+                GameEvent::VillageFounded(Box::new(village)),
+            ],
+        ))
     }
 }
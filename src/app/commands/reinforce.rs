@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+
+use super::{attack::check_outgoing_movements, Command};
+use crate::{
+    app::events::GameEvent,
+    app::jobs::{Job, JobTask},
+    game::models::{
+        army::Army,
+        map::{travel_time_secs, TravelSettings},
+    },
+    repository::Repository,
+};
+
+pub struct ReinforceCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    army: Army,
+    target_village_id: u32,
+    max_outgoing_movements: u32,
+    travel: TravelSettings,
+}
+
+impl ReinforceCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        army: Army,
+        target_village_id: u32,
+        max_outgoing_movements: u32,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo: repo.clone(),
+            village_id,
+            army,
+            target_village_id,
+            max_outgoing_movements,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for ReinforceCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        if self.village_id == self.target_village_id {
+            return Err(Error::msg("Troops are already in this village."));
+        }
+
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let target = self.repo.get_village_by_id(self.target_village_id).await?;
+
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+        let max = village.max_outgoing_movements();
+        if max == 0 {
+            return Err(Error::msg("A Rally Point is needed to send armies."));
+        }
+        check_outgoing_movements(&pending, max.min(self.max_outgoing_movements))?;
+
+        let sendable = village.sendable_troops();
+        if self
+            .army
+            .units
+            .iter()
+            .zip(sendable.iter())
+            .any(|(q, s)| q > s)
+        {
+            return Err(Error::msg("Not enough troops at home to send."));
+        }
+
+        let speed = village.army_speed(&self.army, Utc::now());
+        let time_secs =
+            travel_time_secs(&village.position, &target.position, speed, self.travel) as u64;
+
+        // the target owner is recorded to detect when the village changes hands meanwhile
+        let job = Job::new(
+            village.player_id,
+            self.village_id,
+            time_secs,
+            JobTask::Reinforcement {
+                army: self.army.clone(),
+                village_id: self.target_village_id,
+                player_id: target.player_id,
+                return_after: None,
+            },
+        );
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobEnqueued(job),
+                GameEvent::ArmyDeployed {
+                    army: self.army.clone(),
+                    village_id: self.village_id,
+                },
+            ],
+        ))
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Command;
+use crate::{
+    app::events::GameEvent, game::models::village::ReinforcementPolicy, repository::Repository,
+};
+
+pub struct SetReinforcementPolicyCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    policy: ReinforcementPolicy,
+}
+
+impl SetReinforcementPolicyCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, policy: ReinforcementPolicy) -> Self {
+        Self {
+            repo,
+            village_id,
+            policy,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SetReinforcementPolicyCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        village.reinforcement_policy = self.policy.clone();
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![]))
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    repository::Repository,
+};
+
+pub struct ReturnMerchantCommand {
+    repo: Arc<dyn Repository>,
+    job_id: Uuid,
+}
+
+impl ReturnMerchantCommand {
+    pub fn new(repo: Arc<dyn Repository>, job_id: Uuid) -> Self {
+        Self { repo, job_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for ReturnMerchantCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let job = self.repo.get_job_by_id(self.job_id).await?;
+        let return_job = recall_merchants(&job, Utc::now())?;
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobCancelled { job_id: job.id },
+                GameEvent::JobEnqueued(return_job),
+            ],
+        ))
+    }
+}
+
+// Turns merchants back home with their load before they reach the target. They take
+// as long to come back as they have travelled so far.
+fn recall_merchants(job: &Job, now: DateTime<Utc>) -> Result<Job> {
+    let (resources, merchants) = match &job.task {
+        JobTask::MerchantGoing {
+            resources,
+            merchants,
+            ..
+        } if !job.done => (resources.clone(), *merchants),
+        _ => return Err(Error::msg("These merchants are not on their way")),
+    };
+
+    let elapsed = job.elapsed_secs(now);
+    if elapsed >= job.duration {
+        return Err(Error::msg("The merchants have already reached the target"));
+    }
+
+    Ok(Job::new(
+        job.player_id,
+        job.village_id,
+        elapsed,
+        JobTask::MerchantReturn {
+            village_id: job.village_id,
+            merchants,
+            resources,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::recall_merchants;
+    use crate::{
+        app::jobs::{Job, JobTask},
+        game::models::ResourceGroup,
+    };
+
+    fn merchants_going() -> Job {
+        Job::new(
+            Uuid::new_v4(),
+            42,
+            600,
+            JobTask::MerchantGoing {
+                resources: ResourceGroup::new(300, 0, 0, 200),
+                village_id: 24,
+                player_id: Uuid::new_v4(),
+                merchants: 1,
+            },
+        )
+    }
+
+    #[test]
+    fn test_merchants_come_back_with_their_load() {
+        let job = merchants_going();
+        let now = job.started_at + Duration::seconds(200);
+
+        let back = recall_merchants(&job, now).unwrap();
+        assert_eq!(back.village_id, 42);
+        assert_eq!(back.duration, 200);
+        match back.task {
+            JobTask::MerchantReturn {
+                village_id,
+                merchants,
+                resources,
+            } => {
+                assert_eq!(village_id, 42);
+                assert_eq!(merchants, 1);
+                assert_eq!(resources, ResourceGroup::new(300, 0, 0, 200));
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_merchants_cant_be_recalled_after_arrival() {
+        let mut job = merchants_going();
+        assert!(recall_merchants(&job, job.started_at + Duration::seconds(600)).is_err());
+
+        job.done = true;
+        assert!(recall_merchants(&job, job.started_at).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{farm_lists::get_farm_list, Command};
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        army::Army,
+        map::{travel_time_secs, Position, TravelSettings},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+// Sends a raid to each target of the list, as long as there are troops at home and
+// movements left. Targets that can't be raided are skipped.
+pub struct SendFarmListCommand {
+    repo: Arc<dyn Repository>,
+    list_id: Uuid,
+    max_outgoing_movements: u32,
+    travel: TravelSettings,
+}
+
+impl SendFarmListCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        list_id: Uuid,
+        max_outgoing_movements: u32,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            list_id,
+            max_outgoing_movements,
+            travel,
+        }
+    }
+
+    // Returns the village at the given position, if it can be raided.
+    async fn target_at(&self, position: &Position, village: &Village) -> Result<Option<Village>> {
+        // oases and empty valleys aren't villages, they're left in the list anyway
+        let village_id = match self
+            .repo
+            .get_valley_by_id(position.to_id(self.travel.world_size))
+            .await
+        {
+            Ok(valley) => valley.village_id,
+            Err(_) => None,
+        };
+        let target = match village_id {
+            Some(id) if id != village.id => self.repo.get_village_by_id(id).await?,
+            _ => return Ok(None),
+        };
+
+        let defender = self.repo.get_player_by_id(target.player_id).await?;
+        if defender.is_protected_at(Utc::now()) {
+            return Ok(None);
+        }
+
+        Ok(Some(target))
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SendFarmListCommand {
+    // The targets the raids were sent to.
+    type Output = Vec<Position>;
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let list = get_farm_list(self.repo.as_ref(), self.list_id).await?;
+        let village = self.repo.get_village_by_id(list.village_id).await?;
+
+        let max = village.max_outgoing_movements();
+        if max == 0 {
+            return Err(Error::msg("A Rally Point is needed to send armies."));
+        }
+        let outgoing = self
+            .repo
+            .get_pending_jobs_by_village_id(village.id)
+            .await?
+            .iter()
+            .filter(|j| !j.done && j.task.is_army_movement())
+            .count() as u32;
+        let mut movements = max
+            .min(self.max_outgoing_movements)
+            .saturating_sub(outgoing);
+        let mut available = village.sendable_troops();
+
+        let mut sent = vec![];
+        let mut events = vec![];
+        for position in &list.targets {
+            if movements == 0 {
+                break;
+            }
+            if list.units.iter().zip(available.iter()).any(|(q, a)| q > a) {
+                continue;
+            }
+            let target = match self.target_at(position, &village).await? {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let army = Army::new(
+                village.id,
+                village.player_id,
+                village.tribe.clone(),
+                list.units,
+                village.army.smithy,
+            );
+            let speed = village.army_speed(&army, Utc::now());
+            let time_secs =
+                travel_time_secs(&village.position, &target.position, speed, self.travel) as u64;
+            let job = Job::new(
+                village.player_id,
+                village.id,
+                time_secs,
+                JobTask::Raid {
+                    army: army.clone(),
+                    village_id: target.id,
+                    player_id: target.player_id,
+                },
+            );
+
+            for (a, q) in available.iter_mut().zip(list.units.iter()) {
+                *a -= q;
+            }
+            movements -= 1;
+            sent.push(position.clone());
+            events.push(GameEvent::JobEnqueued(job));
+            events.push(GameEvent::ArmyDeployed {
+                army,
+                village_id: village.id,
+            });
+        }
+
+        Ok((sent, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::SendFarmListCommand;
+    use crate::{
+        app::{commands::Command, events::GameEvent, jobs::JobTask},
+        config::Config,
+        db::test_utils::{insert_oasis, insert_valley, setup_repo},
+        game::models::{
+            buildings::{Building, BuildingName},
+            farm_list::FarmList,
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    async fn village(repo: &dyn Repository, name: &str, position: &Position) -> Village {
+        let mut player = repo
+            .register_player(name.to_string(), Tribe::Teuton)
+            .await
+            .unwrap();
+        player.protected_until = None;
+        repo.update_player(player.clone()).await.unwrap();
+        let valley = repo
+            .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let village = Village::new(name.to_string(), &valley, &player, true);
+        repo.found_village(village.clone(), None).await.unwrap();
+        village
+    }
+
+    #[tokio::test]
+    async fn test_targets_without_troops_left_are_skipped() {
+        let db = setup_repo().await;
+        let positions: Vec<Position> = (0..4).map(|x| Position { x, y: 0 }).collect();
+        for position in &positions[..3] {
+            insert_valley(&db, position).await;
+        }
+        insert_oasis(&db, &positions[3], [0; 10]).await;
+        let repo: Arc<dyn Repository> = Arc::new(db);
+
+        let mut home = village(repo.as_ref(), "alice", &positions[0]).await;
+        home.buildings.insert(
+            39,
+            Building::new(BuildingName::RallyPoint).at_level(1).unwrap(),
+        );
+        home.army.units = [30, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(home.clone()).await.unwrap();
+        let bob = village(repo.as_ref(), "bob", &positions[1]).await;
+        village(repo.as_ref(), "carol", &positions[2]).await;
+
+        let mut list = FarmList::new(
+            home.player_id,
+            home.id,
+            "Farms".to_string(),
+            [20, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        )
+        .unwrap();
+        // the oasis is not a village, then there are troops for a single raid
+        for position in [&positions[3], &positions[1], &positions[2]] {
+            list.add_target(position.clone()).unwrap();
+        }
+        repo.add_farm_list(list.clone()).await.unwrap();
+
+        let config = Config::default();
+        let (sent, events) = SendFarmListCommand::new(
+            repo.clone(),
+            list.id,
+            config.max_outgoing_movements,
+            config.travel_settings(),
+        )
+        .run()
+        .await
+        .unwrap();
+
+        assert_eq!(sent, vec![positions[1].clone()]);
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            GameEvent::JobEnqueued(job) => match &job.task {
+                JobTask::Raid {
+                    army, village_id, ..
+                } => {
+                    assert_eq!(*village_id, bob.id);
+                    assert_eq!(army.units[0], 20);
+                }
+                t => panic!("unexpected task {:?}", t),
+            },
+            e => panic!("unexpected event {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use uuid::Uuid;
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        adventure::Adventure,
+        hero::{Hero, HeroStatus},
+        map::{travel_time_secs, TravelSettings},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct SendHeroOnAdventureCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    adventure_id: Uuid,
+    travel: TravelSettings,
+}
+
+impl SendHeroOnAdventureCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        adventure_id: Uuid,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            adventure_id,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SendHeroOnAdventureCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut hero = self.repo.get_hero_by_player_id(self.player_id).await?;
+        let adventure = self.repo.get_adventure_by_id(self.adventure_id).await?;
+        let village = self.repo.get_village_by_id(hero.village_id).await?;
+
+        let job = send_hero(&mut hero, &village, adventure, self.travel)?;
+
+        // the adventure leaves the map as soon as the hero heads there
+        self.repo.remove_adventure(self.adventure_id).await?;
+        self.repo.update_hero(hero.clone()).await?;
+
+        Ok((
+            (),
+            vec![GameEvent::JobEnqueued(job), GameEvent::HeroUpdated(hero)],
+        ))
+    }
+}
+
+// Sends the hero away and returns the job resolving the adventure once the hero is
+// back home, after the round trip.
+fn send_hero(
+    hero: &mut Hero,
+    village: &Village,
+    adventure: Adventure,
+    travel: TravelSettings,
+) -> Result<Job> {
+    if adventure.player_id != hero.player_id {
+        return Err(Error::msg("This adventure belongs to another player."));
+    }
+    match hero.status {
+        HeroStatus::Home => (),
+        HeroStatus::Away => return Err(Error::msg("The hero is not at home.")),
+        HeroStatus::Dead => return Err(Error::msg("The hero is dead.")),
+    }
+
+    let one_way = travel_time_secs(&village.position, &adventure.position, hero.speed(), travel);
+    hero.status = HeroStatus::Away;
+
+    Ok(Job::new(
+        hero.player_id,
+        hero.village_id,
+        2 * one_way as u64,
+        JobTask::HeroAdventure { adventure },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::send_hero;
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            adventure::{Adventure, AdventureDifficulty, AdventureReward},
+            hero::{Hero, HeroStatus},
+            map::{travel_time_secs, Position, TravelSettings},
+            Tribe,
+        },
+    };
+
+    #[test]
+    fn test_send_hero_on_adventure() {
+        let position = Position { x: 10, y: 20 };
+        let player = test_player(Tribe::Gaul);
+        let village = test_village(&player, position.clone());
+        let adventure = Adventure::new(
+            player.id,
+            Position { x: 13, y: 24 },
+            AdventureDifficulty::Normal,
+            AdventureReward::Experience(10),
+            Utc::now(),
+        );
+        let travel = TravelSettings::default();
+
+        let mut stranger = Hero::new(Uuid::new_v4(), village.id);
+        assert!(send_hero(&mut stranger, &village, adventure.clone(), travel).is_err());
+
+        let mut hero = Hero::new(player.id, village.id);
+        let job = send_hero(&mut hero, &village, adventure.clone(), travel).unwrap();
+        let one_way =
+            travel_time_secs(&village.position, &adventure.position, hero.speed(), travel);
+        assert_eq!(job.duration, 2 * one_way as u64);
+        assert!(matches!(job.task, JobTask::HeroAdventure { .. }));
+        assert_eq!(hero.status, HeroStatus::Away);
+
+        // one adventure at a time
+        assert!(send_hero(&mut hero, &village, adventure.clone(), travel).is_err());
+
+        hero.status = HeroStatus::Dead;
+        assert!(send_hero(&mut hero, &village, adventure, travel).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        map::{travel_time_secs, TravelSettings},
+        merchant::{merchant_speed, merchants_needed},
+        village::Village,
+        ResourceGroup,
+    },
+    repository::Repository,
+};
+
+pub struct SendMerchantCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    target_village_id: u32,
+    resources: ResourceGroup,
+    travel: TravelSettings,
+}
+
+impl SendMerchantCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            target_village_id,
+            resources,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SendMerchantCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let target = self.repo.get_village_by_id(self.target_village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        // TODO: restrict targets to own and allied villages once alliances exist
+        let job = load_merchants(
+            &mut village,
+            &target,
+            &self.resources,
+            &pending,
+            self.travel,
+        )?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Takes the resources out of the village and returns the job moving merchants
+// towards the target. Merchants still on the road can't be loaded again.
+pub fn load_merchants(
+    village: &mut Village,
+    target: &Village,
+    resources: &ResourceGroup,
+    pending: &[Job],
+    travel: TravelSettings,
+) -> Result<Job> {
+    if village.id == target.id {
+        return Err(Error::msg("Merchants can't be sent to the same village"));
+    }
+    if resources.total() == 0 {
+        return Err(Error::msg("No resources to send"));
+    }
+
+    let merchants = merchants_needed(village.merchant_capacity(), resources);
+    if merchants > village.merchants().saturating_sub(merchants_away(pending)) {
+        return Err(Error::msg("Not enough merchants"));
+    }
+
+    village.withdraw_resources(resources)?;
+
+    let time_secs = travel_time_secs(
+        &village.position,
+        &target.position,
+        merchant_speed(&village.tribe),
+        travel,
+    );
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        time_secs as u64,
+        JobTask::MerchantGoing {
+            resources: resources.clone(),
+            village_id: target.id,
+            player_id: target.player_id,
+            merchants,
+        },
+    ))
+}
+
+// Returns how many merchants of the village are delivering resources or coming back.
+fn merchants_away(pending: &[Job]) -> u32 {
+    pending
+        .iter()
+        .filter(|j| !j.done)
+        .map(|j| match j.task {
+            JobTask::MerchantGoing { merchants, .. }
+            | JobTask::MerchantReturn { merchants, .. } => merchants,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_merchants;
+    use crate::{
+        app::jobs::{Job, JobTask},
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, TravelSettings},
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village(x: i32, y: i32) -> Village {
+        test_village(&test_player(Tribe::Teuton), Position { x, y })
+    }
+
+    #[test]
+    fn test_send_resources_to_ww() {
+        let mut v = village(0, 0);
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Marketplace)
+                .at_level(1)
+                .unwrap(),
+        );
+        let ww = village(10, 10);
+
+        let resources = ResourceGroup::new(300, 300, 300, 100);
+        let job = load_merchants(&mut v, &ww, &resources, &[], TravelSettings::default()).unwrap();
+
+        assert_eq!(v.resources, ResourceGroup::new(450, 450, 450, 650));
+        match job.task {
+            JobTask::MerchantGoing {
+                resources: r,
+                village_id,
+                merchants,
+                ..
+            } => {
+                assert_eq!(r, resources);
+                assert_eq!(village_id, ww.id);
+                // teuton merchants carry 1000 resources each
+                assert_eq!(merchants, 1);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_send_resources_needs_merchants_and_stocks() {
+        let mut v = village(0, 0);
+        let ww = village(10, 10);
+        let resources = ResourceGroup::new(100, 0, 0, 0);
+
+        assert!(
+            load_merchants(&mut v, &ww, &resources, &[], TravelSettings::default()).is_err(),
+            "no marketplace"
+        );
+
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Marketplace)
+                .at_level(1)
+                .unwrap(),
+        );
+        let too_much = ResourceGroup::new(800, 0, 0, 0);
+        assert!(load_merchants(&mut v, &ww, &too_much, &[], TravelSettings::default()).is_err());
+        assert_eq!(v.resources, ResourceGroup::new(750, 750, 750, 750));
+    }
+
+    #[test]
+    fn test_merchants_on_the_road_are_busy() {
+        let mut v = village(0, 0);
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Marketplace)
+                .at_level(2)
+                .unwrap(),
+        );
+        let target = village(10, 10);
+
+        let resources = ResourceGroup::new(500, 500, 500, 0);
+        let going =
+            load_merchants(&mut v, &target, &resources, &[], TravelSettings::default()).unwrap();
+        let mut pending = vec![going];
+        assert!(
+            load_merchants(
+                &mut v,
+                &target,
+                &resources,
+                &pending,
+                TravelSettings::default()
+            )
+            .is_err(),
+            "both merchants are on their way"
+        );
+
+        let back = Job::new(
+            v.player_id,
+            v.id,
+            60,
+            JobTask::MerchantReturn {
+                village_id: v.id,
+                merchants: 1,
+                resources: ResourceGroup::default(),
+            },
+        );
+        let small = ResourceGroup::new(100, 0, 0, 0);
+        pending.push(back);
+        assert!(
+            load_merchants(&mut v, &target, &small, &pending, TravelSettings::default()).is_err()
+        );
+        assert!(load_merchants(
+            &mut v,
+            &target,
+            &small,
+            &pending[1..],
+            TravelSettings::default()
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,406 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{attack::check_outgoing_movements, Command};
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::{
+        battle::CataTargets,
+        models::{
+            army::{Army, TroopSet},
+            map::{travel_time_secs, TravelSettings},
+            village::Village,
+        },
+    },
+    repository::Repository,
+};
+
+// What troops sent from the Rally Point do once they reach the target.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum MovementKind {
+    Attack(CataTargets),
+    Raid,
+    Reinforcement,
+}
+
+// Sends units of a village owned by the player to another village, as chosen in the
+// Rally Point.
+pub struct SendTroopsCommand {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    units: TroopSet,
+    kind: MovementKind,
+    max_outgoing_movements: u32,
+    travel: TravelSettings,
+}
+
+impl SendTroopsCommand {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        units: TroopSet,
+        kind: MovementKind,
+        max_outgoing_movements: u32,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            units,
+            kind,
+            max_outgoing_movements,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for SendTroopsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        if village.player_id != self.player_id {
+            return Err(Error::msg("This village doesn't belong to the player."));
+        }
+        let target = self.repo.get_village_by_id(self.target_village_id).await?;
+
+        let max = village.max_outgoing_movements();
+        if max == 0 {
+            return Err(Error::msg("A Rally Point is needed to send armies."));
+        }
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+        check_outgoing_movements(&pending, max.min(self.max_outgoing_movements))?;
+
+        // reinforcements are welcome even under beginners' protection
+        if !matches!(self.kind, MovementKind::Reinforcement) {
+            let defender = self.repo.get_player_by_id(target.player_id).await?;
+            if defender.is_protected_at(Utc::now()) {
+                return Err(Error::msg("Target player is under beginners' protection."));
+            }
+        }
+
+        let job = movement_job(&village, &target, self.units, &self.kind, self.travel)?;
+        let army = match &job.task {
+            JobTask::Attack { army, .. }
+            | JobTask::Raid { army, .. }
+            | JobTask::Reinforcement { army, .. } => army.clone(),
+            _ => unreachable!(),
+        };
+
+        Ok((
+            (),
+            vec![
+                GameEvent::JobEnqueued(job),
+                GameEvent::ArmyDeployed {
+                    army,
+                    village_id: self.village_id,
+                },
+            ],
+        ))
+    }
+}
+
+// Returns the job moving the given units towards the target, failing when they're not
+// all at home.
+pub fn movement_job(
+    village: &Village,
+    target: &Village,
+    units: TroopSet,
+    kind: &MovementKind,
+    travel: TravelSettings,
+) -> Result<Job> {
+    if village.id == target.id {
+        return Err(Error::msg("Troops are already in this village."));
+    }
+    if units.iter().all(|q| *q == 0) {
+        return Err(Error::msg("No troops selected."));
+    }
+    let sendable = village.sendable_troops();
+    if units.iter().zip(sendable.iter()).any(|(q, s)| q > s) {
+        return Err(Error::msg("Not enough troops at home to send."));
+    }
+
+    let army = Army::new(
+        village.id,
+        village.player_id,
+        village.tribe.clone(),
+        units,
+        village.army.smithy,
+    );
+    let speed = village.army_speed(&army, Utc::now());
+    let time_secs = travel_time_secs(&village.position, &target.position, speed, travel) as u64;
+
+    let task = match kind {
+        MovementKind::Attack(cata_targets) => JobTask::Attack {
+            army,
+            cata_targets: cata_targets.clone(),
+            village_id: target.id,
+            player_id: target.player_id,
+        },
+        MovementKind::Raid => JobTask::Raid {
+            army,
+            village_id: target.id,
+            player_id: target.player_id,
+        },
+        MovementKind::Reinforcement => JobTask::Reinforcement {
+            army,
+            village_id: target.id,
+            player_id: target.player_id,
+            return_after: None,
+        },
+    };
+
+    Ok(Job::new(village.player_id, village.id, time_secs, task))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::{MovementKind, SendTroopsCommand};
+    use crate::{
+        app::{commands::Command, events::GameEvent, jobs::JobTask, App},
+        config::Config,
+        db::test_utils::{insert_valley, setup_repo},
+        game::{
+            battle::CataTargets,
+            models::{
+                army::TroopSet,
+                buildings::{Building, BuildingName},
+                map::{Position, WORLD_MAX_SIZE},
+                village::Village,
+                Tribe,
+            },
+        },
+        repository::Repository,
+    };
+
+    // Returns a village with a Rally Point and 50 clubswingers, and its target.
+    async fn villages(repo: &Arc<dyn Repository>) -> (Village, Village) {
+        let mut villages = vec![];
+        for (name, x) in [("alice", 1), ("bob", 2)] {
+            let mut player = repo
+                .register_player(name.to_string(), Tribe::Teuton)
+                .await
+                .unwrap();
+            player.protected_until = None;
+            repo.update_player(player.clone()).await.unwrap();
+            let valley = repo
+                .get_valley_by_id(Position { x, y: 0 }.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let village = Village::new(name.to_string(), &valley, &player, true);
+            repo.found_village(village.clone(), None).await.unwrap();
+            villages.push(village);
+        }
+
+        let mut home = villages.remove(0);
+        home.buildings.insert(
+            39,
+            Building::new(BuildingName::RallyPoint).at_level(1).unwrap(),
+        );
+        home.army.units = [50, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(home.clone()).await.unwrap();
+
+        (home, villages.remove(0))
+    }
+
+    async fn setup() -> (Arc<dyn Repository>, Village, Village) {
+        let db = setup_repo().await;
+        for x in [1, 2] {
+            insert_valley(&db, &Position { x, y: 0 }).await;
+        }
+        let repo: Arc<dyn Repository> = Arc::new(db);
+        let (home, target) = villages(&repo).await;
+        (repo, home, target)
+    }
+
+    fn send(
+        repo: &Arc<dyn Repository>,
+        player_id: Uuid,
+        home: &Village,
+        target: &Village,
+        units: TroopSet,
+        kind: MovementKind,
+    ) -> SendTroopsCommand {
+        let config = Config::default();
+        SendTroopsCommand::new(
+            repo.clone(),
+            player_id,
+            home.id,
+            target.id,
+            units,
+            kind,
+            config.max_outgoing_movements,
+            config.travel_settings(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_each_kind_enqueues_its_movement() {
+        let (repo, home, target) = setup().await;
+        let units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let kinds = [
+            MovementKind::Attack(CataTargets::default()),
+            MovementKind::Raid,
+            MovementKind::Reinforcement,
+        ];
+
+        for kind in kinds {
+            let (_, events) = send(&repo, home.player_id, &home, &target, units, kind.clone())
+                .run()
+                .await
+                .unwrap();
+            assert_eq!(events.len(), 2);
+
+            let job = match &events[0] {
+                GameEvent::JobEnqueued(job) => job,
+                e => panic!("unexpected event {:?}", e),
+            };
+            assert!(job.duration > 0);
+            let (army, village_id) = match (&kind, &job.task) {
+                (
+                    MovementKind::Attack(_),
+                    JobTask::Attack {
+                        army, village_id, ..
+                    },
+                )
+                | (
+                    MovementKind::Raid,
+                    JobTask::Raid {
+                        army, village_id, ..
+                    },
+                )
+                | (
+                    MovementKind::Reinforcement,
+                    JobTask::Reinforcement {
+                        army, village_id, ..
+                    },
+                ) => (army, *village_id),
+                (k, t) => panic!("unexpected task {:?} for {:?}", t, k),
+            };
+            assert_eq!(army.units, units);
+            assert_eq!(village_id, target.id);
+            assert!(matches!(
+                &events[1],
+                GameEvent::ArmyDeployed { village_id, .. } if *village_id == home.id
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_troops_must_be_home_and_owned() {
+        let (repo, home, target) = setup().await;
+
+        let too_many = [51, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(send(
+            &repo,
+            home.player_id,
+            &home,
+            &target,
+            too_many,
+            MovementKind::Raid
+        )
+        .run()
+        .await
+        .is_err());
+
+        let units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(
+            send(
+                &repo,
+                target.player_id,
+                &home,
+                &target,
+                units,
+                MovementKind::Raid
+            )
+            .run()
+            .await
+            .is_err(),
+            "the village belongs to someone else"
+        );
+        assert!(send(
+            &repo,
+            home.player_id,
+            &home,
+            &home,
+            units,
+            MovementKind::Raid
+        )
+        .run()
+        .await
+        .is_err());
+        assert!(send(
+            &repo,
+            home.player_id,
+            &home,
+            &target,
+            [0; 10],
+            MovementKind::Raid
+        )
+        .run()
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sent_attack_is_fought_and_comes_back() {
+        let (repo, home, mut target) = setup().await;
+        target.army.units = [5, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(target.clone()).await.unwrap();
+        let app = App::new(repo.clone(), Config::default());
+
+        let units = [50, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        app.execute(send(
+            &repo,
+            home.player_id,
+            &home,
+            &target,
+            units,
+            MovementKind::Attack(CataTargets::default()),
+        ))
+        .await
+        .unwrap();
+        let home_after = repo.get_village_by_id(home.id).await.unwrap();
+        assert_eq!(home_after.army.units[0], 0, "the troops have left");
+
+        let attack = repo.get_pending_jobs_by_village_id(home.id).await.unwrap()[0].clone();
+        app.process_job(attack.clone()).await.unwrap();
+        assert!(repo.get_job_by_id(attack.id).await.unwrap().done);
+        let target = repo.get_village_by_id(target.id).await.unwrap();
+        assert_eq!(target.army.immensity(), 0);
+
+        let pending = repo.get_pending_jobs_by_village_id(home.id).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        let survivors = match &pending[0].task {
+            JobTask::ArmyReturn { army, .. } => army.units[0],
+            t => panic!("unexpected task {:?}", t),
+        };
+        assert!(survivors > 0);
+        app.process_job(pending[0].clone()).await.unwrap();
+
+        let home_after = repo.get_village_by_id(home.id).await.unwrap();
+        assert_eq!(home_after.army.units[0], survivors);
+    }
+}
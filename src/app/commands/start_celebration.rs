@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        buildings::BuildingName,
+        celebration::{
+            celebration_cost, celebration_time_secs, BIG_CELEBRATION_TOWN_HALL_LEVEL,
+            BREWERY_CELEBRATION_TIME_SECS,
+        },
+        scale_time,
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct StartTownHallCelebrationCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    big: bool,
+    server_speed: u8,
+}
+
+impl StartTownHallCelebrationCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, big: bool, server_speed: u8) -> Self {
+        Self {
+            repo,
+            village_id,
+            big,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for StartTownHallCelebrationCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        let job = start_town_hall_celebration(&mut village, &pending, self.big, self.server_speed)?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Pays the celebration and returns the job ending it. Big celebrations need a level 10
+// Town Hall, and a village holds one celebration at a time.
+fn start_town_hall_celebration(
+    village: &mut Village,
+    pending: &[Job],
+    big: bool,
+    server_speed: u8,
+) -> Result<Job> {
+    let level = village
+        .get_building_by_name(BuildingName::TownHall)
+        .map_or(0, |b| b.level);
+    if level == 0 {
+        return Err(Error::msg("A Town Hall is needed to hold celebrations."));
+    }
+    if big && level < BIG_CELEBRATION_TOWN_HALL_LEVEL {
+        return Err(Error::msg(
+            "Big celebrations need a level 10 Town Hall, upgrade it first.",
+        ));
+    }
+    check_no_celebration(pending)?;
+
+    village.withdraw_resources(&celebration_cost(big))?;
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        celebration_time_secs(big, level, server_speed) as u64,
+        JobTask::CelebrationTownHall { big },
+    ))
+}
+
+pub struct StartBreweryCelebrationCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    server_speed: u8,
+}
+
+impl StartBreweryCelebrationCommand {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, server_speed: u8) -> Self {
+        Self {
+            repo,
+            village_id,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for StartBreweryCelebrationCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        let job = start_brewery_celebration(&mut village, &pending, self.server_speed, Utc::now())?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Pays the celebration and returns the job ending it. Troops leaving the village hit
+// harder but march slower until then.
+fn start_brewery_celebration(
+    village: &mut Village,
+    pending: &[Job],
+    server_speed: u8,
+    now: DateTime<Utc>,
+) -> Result<Job> {
+    if village
+        .get_building_by_name(BuildingName::Brewery)
+        .is_none()
+    {
+        return Err(Error::msg("A Brewery is needed to hold this celebration."));
+    }
+    check_no_celebration(pending)?;
+
+    village.withdraw_resources(&celebration_cost(false))?;
+    let time_secs = scale_time(BREWERY_CELEBRATION_TIME_SECS, 1.0, server_speed);
+    village.brewery_celebration_until = Some(now + Duration::seconds(time_secs as i64));
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        time_secs as u64,
+        JobTask::CelebrationBrewery,
+    ))
+}
+
+// A village holds one celebration at a time, whatever the building.
+fn check_no_celebration(pending: &[Job]) -> Result<()> {
+    let running = pending.iter().any(|j| {
+        !j.done
+            && matches!(
+                j.task,
+                JobTask::CelebrationTownHall { .. } | JobTask::CelebrationBrewery
+            )
+    });
+    if running {
+        return Err(Error::msg(
+            "A celebration is already going on in this village.",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{start_brewery_celebration, start_town_hall_celebration};
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::Army,
+            buildings::{Building, BuildingName},
+            celebration::{BIG_CELEBRATION_COST, SMALL_CELEBRATION_COST},
+            map::Position,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village(town_hall_level: u8) -> Village {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::TownHall)
+                .at_level(town_hall_level)
+                .unwrap(),
+        );
+        village.resources = ResourceGroup::new(40000, 40000, 40000, 10000);
+        village
+    }
+
+    #[test]
+    fn test_small_and_big_celebrations() {
+        let mut small_hall = village(1);
+        let job = start_town_hall_celebration(&mut small_hall, &[], false, 1).unwrap();
+        assert!(matches!(
+            job.task,
+            JobTask::CelebrationTownHall { big: false }
+        ));
+        assert_eq!(job.duration, 86_400);
+        let mut left = ResourceGroup::new(40000, 40000, 40000, 10000);
+        left.sub(&SMALL_CELEBRATION_COST);
+        assert_eq!(small_hall.resources, left);
+
+        let mut small_hall = village(1);
+        assert!(start_town_hall_celebration(&mut small_hall, &[], true, 1).is_err());
+
+        let mut big_hall = village(10);
+        let job = start_town_hall_celebration(&mut big_hall, &[], true, 1).unwrap();
+        assert!(matches!(
+            job.task,
+            JobTask::CelebrationTownHall { big: true }
+        ));
+        assert!(job.duration < 216_000, "higher levels are faster");
+        let mut left = ResourceGroup::new(40000, 40000, 40000, 10000);
+        left.sub(&BIG_CELEBRATION_COST);
+        assert_eq!(big_hall.resources, left);
+    }
+
+    #[test]
+    fn test_one_celebration_at_a_time() {
+        let mut village = village(10);
+        let running = start_town_hall_celebration(&mut village, &[], false, 1).unwrap();
+        let stocks = village.resources.clone();
+
+        assert!(
+            start_town_hall_celebration(&mut village, std::slice::from_ref(&running), true, 1)
+                .is_err()
+        );
+        assert_eq!(village.resources, stocks, "nothing is paid");
+
+        let mut ended = running;
+        ended.done = true;
+        assert!(start_town_hall_celebration(&mut village, &[ended], false, 1).is_ok());
+    }
+
+    #[test]
+    fn test_brewery_celebration_window() {
+        let mut village = village(1);
+        village.tribe = Tribe::Teuton;
+        let now = Utc::now();
+        assert!(start_brewery_celebration(&mut village, &[], 1, now).is_err());
+
+        village.buildings.insert(
+            21,
+            Building::new(BuildingName::Brewery).at_level(10).unwrap(),
+        );
+        let army = Army::new(
+            village.id,
+            village.player_id,
+            Tribe::Teuton,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        assert_eq!(village.attack_bonus(now), 1.0);
+        assert_eq!(village.army_speed(&army, now), army.speed());
+
+        let job = start_brewery_celebration(&mut village, &[], 1, now).unwrap();
+        assert!(matches!(job.task, JobTask::CelebrationBrewery));
+        assert!(
+            start_town_hall_celebration(&mut village, std::slice::from_ref(&job), false, 1)
+                .is_err()
+        );
+
+        // 1% per Brewery level, troops 20% slower
+        let during = now + Duration::hours(1);
+        assert!((village.attack_bonus(during) - 1.1).abs() < 1e-9);
+        assert_eq!(village.army_speed(&army, during), army.speed() * 4 / 5);
+
+        let after = now + Duration::seconds(job.duration as i64);
+        assert_eq!(village.attack_bonus(after), 1.0);
+        assert_eq!(village.army_speed(&army, after), army.speed());
+    }
+}
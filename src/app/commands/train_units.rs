@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        army::{get_unit_by_name, training_group, UnitGroup, UnitName},
+        artifact::ArtifactEffect,
+        buildings::BuildingName,
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct TrainUnitsCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    unit: UnitName,
+    quantity: u32,
+    great_cost_factor: u32,
+    server_speed: u8,
+}
+
+impl TrainUnitsCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        slot_id: u8,
+        unit: UnitName,
+        quantity: u32,
+        great_cost_factor: u32,
+        server_speed: u8,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            unit,
+            quantity,
+            great_cost_factor,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for TrainUnitsCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+
+        let job = train_units(
+            &mut village,
+            self.slot_id,
+            &self.unit,
+            self.quantity,
+            self.great_cost_factor,
+            self.server_speed,
+        )?;
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Pays the units and returns the job training them in the building at the given slot.
+// Great buildings train at the same pace, but cost more and can't be used in the capital.
+fn train_units(
+    village: &mut Village,
+    slot_id: u8,
+    unit: &UnitName,
+    quantity: u32,
+    great_cost_factor: u32,
+    server_speed: u8,
+) -> Result<Job> {
+    if quantity == 0 {
+        return Err(Error::msg("At least one unit must be trained."));
+    }
+
+    let building = village
+        .get_building_by_slot_id(slot_id)
+        .ok_or_else(|| Error::msg("There's no building in this slot."))?;
+    // settlers and chiefs are limited by the expansion slots, they're not trained here
+    let (group, great) = match training_group(&building.name) {
+        Some((UnitGroup::Expansion, _)) | None => {
+            return Err(Error::msg("This building can't train units."))
+        }
+        Some(training) => training,
+    };
+    building
+        .validate_capital(village.is_capital)
+        .map_err(|_| Error::msg("This building can't train units in the capital."))?;
+
+    let (_, data) = get_unit_by_name(&village.tribe, unit)
+        .ok_or_else(|| Error::msg("This unit doesn't belong to the village tribe."))?;
+    if data.group != group {
+        return Err(Error::msg("This unit can't be trained in this building."));
+    }
+
+    let cost_factor = if great { great_cost_factor } else { 1 };
+    village.withdraw_resources(&data.training_cost(quantity, cost_factor))?;
+
+    let time_per_unit_secs = data.training_time_secs(
+        building.level,
+        village.artifact_multiplier(ArtifactEffect::Trainer),
+        server_speed,
+    );
+    let unit = unit.clone();
+    let task = match building.name {
+        BuildingName::Barracks => JobTask::TrainBarracks {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+        BuildingName::GreatBarracks => JobTask::TrainGreatBarracks {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+        BuildingName::Stable => JobTask::TrainStable {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+        BuildingName::GreatStable => JobTask::TrainGreatStable {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+        BuildingName::Workshop => JobTask::TrainWorkshop {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+        _ => JobTask::TrainGreatWorkshop {
+            slot_id,
+            unit,
+            quantity,
+            time_per_unit_secs,
+        },
+    };
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        time_per_unit_secs as u64 * quantity as u64,
+        task,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::train_units;
+    use crate::{
+        app::{
+            jobs::{Job, JobTask},
+            App,
+        },
+        config::Config,
+        db::test_utils::{insert_village, setup_repo},
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::UnitName,
+            artifact::{Artifact, ArtifactEffect, ArtifactSize},
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+        repository::Repository,
+    };
+
+    fn village(is_capital: bool) -> Village {
+        let mut v = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        v.is_capital = is_capital;
+        v.buildings
+            .insert(20, Building::new(BuildingName::Barracks));
+        v.buildings
+            .insert(21, Building::new(BuildingName::GreatBarracks));
+        v.resources = ResourceGroup::new(10000, 10000, 10000, 10000);
+        v
+    }
+
+    #[test]
+    fn test_great_buildings_train_at_higher_cost() {
+        // legionnaires cost 120 lumber, 100 clay, 150 iron and 30 crop
+        let mut v = village(false);
+        let normal = train_units(&mut v, 20, &UnitName::Legionnaire, 2, 3, 1).unwrap();
+        assert_eq!(v.resources, ResourceGroup::new(9760, 9800, 9700, 9940));
+
+        let mut v = village(false);
+        let great = train_units(&mut v, 21, &UnitName::Legionnaire, 2, 3, 1).unwrap();
+        assert_eq!(v.resources, ResourceGroup::new(9280, 9400, 9100, 9820));
+
+        // the factor comes from the server settings
+        let mut v = village(false);
+        train_units(&mut v, 21, &UnitName::Legionnaire, 2, 2, 1).unwrap();
+        assert_eq!(v.resources, ResourceGroup::new(9520, 9600, 9400, 9880));
+
+        // but the training time is the same
+        assert_eq!(normal.duration, great.duration);
+        assert_eq!(normal.duration, 533 * 2);
+        assert!(matches!(
+            normal.task,
+            JobTask::TrainBarracks { quantity: 2, .. }
+        ));
+        assert!(matches!(
+            great.task,
+            JobTask::TrainGreatBarracks {
+                time_per_unit_secs: 533,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_great_buildings_dont_train_in_the_capital() {
+        let mut v = village(true);
+        assert!(train_units(&mut v, 21, &UnitName::Legionnaire, 1, 3, 1).is_err());
+        assert_eq!(v.resources, ResourceGroup::new(10000, 10000, 10000, 10000));
+
+        assert!(train_units(&mut v, 20, &UnitName::Legionnaire, 1, 3, 1).is_ok());
+    }
+
+    #[test]
+    fn test_training_time_gets_shorter_with_building_level() {
+        let mut v = village(false);
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Barracks).at_level(3).unwrap(),
+        );
+
+        let job = train_units(&mut v, 20, &UnitName::Legionnaire, 1, 3, 2).unwrap();
+        // 533 * 0.9^2 at double speed
+        assert_eq!(job.duration, 215);
+    }
+
+    #[test]
+    fn test_trainer_artifacts_speed_up_training() {
+        let mut v = village(false);
+        v.artifacts.push(Artifact::Boost {
+            size: ArtifactSize::Small,
+            effect: ArtifactEffect::Trainer,
+        });
+
+        let job = train_units(&mut v, 20, &UnitName::Legionnaire, 2, 3, 1).unwrap();
+        assert_eq!(job.duration, 266 * 2);
+    }
+
+    #[test]
+    fn test_units_must_fit_the_building() {
+        let mut v = village(false);
+
+        assert!(train_units(&mut v, 20, &UnitName::EquitesLegati, 1, 3, 1).is_err());
+        assert!(train_units(&mut v, 20, &UnitName::Phalanx, 1, 3, 1).is_err());
+        assert!(train_units(&mut v, 22, &UnitName::Legionnaire, 1, 3, 1).is_err());
+        assert!(train_units(&mut v, 20, &UnitName::Legionnaire, 0, 3, 1).is_err());
+
+        v.resources = ResourceGroup::default();
+        assert!(train_units(&mut v, 20, &UnitName::Legionnaire, 1, 3, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trained_units_reach_the_garrison() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let app = App::new(repo.clone(), Config::default());
+
+        let job = Job::new(
+            village.player_id,
+            village.id,
+            60,
+            JobTask::TrainBarracks {
+                slot_id: 20,
+                unit: UnitName::Praetorian,
+                quantity: 3,
+                time_per_unit_secs: 20,
+            },
+        );
+        repo.add_job(job.clone()).await.unwrap();
+        app.process_job(job).await.unwrap();
+
+        let village = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(village.army.units, [0, 3, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let pending = repo
+            .get_pending_jobs_by_village_id(village.id)
+            .await
+            .unwrap();
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Command;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+        queries::queue_completion::queue_full,
+    },
+    game::models::{buildings::BuildingName, village::Village},
+    repository::Repository,
+};
+
+pub struct UpgradeBuildingCommand {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    target_level: Option<u8>,
+    server_speed: u8,
+}
+
+impl UpgradeBuildingCommand {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        slot_id: u8,
+        target_level: Option<u8>,
+        server_speed: u8,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            target_level,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for UpgradeBuildingCommand {
+    type Output = ();
+
+    async fn run(&self) -> Result<(Self::Output, Vec<GameEvent>)> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        let job = start_upgrade(
+            &mut village,
+            self.slot_id,
+            self.target_level,
+            self.server_speed,
+        )?;
+        if queue_full(&pending, &job.task, &village.tribe) {
+            return Err(Error::msg(
+                "The builders are already at work, wait for the current construction to finish.",
+            ));
+        }
+        self.repo.update_village(village).await?;
+
+        Ok(((), vec![GameEvent::JobEnqueued(job)]))
+    }
+}
+
+// Pays the next level of the building on the given slot and returns the job to build it.
+pub fn start_upgrade(
+    village: &mut Village,
+    slot_id: u8,
+    target_level: Option<u8>,
+    server_speed: u8,
+) -> Result<Job> {
+    let building = village
+        .get_building_by_slot_id(slot_id)
+        .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+    building.validate_upgrade()?;
+    let next = building.next_level()?;
+
+    village.withdraw_resources(&next.cost().resources)?;
+
+    let main_building_level = village
+        .get_building_by_name(BuildingName::MainBuilding)
+        .map_or(0, |b| b.level);
+
+    Ok(Job::new(
+        village.player_id,
+        village.id,
+        next.calculate_build_time_secs(main_building_level, server_speed) as u64,
+        JobTask::BuildingUpgrade {
+            slot_id,
+            building_name: building.name,
+            target_level,
+        },
+    ))
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::EventConsumer;
+use crate::{
+    app::events::{GameEvent, UnitsTrained},
+    game::models::{
+        army::{get_unit_by_name, Army, UnitName},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct ArmyConsumer {
+    repo: Arc<dyn Repository>,
+}
+
+impl ArmyConsumer {
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait::async_trait]
+impl EventConsumer for ArmyConsumer {
+    async fn process(&self, event: GameEvent) -> Result<()> {
+        match event {
+            GameEvent::ArmyDeployed { army, village_id } => {
+                let mut village = self.repo.get_village_by_id(village_id).await?;
+                withdraw_army(&mut village, &army)?;
+                self.repo.update_village(village).await?;
+            }
+            GameEvent::BarracksUnitTrained(trained)
+            | GameEvent::StableUnitTrained(trained)
+            | GameEvent::WorkshopUnitTrained(trained)
+            | GameEvent::ExpansionUnitTrained(trained)
+            | GameEvent::TrapperUnitTrained(trained)
+            | GameEvent::GreatBarracksUnitTrained(trained)
+            | GameEvent::GreatStableUnitTrained(trained)
+            | GameEvent::GreatWorkshopUnitTrained(trained) => {
+                let UnitsTrained {
+                    village_id,
+                    unit,
+                    quantity,
+                } = trained;
+                let mut village = self.repo.get_village_by_id(village_id).await?;
+                add_trained_units(&mut village, &unit, quantity)?;
+                self.repo.update_village(village).await?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+// Takes the deployed units (and the hero, if it leaves with them) out of the garrison.
+fn withdraw_army(village: &mut Village, army: &Army) -> Result<()> {
+    village.army.deploy(army.units)?;
+    if army.hero.is_some() {
+        village.army.hero = None;
+    }
+    Ok(())
+}
+
+// Adds the trained units to the village garrison.
+fn add_trained_units(village: &mut Village, unit: &UnitName, quantity: u32) -> Result<()> {
+    let (idx, _) = get_unit_by_name(&village.tribe, unit)
+        .ok_or_else(|| Error::msg("This unit doesn't belong to the village tribe"))?;
+    village.army.units[idx as usize] += quantity;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_trained_units, withdraw_army};
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::{Army, UnitName},
+            map::Position,
+            Tribe,
+        },
+    };
+
+    #[test]
+    fn test_withdraw_army_leaves_trapped_units() {
+        let player = test_player(Tribe::Roman);
+        let mut village = test_village(&player, Position { x: 10, y: 20 });
+        village.army.units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        village.army.trapped = [4, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let village_id = village.id;
+        let army = |units| Army::new(village_id, player.id, Tribe::Roman, units, [0; 10]);
+
+        assert!(
+            withdraw_army(&mut village.clone(), &army([10, 0, 0, 0, 0, 0, 0, 0, 0, 0])).is_err()
+        );
+
+        withdraw_army(&mut village, &army([6, 0, 0, 0, 0, 0, 0, 0, 0, 0])).unwrap();
+        assert_eq!(village.army.units, [4, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(village.sendable_troops(), [0; 10]);
+    }
+
+    #[test]
+    fn test_trained_units_join_the_garrison() {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        village.army.units = [5, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        add_trained_units(&mut village, &UnitName::EquitesImperatoris, 3).unwrap();
+        assert_eq!(village.army.units, [5, 0, 0, 0, 3, 0, 0, 0, 0, 0]);
+
+        assert!(add_trained_units(&mut village, &UnitName::Phalanx, 1).is_err());
+    }
+}
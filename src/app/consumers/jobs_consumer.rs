@@ -1,14 +1,120 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 
 use super::EventConsumer;
-use crate::app::events::GameEvent;
+use crate::{
+    app::{commands::upgrade_building::start_upgrade, events::GameEvent, jobs::Job},
+    game::models::village::Village,
+    repository::Repository,
+};
+
+pub struct JobConsumer {
+    repo: Arc<dyn Repository>,
+    server_speed: u8,
+}
 
-#[derive(Debug, Clone)]
-pub struct JobConsumer;
+impl JobConsumer {
+    pub fn new(repo: Arc<dyn Repository>, server_speed: u8) -> Self {
+        Self { repo, server_speed }
+    }
+}
 
+#[async_trait::async_trait]
 impl EventConsumer for JobConsumer {
-    fn process(_: GameEvent) -> Result<()> {
-        // TODO: extract job from event -> store on db
+    async fn process(&self, event: GameEvent) -> Result<()> {
+        match event {
+            GameEvent::JobEnqueued(job) => self.repo.add_job(job).await?,
+            GameEvent::JobCancelled { job_id } => self.repo.remove_job(job_id).await?,
+            GameEvent::JobCompleted { job_id } => self.repo.mark_job_done(job_id).await?,
+            GameEvent::BuildingCompleted {
+                village_id,
+                slot_id,
+                level,
+                target_level: Some(target_level),
+                ..
+            } if level < target_level => {
+                let mut village = self.repo.get_village_by_id(village_id).await?;
+                if let Some(job) =
+                    continue_upgrade(&mut village, slot_id, target_level, self.server_speed)
+                {
+                    self.repo.update_village(village).await?;
+                    self.repo.add_job(job).await?;
+                }
+            }
+            _ => (),
+        }
         Ok(())
     }
 }
+
+// Enqueues the next level of a building upgraded towards a target level. The chain
+// stops when the village can't afford the next level.
+fn continue_upgrade(
+    village: &mut Village,
+    slot_id: u8,
+    target_level: u8,
+    server_speed: u8,
+) -> Option<Job> {
+    let building = village.get_building_by_slot_id(slot_id)?;
+    if building.level >= target_level {
+        return None;
+    }
+
+    start_upgrade(village, slot_id, Some(target_level), server_speed).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::continue_upgrade;
+    use crate::{
+        app::{commands::upgrade_building::start_upgrade, jobs::JobTask},
+        db::test_utils::{test_player, test_village},
+        game::models::{map::Position, village::Village, ResourceGroup, Tribe},
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_upgrade_chain_advances_while_affordable() {
+        let mut v = village();
+
+        let job = start_upgrade(&mut v, 1, Some(3), 1).unwrap();
+        assert!(matches!(
+            job.task,
+            JobTask::BuildingUpgrade {
+                target_level: Some(3),
+                ..
+            }
+        ));
+        v.upgrade_building(1).unwrap();
+
+        let job = continue_upgrade(&mut v, 1, 3, 1).expect("level 2 is enqueued");
+        assert_eq!(job.duration, 620);
+        v.upgrade_building(1).unwrap();
+
+        assert!(continue_upgrade(&mut v, 1, 3, 1).is_some());
+        v.upgrade_building(1).unwrap();
+
+        // target level reached
+        assert!(continue_upgrade(&mut v, 1, 3, 1).is_none());
+        assert_eq!(v.resources, ResourceGroup::new(535, 205, 475, 425));
+    }
+
+    #[test]
+    fn test_upgrade_chain_halts_when_resources_run_out() {
+        let mut v = village();
+        v.resources = ResourceGroup::new(1000, 300, 1000, 1000);
+
+        start_upgrade(&mut v, 1, Some(3), 1).unwrap();
+        v.upgrade_building(1).unwrap();
+        assert!(continue_upgrade(&mut v, 1, 3, 1).is_some());
+        v.upgrade_building(1).unwrap();
+
+        // level 3 needs 280 clay, only 35 are left
+        assert!(continue_upgrade(&mut v, 1, 3, 1).is_none());
+        assert_eq!(v.resources.clay(), 35);
+    }
+}
@@ -1,48 +1,178 @@
+mod army_consumer;
 mod jobs_consumer;
+mod unhandled_consumer;
+
+use std::sync::Arc;
 
 use anyhow::Result;
 
-use self::jobs_consumer::JobConsumer;
+use self::{
+    army_consumer::ArmyConsumer, jobs_consumer::JobConsumer, unhandled_consumer::UnhandledConsumer,
+};
 use super::events::GameEvent;
+use crate::repository::Repository;
 
+#[async_trait::async_trait]
 pub trait EventConsumer {
-    fn process(event: GameEvent) -> Result<()>;
+    async fn process(&self, event: GameEvent) -> Result<()>;
 }
 
-#[derive(Debug, Clone)]
-pub struct MainConsumer;
+pub struct MainConsumer {
+    armies: ArmyConsumer,
+    jobs: JobConsumer,
+    unhandled: UnhandledConsumer,
+}
 
 impl MainConsumer {
-    pub fn process_events(events: Vec<GameEvent>) -> Result<()> {
+    pub fn new(repo: Arc<dyn Repository>, server_speed: u8) -> Self {
+        Self {
+            armies: ArmyConsumer::new(repo.clone()),
+            jobs: JobConsumer::new(repo, server_speed),
+            unhandled: UnhandledConsumer,
+        }
+    }
+
+    pub async fn process_events(&self, events: Vec<GameEvent>) -> Result<()> {
         for e in events.into_iter() {
             match e {
-                GameEvent::VillageFounded(_) => JobConsumer::process(e.clone())?,
-                GameEvent::PlayerRegistered(_) => JobConsumer::process(e.clone())?,
-                GameEvent::JobEnqueued(_) => JobConsumer::process(e.clone())?,
-                GameEvent::ArmyDeployed {
-                    army: _,
-                    village_id: _,
-                } => todo!(),
-                GameEvent::TargetAttacked => todo!(),
-                GameEvent::TargetRaided => todo!(),
-                GameEvent::TargetReinforced => todo!(),
-                GameEvent::ArmyReturned => todo!(),
-                GameEvent::MerchantArrived => todo!(),
-                GameEvent::MerchantReturned => todo!(),
-                GameEvent::BarracksUnitTrained => todo!(),
-                GameEvent::StableUnitTrained => todo!(),
-                GameEvent::WorkshopUnitTrained => todo!(),
-                GameEvent::ExpansionUnitTrained => todo!(),
-                GameEvent::TrapperUnitTrained => todo!(),
-                GameEvent::GreatBarracksUnitTrained => todo!(),
-                GameEvent::GreatStableUnitTrained => todo!(),
-                GameEvent::GreatWorkshopUnitTrained => todo!(),
-                GameEvent::ResearchAcademyCompleted => todo!(),
-                GameEvent::ResearchSmithyCompleted => todo!(),
-                GameEvent::CelebrationTownHallEnded => todo!(),
-                GameEvent::CelebrationBreweryEnded => todo!(),
+                GameEvent::VillageFounded(_) => self.jobs.process(e.clone()).await?,
+                GameEvent::PlayerRegistered(_) => self.jobs.process(e.clone()).await?,
+                GameEvent::JobEnqueued(_) => self.jobs.process(e.clone()).await?,
+                GameEvent::JobCancelled { .. } => self.jobs.process(e.clone()).await?,
+                GameEvent::JobCompleted { .. } => self.jobs.process(e.clone()).await?,
+                GameEvent::BuildingCompleted { .. } => self.jobs.process(e.clone()).await?,
+                GameEvent::HeroUpdated(_) => (),
+                GameEvent::WonderCompleted {
+                    village_id,
+                    player_id,
+                } => tracing::info!(
+                    "Wonder of the World completed in village {} by player {}",
+                    village_id,
+                    player_id
+                ),
+                GameEvent::VillageConquered {
+                    village_id,
+                    player_id,
+                    previous_player_id,
+                } => tracing::info!(
+                    "Village {} conquered by player {} from player {}",
+                    village_id,
+                    player_id,
+                    previous_player_id
+                ),
+                GameEvent::ProtectionEnded { .. } => (),
+                GameEvent::ArmyDeployed { .. } => self.armies.process(e.clone()).await?,
+                GameEvent::TargetAttacked => self.unhandled.process(e.clone()).await?,
+                GameEvent::TargetRaided => self.unhandled.process(e.clone()).await?,
+                GameEvent::TargetReinforced => self.unhandled.process(e.clone()).await?,
+                GameEvent::ArmyReturned => self.unhandled.process(e.clone()).await?,
+                GameEvent::MerchantArrived => self.unhandled.process(e.clone()).await?,
+                GameEvent::MerchantReturned => self.unhandled.process(e.clone()).await?,
+                GameEvent::BarracksUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::StableUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::WorkshopUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::ExpansionUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::TrapperUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::GreatBarracksUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::GreatStableUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::GreatWorkshopUnitTrained(_) => self.armies.process(e.clone()).await?,
+                GameEvent::ResearchAcademyCompleted => self.unhandled.process(e.clone()).await?,
+                GameEvent::ResearchSmithyCompleted => self.unhandled.process(e.clone()).await?,
+                GameEvent::CelebrationTownHallEnded => (),
+                GameEvent::CelebrationBreweryEnded => (),
             };
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::MainConsumer;
+    use crate::{
+        app::{
+            events::{GameEvent, UnitsTrained},
+            jobs::{Job, JobTask},
+        },
+        db::test_utils::{insert_village, setup_repo},
+        game::models::{army::UnitName, buildings::BuildingName, hero::Hero, map::Position, Tribe},
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_every_event_is_consumed() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+        let alice = repo.get_player_by_id(village.player_id).await.unwrap();
+
+        let job = Job::new(
+            alice.id,
+            village.id,
+            60,
+            JobTask::BuildingUpgrade {
+                slot_id: 1,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        let trained = |unit| UnitsTrained {
+            village_id: village.id,
+            unit,
+            quantity: 1,
+        };
+        let events = vec![
+            GameEvent::PlayerRegistered(alice.clone()),
+            GameEvent::VillageFounded(Box::new(village.clone())),
+            GameEvent::JobEnqueued(job.clone()),
+            GameEvent::JobCompleted { job_id: job.id },
+            GameEvent::JobCancelled { job_id: job.id },
+            GameEvent::ArmyDeployed {
+                army: village.army.clone(),
+                village_id: village.id,
+            },
+            GameEvent::HeroUpdated(Hero::new(alice.id, village.id)),
+            GameEvent::ProtectionEnded {
+                player_id: alice.id,
+            },
+            GameEvent::BuildingCompleted {
+                village_id: village.id,
+                slot_id: 1,
+                building: BuildingName::Woodcutter,
+                level: 1,
+                target_level: None,
+            },
+            GameEvent::WonderCompleted {
+                village_id: village.id,
+                player_id: alice.id,
+            },
+            GameEvent::VillageConquered {
+                village_id: village.id,
+                player_id: alice.id,
+                previous_player_id: alice.id,
+            },
+            GameEvent::TargetAttacked,
+            GameEvent::TargetRaided,
+            GameEvent::TargetReinforced,
+            GameEvent::ArmyReturned,
+            GameEvent::MerchantArrived,
+            GameEvent::MerchantReturned,
+            GameEvent::BarracksUnitTrained(trained(UnitName::Legionnaire)),
+            GameEvent::StableUnitTrained(trained(UnitName::EquitesLegati)),
+            GameEvent::WorkshopUnitTrained(trained(UnitName::BatteringRam)),
+            GameEvent::ExpansionUnitTrained(trained(UnitName::Settler)),
+            GameEvent::TrapperUnitTrained(trained(UnitName::Legionnaire)),
+            GameEvent::GreatBarracksUnitTrained(trained(UnitName::Praetorian)),
+            GameEvent::GreatStableUnitTrained(trained(UnitName::EquitesImperatoris)),
+            GameEvent::GreatWorkshopUnitTrained(trained(UnitName::FireCatapult)),
+            GameEvent::ResearchAcademyCompleted,
+            GameEvent::ResearchSmithyCompleted,
+            GameEvent::CelebrationTownHallEnded,
+            GameEvent::CelebrationBreweryEnded,
+        ];
+
+        let consumer = MainConsumer::new(Arc::new(repo), 1);
+        consumer.process_events(events).await.unwrap();
+    }
+}
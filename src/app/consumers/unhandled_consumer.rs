@@ -0,0 +1,18 @@
+use anyhow::Result;
+
+use super::EventConsumer;
+use crate::app::events::GameEvent;
+
+// Takes the events nobody reacts to yet. The combat, merchant and research events
+// carry no payload and no processor emits them so far: battles, deliveries and
+// returns are applied by their processors and finalized through `JobCompleted`.
+// They are only logged instead of crashing the worker.
+pub struct UnhandledConsumer;
+
+#[async_trait::async_trait]
+impl EventConsumer for UnhandledConsumer {
+    async fn process(&self, event: GameEvent) -> Result<()> {
+        tracing::warn!("No consumer for event {:?}, skipping it", event);
+        Ok(())
+    }
+}
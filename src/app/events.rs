@@ -1,7 +1,14 @@
 use anyhow::Result;
+use uuid::Uuid;
 
 use super::jobs::Job;
-use crate::game::models::{army::Army, village::Village, Player};
+use crate::game::models::{
+    army::{Army, UnitName},
+    buildings::BuildingName,
+    hero::Hero,
+    village::Village,
+    Player,
+};
 
 pub trait EventStore {
     fn emit(event: GameEvent) -> Result<()>;
@@ -11,25 +18,64 @@ pub trait EventStore {
 #[derive(Debug, Clone)]
 pub enum GameEvent {
     PlayerRegistered(Player),
-    VillageFounded(Village),
+    VillageFounded(Box<Village>),
     JobEnqueued(Job),
-    ArmyDeployed { army: Army, village_id: u32 },
+    JobCancelled {
+        job_id: Uuid,
+    },
+    JobCompleted {
+        job_id: Uuid,
+    },
+    ArmyDeployed {
+        army: Army,
+        village_id: u32,
+    },
+    HeroUpdated(Hero),
+    ProtectionEnded {
+        player_id: Uuid,
+    },
+    BuildingCompleted {
+        village_id: u32,
+        slot_id: u8,
+        building: BuildingName,
+        level: u8,
+        target_level: Option<u8>,
+    },
+    // A Wonder of the World reached the last level, its owner won the game.
+    WonderCompleted {
+        village_id: u32,
+        player_id: Uuid,
+    },
+    // The attacker chiefs took the village over.
+    VillageConquered {
+        village_id: u32,
+        player_id: Uuid,
+        previous_player_id: Uuid,
+    },
     TargetAttacked,
     TargetRaided,
     TargetReinforced,
     ArmyReturned,
     MerchantArrived,
     MerchantReturned,
-    BarracksUnitTrained,
-    StableUnitTrained,
-    WorkshopUnitTrained,
-    ExpansionUnitTrained,
-    TrapperUnitTrained,
-    GreatBarracksUnitTrained,
-    GreatStableUnitTrained,
-    GreatWorkshopUnitTrained,
+    BarracksUnitTrained(UnitsTrained),
+    StableUnitTrained(UnitsTrained),
+    WorkshopUnitTrained(UnitsTrained),
+    ExpansionUnitTrained(UnitsTrained),
+    TrapperUnitTrained(UnitsTrained),
+    GreatBarracksUnitTrained(UnitsTrained),
+    GreatStableUnitTrained(UnitsTrained),
+    GreatWorkshopUnitTrained(UnitsTrained),
     ResearchAcademyCompleted,
     ResearchSmithyCompleted,
     CelebrationTownHallEnded,
     CelebrationBreweryEnded,
 }
+
+// Units a training job has completed, ready to join the village garrison.
+#[derive(Debug, Clone)]
+pub struct UnitsTrained {
+    pub village_id: u32,
+    pub unit: UnitName,
+    pub quantity: u32,
+}
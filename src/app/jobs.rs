@@ -1,15 +1,53 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::game::{
     battle::CataTargets,
     models::{
+        adventure::Adventure,
         army::{Army, UnitName},
         buildings::BuildingName,
         ResourceGroup,
     },
 };
 
+// How jobs failing for transient reasons are retried.
 #[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_backoff_secs: u64,
+}
+
+impl RetryPolicy {
+    // Returns the delay before the given retry, counting from 1, or None once retries
+    // are exhausted. Each delay is 4 times the previous one.
+    pub fn backoff_secs(&self, retry: u32) -> Option<u64> {
+        if retry == 0 || retry > self.max_retries {
+            return None;
+        }
+        Some(
+            self.base_backoff_secs
+                .saturating_mul(4u64.saturating_pow(retry - 1)),
+        )
+    }
+}
+
+// Database hiccups (busy or locked database, pool exhaustion, I/O) are worth another
+// try, any other error would happen again.
+fn is_transient(err: &Error) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::PoolTimedOut) | Some(sqlx::Error::Io(_)) => true,
+        Some(sqlx::Error::Database(e)) => {
+            // SQLITE_BUSY and SQLITE_LOCKED
+            matches!(e.code().as_deref(), Some("5") | Some("6"))
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Job {
     pub id: Uuid,
     pub player_id: Uuid,
@@ -17,12 +55,17 @@ pub struct Job {
     pub task: JobTask,
     pub duration: u64,
     pub done: bool,        // ??? if true it means it has been "consumed"
-    pub cancellable: bool, // TODO: some tasks are only cancellable for some time (eg: army actions)
+    pub cancellable: bool, // army movements can be called back within a grace period
+    pub started_at: DateTime<Utc>,
+    pub attempts: u32,                           // retries after transient failures
+    pub failed: bool, // gave up after a permanent failure or too many retries
+    pub processing_since: Option<DateTime<Utc>>, // set while a worker is running it
 }
 
 impl Job {
     pub fn new(player_id: Uuid, village_id: u32, duration: u64, task: JobTask) -> Self {
         let id = Uuid::new_v4();
+        let cancellable = task.is_army_movement();
 
         Self {
             id,
@@ -31,12 +74,102 @@ impl Job {
             task,
             duration,
             done: false,
-            cancellable: false,
+            cancellable,
+            started_at: Utc::now(),
+            attempts: 0,
+            failed: false,
+            processing_since: None,
         }
     }
+
+    pub fn ends_at(&self) -> DateTime<Utc> {
+        self.started_at + Duration::seconds(self.duration as i64)
+    }
+
+    // Returns the seconds passed since the job has started.
+    pub fn elapsed_secs(&self, now: DateTime<Utc>) -> u64 {
+        (now - self.started_at).num_seconds().max(0) as u64
+    }
+
+    // Handles a failed run: transient errors put the job back in the queue after the
+    // policy backoff, the other ones (or too many retries) mark it as failed for good.
+    pub fn fail(&mut self, err: &Error, policy: &RetryPolicy, now: DateTime<Utc>) {
+        let backoff = match is_transient(err) {
+            true => policy.backoff_secs(self.attempts + 1),
+            false => None,
+        };
+
+        match backoff {
+            Some(secs) => {
+                self.attempts += 1;
+                // the job is due when it ends, so it ends again after the backoff
+                self.started_at =
+                    now + Duration::seconds(secs as i64) - Duration::seconds(self.duration as i64);
+            }
+            None => self.failed = true,
+        }
+    }
+
+    // Turns an outgoing army back to its village. Troops take as long to come back as
+    // they have travelled so far. It fails once the grace period has expired.
+    pub fn cancel(&self, grace_secs: u64, now: DateTime<Utc>) -> Result<Job> {
+        if !self.cancellable {
+            return Err(Error::msg("This job can't be cancelled"));
+        }
+
+        let elapsed = self.elapsed_secs(now);
+        if elapsed >= self.duration {
+            return Err(Error::msg("The army has already reached its target"));
+        }
+        if elapsed > grace_secs {
+            return Err(Error::msg("The time to cancel this movement has expired"));
+        }
+
+        self.return_from(elapsed)
+    }
+
+    // Turns reinforcements still on their way back to their village, at any time before
+    // they arrive. Like cancelled movements, they come back from where they got to.
+    pub fn recall(&self, now: DateTime<Utc>) -> Result<Job> {
+        if !matches!(self.task, JobTask::Reinforcement { .. }) {
+            return Err(Error::msg(
+                "Only reinforcements can be recalled on their way",
+            ));
+        }
+
+        let elapsed = self.elapsed_secs(now);
+        if self.done || elapsed >= self.duration {
+            return Err(Error::msg("The reinforcements have already arrived"));
+        }
+
+        self.return_from(elapsed)
+    }
+
+    // Returns the job bringing the army back, taking as long as it has travelled.
+    fn return_from(&self, elapsed: u64) -> Result<Job> {
+        let army = match &self.task {
+            JobTask::Attack { army, .. }
+            | JobTask::Raid { army, .. }
+            | JobTask::Scout { army, .. }
+            | JobTask::OasisAttack { army, .. }
+            | JobTask::Reinforcement { army, .. } => army.clone(),
+            _ => return Err(Error::msg("This job is not an army movement")),
+        };
+
+        Ok(Job::new(
+            self.player_id,
+            self.village_id,
+            elapsed,
+            JobTask::ArmyReturn {
+                army,
+                resources: Default::default(),
+                village_id: self.village_id,
+            },
+        ))
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum JobTask {
     Attack {
         army: Army,
@@ -49,24 +182,56 @@ pub enum JobTask {
         village_id: u32,
         player_id: Uuid,
     },
+    // Scouts sent to spy on the target village.
+    Scout {
+        army: Army,
+        village_id: u32,
+        player_id: Uuid,
+    },
+    // Troops fighting the animals of an unoccupied oasis.
+    OasisAttack {
+        army: Army,
+        oasis_id: u32,
+        is_normal: bool,
+    },
     Reinforcement {
         army: Army,
         village_id: u32,
         player_id: Uuid,
+        // when set, the army comes back home after staying this long in the target
+        #[serde(default)]
+        return_after: Option<u64>,
     },
     ArmyReturn {
         army: Army,
         resources: ResourceGroup,
         village_id: u32,
     },
+    // Sends home the reinforcements stationed in the given village.
+    ReinforcementRecall {
+        village_id: u32,
+    },
 
     MerchantGoing {
         resources: ResourceGroup,
         village_id: u32,
         player_id: Uuid,
+        // merchants busy until they're back home, left out when unset so that tasks
+        // stored before it keep the same payload
+        #[serde(default, skip_serializing_if = "is_default")]
+        merchants: u32,
     },
     MerchantReturn {
         village_id: u32,
+        #[serde(default, skip_serializing_if = "is_default")]
+        merchants: u32,
+        // resources carried back by merchants called back before arriving
+        #[serde(default, skip_serializing_if = "is_default")]
+        resources: ResourceGroup,
+    },
+    // Ships the resources of a trade route and schedules its next run.
+    TradeRoute {
+        route_id: Uuid,
     },
     // props: unit_type (Infantry, Cavalry, Siege, Expansion), quantity, building_slot_id, time_for_each_unit? (so it enqueues a new job when 1 unit is finished)
     TrainBarracks {
@@ -115,6 +280,9 @@ pub enum JobTask {
     BuildingUpgrade {
         slot_id: u8,
         building_name: BuildingName,
+        // when set, the next level is enqueued on completion until this level is reached
+        #[serde(default)]
+        target_level: Option<u8>,
     },
     BuildingDowngrade {
         slot_id: u8,
@@ -132,4 +300,409 @@ pub enum JobTask {
         big: bool,
     },
     CelebrationBrewery,
+
+    // The hero explores the adventure and comes back home with its reward.
+    HeroAdventure {
+        adventure: Adventure,
+    },
+}
+
+impl JobTask {
+    // Returns true for armies travelling towards another village.
+    pub fn is_army_movement(&self) -> bool {
+        matches!(
+            self,
+            JobTask::Attack { .. }
+                | JobTask::Raid { .. }
+                | JobTask::Scout { .. }
+                | JobTask::OasisAttack { .. }
+                | JobTask::Reinforcement { .. }
+        )
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use std::collections::HashSet;
+
+    use super::{Job, JobTask, RetryPolicy};
+    use crate::game::{
+        battle::CataTargets,
+        models::{army::Army, buildings::BuildingName, ResourceGroup, Tribe},
+    };
+
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_backoff_secs: 1,
+        }
+    }
+
+    #[test]
+    fn test_retry_backoff_schedule() {
+        let policy = retry_policy();
+
+        assert_eq!(policy.backoff_secs(1), Some(1));
+        assert_eq!(policy.backoff_secs(2), Some(4));
+        assert_eq!(policy.backoff_secs(3), Some(16));
+        assert_eq!(policy.backoff_secs(4), None);
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried_with_backoff() {
+        let mut job = attack_job(60);
+        let now = job.started_at + Duration::seconds(60);
+        let err = anyhow::Error::from(sqlx::Error::PoolTimedOut);
+
+        for (attempt, backoff) in [(1, 1), (2, 4), (3, 16)] {
+            job.fail(&err, &retry_policy(), now);
+            assert!(!job.failed);
+            assert_eq!(job.attempts, attempt);
+            assert_eq!(job.ends_at(), now + Duration::seconds(backoff));
+        }
+
+        // retries are exhausted
+        job.fail(&err, &retry_policy(), now);
+        assert!(job.failed);
+        assert_eq!(job.attempts, 3);
+    }
+
+    #[test]
+    fn test_permanent_failures_are_not_retried() {
+        let mut job = attack_job(60);
+        let now = job.started_at + Duration::seconds(60);
+
+        job.fail(
+            &anyhow::Error::msg("Village not found"),
+            &retry_policy(),
+            now,
+        );
+        assert!(job.failed);
+        assert_eq!(job.attempts, 0);
+        assert_eq!(job.ends_at(), now);
+    }
+
+    fn attack_job(duration: u64) -> Job {
+        let player_id = Uuid::new_v4();
+        let army = Army::new(
+            1,
+            player_id,
+            Tribe::Roman,
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        Job::new(
+            player_id,
+            1,
+            duration,
+            JobTask::Attack {
+                army,
+                cata_targets: CataTargets::default(),
+                village_id: 2,
+                player_id: Uuid::new_v4(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_cancel_within_grace_period() {
+        let job = attack_job(600);
+        let now = job.started_at + Duration::seconds(40);
+
+        let ret = job.cancel(90, now).unwrap();
+
+        assert_eq!(ret.duration, 40, "return takes the elapsed travel time");
+        assert_eq!(ret.village_id, job.village_id);
+        assert!(!ret.cancellable);
+        match ret.task {
+            JobTask::ArmyReturn {
+                army, village_id, ..
+            } => {
+                assert_eq!(village_id, 1);
+                assert_eq!(army.units[0], 10);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_cancel_outside_grace_period() {
+        let job = attack_job(600);
+
+        let now = job.started_at + Duration::seconds(91);
+        assert!(job.cancel(90, now).is_err());
+
+        let now = job.started_at + Duration::seconds(600);
+        assert!(job.cancel(1000, now).is_err(), "army already arrived");
+    }
+
+    fn reinforcement_job(duration: u64) -> Job {
+        let mut job = attack_job(duration);
+        if let JobTask::Attack {
+            army,
+            village_id,
+            player_id,
+            ..
+        } = job.task
+        {
+            job.task = JobTask::Reinforcement {
+                army,
+                village_id,
+                player_id,
+                return_after: None,
+            };
+        }
+        job
+    }
+
+    #[test]
+    fn test_recall_reinforcements_on_their_way() {
+        let job = reinforcement_job(1000);
+
+        // a quarter of the way, well past the grace period of cancellations
+        let ret = job.recall(job.started_at + Duration::seconds(250)).unwrap();
+        assert_eq!(ret.duration, 250);
+        match ret.task {
+            JobTask::ArmyReturn {
+                army, village_id, ..
+            } => {
+                assert_eq!(village_id, 1);
+                assert_eq!(army.units[0], 10);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+
+        let ret = job.recall(job.started_at + Duration::seconds(900)).unwrap();
+        assert_eq!(ret.duration, 900, "troops come back from where they got to");
+    }
+
+    #[test]
+    fn test_recall_only_reinforcements_not_arrived() {
+        let job = reinforcement_job(1000);
+        assert!(job
+            .recall(job.started_at + Duration::seconds(1000))
+            .is_err());
+
+        let mut done = job.clone();
+        done.done = true;
+        assert!(done.recall(done.started_at).is_err());
+
+        let attack = attack_job(1000);
+        assert!(attack.recall(attack.started_at).is_err());
+    }
+
+    #[test]
+    fn test_cancel_oasis_attack() {
+        let mut job = attack_job(600);
+        if let JobTask::Attack { army, .. } = job.task {
+            job.task = JobTask::OasisAttack {
+                army,
+                oasis_id: 2,
+                is_normal: false,
+            };
+        }
+
+        let ret = job
+            .cancel(90, job.started_at + Duration::seconds(40))
+            .unwrap();
+        assert_eq!(ret.duration, 40);
+        match ret.task {
+            JobTask::ArmyReturn {
+                army, village_id, ..
+            } => {
+                assert_eq!(village_id, 1);
+                assert_eq!(army.units[0], 10);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+
+        // like any other attack, it can't be recalled once the grace period is over
+        let err = job
+            .recall(job.started_at + Duration::seconds(300))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only reinforcements can be recalled on their way"
+        );
+    }
+
+    #[test]
+    fn test_cancel_non_movement() {
+        let job = Job::new(
+            Uuid::new_v4(),
+            1,
+            600,
+            JobTask::BuildingUpgrade {
+                slot_id: 1,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        assert!(job.cancel(90, job.started_at).is_err());
+    }
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            (
+                $name,
+                include_str!(concat!("../../tests/fixtures/job_tasks/", $name, ".json")),
+            )
+        };
+    }
+
+    // Canonical payload of every task, as it's stored in the jobs table. In-flight jobs
+    // must keep loading after upgrades: never edit these files, add a new variant with
+    // its own fixture instead.
+    const FIXTURES: &[(&str, &str)] = &[
+        fixture!("attack"),
+        fixture!("raid"),
+        fixture!("reinforcement"),
+        fixture!("army_return"),
+        fixture!("reinforcement_recall"),
+        fixture!("merchant_going"),
+        fixture!("merchant_return"),
+        fixture!("trade_route"),
+        fixture!("train_barracks"),
+        fixture!("train_great_barracks"),
+        fixture!("train_stable"),
+        fixture!("train_great_stable"),
+        fixture!("train_workshop"),
+        fixture!("train_great_workshop"),
+        fixture!("train_expansion"),
+        fixture!("building_upgrade"),
+        fixture!("building_downgrade"),
+        fixture!("research_academy"),
+        fixture!("research_smithy"),
+        fixture!("celebration_town_hall"),
+        fixture!("celebration_brewery"),
+        fixture!("hero_adventure"),
+        fixture!("scout"),
+        fixture!("oasis_attack"),
+        fixture!("merchant_going_busy"),
+        fixture!("merchant_return_called_back"),
+    ];
+
+    // Payloads stored before some fields were added, relying on serde defaults.
+    const LEGACY_FIXTURES: &[(&str, &str)] = &[
+        fixture!("legacy/reinforcement"),
+        fixture!("legacy/building_upgrade"),
+    ];
+
+    // Adding a variant breaks this match, as a reminder to add its fixture.
+    fn fixture_name(task: &JobTask) -> &'static str {
+        match task {
+            JobTask::Attack { .. } => "attack",
+            JobTask::Raid { .. } => "raid",
+            JobTask::Reinforcement { .. } => "reinforcement",
+            JobTask::ArmyReturn { .. } => "army_return",
+            JobTask::ReinforcementRecall { .. } => "reinforcement_recall",
+            JobTask::MerchantGoing { merchants: 0, .. } => "merchant_going",
+            JobTask::MerchantGoing { .. } => "merchant_going_busy",
+            JobTask::MerchantReturn { merchants: 0, .. } => "merchant_return",
+            JobTask::MerchantReturn { .. } => "merchant_return_called_back",
+            JobTask::TradeRoute { .. } => "trade_route",
+            JobTask::TrainBarracks { .. } => "train_barracks",
+            JobTask::TrainGreatBarracks { .. } => "train_great_barracks",
+            JobTask::TrainStable { .. } => "train_stable",
+            JobTask::TrainGreatStable { .. } => "train_great_stable",
+            JobTask::TrainWorkshop { .. } => "train_workshop",
+            JobTask::TrainGreatWorkshop { .. } => "train_great_workshop",
+            JobTask::TrainExpansion { .. } => "train_expansion",
+            JobTask::BuildingUpgrade { .. } => "building_upgrade",
+            JobTask::BuildingDowngrade { .. } => "building_downgrade",
+            JobTask::ResearchAcademy { .. } => "research_academy",
+            JobTask::ResearchSmithy { .. } => "research_smithy",
+            JobTask::CelebrationTownHall { .. } => "celebration_town_hall",
+            JobTask::CelebrationBrewery => "celebration_brewery",
+            JobTask::HeroAdventure { .. } => "hero_adventure",
+            JobTask::Scout { .. } => "scout",
+            JobTask::OasisAttack { .. } => "oasis_attack",
+        }
+    }
+
+    fn parse(name: &str, json: &str) -> JobTask {
+        serde_json::from_str(json)
+            .unwrap_or_else(|e| panic!("stored {} task can't be loaded: {}", name, e))
+    }
+
+    #[test]
+    fn test_stored_job_tasks_round_trip() {
+        for (name, json) in FIXTURES {
+            let task = parse(name, json);
+            assert_eq!(fixture_name(&task), *name);
+
+            let stored: serde_json::Value = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                serde_json::to_value(&task).unwrap(),
+                stored,
+                "{} task is serialized differently",
+                name
+            );
+        }
+
+        let covered: HashSet<&str> = FIXTURES.iter().map(|(name, _)| *name).collect();
+        assert_eq!(covered.len(), FIXTURES.len());
+    }
+
+    #[test]
+    fn test_stored_attack_keeps_catapult_targets() {
+        match parse("attack", FIXTURES[0].1) {
+            JobTask::Attack {
+                army, cata_targets, ..
+            } => {
+                assert_eq!(army.units[7], 5);
+                assert_eq!(
+                    cata_targets.targets(),
+                    vec![BuildingName::Granary, BuildingName::Warehouse]
+                );
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_legacy_job_tasks_load_with_defaults() {
+        match parse("legacy reinforcement", LEGACY_FIXTURES[0].1) {
+            JobTask::Reinforcement {
+                army, return_after, ..
+            } => {
+                assert_eq!(return_after, None);
+                assert!(army.hero.is_none());
+                assert_eq!(army.trapped, [0; 10]);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+        assert!(matches!(
+            parse("legacy building upgrade", LEGACY_FIXTURES[1].1),
+            JobTask::BuildingUpgrade {
+                target_level: None,
+                ..
+            }
+        ));
+
+        // merchants weren't tracked when the first merchant tasks were stored
+        assert!(matches!(
+            parse("merchant going", FIXTURES[5].1),
+            JobTask::MerchantGoing { merchants: 0, .. }
+        ));
+        match parse("merchant return", FIXTURES[6].1) {
+            JobTask::MerchantReturn {
+                merchants,
+                resources,
+                ..
+            } => {
+                assert_eq!(merchants, 0);
+                assert_eq!(resources, ResourceGroup::default());
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
 }
@@ -1,35 +1,113 @@
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
 
-use crate::repository::Repository;
+use crate::{config::Config, repository::Repository};
 
 use self::{
-    commands::{attack::AttackCommand, register_player::RegisterPlayerCommand, Cmd, Command},
+    commands::{
+        admin::{SetBuildingLevelCommand, SetVillageResourcesCommand},
+        annex_oasis::AnnexOasisCommand,
+        attack::AttackCommand,
+        attack_oasis::AttackOasisCommand,
+        cancel_building_upgrade::CancelBuildingUpgradeCommand,
+        cancel_celebration::CancelCelebrationCommand,
+        cancel_movement::CancelMovementCommand,
+        create_trade_route::CreateTradeRouteCommand,
+        delete_trade_route::DeleteTradeRouteCommand,
+        delete_village::DeleteVillageCommand,
+        demolish_building::DemolishBuildingCommand,
+        dodge_troops::DodgeTroopsCommand,
+        farm_lists::{
+            AddFarmListTargetCommand, CreateFarmListCommand, DeleteFarmListCommand,
+            RemoveFarmListTargetCommand, UpdateFarmListCommand,
+        },
+        found_village::FoundVillageAtCommand,
+        hero_attributes::ReallocateHeroPointsCommand,
+        hero_equipment::{EquipHeroItemCommand, UnequipHeroItemCommand},
+        manage_reports::{ArchiveReportsCommand, DeleteReportsCommand},
+        mark_report_read::MarkReportReadCommand,
+        npc_trade::NpcTradeCommand,
+        recall_reinforcement::RecallReinforcementCommand,
+        register_player::RegisterPlayerCommand,
+        reinforce::ReinforceCommand,
+        reinforcement_policy::SetReinforcementPolicyCommand,
+        return_merchant::ReturnMerchantCommand,
+        send_farm_list::SendFarmListCommand,
+        send_hero_on_adventure::SendHeroOnAdventureCommand,
+        send_merchant::SendMerchantCommand,
+        send_troops::SendTroopsCommand,
+        start_celebration::{StartBreweryCelebrationCommand, StartTownHallCelebrationCommand},
+        train_units::TrainUnitsCommand,
+        upgrade_building::UpgradeBuildingCommand,
+        Cmd, Command,
+    },
     consumers::MainConsumer,
+    events::GameEvent,
+    jobs::{Job, JobTask},
+    processors::{
+        army_return::ArmyReturnProcessor, attack::AttackProcessor,
+        brewery_celebration::BreweryCelebrationProcessor,
+        building_downgrade::BuildingDowngradeProcessor, building_upgrade::BuildingUpgradeProcessor,
+        hero_adventure::HeroAdventureProcessor, merchant_going::MerchantGoingProcessor,
+        merchant_return::MerchantReturnProcessor, oasis_attack::OasisAttackProcessor,
+        raid::RaidProcessor, reinforcement::ReinforcementProcessor,
+        reinforcement_recall::ReinforcementRecallProcessor, scout::ScoutProcessor,
+        town_hall_celebration::TownHallCelebrationProcessor, trade_route::TradeRouteProcessor,
+        training::TrainingProcessor, Processor,
+    },
+    queries::Query,
 };
 
 pub mod commands;
 pub mod consumers;
 pub mod events;
 pub mod jobs;
+pub mod processors;
+pub mod queries;
+pub mod worker;
 
 pub struct App {
     repo: Arc<dyn Repository>,
+    config: Config,
+    consumer: MainConsumer,
 }
 
 impl App {
-    pub fn new(repo: Arc<dyn Repository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<dyn Repository>, config: Config) -> Self {
+        let consumer = MainConsumer::new(repo.clone(), config.server_speed);
+        Self {
+            repo,
+            config,
+            consumer,
+        }
     }
 
+    pub async fn query<Q: Query>(&self, query: Q) -> Result<Q::Output> {
+        query.run().await
+    }
+
+    // Runs a command and processes its events, returning what the command produced.
+    pub async fn execute<C: Command + Send + Sync>(&self, command: C) -> Result<C::Output> {
+        // command.validate()?;
+        let (output, events) = command.run().await?;
+
+        tracing::trace!("Produced events -> {:?}", events);
+
+        self.consumer.process_events(events).await?;
+
+        Ok(output)
+    }
+
+    // Runs a command without output. Use `execute` to get the player or village a command
+    // creates.
     pub async fn command(&self, cmd: Cmd) -> Result<()> {
-        let command: Box<dyn Command> = match cmd {
-            Cmd::RegisterPlayer { username, tribe } => Box::new(RegisterPlayerCommand::new(
-                self.repo.clone(),
-                username,
-                tribe,
-            )),
+        let command: Box<dyn Command<Output = ()> + Send + Sync> = match cmd {
+            Cmd::RegisterPlayer { username, tribe } => {
+                let command = RegisterPlayerCommand::new(self.repo.clone(), username, tribe);
+                return self.execute(command).await.map(|_| ());
+            }
             Cmd::Attack {
                 village_id,
                 army,
@@ -38,36 +116,971 @@ impl App {
             } => Box::new(AttackCommand::new(
                 self.repo.clone(),
                 village_id,
-                army.clone(),
+                *army,
                 cata_targets.clone(),
                 defender_village_id,
+                self.config.max_outgoing_movements,
+                self.config.travel_settings(),
+            )),
+            Cmd::AttackOasis {
+                village_id,
+                army,
+                oasis_id,
+                is_normal,
+            } => Box::new(AttackOasisCommand::new(
+                self.repo.clone(),
+                village_id,
+                *army,
+                oasis_id,
+                is_normal,
+                self.config.max_outgoing_movements,
+                self.config.travel_settings(),
+            )),
+            Cmd::CancelMovement { player_id, job_id } => Box::new(CancelMovementCommand::new(
+                self.repo.clone(),
+                player_id,
+                job_id,
+                self.config.cancel_grace_secs,
+            )),
+            Cmd::RecallReinforcement { player_id, job_id } => Box::new(
+                RecallReinforcementCommand::new(self.repo.clone(), player_id, job_id),
+            ),
+            Cmd::CancelCelebration { job_id } => {
+                Box::new(CancelCelebrationCommand::new(self.repo.clone(), job_id))
+            }
+            Cmd::CancelBuildingUpgrade { job_id } => {
+                Box::new(CancelBuildingUpgradeCommand::new(self.repo.clone(), job_id))
+            }
+            Cmd::FoundVillageAt {
+                player_id,
+                position,
+            } => {
+                let command = FoundVillageAtCommand::new(
+                    self.repo.clone(),
+                    player_id,
+                    position,
+                    self.config.world_size,
+                );
+                return self.execute(command).await.map(|_| ());
+            }
+            Cmd::UpgradeBuilding {
+                village_id,
+                slot_id,
+                target_level,
+            } => Box::new(UpgradeBuildingCommand::new(
+                self.repo.clone(),
+                village_id,
+                slot_id,
+                target_level,
+                self.config.server_speed,
+            )),
+            Cmd::DemolishBuilding {
+                village_id,
+                slot_id,
+            } => Box::new(DemolishBuildingCommand::new(
+                self.repo.clone(),
+                village_id,
+                slot_id,
+                self.config.server_speed,
+            )),
+            Cmd::SetReinforcementPolicy { village_id, policy } => Box::new(
+                SetReinforcementPolicyCommand::new(self.repo.clone(), village_id, policy),
+            ),
+            Cmd::EquipHeroItem { player_id, item_id } => Box::new(EquipHeroItemCommand::new(
+                self.repo.clone(),
+                player_id,
+                item_id,
+            )),
+            Cmd::UnequipHeroItem { player_id, slot } => Box::new(UnequipHeroItemCommand::new(
+                self.repo.clone(),
+                player_id,
+                slot,
+            )),
+            Cmd::ReallocateHeroPoints {
+                player_id,
+                attributes,
+            } => Box::new(ReallocateHeroPointsCommand::new(
+                self.repo.clone(),
+                player_id,
+                attributes,
+            )),
+            Cmd::SendHeroOnAdventure {
+                player_id,
+                adventure_id,
+            } => Box::new(SendHeroOnAdventureCommand::new(
+                self.repo.clone(),
+                player_id,
+                adventure_id,
+                self.config.travel_settings(),
+            )),
+            Cmd::SetVillageResources {
+                admin_id,
+                village_id,
+                resources,
+            } => Box::new(SetVillageResourcesCommand::new(
+                self.repo.clone(),
+                self.config.admins.clone(),
+                admin_id,
+                village_id,
+                resources,
+            )),
+            Cmd::SetBuildingLevel {
+                admin_id,
+                village_id,
+                slot_id,
+                level,
+            } => Box::new(SetBuildingLevelCommand::new(
+                self.repo.clone(),
+                self.config.admins.clone(),
+                admin_id,
+                village_id,
+                slot_id,
+                level,
+            )),
+            Cmd::DeleteVillage { village_id } => Box::new(DeleteVillageCommand::new(
+                self.repo.clone(),
+                village_id,
+                self.config.delete_account_with_last_village,
+            )),
+            Cmd::AnnexOasis {
+                village_id,
+                oasis_id,
+            } => Box::new(AnnexOasisCommand::new(
+                self.repo.clone(),
+                village_id,
+                oasis_id,
+                self.config.world_size,
+            )),
+            Cmd::DodgeTroops {
+                village_id,
+                safe_target,
+                return_after,
+            } => Box::new(DodgeTroopsCommand::new(
+                self.repo.clone(),
+                village_id,
+                safe_target,
+                return_after,
+                self.config.travel_settings(),
             )),
             Cmd::Raid => todo!(),
-            Cmd::Reinforce => todo!(),
+            Cmd::SendTroops {
+                player_id,
+                village_id,
+                target_village_id,
+                units,
+                kind,
+            } => Box::new(SendTroopsCommand::new(
+                self.repo.clone(),
+                player_id,
+                village_id,
+                target_village_id,
+                units,
+                kind,
+                self.config.max_outgoing_movements,
+                self.config.travel_settings(),
+            )),
+            Cmd::Reinforce {
+                village_id,
+                army,
+                target_village_id,
+            } => Box::new(ReinforceCommand::new(
+                self.repo.clone(),
+                village_id,
+                *army,
+                target_village_id,
+                self.config.max_outgoing_movements,
+                self.config.travel_settings(),
+            )),
             Cmd::ReturnArmy => todo!(),
-            Cmd::SendMerchant => todo!(),
-            Cmd::ReturnMerchant => todo!(),
-            Cmd::TrainBarracksUnit => todo!(),
-            Cmd::TrainStableUnit => todo!(),
-            Cmd::TrainWorkshopUnit => todo!(),
+            Cmd::CreateFarmList {
+                village_id,
+                name,
+                units,
+            } => Box::new(CreateFarmListCommand::new(
+                self.repo.clone(),
+                village_id,
+                name,
+                units,
+            )),
+            Cmd::UpdateFarmList {
+                list_id,
+                name,
+                units,
+            } => Box::new(UpdateFarmListCommand::new(
+                self.repo.clone(),
+                list_id,
+                name,
+                units,
+            )),
+            Cmd::DeleteFarmList { list_id } => {
+                Box::new(DeleteFarmListCommand::new(self.repo.clone(), list_id))
+            }
+            Cmd::AddFarmListTarget { list_id, position } => Box::new(
+                AddFarmListTargetCommand::new(self.repo.clone(), list_id, position),
+            ),
+            Cmd::RemoveFarmListTarget { list_id, position } => Box::new(
+                RemoveFarmListTargetCommand::new(self.repo.clone(), list_id, position),
+            ),
+            Cmd::SendFarmList { list_id } => {
+                let command = SendFarmListCommand::new(
+                    self.repo.clone(),
+                    list_id,
+                    self.config.max_outgoing_movements,
+                    self.config.travel_settings(),
+                );
+                return self.execute(command).await.map(|_| ());
+            }
+            Cmd::SendMerchant {
+                village_id,
+                target_village_id,
+                resources,
+            } => Box::new(SendMerchantCommand::new(
+                self.repo.clone(),
+                village_id,
+                target_village_id,
+                resources,
+                self.config.travel_settings(),
+            )),
+            Cmd::ReturnMerchant { job_id } => {
+                Box::new(ReturnMerchantCommand::new(self.repo.clone(), job_id))
+            }
+            Cmd::CreateTradeRoute {
+                village_id,
+                target_village_id,
+                resources,
+                interval_secs,
+            } => Box::new(CreateTradeRouteCommand::new(
+                self.repo.clone(),
+                village_id,
+                target_village_id,
+                resources,
+                interval_secs,
+            )),
+            Cmd::DeleteTradeRoute { route_id } => {
+                Box::new(DeleteTradeRouteCommand::new(self.repo.clone(), route_id))
+            }
+            Cmd::MarkReportRead {
+                player_id,
+                report_id,
+            } => {
+                let command = MarkReportReadCommand::new(self.repo.clone(), player_id, report_id);
+                return self.execute(command).await.map(|_| ());
+            }
+            Cmd::ArchiveReports {
+                player_id,
+                report_ids,
+            } => Box::new(ArchiveReportsCommand::new(
+                self.repo.clone(),
+                player_id,
+                report_ids,
+            )),
+            Cmd::DeleteReports {
+                player_id,
+                report_ids,
+            } => Box::new(DeleteReportsCommand::new(
+                self.repo.clone(),
+                player_id,
+                report_ids,
+            )),
+            Cmd::NpcTrade {
+                village_id,
+                resources,
+            } => Box::new(NpcTradeCommand::new(
+                self.repo.clone(),
+                village_id,
+                resources,
+                self.config.npc_trade_enabled,
+            )),
+            Cmd::TrainUnits {
+                village_id,
+                slot_id,
+                unit,
+                quantity,
+            } => Box::new(TrainUnitsCommand::new(
+                self.repo.clone(),
+                village_id,
+                slot_id,
+                unit,
+                quantity,
+                self.config.great_training_cost_factor,
+                self.config.server_speed,
+            )),
             Cmd::TrainExpansionUnit => todo!(),
             Cmd::TrainTrapperUnit => todo!(),
-            Cmd::TrainGreatBarracksUnit => todo!(),
-            Cmd::TrainGreatStableUnit => todo!(),
-            Cmd::TrainGreatWorkshopUnit => todo!(),
             Cmd::ResearchAcademy => todo!(),
             Cmd::ResearchSmithy => todo!(),
-            Cmd::StartTownHallCelebration => todo!(),
-            Cmd::StartBreweryCelebration => todo!(),
+            Cmd::StartTownHallCelebration { village_id, big } => {
+                Box::new(StartTownHallCelebrationCommand::new(
+                    self.repo.clone(),
+                    village_id,
+                    big,
+                    self.config.server_speed,
+                ))
+            }
+            Cmd::StartBreweryCelebration { village_id } => {
+                Box::new(StartBreweryCelebrationCommand::new(
+                    self.repo.clone(),
+                    village_id,
+                    self.config.server_speed,
+                ))
+            }
         };
 
-        // command.validate()?;
-        let events = command.run().await?;
+        self.execute(command).await
+    }
+
+    // Runs a due job. When it fails, it's either scheduled again or marked as failed,
+    // according to the configured retry policy.
+    pub async fn run_job(&self, job: Job, now: DateTime<Utc>) -> Result<()> {
+        let mut job = job;
+        job.processing_since = Some(now);
+        self.repo.update_job(job.clone()).await?;
+
+        let err = match self.process_job(job.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        job.processing_since = None;
+        job.fail(&err, &self.config.retry_policy(), now);
+        match job.failed {
+            true => tracing::warn!("Job {} failed: {}", job.id, err),
+            false => tracing::warn!("Job {} will be retried ({}): {}", job.id, job.attempts, err),
+        }
+
+        self.repo.update_job(job).await
+    }
+
+    // Runs every job that is due by now, in the order they end, and returns how many ran.
+    // Jobs enqueued meanwhile are picked up by the next call.
+    pub async fn run_due_jobs(&self, now: DateTime<Utc>) -> Result<usize> {
+        let jobs = self.repo.get_due_jobs(now).await?;
+        for job in jobs.iter() {
+            self.run_job(job.clone(), now).await?;
+        }
+
+        Ok(jobs.len())
+    }
+
+    // Puts back in the queue the jobs a previous worker started but never finished, e.g.
+    // because the server restarted mid-batch. Jobs already marked as done are left alone,
+    // so their effects aren't applied twice.
+    pub async fn recover_stuck_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>> {
+        let timeout = Duration::seconds(self.config.stuck_job_timeout_secs as i64);
+        let mut recovered = vec![];
+
+        for mut job in self.repo.get_stuck_jobs(now - timeout).await? {
+            tracing::warn!("Job {} was left processing, requeueing it", job.id);
+            job.processing_since = None;
+            self.repo.update_job(job.clone()).await?;
+            recovered.push(job);
+        }
+
+        Ok(recovered)
+    }
+
+    // Runs the effects of a job whose duration has elapsed.
+    pub async fn process_job(&self, job: Job) -> Result<()> {
+        // night bonuses depend on when the troops land, not on when the job runs
+        let defense_multiplier = self.config.defense_multiplier_at(job.ends_at());
+        let processor: Box<dyn Processor> = match job.task {
+            JobTask::Attack {
+                army,
+                cata_targets,
+                village_id,
+                ..
+            } => Box::new(AttackProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                job.village_id,
+                village_id,
+                army,
+                cata_targets,
+                self.config.min_attacker_losses_percent,
+                defense_multiplier,
+                self.config.travel_settings(),
+            )),
+            JobTask::BuildingUpgrade {
+                slot_id,
+                building_name,
+                target_level,
+            } => Box::new(BuildingUpgradeProcessor::new(
+                self.repo.clone(),
+                job.village_id,
+                slot_id,
+                building_name,
+                target_level,
+            )),
+            JobTask::BuildingDowngrade {
+                slot_id,
+                building_name,
+            } => Box::new(BuildingDowngradeProcessor::new(
+                self.repo.clone(),
+                job.village_id,
+                slot_id,
+                building_name,
+            )),
+            JobTask::MerchantGoing {
+                resources,
+                village_id,
+                merchants,
+                ..
+            } => Box::new(MerchantGoingProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                job.village_id,
+                village_id,
+                resources,
+                merchants,
+                job.duration,
+            )),
+            JobTask::MerchantReturn {
+                village_id,
+                resources,
+                ..
+            } => Box::new(MerchantReturnProcessor::new(
+                self.repo.clone(),
+                village_id,
+                resources,
+            )),
+            JobTask::TradeRoute { route_id } => Box::new(TradeRouteProcessor::new(
+                self.repo.clone(),
+                route_id,
+                self.config.travel_settings(),
+            )),
+            JobTask::Raid {
+                army, village_id, ..
+            } => Box::new(RaidProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                job.village_id,
+                village_id,
+                army,
+                self.config.min_attacker_losses_percent,
+                defense_multiplier,
+                self.config.travel_settings(),
+            )),
+            JobTask::Scout {
+                army, village_id, ..
+            } => Box::new(ScoutProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                job.village_id,
+                village_id,
+                army,
+                defense_multiplier,
+                self.config.travel_settings(),
+            )),
+            JobTask::OasisAttack {
+                army,
+                oasis_id,
+                is_normal,
+            } => Box::new(OasisAttackProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                job.village_id,
+                oasis_id,
+                army,
+                is_normal,
+                self.config.travel_settings(),
+            )),
+            JobTask::Reinforcement {
+                army,
+                village_id,
+                player_id,
+                return_after,
+            } => Box::new(ReinforcementProcessor::new(
+                self.repo.clone(),
+                job.village_id,
+                village_id,
+                player_id,
+                army,
+                job.duration,
+                return_after,
+            )),
+            JobTask::ReinforcementRecall { village_id } => {
+                Box::new(ReinforcementRecallProcessor::new(
+                    self.repo.clone(),
+                    job.player_id,
+                    job.village_id,
+                    village_id,
+                    self.config.travel_settings(),
+                ))
+            }
+            JobTask::ArmyReturn {
+                army,
+                resources,
+                village_id,
+            } => Box::new(ArmyReturnProcessor::new(
+                self.repo.clone(),
+                village_id,
+                army,
+                resources,
+            )),
+            JobTask::CelebrationTownHall { big } => Box::new(TownHallCelebrationProcessor::new(
+                self.repo.clone(),
+                job.village_id,
+                big,
+            )),
+            JobTask::CelebrationBrewery => Box::new(BreweryCelebrationProcessor::new(
+                self.repo.clone(),
+                job.village_id,
+            )),
+            JobTask::HeroAdventure { adventure } => Box::new(HeroAdventureProcessor::new(
+                self.repo.clone(),
+                job.player_id,
+                adventure,
+            )),
+            JobTask::TrainBarracks { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::BarracksUnitTrained,
+            )),
+            JobTask::TrainGreatBarracks { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::GreatBarracksUnitTrained,
+            )),
+            JobTask::TrainStable { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::StableUnitTrained,
+            )),
+            JobTask::TrainGreatStable { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::GreatStableUnitTrained,
+            )),
+            JobTask::TrainWorkshop { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::WorkshopUnitTrained,
+            )),
+            JobTask::TrainGreatWorkshop { unit, quantity, .. } => Box::new(TrainingProcessor::new(
+                job.village_id,
+                unit,
+                quantity,
+                GameEvent::GreatWorkshopUnitTrained,
+            )),
+            JobTask::TrainExpansion { .. } => {
+                return Err(Error::msg("Expansion units can't be trained yet."))
+            }
+            JobTask::ResearchAcademy { .. } => {
+                return Err(Error::msg("Academy researches aren't available yet."))
+            }
+            JobTask::ResearchSmithy { .. } => {
+                return Err(Error::msg("Smithy upgrades aren't available yet."))
+            }
+        };
 
-        println!("Produced events -> {:?}", events.clone());
+        let mut events = processor.process().await?;
+        events.push(GameEvent::JobCompleted { job_id: job.id });
 
-        MainConsumer::process_events(events)?;
+        tracing::trace!("Produced events -> {:?}", events);
+
+        self.consumer.process_events(events).await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use super::App;
+    use crate::{
+        app::{
+            commands::register_player::RegisterPlayerCommand,
+            jobs::{Job, JobTask},
+        },
+        config::{Config, NightDefense},
+        db::test_utils::{insert_valley, insert_village, setup_repo},
+        game::models::{
+            army::Army,
+            buildings::BuildingName,
+            map::{Position, WORLD_MAX_SIZE},
+            report::{ReportAudience, ReportContent, ReportKind},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_stuck_job_is_recovered_after_restart() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        // a worker crashed while running these jobs
+        let now = Utc::now();
+        let upgrade = |since| {
+            let mut job = Job::new(
+                village.player_id,
+                village.id,
+                60,
+                JobTask::BuildingUpgrade {
+                    slot_id: 1,
+                    building_name: BuildingName::Woodcutter,
+                    target_level: None,
+                },
+            );
+            job.processing_since = Some(since);
+            job
+        };
+        let stuck = upgrade(now - Duration::minutes(10));
+        let running = upgrade(now - Duration::seconds(10));
+        repo.add_job(stuck.clone()).await.unwrap();
+        repo.add_job(running.clone()).await.unwrap();
+
+        let app = App::new(repo.clone(), Config::default());
+        let recovered = app.recover_stuck_jobs(now).await.unwrap();
+        assert_eq!(
+            recovered.len(),
+            1,
+            "jobs within the timeout are still running"
+        );
+        assert_eq!(recovered[0].id, stuck.id);
+        assert_eq!(recovered[0].processing_since, None);
+
+        app.run_job(recovered[0].clone(), now).await.unwrap();
+        assert!(repo.get_job_by_id(stuck.id).await.unwrap().done);
+        let village = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(village.get_building_by_slot_id(1).unwrap().level, 1);
+
+        // completed jobs aren't recovered again
+        let later = now + Duration::hours(1);
+        let recovered = app.recover_stuck_jobs(later).await.unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].id, running.id);
+    }
+
+    #[tokio::test]
+    async fn test_due_jobs_run_in_order() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let upgrade = |slot_id, duration| {
+            Job::new(
+                village.player_id,
+                village.id,
+                duration,
+                JobTask::BuildingUpgrade {
+                    slot_id,
+                    building_name: BuildingName::Woodcutter,
+                    target_level: None,
+                },
+            )
+        };
+        let (first, second, later) = (upgrade(1, 10), upgrade(3, 20), upgrade(14, 600));
+        for job in [&second, &first, &later] {
+            repo.add_job(job.clone()).await.unwrap();
+        }
+
+        let app = App::new(repo.clone(), Config::default());
+        let now = first.started_at + Duration::seconds(30);
+        let due = repo.get_due_jobs(now).await.unwrap();
+        let ids: Vec<_> = due.iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+
+        assert_eq!(app.run_due_jobs(now).await.unwrap(), 2);
+        assert!(repo.get_job_by_id(first.id).await.unwrap().done);
+        assert!(repo.get_job_by_id(second.id).await.unwrap().done);
+        assert!(!repo.get_job_by_id(later.id).await.unwrap().done);
+        let village = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(village.get_building_by_slot_id(1).unwrap().level, 1);
+        assert_eq!(village.get_building_by_slot_id(3).unwrap().level, 1);
+
+        // nothing is left to run until the last one ends
+        assert_eq!(app.run_due_jobs(now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_raid_writes_battle_report() {
+        let repo = setup_repo().await;
+        let mut villages = vec![];
+        for (name, tribe, position) in [
+            ("alice", Tribe::Roman, Position { x: 3, y: 4 }),
+            ("bob", Tribe::Gaul, Position { x: 5, y: 4 }),
+        ] {
+            villages.push(insert_village(&repo, name, tribe, &position).await);
+        }
+        let (home, mut target) = (villages[0].clone(), villages[1].clone());
+        target.army.units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(target.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let army = Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let job = Job::new(
+            home.player_id,
+            home.id,
+            0,
+            JobTask::Raid {
+                army,
+                village_id: target.id,
+                player_id: target.player_id,
+            },
+        );
+        let app = App::new(repo.clone(), Config::default());
+        app.process_job(job).await.unwrap();
+
+        // the same report shows up for both sides
+        for player_id in [home.player_id, target.player_id] {
+            let reports = repo.get_reports_by_player_id(player_id).await.unwrap();
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].audience, ReportAudience::Everyone);
+        }
+
+        let report = repo.get_reports_by_player_id(home.player_id).await.unwrap()[0].clone();
+        assert_eq!(report.attacker_village_id, home.id);
+        assert_eq!(report.defender_village_id, target.id);
+        let casualties = match report.content {
+            ReportContent::Battle(casualties) => casualties,
+            other => panic!("unexpected report content: {:?}", other),
+        };
+        assert_eq!(casualties.attacker_sent[0], 100);
+        assert!(casualties.attacker_losses.units[0] > 0);
+        assert!(casualties.attacker_losses.units[0] < 100);
+        assert_eq!(casualties.defenders_before[0].units[0], 10);
+        let target = repo.get_village_by_id(target.id).await.unwrap();
+        assert!(casualties.defender_losses[0].units[0] > 0);
+        assert_eq!(
+            casualties.defender_losses[0].units[0],
+            10 - target.army.units[0]
+        );
+        assert_eq!(casualties.wall_levels, None);
+    }
+
+    #[tokio::test]
+    async fn test_attack_kills_defenders_and_sends_survivors_home() {
+        let repo = setup_repo().await;
+        let mut villages = vec![];
+        for (name, tribe, position) in [
+            ("alice", Tribe::Roman, Position { x: 3, y: 4 }),
+            ("bob", Tribe::Gaul, Position { x: 5, y: 4 }),
+        ] {
+            villages.push(insert_village(&repo, name, tribe, &position).await);
+        }
+        let (home, mut target) = (villages[0].clone(), villages[1].clone());
+        target.army.units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(target.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let army = Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let job = Job::new(
+            home.player_id,
+            home.id,
+            0,
+            JobTask::Attack {
+                army,
+                cata_targets: Default::default(),
+                village_id: target.id,
+                player_id: target.player_id,
+            },
+        );
+        let app = App::new(repo.clone(), Config::default());
+        app.process_job(job).await.unwrap();
+
+        // in a normal attack the loser loses everything
+        let target = repo.get_village_by_id(target.id).await.unwrap();
+        assert_eq!(target.army.immensity(), 0);
+
+        let report = repo
+            .get_reports_by_player_id(target.player_id)
+            .await
+            .unwrap()[0]
+            .clone();
+        assert_eq!(report.kind, ReportKind::Battle);
+        assert_eq!(report.attacker_village_id, home.id);
+
+        let pending = repo.get_pending_jobs_by_village_id(home.id).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        match &pending[0].task {
+            JobTask::ArmyReturn {
+                army, village_id, ..
+            } => {
+                assert_eq!(*village_id, home.id);
+                assert!(army.units[0] > 0 && army.units[0] < 100);
+            }
+            other => panic!("unexpected job: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attack_conquers_village_when_loyalty_drops_to_zero() {
+        let repo = setup_repo().await;
+        let mut villages = vec![];
+        for (name, tribe, position, is_capital) in [
+            ("alice", Tribe::Roman, Position { x: 3, y: 4 }, true),
+            ("bob", Tribe::Gaul, Position { x: 5, y: 4 }, false),
+        ] {
+            insert_valley(&repo, &position).await;
+            let player = repo.register_player(name.to_string(), tribe).await.unwrap();
+            let valley = repo
+                .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let village = Village::new(name.to_string(), &valley, &player, is_capital);
+            repo.found_village(village.clone(), None).await.unwrap();
+            villages.push(village);
+        }
+        let (home, mut target) = (villages[0].clone(), villages[1].clone());
+        // a few waves already went through
+        target.loyalty = 10;
+        repo.update_village(target.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let army = Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 1, 0],
+            [0; 10],
+        );
+        let job = Job::new(
+            home.player_id,
+            home.id,
+            0,
+            JobTask::Attack {
+                army,
+                cata_targets: Default::default(),
+                village_id: target.id,
+                player_id: target.player_id,
+            },
+        );
+        let app = App::new(repo.clone(), Config::default());
+        app.process_job(job).await.unwrap();
+
+        let conquered = repo.get_village_by_id(target.id).await.unwrap();
+        assert_eq!(conquered.player_id, home.player_id);
+        assert_eq!(conquered.parent_village_id, Some(home.id));
+        assert_eq!(conquered.loyalty, 0);
+        let valley = repo.get_valley_by_id(target.id).await.unwrap();
+        assert_eq!(valley.player_id, Some(home.player_id));
+        assert_eq!(valley.village_id, Some(target.id));
+
+        let alice_villages = repo
+            .get_villages_by_player_id(home.player_id)
+            .await
+            .unwrap();
+        assert_eq!(alice_villages.len(), 2);
+        assert!(repo
+            .get_villages_by_player_id(target.player_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // the senator stays in the conquered village, the legionnaires go home
+        match &repo.get_pending_jobs_by_village_id(home.id).await.unwrap()[0].task {
+            JobTask::ArmyReturn { army, .. } => assert_eq!(army.units[8], 0),
+            other => panic!("unexpected job: {:?}", other),
+        }
+    }
+
+    // Sends 100 legionnaires against 60 of them, landing at the given time, and returns
+    // the defending village after the battle.
+    async fn attack_landing_at(config: Config, at: DateTime<Utc>) -> Village {
+        let repo = setup_repo().await;
+        let mut villages = vec![];
+        for (name, position) in [
+            ("alice", Position { x: 3, y: 4 }),
+            ("bob", Position { x: 5, y: 4 }),
+        ] {
+            villages.push(insert_village(&repo, name, Tribe::Roman, &position).await);
+        }
+        let (home, mut target) = (villages[0].clone(), villages[1].clone());
+        target.army.units = [60, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        repo.update_village(target.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let army = Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let mut job = Job::new(
+            home.player_id,
+            home.id,
+            3600,
+            JobTask::Attack {
+                army,
+                cata_targets: Default::default(),
+                village_id: target.id,
+                player_id: target.player_id,
+            },
+        );
+        job.started_at = at - Duration::hours(1);
+        App::new(repo.clone(), config)
+            .process_job(job)
+            .await
+            .unwrap();
+
+        repo.get_village_by_id(target.id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_night_defense_applies_when_the_attack_lands() {
+        let config = Config {
+            night_defense: Some(NightDefense::parse("22-6:2").unwrap()),
+            ..Default::default()
+        };
+        let day = Utc.with_ymd_and_hms(2023, 3, 10, 12, 0, 0).unwrap();
+        let night = Utc.with_ymd_and_hms(2023, 3, 10, 23, 0, 0).unwrap();
+
+        // 4000 attack points beat 2110 defense points
+        let target = attack_landing_at(config.clone(), day).await;
+        assert_eq!(target.army.units[0], 0);
+
+        // doubled at night, they're 4220
+        let target = attack_landing_at(config, night).await;
+        assert!(target.army.units[0] > 0);
+    }
+
+    #[tokio::test]
+    async fn test_register_player_returns_the_stored_player() {
+        let repo = setup_repo().await;
+        let position = Position { x: 3, y: 4 };
+        insert_valley(&repo, &position).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let app = App::new(repo.clone(), Config::default());
+
+        let player = app
+            .execute(RegisterPlayerCommand::new(
+                repo.clone(),
+                "pavonz".to_string(),
+                Tribe::Gaul,
+            ))
+            .await
+            .unwrap();
+
+        let stored = repo
+            .get_player_by_username("pavonz".to_string())
+            .await
+            .unwrap();
+        assert_eq!(player.id, stored.id);
+        assert_eq!(player.tribe, Tribe::Gaul);
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Processor;
+use crate::{
+    app::events::GameEvent,
+    game::models::{army::Army, village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+pub struct ArmyReturnProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    army: Army,
+    resources: ResourceGroup,
+}
+
+impl ArmyReturnProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        army: Army,
+        resources: ResourceGroup,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            army,
+            resources,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ArmyReturnProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        return_army(&mut village, self.army.clone(), &self.resources);
+        self.repo.update_village(village).await?;
+
+        Ok(vec![])
+    }
+}
+
+// Puts the army back into the village garrison, along with whatever it carries.
+fn return_army(village: &mut Village, army: Army, resources: &ResourceGroup) {
+    village.army.merge(army);
+    village.deposit_resources(resources);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::return_army;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{army::Army, map::Position, ResourceGroup, Tribe},
+    };
+
+    #[test]
+    fn test_return_army_restores_garrison() {
+        let player = test_player(Tribe::Roman);
+        let mut village = test_village(&player, Position { x: 10, y: 20 });
+        village.army.units = [5, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        village.resources = ResourceGroup::new(0, 0, 0, 0);
+        let army = Army::new(
+            village.id,
+            player.id,
+            Tribe::Roman,
+            [10, 3, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        return_army(&mut village, army, &ResourceGroup::new(100, 0, 0, 0));
+
+        assert_eq!(village.army.units, [15, 3, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(village.resources.lumber(), 100);
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::{raid::loot, Processor};
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::{
+        battle::{Battle, CataTargets},
+        models::{
+            army::Army,
+            map::{travel_time_secs, TravelSettings},
+            report::{BattleCasualties, Report, ReportAudience, ReportContent},
+            village::Village,
+            ResourceGroup,
+        },
+    },
+    repository::Repository,
+};
+
+pub struct AttackProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    army: Army,
+    cata_targets: CataTargets,
+    min_attacker_losses_percent: f64,
+    defense_multiplier: f64,
+    travel: TravelSettings,
+}
+
+impl AttackProcessor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        army: Army,
+        cata_targets: CataTargets,
+        min_attacker_losses_percent: f64,
+        defense_multiplier: f64,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            army,
+            cata_targets,
+            min_attacker_losses_percent,
+            defense_multiplier,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for AttackProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let home = self.repo.get_village_by_id(self.village_id).await?;
+        let mut target = self.repo.get_village_by_id(self.target_village_id).await?;
+        let target_before = target.clone();
+
+        let (survivors, loot, conquered) = attack(
+            &home,
+            &mut target,
+            self.army.clone(),
+            self.cata_targets.clone(),
+            self.min_attacker_losses_percent,
+            self.defense_multiplier,
+        );
+        let mut events = vec![];
+        match conquered {
+            true => {
+                self.repo.conquer_village(target.clone()).await?;
+                events.push(GameEvent::VillageConquered {
+                    village_id: target.id,
+                    player_id: target.player_id,
+                    previous_player_id: target_before.player_id,
+                });
+            }
+            false => self.repo.update_village(target.clone()).await?,
+        }
+
+        let report = Report::new(
+            self.player_id,
+            self.village_id,
+            target_before.player_id,
+            target.id,
+            ReportAudience::Everyone,
+            ReportContent::Battle(Box::new(BattleCasualties::new(
+                &self.army,
+                &survivors,
+                &target_before,
+                &target,
+                loot.clone(),
+            ))),
+        );
+        self.repo.add_report(report).await?;
+
+        // nobody left to bring the loot home
+        if survivors.immensity() == 0 && survivors.hero.is_none() {
+            return Ok(events);
+        }
+
+        let time_secs = travel_time_secs(
+            &home.position,
+            &target.position,
+            survivors.speed(),
+            self.travel,
+        );
+        let job = Job::new(
+            self.player_id,
+            self.village_id,
+            time_secs as u64,
+            JobTask::ArmyReturn {
+                army: survivors,
+                resources: loot,
+                village_id: self.village_id,
+            },
+        );
+
+        events.push(GameEvent::JobEnqueued(job));
+
+        Ok(events)
+    }
+}
+
+// Fights a normal attack against the target: the loser loses everything, rams and
+// catapults damage the buildings. The survivors of a won attack loot what they can
+// carry, chiefs lower the loyalty of the village until it's conquered. Returns the
+// survivors, their loot and whether the village has been conquered.
+fn attack(
+    home: &Village,
+    target: &mut Village,
+    army: Army,
+    cata_targets: CataTargets,
+    min_attacker_losses_percent: f64,
+    defense_multiplier: f64,
+) -> (Army, ResourceGroup, bool) {
+    let mut battle = Battle::new(
+        army,
+        home.clone(),
+        target.clone(),
+        true,
+        false,
+        cata_targets,
+    );
+    battle.min_attacker_losses_percent = min_attacker_losses_percent;
+    battle.defense_multiplier = defense_multiplier;
+    battle.combat();
+
+    *target = battle.defender_village;
+    let survivors = battle.attacker_army;
+    let lootable = target.lootable_resources(&survivors.tribe);
+    let loot = loot(&lootable, survivors.carry_capacity());
+    target.resources.sub(&loot);
+
+    (survivors, loot, battle.conquered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::attack;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::{
+            battle::CataTargets,
+            models::{army::Army, map::Position, village::Village, ResourceGroup, Tribe},
+        },
+    };
+
+    fn village(x: i32, y: i32) -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x, y })
+    }
+
+    fn legionnaires(home: &Village, quantity: u32) -> Army {
+        Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [quantity, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        )
+    }
+
+    #[test]
+    fn test_won_attack_kills_every_defender() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+        target.army.units = [50, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, bounty, _) = attack(
+            &home,
+            &mut target,
+            legionnaires(&home, 200),
+            CataTargets::default(),
+            0.0,
+            1.0,
+        );
+
+        assert!(survivors.units[0] > 0 && survivors.units[0] < 200);
+        assert_eq!(target.army.units[0], 0);
+        // the survivors carry more than the village has
+        assert_eq!(bounty, ResourceGroup::new(750, 750, 750, 750));
+        assert_eq!(target.resources, ResourceGroup::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_lost_attack_brings_nothing_home() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+        target.army.units = [200, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, bounty, _) = attack(
+            &home,
+            &mut target,
+            legionnaires(&home, 10),
+            CataTargets::default(),
+            0.0,
+            1.0,
+        );
+
+        assert_eq!(survivors.immensity(), 0);
+        assert_eq!(bounty, ResourceGroup::default());
+        assert!(target.army.units[0] > 0 && target.army.units[0] < 200);
+    }
+}
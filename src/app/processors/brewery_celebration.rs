@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Processor;
+use crate::{app::events::GameEvent, repository::Repository};
+
+pub struct BreweryCelebrationProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl BreweryCelebrationProcessor {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for BreweryCelebrationProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        // the bonus already expires on its own, this only clears it up
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        village.brewery_celebration_until = None;
+        self.repo.update_village(village).await?;
+
+        Ok(vec![GameEvent::CelebrationBreweryEnded])
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Processor;
+use crate::{
+    app::events::GameEvent,
+    game::models::{buildings::BuildingName, village::Village},
+    repository::Repository,
+};
+
+pub struct BuildingDowngradeProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    building_name: BuildingName,
+}
+
+impl BuildingDowngradeProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        slot_id: u8,
+        building_name: BuildingName,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            building_name,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for BuildingDowngradeProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let event = complete_demolition(&mut village, self.slot_id, &self.building_name)?;
+        self.repo.update_village(village).await?;
+
+        Ok(vec![event])
+    }
+}
+
+// Tears down one level of the building and returns the event with its new level.
+fn complete_demolition(
+    village: &mut Village,
+    slot_id: u8,
+    building_name: &BuildingName,
+) -> Result<GameEvent> {
+    match village.get_building_by_slot_id(slot_id) {
+        Some(b) if &b.name == building_name => (),
+        _ => {
+            return Err(Error::msg(
+                "the building on this slot has changed since the demolition was enqueued",
+            ))
+        }
+    }
+
+    let level = village.demolish_building(slot_id)?;
+
+    Ok(GameEvent::BuildingCompleted {
+        village_id: village.id,
+        slot_id,
+        building: building_name.clone(),
+        level,
+        target_level: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::complete_demolition;
+    use crate::{
+        app::events::GameEvent,
+        db::test_utils::{test_player, test_village},
+        game::models::{buildings::BuildingName, map::Position, Tribe},
+    };
+
+    #[test]
+    fn test_demolition_emits_new_level() {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        village.set_building_level(19, 10).unwrap();
+
+        let event = complete_demolition(&mut village, 19, &BuildingName::MainBuilding).unwrap();
+        assert!(matches!(
+            event,
+            GameEvent::BuildingCompleted {
+                slot_id: 19,
+                level: 9,
+                ..
+            }
+        ));
+        assert!(complete_demolition(&mut village, 1, &BuildingName::Cropland).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+        queries::queue_completion::lane,
+    },
+    game::models::{
+        buildings::{BuildingName, WONDER_VICTORY_LEVEL},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct BuildingUpgradeProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    building_name: BuildingName,
+    target_level: Option<u8>,
+}
+
+impl BuildingUpgradeProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        slot_id: u8,
+        building_name: BuildingName,
+        target_level: Option<u8>,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            building_name,
+            target_level,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for BuildingUpgradeProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut village = self.repo.get_village_by_id(self.village_id).await?;
+        let events = complete_upgrade(
+            &mut village,
+            self.slot_id,
+            &self.building_name,
+            self.target_level,
+        )?;
+
+        let completed = JobTask::BuildingUpgrade {
+            slot_id: self.slot_id,
+            building_name: self.building_name.clone(),
+            target_level: self.target_level,
+        };
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+        let promoted = promote_queued(&completed, &village, &pending, Utc::now());
+        self.repo.update_village(village).await?;
+        for job in promoted {
+            self.repo.update_job(job).await?;
+        }
+
+        Ok(events)
+    }
+}
+
+// Moves forward the jobs waiting on the lane of the completed upgrade, so that the first
+// one starts as soon as the builders are free and the others keep following it. They're
+// returned to be saved.
+fn promote_queued(
+    completed: &JobTask,
+    village: &Village,
+    pending: &[Job],
+    now: DateTime<Utc>,
+) -> Vec<Job> {
+    let completed_lane = match lane(completed, &village.tribe) {
+        Some(lane) => lane,
+        None => return vec![],
+    };
+    let mut queued: Vec<Job> = pending
+        .iter()
+        .filter(|j| !j.done && j.started_at > now)
+        .filter(|j| lane(&j.task, &village.tribe) == Some(completed_lane))
+        .cloned()
+        .collect();
+
+    let early = match queued.iter().map(|j| j.started_at).min() {
+        Some(first) => first - now,
+        None => return vec![],
+    };
+    for job in queued.iter_mut() {
+        job.started_at -= early;
+    }
+
+    queued
+}
+
+// Applies the upgrade to the village and returns the events describing the completed
+// building, along with the victory when it's the last level of a Wonder of the World.
+fn complete_upgrade(
+    village: &mut Village,
+    slot_id: u8,
+    building_name: &BuildingName,
+    target_level: Option<u8>,
+) -> Result<Vec<GameEvent>> {
+    if let Some(b) = village.get_building_by_slot_id(slot_id) {
+        if &b.name != building_name {
+            return Err(anyhow::Error::msg(
+                "the building on this slot has changed since the upgrade was enqueued",
+            ));
+        }
+    }
+
+    let building = village.upgrade_building(slot_id)?;
+
+    let victory =
+        building.name == BuildingName::WonderOfTheWorld && building.level >= WONDER_VICTORY_LEVEL;
+
+    let mut events = vec![GameEvent::BuildingCompleted {
+        village_id: village.id,
+        slot_id,
+        building: building.name,
+        level: building.level,
+        target_level,
+    }];
+    if victory {
+        events.push(GameEvent::WonderCompleted {
+            village_id: village.id,
+            player_id: village.player_id,
+        });
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::{complete_upgrade, promote_queued, BuildingUpgradeProcessor};
+    use crate::{
+        app::{
+            events::GameEvent,
+            jobs::{Job, JobTask},
+            processors::Processor,
+        },
+        db::test_utils::{insert_village, setup_repo, test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    fn new_village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_completion_emits_building_completed() {
+        let mut village = new_village();
+
+        // slot 1 is a woodcutter at level 0
+        let events = complete_upgrade(&mut village, 1, &BuildingName::Woodcutter, None).unwrap();
+        assert_eq!(events.len(), 1);
+        match events[0].clone() {
+            GameEvent::BuildingCompleted {
+                village_id,
+                slot_id,
+                building,
+                level,
+                ..
+            } => {
+                assert_eq!(village_id, village.id);
+                assert_eq!(slot_id, 1);
+                assert_eq!(building, BuildingName::Woodcutter);
+                assert_eq!(level, 1);
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+
+        // slot 19 is the main building at level 1
+        let events = complete_upgrade(&mut village, 19, &BuildingName::MainBuilding, None).unwrap();
+        match events[0].clone() {
+            GameEvent::BuildingCompleted {
+                slot_id,
+                building,
+                level,
+                ..
+            } => {
+                assert_eq!(slot_id, 19);
+                assert_eq!(building, BuildingName::MainBuilding);
+                assert_eq!(level, 2);
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_last_wonder_level_wins_the_game() {
+        let mut village = new_village();
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::WonderOfTheWorld)
+                .at_level(98)
+                .unwrap(),
+        );
+
+        let events =
+            complete_upgrade(&mut village, 20, &BuildingName::WonderOfTheWorld, None).unwrap();
+        assert_eq!(events.len(), 1);
+
+        let events =
+            complete_upgrade(&mut village, 20, &BuildingName::WonderOfTheWorld, None).unwrap();
+        match events.as_slice() {
+            [GameEvent::BuildingCompleted { level: 100, .. }, GameEvent::WonderCompleted {
+                village_id,
+                player_id,
+            }] => {
+                assert_eq!(*village_id, village.id);
+                assert_eq!(*player_id, village.player_id);
+            }
+            e => panic!("unexpected events {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_completion_rejects_mismatching_building() {
+        let mut village = new_village();
+        assert!(complete_upgrade(&mut village, 1, &BuildingName::Cropland, None).is_err());
+        assert!(complete_upgrade(&mut village, 30, &BuildingName::Cropland, None).is_err());
+    }
+
+    fn upgrade(village: &Village, slot_id: u8, started_at: DateTime<Utc>) -> Job {
+        let mut job = Job::new(
+            village.player_id,
+            village.id,
+            100,
+            JobTask::BuildingUpgrade {
+                slot_id,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        job.started_at = started_at;
+        job
+    }
+
+    #[test]
+    fn test_completion_promotes_queued_jobs_of_the_lane() {
+        let village = new_village();
+        let now = Utc::now();
+        let completed = upgrade(&village, 1, now - Duration::seconds(60));
+        let queued = upgrade(&village, 2, now + Duration::seconds(40));
+        let next = upgrade(&village, 3, now + Duration::seconds(140));
+        // Romans build village buildings on their own lane
+        let other_lane = upgrade(&village, 20, now + Duration::seconds(40));
+        let pending = vec![completed.clone(), queued.clone(), next.clone(), other_lane];
+
+        let promoted = promote_queued(&completed.task, &village, &pending, now);
+
+        assert_eq!(promoted.len(), 2);
+        assert_eq!(promoted[0].id, queued.id);
+        assert_eq!(promoted[0].started_at, now);
+        assert_eq!(promoted[1].id, next.id);
+        assert_eq!(promoted[1].started_at, now + Duration::seconds(100));
+    }
+
+    #[test]
+    fn test_completion_without_queued_jobs_promotes_nothing() {
+        let village = new_village();
+        let now = Utc::now();
+        let completed = upgrade(&village, 1, now - Duration::seconds(100));
+        // already started when the builders got free
+        let running = upgrade(&village, 2, now - Duration::seconds(10));
+        let pending = vec![completed.clone(), running];
+
+        assert!(promote_queued(&completed.task, &village, &pending, now).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_processor_saves_promoted_jobs() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Teuton, &Position { x: 3, y: 4 }).await;
+        let queued = upgrade(&village, 2, Utc::now() + Duration::seconds(300));
+        repo.add_job(queued.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        BuildingUpgradeProcessor::new(repo.clone(), village.id, 1, BuildingName::Woodcutter, None)
+            .process()
+            .await
+            .unwrap();
+
+        let promoted = repo.get_job_by_id(queued.id).await.unwrap();
+        assert!(promoted.started_at <= Utc::now());
+        assert_eq!(promoted.duration, queued.duration);
+    }
+}
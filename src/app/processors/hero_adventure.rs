@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::events::GameEvent,
+    game::models::{
+        adventure::{adventure_damage, Adventure, AdventureReward},
+        hero::Hero,
+        report::{Report, ReportAudience, ReportContent},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct HeroAdventureProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    adventure: Adventure,
+}
+
+impl HeroAdventureProcessor {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, adventure: Adventure) -> Self {
+        Self {
+            repo,
+            player_id,
+            adventure,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for HeroAdventureProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut hero = self.repo.get_hero_by_player_id(self.player_id).await?;
+        let mut village = self.repo.get_village_by_id(hero.village_id).await?;
+
+        let damage = adventure_damage(&self.adventure.difficulty, &mut rand::thread_rng());
+        let report = complete_adventure(&mut hero, &mut village, &self.adventure, damage);
+
+        self.repo.update_village(village).await?;
+        self.repo.update_hero(hero.clone()).await?;
+        self.repo.add_report(report).await?;
+
+        Ok(vec![GameEvent::HeroUpdated(hero)])
+    }
+}
+
+// Resolves the adventure for the hero, stores any resources found in its village and
+// returns the report for the player.
+fn complete_adventure(
+    hero: &mut Hero,
+    village: &mut Village,
+    adventure: &Adventure,
+    damage: u8,
+) -> Report {
+    let outcome = adventure.resolve(hero, damage);
+    if let Some(AdventureReward::Resources(resources)) = &outcome.reward {
+        village.deposit_resources(resources);
+    }
+
+    Report::new(
+        hero.player_id,
+        village.id,
+        hero.player_id,
+        village.id,
+        ReportAudience::Everyone,
+        ReportContent::Adventure(outcome),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::complete_adventure;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            adventure::{Adventure, AdventureDifficulty, AdventureReward},
+            hero::{Hero, HeroStatus},
+            map::Position,
+            report::ReportContent,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    #[test]
+    fn test_adventure_resources_are_stored_in_village() {
+        let player = test_player(Tribe::Teuton);
+        let mut village = test_village(&player, Position { x: 10, y: 20 });
+        village.resources = ResourceGroup::default();
+        let found = ResourceGroup::new(100, 100, 100, 100);
+        let adventure = Adventure::new(
+            player.id,
+            Position { x: 12, y: 22 },
+            AdventureDifficulty::Hard,
+            AdventureReward::Resources(found.clone()),
+            Utc::now(),
+        );
+
+        let mut hero = Hero::new(player.id, village.id);
+        hero.status = HeroStatus::Away;
+        let report = complete_adventure(&mut hero, &mut village, &adventure, 40);
+
+        assert_eq!(village.resources, found);
+        assert_eq!(hero.status, HeroStatus::Home);
+        match report.content {
+            ReportContent::Adventure(outcome) => assert_eq!(outcome.health_lost, 40),
+            _ => panic!("adventure report expected"),
+        }
+
+        // a dead hero brings nothing back
+        let mut hero = Hero::new(player.id, village.id);
+        hero.health = 30;
+        complete_adventure(&mut hero, &mut village, &adventure, 40);
+        assert_eq!(hero.status, HeroStatus::Dead);
+        assert_eq!(village.resources, found);
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        report::{MerchantDelivery, Report, ReportAudience, ReportContent},
+        village::Village,
+        ResourceGroup,
+    },
+    repository::Repository,
+};
+
+pub struct MerchantGoingProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    resources: ResourceGroup,
+    merchants: u32,
+    duration: u64,
+}
+
+impl MerchantGoingProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+        merchants: u32,
+        duration: u64,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            resources,
+            merchants,
+            duration,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for MerchantGoingProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut target = self.repo.get_village_by_id(self.target_village_id).await?;
+        let report = deliver_resources(
+            &mut target,
+            self.player_id,
+            self.village_id,
+            &self.resources,
+        );
+        self.repo.update_village(target).await?;
+        self.repo.add_report(report).await?;
+
+        // merchants take the same time to come back home
+        let job = Job::new(
+            self.player_id,
+            self.village_id,
+            self.duration,
+            JobTask::MerchantReturn {
+                village_id: self.village_id,
+                merchants: self.merchants,
+                resources: Default::default(),
+            },
+        );
+
+        Ok(vec![GameEvent::JobEnqueued(job)])
+    }
+}
+
+// Deposits the resources into the target village and returns the report for both
+// players. Stocks are taken right away, so later changes don't show up in the report.
+fn deliver_resources(
+    target: &mut Village,
+    player_id: Uuid,
+    village_id: u32,
+    resources: &ResourceGroup,
+) -> Report {
+    target.deposit_resources(resources);
+
+    Report::new(
+        player_id,
+        village_id,
+        target.player_id,
+        target.id,
+        ReportAudience::Everyone,
+        ReportContent::Delivery(MerchantDelivery {
+            delivered: resources.clone(),
+            stored: target.resources.clone(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::{deliver_resources, MerchantGoingProcessor};
+    use crate::{
+        app::processors::Processor,
+        db::test_utils::{insert_valley, setup_repo},
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            map::{Position, WORLD_MAX_SIZE},
+            report::ReportContent,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[test]
+    fn test_delivery_is_capped_by_storage() {
+        let mut target = test_village(&test_player(Tribe::Gaul), Position { x: 10, y: 20 });
+        target.resources = ResourceGroup::new(700, 100, 100, 750);
+
+        // the 800 stocks of a new village can't hold everything
+        deliver_resources(
+            &mut target,
+            Uuid::new_v4(),
+            42,
+            &ResourceGroup::new(500, 300, 0, 100),
+        );
+        assert_eq!(target.resources, ResourceGroup::new(800, 400, 100, 800));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_report_keeps_resources_at_arrival() {
+        let repo = setup_repo().await;
+        let (home, target) = (Position { x: 3, y: 4 }, Position { x: 5, y: 6 });
+        insert_valley(&repo, &home).await;
+        insert_valley(&repo, &target).await;
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let bob = repo
+            .register_player("bob".to_string(), Tribe::Gaul)
+            .await
+            .unwrap();
+        for (player, position) in [(&alice, &home), (&bob, &target)] {
+            let valley = repo
+                .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let mut village = Village::new("Gino".to_string(), &valley, player, true);
+            village.resources = ResourceGroup::new(100, 100, 100, 100);
+            repo.found_village(village, None).await.unwrap();
+        }
+
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let (home_id, target_id) = (home.to_id(WORLD_MAX_SIZE), target.to_id(WORLD_MAX_SIZE));
+        MerchantGoingProcessor::new(
+            repo.clone(),
+            alice.id,
+            home_id,
+            target_id,
+            ResourceGroup::new(200, 0, 0, 0),
+            1,
+            60,
+        )
+        .process()
+        .await
+        .unwrap();
+
+        let mut village = repo.get_village_by_id(target_id).await.unwrap();
+        let stored_at_arrival = village.resources.clone();
+        assert_eq!(stored_at_arrival, ResourceGroup::new(300, 100, 100, 100));
+
+        // the village keeps changing after the delivery
+        village.resources = ResourceGroup::new(0, 0, 0, 0);
+        repo.update_village(village).await.unwrap();
+
+        let reports = repo.get_reports_by_player_id(bob.id).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        match &reports[0].content {
+            ReportContent::Delivery(delivery) => {
+                assert_eq!(delivery.delivered, ResourceGroup::new(200, 0, 0, 0));
+                assert_eq!(delivery.stored, stored_at_arrival);
+            }
+            c => panic!("unexpected report content {:?}", c),
+        }
+    }
+}
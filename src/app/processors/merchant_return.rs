@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Processor;
+use crate::{app::events::GameEvent, game::models::ResourceGroup, repository::Repository};
+
+pub struct MerchantReturnProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    resources: ResourceGroup,
+}
+
+impl MerchantReturnProcessor {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, resources: ResourceGroup) -> Self {
+        Self {
+            repo,
+            village_id,
+            resources,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for MerchantReturnProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        // merchants are free again as soon as the job is done, only the ones called
+        // back before arriving still carry something
+        if self.resources.total() > 0 {
+            let mut village = self.repo.get_village_by_id(self.village_id).await?;
+            village.deposit_resources(&self.resources);
+            self.repo.update_village(village).await?;
+        }
+
+        Ok(vec![])
+    }
+}
@@ -0,0 +1,26 @@
+pub mod army_return;
+pub mod attack;
+pub mod brewery_celebration;
+pub mod building_downgrade;
+pub mod building_upgrade;
+pub mod hero_adventure;
+pub mod merchant_going;
+pub mod merchant_return;
+pub mod oasis_attack;
+pub mod raid;
+pub mod reinforcement;
+pub mod reinforcement_recall;
+pub mod scout;
+pub mod town_hall_celebration;
+pub mod trade_route;
+pub mod training;
+
+use anyhow::Result;
+
+use super::events::GameEvent;
+
+// Processors run the effects of a job once its duration has elapsed.
+#[async_trait::async_trait]
+pub trait Processor {
+    async fn process(&self) -> Result<Vec<GameEvent>>;
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::{
+        battle::oasis_battle,
+        models::{
+            army::Army,
+            map::{travel_time_secs, TravelSettings},
+        },
+    },
+    repository::Repository,
+};
+
+pub struct OasisAttackProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    oasis_id: u32,
+    army: Army,
+    is_normal: bool,
+    travel: TravelSettings,
+}
+
+impl OasisAttackProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        oasis_id: u32,
+        army: Army,
+        is_normal: bool,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            oasis_id,
+            army,
+            is_normal,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for OasisAttackProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let home = self.repo.get_village_by_id(self.village_id).await?;
+        let mut oasis = self.repo.get_oasis_by_id(self.oasis_id).await?;
+
+        // the oasis was annexed while the troops were on the way, there's nothing to fight
+        let mut survivors = self.army.clone();
+        if oasis.player_id.is_none() {
+            oasis_battle(&mut survivors, &mut oasis, self.is_normal);
+            self.repo.update_oasis(oasis.clone()).await?;
+        }
+
+        if survivors.immensity() == 0 && survivors.hero.is_none() {
+            return Ok(vec![]);
+        }
+
+        let time_secs = travel_time_secs(
+            &home.position,
+            &oasis.position,
+            survivors.speed(),
+            self.travel,
+        );
+        let job = Job::new(
+            self.player_id,
+            self.village_id,
+            time_secs as u64,
+            JobTask::ArmyReturn {
+                army: survivors,
+                resources: Default::default(),
+                village_id: self.village_id,
+            },
+        );
+
+        Ok(vec![GameEvent::JobEnqueued(job)])
+    }
+}
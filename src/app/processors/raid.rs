@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::{
+        battle::{Battle, CataTargets},
+        models::{
+            army::Army,
+            map::{travel_time_secs, TravelSettings},
+            report::{BattleCasualties, Report, ReportAudience, ReportContent, ReportKind},
+            village::Village,
+            ResourceGroup,
+        },
+    },
+    repository::Repository,
+};
+
+pub struct RaidProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    army: Army,
+    min_attacker_losses_percent: f64,
+    defense_multiplier: f64,
+    travel: TravelSettings,
+}
+
+impl RaidProcessor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        army: Army,
+        min_attacker_losses_percent: f64,
+        defense_multiplier: f64,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            army,
+            min_attacker_losses_percent,
+            defense_multiplier,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for RaidProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let home = self.repo.get_village_by_id(self.village_id).await?;
+        let mut target = self.repo.get_village_by_id(self.target_village_id).await?;
+        let target_before = target.clone();
+
+        let (survivors, loot) = raid(
+            &home,
+            &mut target,
+            self.army.clone(),
+            self.min_attacker_losses_percent,
+            self.defense_multiplier,
+        );
+        self.repo.update_village(target.clone()).await?;
+
+        let mut report = Report::new(
+            self.player_id,
+            self.village_id,
+            target.player_id,
+            target.id,
+            ReportAudience::Everyone,
+            ReportContent::Battle(Box::new(BattleCasualties::new(
+                &self.army,
+                &survivors,
+                &target_before,
+                &target,
+                loot.clone(),
+            ))),
+        );
+        report.kind = ReportKind::Raid;
+        self.repo.add_report(report).await?;
+
+        // nobody left to bring the loot home
+        if survivors.immensity() == 0 && survivors.hero.is_none() {
+            return Ok(vec![]);
+        }
+
+        let time_secs = travel_time_secs(
+            &home.position,
+            &target.position,
+            survivors.speed(),
+            self.travel,
+        );
+        let job = Job::new(
+            self.player_id,
+            self.village_id,
+            time_secs as u64,
+            JobTask::ArmyReturn {
+                army: survivors,
+                resources: loot,
+                village_id: self.village_id,
+            },
+        );
+
+        Ok(vec![GameEvent::JobEnqueued(job)])
+    }
+}
+
+// Fights a raid battle against the target, then takes as many resources as the
+// surviving troops can carry. Returns the survivors and their loot.
+fn raid(
+    home: &Village,
+    target: &mut Village,
+    army: Army,
+    min_attacker_losses_percent: f64,
+    defense_multiplier: f64,
+) -> (Army, ResourceGroup) {
+    let mut battle = Battle::new(
+        army,
+        home.clone(),
+        target.clone(),
+        false,
+        false,
+        CataTargets::default(),
+    );
+    battle.min_attacker_losses_percent = min_attacker_losses_percent;
+    battle.defense_multiplier = defense_multiplier;
+    battle.combat();
+
+    *target = battle.defender_village;
+    let survivors = battle.attacker_army;
+    let lootable = target.lootable_resources(&survivors.tribe);
+    let loot = loot(&lootable, survivors.carry_capacity());
+    target.resources.sub(&loot);
+
+    (survivors, loot)
+}
+
+// Splits the carry capacity across the resources in proportion to how much of each is
+// available. When the army can carry everything, the target is emptied.
+pub fn loot(available: &ResourceGroup, capacity: u64) -> ResourceGroup {
+    let available = [
+        available.lumber() as u64,
+        available.clay() as u64,
+        available.iron() as u64,
+        available.crop() as u64,
+    ];
+    let total: u64 = available.iter().sum();
+    if total <= capacity {
+        return ResourceGroup::new(
+            available[0] as u32,
+            available[1] as u32,
+            available[2] as u32,
+            available[3] as u32,
+        );
+    }
+
+    let mut taken = available.map(|a| a * capacity / total);
+    // hand out the units lost to rounding
+    let mut left = capacity - taken.iter().sum::<u64>();
+    for i in 0..4 {
+        if left > 0 && taken[i] < available[i] {
+            taken[i] += 1;
+            left -= 1;
+        }
+    }
+
+    ResourceGroup::new(
+        taken[0] as u32,
+        taken[1] as u32,
+        taken[2] as u32,
+        taken[3] as u32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loot, raid};
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{army::Army, map::Position, village::Village, ResourceGroup, Tribe},
+    };
+
+    fn village(x: i32, y: i32) -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x, y })
+    }
+
+    fn legionnaires(home: &Village, quantity: u32) -> Army {
+        Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [quantity, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        )
+    }
+
+    #[test]
+    fn test_raid_empty_village() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+
+        let (survivors, bounty) = raid(&home, &mut target, legionnaires(&home, 10), 0.0, 1.0);
+
+        // 10 legionnaires carry 50 resources each
+        assert_eq!(survivors.units[0], 10);
+        assert_eq!(bounty, ResourceGroup::new(125, 125, 125, 125));
+        assert_eq!(target.resources, ResourceGroup::new(625, 625, 625, 625));
+    }
+
+    #[test]
+    fn test_raid_defended_village() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+        target.army.units = [100, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, bounty) = raid(&home, &mut target, legionnaires(&home, 100), 0.0, 1.0);
+
+        assert!(survivors.units[0] > 0 && survivors.units[0] < 100);
+        assert!(target.army.units[0] < 100);
+        assert_eq!(bounty.total() as u64, survivors.carry_capacity());
+        assert_eq!(
+            target.resources.total() + bounty.total(),
+            ResourceGroup::new(750, 750, 750, 750).total()
+        );
+    }
+
+    #[test]
+    fn test_min_attacker_losses_against_small_defense() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+        target.army.units = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, _) = raid(
+            &home,
+            &mut target.clone(),
+            legionnaires(&home, 100),
+            0.0,
+            1.0,
+        );
+        assert_eq!(survivors.units[0], 100);
+
+        let (survivors, _) = raid(&home, &mut target, legionnaires(&home, 100), 5.0, 1.0);
+        assert_eq!(survivors.units[0], 95);
+    }
+
+    #[test]
+    fn test_min_attacker_losses_without_defense() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+
+        let (survivors, _) = raid(&home, &mut target, legionnaires(&home, 100), 5.0, 1.0);
+        assert_eq!(survivors.units[0], 100);
+    }
+
+    #[test]
+    fn test_loot_split_proportionally() {
+        let available = ResourceGroup::new(10, 1000, 1000, 0);
+        assert_eq!(loot(&available, 610), ResourceGroup::new(4, 303, 303, 0));
+
+        let available = ResourceGroup::new(600, 300, 300, 0);
+        assert_eq!(loot(&available, 400), ResourceGroup::new(200, 100, 100, 0));
+    }
+
+    #[test]
+    fn test_loot_limited_by_stored_resources() {
+        let available = ResourceGroup::new(10, 1000, 1000, 0);
+        assert_eq!(loot(&available, 5000), available);
+    }
+
+    #[test]
+    fn test_raid_leaves_resources_beyond_stocks() {
+        let home = village(10, 20);
+        let mut target = village(12, 20);
+        // a Wonder of the World stores more than its warehouse can hold
+        target.resources = ResourceGroup::new(2000, 2000, 2000, 2000);
+
+        let (_, bounty) = raid(&home, &mut target, legionnaires(&home, 100), 0.0, 1.0);
+
+        assert_eq!(bounty, ResourceGroup::new(800, 800, 800, 800));
+        assert_eq!(target.resources, ResourceGroup::new(1200, 1200, 1200, 1200));
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{army::Army, village::Village},
+    repository::Repository,
+};
+
+pub struct ReinforcementProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    target_village_id: u32,
+    target_player_id: Uuid,
+    army: Army,
+    duration: u64,
+    return_after: Option<u64>,
+}
+
+impl ReinforcementProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        target_village_id: u32,
+        target_player_id: Uuid,
+        army: Army,
+        duration: u64,
+        return_after: Option<u64>,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            target_village_id,
+            target_player_id,
+            army,
+            duration,
+            return_after,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ReinforcementProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let mut target = self.repo.get_village_by_id(self.target_village_id).await?;
+
+        match station_reinforcement(
+            &mut target,
+            self.army.player_id,
+            self.village_id,
+            self.target_player_id,
+            self.army.clone(),
+            self.duration,
+            self.return_after,
+        ) {
+            Stationing::Stationed(recall) => {
+                self.repo.update_village(target).await?;
+                Ok(recall.into_iter().map(GameEvent::JobEnqueued).collect())
+            }
+            Stationing::Refused(return_job) => Ok(vec![GameEvent::JobEnqueued(return_job)]),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Stationing {
+    // The army joined the village, with the job recalling it if it stays for a while.
+    Stationed(Option<Job>),
+    // The army has been refused and goes back home.
+    Refused(Job),
+}
+
+// Adds the army to the reinforcements of the target village, if its policy allows it.
+// Troops already stationed there from the same village are merged together, while each
+// sending village keeps its own entry. The army goes back home when the target changed
+// owner while it was on the way (eg: conquered) or when it's refused by the policy.
+fn station_reinforcement(
+    target: &mut Village,
+    player_id: Uuid,
+    village_id: u32,
+    expected_owner: Uuid,
+    army: Army,
+    duration: u64,
+    return_after: Option<u64>,
+) -> Stationing {
+    if target.player_id == expected_owner && target.accepts_reinforcements_from(player_id) {
+        match target
+            .reinforcements
+            .iter_mut()
+            .find(|r| r.village_id == village_id && r.player_id == player_id)
+        {
+            Some(stationed) => stationed.merge(army),
+            None => target.reinforcements.push(army),
+        }
+
+        let recall = return_after.map(|secs| {
+            Job::new(
+                player_id,
+                village_id,
+                secs,
+                JobTask::ReinforcementRecall {
+                    village_id: target.id,
+                },
+            )
+        });
+        return Stationing::Stationed(recall);
+    }
+
+    Stationing::Refused(Job::new(
+        player_id,
+        village_id,
+        duration,
+        JobTask::ArmyReturn {
+            army,
+            resources: Default::default(),
+            village_id,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{station_reinforcement, Stationing};
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::Army,
+            map::Position,
+            village::{ReinforcementPolicy, Village},
+            Player, Tribe,
+        },
+    };
+
+    fn village(player: &Player) -> Village {
+        test_village(player, Position { x: 10, y: 20 })
+    }
+
+    fn player() -> Player {
+        test_player(Tribe::Roman)
+    }
+
+    fn army(player: &Player) -> Army {
+        Army::new(
+            42,
+            player.id,
+            player.tribe.clone(),
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        )
+    }
+
+    #[test]
+    fn test_reinforcement_policy_anyone() {
+        let (owner, stranger) = (player(), player());
+        let mut target = village(&owner);
+
+        assert!(matches!(
+            station_reinforcement(
+                &mut target,
+                stranger.id,
+                42,
+                owner.id,
+                army(&stranger),
+                60,
+                None
+            ),
+            Stationing::Stationed(None)
+        ));
+        assert_eq!(target.reinforcements.len(), 1);
+    }
+
+    #[test]
+    fn test_reinforcement_policy_ally_only() {
+        let (owner, stranger) = (player(), player());
+        let mut target = village(&owner);
+        target.reinforcement_policy = ReinforcementPolicy::AllyOnly;
+
+        assert!(matches!(
+            station_reinforcement(
+                &mut target,
+                stranger.id,
+                42,
+                owner.id,
+                army(&stranger),
+                60,
+                None
+            ),
+            Stationing::Refused(_)
+        ));
+        assert!(matches!(
+            station_reinforcement(&mut target, owner.id, 42, owner.id, army(&owner), 60, None),
+            Stationing::Stationed(None)
+        ));
+        assert_eq!(target.reinforcements.len(), 1);
+    }
+
+    #[test]
+    fn test_reinforcement_policy_none_bounces_back() {
+        let (owner, stranger) = (player(), player());
+        let mut target = village(&owner);
+        target.reinforcement_policy = ReinforcementPolicy::None;
+
+        let job = match station_reinforcement(
+            &mut target,
+            stranger.id,
+            42,
+            owner.id,
+            army(&stranger),
+            60,
+            None,
+        ) {
+            Stationing::Refused(job) => job,
+            s => panic!("reinforcement should be refused, got {:?}", s),
+        };
+        assert!(target.reinforcements.is_empty());
+        assert_eq!(job.village_id, 42);
+        assert_eq!(job.duration, 60);
+        match job.task {
+            JobTask::ArmyReturn {
+                army, village_id, ..
+            } => {
+                assert_eq!(village_id, 42);
+                assert_eq!(army.units[0], 10);
+            }
+            t => panic!("unexpected task {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_stationed_army_is_recalled_later() {
+        let owner = player();
+        let mut target = village(&owner);
+
+        let recall = match station_reinforcement(
+            &mut target,
+            owner.id,
+            42,
+            owner.id,
+            army(&owner),
+            60,
+            Some(3600),
+        ) {
+            Stationing::Stationed(Some(job)) => job,
+            s => panic!("expected a recall job, got {:?}", s),
+        };
+        assert_eq!(target.reinforcements.len(), 1);
+        assert_eq!(recall.village_id, 42);
+        assert_eq!(recall.duration, 3600);
+        assert!(matches!(
+            recall.task,
+            JobTask::ReinforcementRecall { village_id } if village_id == target.id
+        ));
+    }
+
+    #[test]
+    fn test_reinforce_own_village() {
+        let owner = player();
+        let mut target = village(&owner);
+
+        assert!(matches!(
+            station_reinforcement(&mut target, owner.id, 42, owner.id, army(&owner), 60, None),
+            Stationing::Stationed(None)
+        ));
+        assert_eq!(target.reinforcements.len(), 1);
+        assert_eq!(target.reinforcements[0].player_id, owner.id);
+    }
+
+    #[test]
+    fn test_target_conquered_en_route_sends_army_back() {
+        let (owner, conqueror, sender) = (player(), player(), player());
+        let mut target = village(&owner);
+        // the village changed hands while the army was on the way
+        target.player_id = conqueror.id;
+
+        let job = match station_reinforcement(
+            &mut target,
+            sender.id,
+            42,
+            owner.id,
+            army(&sender),
+            60,
+            Some(3600),
+        ) {
+            Stationing::Refused(job) => job,
+            s => panic!("reinforcement should be refused, got {:?}", s),
+        };
+        assert!(target.reinforcements.is_empty());
+        assert_eq!(job.player_id, sender.id);
+        assert!(matches!(
+            job.task,
+            JobTask::ArmyReturn { village_id: 42, .. }
+        ));
+    }
+
+    #[test]
+    fn test_reinforcements_stack_by_sender() {
+        let (owner, first, second) = (player(), player(), player());
+        let mut target = village(&owner);
+
+        station_reinforcement(&mut target, first.id, 42, owner.id, army(&first), 60, None);
+        station_reinforcement(
+            &mut target,
+            second.id,
+            43,
+            owner.id,
+            army(&second),
+            60,
+            None,
+        );
+        assert_eq!(target.reinforcements.len(), 2);
+
+        // another wave from the same village joins the troops already there
+        station_reinforcement(&mut target, first.id, 42, owner.id, army(&first), 60, None);
+        assert_eq!(target.reinforcements.len(), 2);
+        assert_eq!(target.reinforcements[0].units[0], 20);
+        assert_eq!(target.reinforcements[1].units[0], 10);
+        assert_eq!(target.reinforcements[1].player_id, second.id);
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::{
+        map::{travel_time_secs, TravelSettings},
+        village::Village,
+    },
+    repository::Repository,
+};
+
+pub struct ReinforcementRecallProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    host_village_id: u32,
+    travel: TravelSettings,
+}
+
+impl ReinforcementRecallProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        host_village_id: u32,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            host_village_id,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ReinforcementRecallProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let home = self.repo.get_village_by_id(self.village_id).await?;
+        let mut host = self.repo.get_village_by_id(self.host_village_id).await?;
+
+        let jobs = recall_reinforcements(&home, &mut host, self.player_id, self.travel);
+        if !jobs.is_empty() {
+            self.repo.update_village(host).await?;
+        }
+
+        Ok(jobs.into_iter().map(GameEvent::JobEnqueued).collect())
+    }
+}
+
+// Removes from the host the reinforcements sent by the home village and returns the
+// jobs bringing them back. Armies already lost or sent back leave nothing to recall.
+fn recall_reinforcements(
+    home: &Village,
+    host: &mut Village,
+    player_id: Uuid,
+    travel: TravelSettings,
+) -> Vec<Job> {
+    let (recalled, staying) = host
+        .reinforcements
+        .drain(..)
+        .partition(|a| a.village_id == home.id && a.player_id == player_id);
+    host.reinforcements = staying;
+
+    recalled
+        .into_iter()
+        .map(|army| {
+            let time_secs = travel_time_secs(&home.position, &host.position, army.speed(), travel);
+            Job::new(
+                player_id,
+                home.id,
+                time_secs as u64,
+                JobTask::ArmyReturn {
+                    army,
+                    resources: Default::default(),
+                    village_id: home.id,
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recall_reinforcements;
+    use crate::{
+        app::jobs::JobTask,
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::Army,
+            map::{travel_time_secs, Position, TravelSettings},
+            village::Village,
+            Player, Tribe,
+        },
+    };
+
+    fn village(player: &Player, x: i32, y: i32) -> Village {
+        test_village(player, Position { x, y })
+    }
+
+    fn player() -> Player {
+        test_player(Tribe::Roman)
+    }
+
+    #[test]
+    fn test_recall_sends_back_only_own_reinforcements() {
+        let (owner, other) = (player(), player());
+        let home = village(&owner, 10, 20);
+        let mut host = village(&owner, 10, 30);
+        let units = [10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        host.reinforcements = vec![
+            Army::new(home.id, owner.id, Tribe::Roman, units, [0; 10]),
+            Army::new(999, other.id, Tribe::Roman, units, [0; 10]),
+        ];
+
+        let jobs = recall_reinforcements(&home, &mut host, owner.id, TravelSettings::default());
+
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(host.reinforcements.len(), 1);
+        assert_eq!(host.reinforcements[0].village_id, 999);
+
+        let job = &jobs[0];
+        assert_eq!(job.village_id, home.id);
+        assert_eq!(
+            job.duration,
+            travel_time_secs(&home.position, &host.position, 6, TravelSettings::default()) as u64
+        );
+        assert!(matches!(
+            &job.task,
+            JobTask::ArmyReturn { army, .. } if army.units == units
+        ));
+    }
+}
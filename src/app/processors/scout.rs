@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::{
+        battle::{Battle, CataTargets},
+        models::{
+            army::Army,
+            map::{travel_time_secs, TravelSettings},
+            report::{Report, ReportAudience, ReportContent, ScoutingIntel},
+            village::Village,
+        },
+    },
+    repository::Repository,
+};
+
+pub struct ScoutProcessor {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    army: Army,
+    defense_multiplier: f64,
+    travel: TravelSettings,
+}
+
+impl ScoutProcessor {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        army: Army,
+        defense_multiplier: f64,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            army,
+            defense_multiplier,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ScoutProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let home = self.repo.get_village_by_id(self.village_id).await?;
+        let mut target = self.repo.get_village_by_id(self.target_village_id).await?;
+
+        let (survivors, intel, detected) = scout(
+            &home,
+            &mut target,
+            self.army.clone(),
+            self.defense_multiplier,
+        );
+        self.repo.update_village(target.clone()).await?;
+
+        let report = Report::new(
+            self.player_id,
+            self.village_id,
+            target.player_id,
+            target.id,
+            ReportAudience::Spy { detected },
+            ReportContent::Scouting(intel),
+        );
+        self.repo.add_report(report).await?;
+
+        if survivors.immensity() == 0 && survivors.hero.is_none() {
+            return Ok(vec![]);
+        }
+
+        let time_secs = travel_time_secs(
+            &home.position,
+            &target.position,
+            survivors.speed(),
+            self.travel,
+        );
+        let job = Job::new(
+            self.player_id,
+            self.village_id,
+            time_secs as u64,
+            JobTask::ArmyReturn {
+                army: survivors,
+                resources: Default::default(),
+                village_id: self.village_id,
+            },
+        );
+
+        Ok(vec![GameEvent::JobEnqueued(job)])
+    }
+}
+
+// Fights the scouting battle against the scouts defending the target. Returns the
+// surviving scouts, what they've learned and whether the defender noticed them: any
+// defending scout raises the alarm, even when it can't stop the attackers.
+fn scout(
+    home: &Village,
+    target: &mut Village,
+    army: Army,
+    defense_multiplier: f64,
+) -> (Army, ScoutingIntel, bool) {
+    let detected = target
+        .defending_armies()
+        .iter()
+        .any(|a| a.scouting_defense_points() > 0);
+
+    let mut battle = Battle::new(
+        army.clone(),
+        home.clone(),
+        target.clone(),
+        true,
+        true,
+        CataTargets::default(),
+    );
+    battle.defense_multiplier = defense_multiplier;
+    battle.combat();
+
+    *target = battle.defender_village;
+    let survivors = battle.attacker_army;
+    let scouts_lost = army.immensity() - survivors.immensity();
+    let intel = match survivors.immensity() {
+        0 => ScoutingIntel {
+            scouts_lost,
+            ..Default::default()
+        },
+        _ => ScoutingIntel::gather(target, scouts_lost),
+    };
+
+    (survivors, intel, detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scout;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::Army, buildings::BuildingName, map::Position, village::Village, Tribe,
+        },
+    };
+
+    fn village(x: i32, y: i32, tribe: Tribe) -> Village {
+        test_village(&test_player(tribe), Position { x, y })
+    }
+
+    fn scouts(home: &Village, quantity: u32) -> Army {
+        Army::new(
+            home.id,
+            home.player_id,
+            Tribe::Roman,
+            [0, 0, 0, quantity, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        )
+    }
+
+    #[test]
+    fn test_undetected_scouting() {
+        let home = village(10, 20, Tribe::Roman);
+        let mut target = village(12, 20, Tribe::Gaul);
+        target.army.units = [50, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, intel, detected) = scout(&home, &mut target, scouts(&home, 5), 1.0);
+
+        // phalanxes don't fight scouts
+        assert!(!detected);
+        assert_eq!(survivors.units[3], 5);
+        assert_eq!(target.army.units[0], 50);
+        assert_eq!(intel.scouts_lost, 0);
+        assert_eq!(intel.units, target.army.units);
+        assert_eq!(intel.resources, target.resources);
+        assert!(intel.buildings.contains(&(BuildingName::MainBuilding, 1)));
+    }
+
+    #[test]
+    fn test_defending_scouts_kill_attackers() {
+        let home = village(10, 20, Tribe::Roman);
+        let mut target = village(12, 20, Tribe::Gaul);
+        // pathfinders sit in the third slot
+        target.army.units = [50, 0, 100, 0, 0, 0, 0, 0, 0, 0];
+
+        let (survivors, intel, detected) = scout(&home, &mut target, scouts(&home, 5), 1.0);
+
+        assert!(detected);
+        assert_eq!(survivors.immensity(), 0);
+        assert_eq!(intel.scouts_lost, 5);
+        assert_eq!(intel.units, [0; 10], "nothing learned");
+        assert!(intel.buildings.is_empty());
+        assert_eq!(target.army.units[0], 50, "only scouts fight scouts");
+        assert!(target.army.units[2] > 0);
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Processor;
+use crate::{
+    app::events::GameEvent,
+    game::models::{celebration::celebration_culture_points, Player},
+    repository::Repository,
+};
+
+pub struct TownHallCelebrationProcessor {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    big: bool,
+}
+
+impl TownHallCelebrationProcessor {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, big: bool) -> Self {
+        Self {
+            repo,
+            village_id,
+            big,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for TownHallCelebrationProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let mut player = self.repo.get_player_by_id(village.player_id).await?;
+
+        end_celebration(&mut player, self.big);
+        self.repo.update_player(player).await?;
+
+        Ok(vec![GameEvent::CelebrationTownHallEnded])
+    }
+}
+
+// Culture points belong to the player, so the village owner gets the reward.
+fn end_celebration(player: &mut Player, big: bool) {
+    player.culture_points += celebration_culture_points(big);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::end_celebration;
+    use crate::{
+        db::test_utils::test_player,
+        game::models::{
+            celebration::{BIG_CELEBRATION_CULTURE_POINTS, SMALL_CELEBRATION_CULTURE_POINTS},
+            Player, Tribe,
+        },
+    };
+
+    #[test]
+    fn test_celebration_rewards() {
+        let mut player = Player {
+            culture_points: 100,
+            ..test_player(Tribe::Roman)
+        };
+
+        end_celebration(&mut player, false);
+        assert_eq!(
+            player.culture_points,
+            100 + SMALL_CELEBRATION_CULTURE_POINTS
+        );
+
+        end_celebration(&mut player, true);
+        assert_eq!(
+            player.culture_points,
+            100 + SMALL_CELEBRATION_CULTURE_POINTS + BIG_CELEBRATION_CULTURE_POINTS
+        );
+    }
+}
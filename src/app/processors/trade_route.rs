@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Processor;
+use crate::{
+    app::{
+        commands::send_merchant::load_merchants,
+        events::GameEvent,
+        jobs::{Job, JobTask},
+    },
+    game::models::map::TravelSettings,
+    repository::Repository,
+};
+
+pub struct TradeRouteProcessor {
+    repo: Arc<dyn Repository>,
+    route_id: Uuid,
+    travel: TravelSettings,
+}
+
+impl TradeRouteProcessor {
+    pub fn new(repo: Arc<dyn Repository>, route_id: Uuid, travel: TravelSettings) -> Self {
+        Self {
+            repo,
+            route_id,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for TradeRouteProcessor {
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        let route = match self.repo.get_trade_route_by_id(self.route_id).await? {
+            Some(route) => route,
+            // deleted routes don't run again
+            None => return Ok(vec![]),
+        };
+        let mut village = self.repo.get_village_by_id(route.village_id).await?;
+        let target = self.repo.get_village_by_id(route.target_village_id).await?;
+        let pending = self
+            .repo
+            .get_pending_jobs_by_village_id(route.village_id)
+            .await?;
+
+        let mut events = vec![];
+        // a cycle without enough merchants or resources is skipped, not retried
+        match load_merchants(
+            &mut village,
+            &target,
+            &route.resources,
+            &pending,
+            self.travel,
+        ) {
+            Ok(job) => {
+                self.repo.update_village(village).await?;
+                events.push(GameEvent::JobEnqueued(job));
+            }
+            Err(err) => tracing::info!("Trade route {} skipped: {}", route.id, err),
+        }
+
+        events.push(GameEvent::JobEnqueued(Job::new(
+            route.player_id,
+            route.village_id,
+            route.interval_secs,
+            JobTask::TradeRoute { route_id: route.id },
+        )));
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::TradeRouteProcessor;
+    use crate::{
+        app::{events::GameEvent, jobs::JobTask, processors::Processor},
+        db::test_utils::{insert_valley, setup_repo},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, TravelSettings, WORLD_MAX_SIZE},
+            trade_route::TradeRoute,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+        repository::Repository,
+    };
+
+    // Returns a repository with two villages of the same player and a route between them.
+    async fn setup_route() -> (Arc<dyn Repository>, TradeRoute) {
+        let db = setup_repo().await;
+        let (home, target) = (Position { x: 3, y: 4 }, Position { x: 5, y: 6 });
+        insert_valley(&db, &home).await;
+        insert_valley(&db, &target).await;
+
+        let player = db
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        db.award_culture_points(player.id, "2023-04-01".to_string(), 2000)
+            .await
+            .unwrap();
+        for (position, is_capital) in [(&home, true), (&target, false)] {
+            let valley = db
+                .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let mut village = Village::new("Gino".to_string(), &valley, &player, is_capital);
+            village.buildings.insert(
+                20,
+                Building::new(BuildingName::Marketplace)
+                    .at_level(1)
+                    .unwrap(),
+            );
+            db.found_village(village, None).await.unwrap();
+        }
+
+        let route = TradeRoute::new(
+            player.id,
+            home.to_id(WORLD_MAX_SIZE),
+            target.to_id(WORLD_MAX_SIZE),
+            ResourceGroup::new(200, 200, 0, 0),
+            3600,
+        )
+        .unwrap();
+        db.add_trade_route(route.clone()).await.unwrap();
+
+        (Arc::new(db), route)
+    }
+
+    #[tokio::test]
+    async fn test_trade_route_ships_and_runs_again() {
+        let (repo, route) = setup_route().await;
+
+        let events = TradeRouteProcessor::new(repo.clone(), route.id, TravelSettings::default())
+            .process()
+            .await
+            .unwrap();
+
+        match events.as_slice() {
+            [GameEvent::JobEnqueued(shipment), GameEvent::JobEnqueued(next)] => {
+                assert!(matches!(
+                    shipment.task,
+                    JobTask::MerchantGoing { merchants: 1, .. }
+                ));
+                assert_eq!(next.duration, 3600);
+                assert!(matches!(
+                    next.task,
+                    JobTask::TradeRoute { route_id } if route_id == route.id
+                ));
+            }
+            e => panic!("unexpected events {:?}", e),
+        }
+        let village = repo.get_village_by_id(route.village_id).await.unwrap();
+        assert_eq!(village.resources, ResourceGroup::new(550, 550, 750, 750));
+    }
+
+    #[tokio::test]
+    async fn test_trade_route_skips_when_resources_are_short() {
+        let (repo, route) = setup_route().await;
+        let mut village = repo.get_village_by_id(route.village_id).await.unwrap();
+        village.resources = ResourceGroup::new(100, 500, 500, 500);
+        repo.update_village(village).await.unwrap();
+
+        let events = TradeRouteProcessor::new(repo.clone(), route.id, TravelSettings::default())
+            .process()
+            .await
+            .unwrap();
+
+        match events.as_slice() {
+            [GameEvent::JobEnqueued(next)] => {
+                assert!(matches!(next.task, JobTask::TradeRoute { .. }));
+            }
+            e => panic!("unexpected events {:?}", e),
+        }
+        let village = repo.get_village_by_id(route.village_id).await.unwrap();
+        assert_eq!(village.resources, ResourceGroup::new(100, 500, 500, 500));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_trade_route_stops() {
+        let (repo, route) = setup_route().await;
+        repo.remove_trade_route(route.id).await.unwrap();
+
+        let events = TradeRouteProcessor::new(repo.clone(), route.id, TravelSettings::default())
+            .process()
+            .await
+            .unwrap();
+
+        assert!(events.is_empty());
+        let village = repo.get_village_by_id(route.village_id).await.unwrap();
+        assert_eq!(village.resources, ResourceGroup::new(750, 750, 750, 750));
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use super::Processor;
+use crate::{
+    app::events::{GameEvent, UnitsTrained},
+    game::models::army::UnitName,
+};
+
+pub struct TrainingProcessor {
+    village_id: u32,
+    unit: UnitName,
+    quantity: u32,
+    // builds the event telling which building trained the units
+    event: fn(UnitsTrained) -> GameEvent,
+}
+
+impl TrainingProcessor {
+    pub fn new(
+        village_id: u32,
+        unit: UnitName,
+        quantity: u32,
+        event: fn(UnitsTrained) -> GameEvent,
+    ) -> Self {
+        Self {
+            village_id,
+            unit,
+            quantity,
+            event,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for TrainingProcessor {
+    // The units join the garrison once the event is consumed, in the same run that
+    // marks the job as done.
+    async fn process(&self) -> Result<Vec<GameEvent>> {
+        Ok(vec![(self.event)(UnitsTrained {
+            village_id: self.village_id,
+            unit: self.unit.clone(),
+            quantity: self.quantity,
+        })])
+    }
+}
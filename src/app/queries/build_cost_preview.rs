@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{
+    game::models::{buildings::BuildingName, village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildCostPreview {
+    pub building: BuildingName,
+    pub level: u8,
+    pub cost: ResourceGroup,
+    pub build_time_secs: u32,
+    pub population_delta: u32,
+    pub culture_points_delta: u32,
+    pub upkeep_delta: u32,
+}
+
+pub struct GetBuildCostPreview {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+    server_speed: u8,
+}
+
+impl GetBuildCostPreview {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, slot_id: u8, server_speed: u8) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetBuildCostPreview {
+    type Output = BuildCostPreview;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        build_cost_preview(&village, self.slot_id, self.server_speed)
+    }
+}
+
+// Returns what upgrading the building on the given slot to its next level would cost.
+pub fn build_cost_preview(
+    village: &Village,
+    slot_id: u8,
+    server_speed: u8,
+) -> Result<BuildCostPreview> {
+    let current = village
+        .get_building_by_slot_id(slot_id)
+        .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+    current.validate_upgrade()?;
+    let next = current.next_level()?;
+
+    let main_building_level = village
+        .get_building_by_name(BuildingName::MainBuilding)
+        .map_or(0, |b| b.level);
+
+    let cost = next.cost();
+    let (population, culture_points) = current.get_cumulative_stats();
+    let (next_population, next_culture_points) = next.get_cumulative_stats();
+
+    Ok(BuildCostPreview {
+        building: next.name.clone(),
+        level: next.level,
+        cost: cost.resources,
+        build_time_secs: next.calculate_build_time_secs(main_building_level, server_speed),
+        population_delta: next_population - population,
+        culture_points_delta: next_culture_points - culture_points,
+        upkeep_delta: cost.upkeep,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_cost_preview;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::BuildingName, map::Position, village::Village, ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_preview_resource_fields() {
+        let mut v = village();
+
+        let preview = build_cost_preview(&v, 1, 1).unwrap();
+        assert_eq!(preview.building, BuildingName::Woodcutter);
+        assert_eq!(preview.level, 1);
+        assert_eq!(preview.cost, ResourceGroup::new(40, 100, 50, 60));
+        assert_eq!(preview.build_time_secs, 260);
+        assert_eq!(preview.population_delta, 2);
+        assert_eq!(preview.upkeep_delta, 2);
+        assert_eq!(preview.culture_points_delta, 1);
+
+        v.upgrade_building(1).unwrap();
+        let preview = build_cost_preview(&v, 1, 1).unwrap();
+        assert_eq!(preview.level, 2);
+        assert_eq!(preview.cost, ResourceGroup::new(65, 165, 85, 100));
+        assert_eq!(preview.build_time_secs, 620);
+        assert_eq!(preview.population_delta, 1);
+
+        let preview = build_cost_preview(&v, 13, 1).unwrap();
+        assert_eq!(preview.building, BuildingName::Cropland);
+        assert_eq!(preview.cost, ResourceGroup::new(70, 90, 70, 20));
+        assert_eq!(preview.build_time_secs, 150);
+        assert_eq!(preview.population_delta, 0);
+    }
+
+    #[test]
+    fn test_preview_main_building() {
+        let mut v = village();
+
+        let preview = build_cost_preview(&v, 19, 1).unwrap();
+        assert_eq!(preview.building, BuildingName::MainBuilding);
+        assert_eq!(preview.level, 2);
+        assert_eq!(preview.cost, ResourceGroup::new(90, 50, 75, 25));
+        assert_eq!(preview.build_time_secs, 3220);
+        assert_eq!(preview.population_delta, 1);
+        assert_eq!(preview.culture_points_delta, 3);
+
+        // a higher Main Building and server speed cut down the time
+        v.upgrade_building(19).unwrap();
+        let preview = build_cost_preview(&v, 1, 1).unwrap();
+        assert_eq!(preview.build_time_secs, (260.0 * 0.964f64).floor() as u32);
+        let preview = build_cost_preview(&v, 1, 2).unwrap();
+        assert_eq!(
+            preview.build_time_secs,
+            (260.0 * 0.964f64).floor() as u32 / 2
+        );
+    }
+
+    #[test]
+    fn test_preview_empty_slot() {
+        assert!(build_cost_preview(&village(), 30, 1).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{build_cost_preview::build_cost_preview, Query};
+use crate::{
+    game::models::{village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildPlanCost {
+    pub resources: ResourceGroup,
+    pub time_secs: u64,
+}
+
+// Sums the cost of a plan of upgrades, where each step is the slot to upgrade by one level.
+pub struct GetBuildPlanCost {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    plan: Vec<u8>,
+    server_speed: u8,
+}
+
+impl GetBuildPlanCost {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        plan: Vec<u8>,
+        server_speed: u8,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            plan,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetBuildPlanCost {
+    type Output = BuildPlanCost;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        build_plan_cost(&village, &self.plan, self.server_speed)
+    }
+}
+
+// Simulates the plan on a copy of the village, so that each step is priced with the
+// levels reached so far (e.g. a Main Building upgraded halfway speeds up the next steps).
+pub fn build_plan_cost(village: &Village, plan: &[u8], server_speed: u8) -> Result<BuildPlanCost> {
+    let mut village = village.clone();
+    let mut total = BuildPlanCost {
+        resources: Default::default(),
+        time_secs: 0,
+    };
+
+    for slot_id in plan {
+        let preview = build_cost_preview(&village, *slot_id, server_speed)?;
+        total.resources.add(&preview.cost);
+        total.time_secs += preview.build_time_secs as u64;
+
+        village.upgrade_building(*slot_id)?;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_plan_cost;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{map::Position, village::Village, ResourceGroup, Tribe},
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_plan_cost_uses_main_building_upgraded_within_plan() {
+        let v = village();
+
+        // woodcutter 0 -> 1, main building 1 -> 2, woodcutter 1 -> 2
+        let cost = build_plan_cost(&v, &[1, 19, 1], 1).unwrap();
+
+        assert_eq!(
+            cost.resources,
+            ResourceGroup::new(40 + 90 + 65, 100 + 50 + 165, 50 + 75 + 85, 60 + 25 + 100)
+        );
+        let reduced = (620.0 * 0.964f64).floor() as u64;
+        assert_eq!(cost.time_secs, 260 + 3220 + reduced);
+
+        // the same steps without the main building keep the base time
+        let cost = build_plan_cost(&v, &[1, 1], 1).unwrap();
+        assert_eq!(cost.time_secs, 260 + 620);
+    }
+
+    #[test]
+    fn test_plan_cost_does_not_change_the_village() {
+        let v = village();
+        build_plan_cost(&v, &[1, 1, 19], 1).unwrap();
+
+        assert_eq!(v.get_building_by_slot_id(1).unwrap().level, 0);
+        assert_eq!(v.get_building_by_slot_id(19).unwrap().level, 1);
+        assert!(build_plan_cost(&v, &[30], 1).is_err());
+    }
+}
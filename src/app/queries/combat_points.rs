@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Query;
+use crate::{game::models::report::CombatPoints, repository::Repository};
+
+pub struct GetCombatPoints {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+}
+
+impl GetCombatPoints {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid) -> Self {
+        Self { repo, player_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetCombatPoints {
+    type Output = CombatPoints;
+
+    async fn run(&self) -> Result<Self::Output> {
+        self.repo.get_combat_points(self.player_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::GetCombatPoints;
+    use crate::{
+        app::queries::Query,
+        db::test_utils::setup_repo,
+        game::models::{
+            army::Army,
+            report::{BattleCasualties, CombatPoints, Report, ReportAudience, ReportContent},
+            ResourceGroup, Tribe,
+        },
+        repository::Repository,
+    };
+
+    // A battle where the attacker loses some Clubswingers and kills some Phalanxes.
+    fn battle(attacker: Uuid, defender: Uuid, clubswingers: u32, phalanxes: u32) -> Report {
+        let mut attacker_losses = Army::new(1, attacker, Tribe::Teuton, [0; 10], [0; 10]);
+        attacker_losses.units[0] = clubswingers;
+        let mut defender_losses = Army::new(2, defender, Tribe::Gaul, [0; 10], [0; 10]);
+        defender_losses.units[0] = phalanxes;
+
+        Report::new(
+            attacker,
+            1,
+            defender,
+            2,
+            ReportAudience::Everyone,
+            ReportContent::Battle(Box::new(BattleCasualties {
+                attacker_losses,
+                defender_losses: vec![defender_losses],
+                attacker_sent: [clubswingers, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                attacker_trapped: [0; 10],
+                defenders_before: vec![],
+                loot: ResourceGroup::default(),
+                wall_levels: None,
+            })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_combat_points_add_up_across_battles() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let points = GetCombatPoints::new(repo.clone(), attacker)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(points, CombatPoints::default());
+
+        repo.add_report(battle(attacker, defender, 5, 30))
+            .await
+            .unwrap();
+        repo.add_report(battle(attacker, defender, 2, 12))
+            .await
+            .unwrap();
+
+        let points = GetCombatPoints::new(repo.clone(), attacker)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(
+            points,
+            CombatPoints {
+                attack: 42,
+                defense: 0
+            }
+        );
+        let points = GetCombatPoints::new(repo.clone(), defender)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(
+            points,
+            CombatPoints {
+                attack: 0,
+                defense: 7
+            }
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Query;
+use crate::{
+    game::models::{
+        village::{allowed_villages, culture_points_needed, Village},
+        Player,
+    },
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CulturePoints {
+    // Points accumulated so far.
+    pub total: u32,
+    // Points produced each day by all the villages of the player.
+    pub daily_production: u32,
+    pub villages: u32,
+    pub allowed_villages: u32,
+    // Points to own one more village than the allowed ones.
+    pub next_village_at: u32,
+}
+
+pub struct GetCulturePoints {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+}
+
+impl GetCulturePoints {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid) -> Self {
+        Self { repo, player_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetCulturePoints {
+    type Output = CulturePoints;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let player = self.repo.get_player_by_id(self.player_id).await?;
+        let villages = self.repo.get_villages_by_player_id(self.player_id).await?;
+
+        Ok(culture_points(&player, &villages))
+    }
+}
+
+pub fn culture_points(player: &Player, villages: &[Village]) -> CulturePoints {
+    let allowed = allowed_villages(player.culture_points);
+
+    CulturePoints {
+        total: player.culture_points,
+        daily_production: villages.iter().map(|v| v.culture_points_production()).sum(),
+        villages: villages.len() as u32,
+        allowed_villages: allowed,
+        next_village_at: culture_points_needed(allowed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::GetCulturePoints;
+    use crate::{
+        app::queries::Query,
+        db::test_utils::{insert_valley, setup_repo},
+        game::models::{
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_culture_points_of_player() {
+        let repo = setup_repo().await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        repo.award_culture_points(alice.id, "2023-04-01".to_string(), 8500)
+            .await
+            .unwrap();
+        let mut daily = 0;
+        for position in [Position { x: 3, y: 4 }, Position { x: 5, y: 6 }] {
+            insert_valley(&repo, &position).await;
+            let valley = repo
+                .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let village = Village::new("Alice".to_string(), &valley, &alice, true);
+            daily += village.culture_points_production();
+            repo.found_village(village, None).await.unwrap();
+        }
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let cp = GetCulturePoints::new(repo, alice.id).run().await.unwrap();
+        assert_eq!(cp.total, 8500);
+        assert_eq!(cp.daily_production, daily);
+        assert_eq!(cp.villages, 2);
+        assert_eq!(cp.allowed_villages, 3);
+        assert_eq!(cp.next_village_at, 20000);
+    }
+}
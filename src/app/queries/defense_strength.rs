@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Query;
+use crate::{
+    game::battle::{defense_strength, DefenseStrength},
+    repository::Repository,
+};
+
+pub struct GetDefenseStrength {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl GetDefenseStrength {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetDefenseStrength {
+    type Output = DefenseStrength;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        Ok(defense_strength(&village))
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Query;
+use crate::{game::models::farm_list::FarmList, repository::Repository};
+
+pub struct ListFarmLists {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl ListFarmLists {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for ListFarmLists {
+    type Output = Vec<FarmList>;
+
+    async fn run(&self) -> Result<Self::Output> {
+        self.repo
+            .get_farm_lists_by_village_id(self.village_id)
+            .await
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use super::Query;
+use crate::{
+    game::models::{
+        army::{get_unit_by_name, training_group, UnitGroup, UnitName},
+        buildings::BuildingName,
+        village::Village,
+    },
+    repository::Repository,
+};
+
+// Settlers and chiefs that can be trained for each expansion slot.
+const EXPANSION_UNITS_PER_SLOT: u32 = 3;
+
+pub struct GetMaxTrainable {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    unit: UnitName,
+    building: BuildingName,
+    great_cost_factor: u32,
+}
+
+impl GetMaxTrainable {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        village_id: u32,
+        unit: UnitName,
+        building: BuildingName,
+        great_cost_factor: u32,
+    ) -> Self {
+        Self {
+            repo,
+            village_id,
+            unit,
+            building,
+            great_cost_factor,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetMaxTrainable {
+    type Output = u32;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        max_trainable(&village, &self.unit, &self.building, self.great_cost_factor)
+    }
+}
+
+// Returns how many units the village can afford to train in the given building. Great
+// buildings train the same units at a higher cost.
+pub fn max_trainable(
+    village: &Village,
+    unit: &UnitName,
+    building: &BuildingName,
+    great_cost_factor: u32,
+) -> Result<u32> {
+    let (idx, data) = get_unit_by_name(&village.tribe, unit)
+        .ok_or_else(|| Error::msg("This unit doesn't belong to the village tribe"))?;
+
+    let (group, great) =
+        training_group(building).ok_or_else(|| Error::msg("This building can't train units"))?;
+    let cost_factor = if great { great_cost_factor } else { 1 };
+    if data.group != group {
+        return Err(Error::msg("This unit can't be trained in this building"));
+    }
+    if village.get_building_by_name(building.clone()).is_none() {
+        return Err(Error::msg("The village doesn't have this building"));
+    }
+
+    let cost = &data.cost.resources;
+    let available = &village.resources;
+    let max = [
+        (available.lumber(), cost.lumber()),
+        (available.clay(), cost.clay()),
+        (available.iron(), cost.iron()),
+        (available.crop(), cost.crop()),
+    ]
+    .into_iter()
+    .filter(|(_, cost)| *cost > 0)
+    .map(|(available, cost)| available / (cost * cost_factor))
+    .min()
+    .unwrap_or(0);
+
+    if group == UnitGroup::Expansion {
+        let slots = village.expansion_slots() as u32 * EXPANSION_UNITS_PER_SLOT;
+        let trained = village.army.units[idx as usize];
+        return Ok(max.min(slots.saturating_sub(trained)));
+    }
+
+    Ok(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::max_trainable;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::UnitName,
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        let mut v = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        v.buildings
+            .insert(20, Building::new(BuildingName::Barracks));
+        v.buildings
+            .insert(21, Building::new(BuildingName::GreatBarracks));
+        v
+    }
+
+    #[test]
+    fn test_max_trainable_limited_by_resources() {
+        let v = village();
+
+        // legionnaires cost 120 lumber, 100 clay, 150 iron and 30 crop: iron runs out first
+        assert_eq!(
+            max_trainable(&v, &UnitName::Legionnaire, &BuildingName::Barracks, 3).unwrap(),
+            5
+        );
+        assert_eq!(
+            max_trainable(&v, &UnitName::Legionnaire, &BuildingName::GreatBarracks, 3).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_max_trainable_without_resources() {
+        let mut v = village();
+        v.resources = ResourceGroup::default();
+
+        assert_eq!(
+            max_trainable(&v, &UnitName::Legionnaire, &BuildingName::Barracks, 3).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_max_trainable_wrong_building_or_tribe() {
+        let v = village();
+
+        assert!(max_trainable(&v, &UnitName::EquitesLegati, &BuildingName::Barracks, 3).is_err());
+        assert!(max_trainable(&v, &UnitName::Legionnaire, &BuildingName::Stable, 3).is_err());
+        assert!(max_trainable(&v, &UnitName::Phalanx, &BuildingName::Barracks, 3).is_err());
+        assert!(
+            max_trainable(&v, &UnitName::Settler, &BuildingName::Residence, 3).is_err(),
+            "no residence in the village"
+        );
+    }
+
+    #[test]
+    fn test_max_trainable_limited_by_expansion_slots() {
+        let mut v = village();
+        v.resources = ResourceGroup::new(100000, 100000, 100000, 100000);
+        v.buildings.insert(
+            22,
+            Building::new(BuildingName::Residence).at_level(10).unwrap(),
+        );
+        assert_eq!(
+            max_trainable(&v, &UnitName::Settler, &BuildingName::Residence, 3).unwrap(),
+            3
+        );
+
+        v.army.units[9] = 2;
+        assert_eq!(
+            max_trainable(&v, &UnitName::Settler, &BuildingName::Residence, 3).unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,29 @@
+pub mod build_cost_preview;
+pub mod build_plan_cost;
+pub mod combat_points;
+pub mod culture_points;
+pub mod defense_strength;
+pub mod farm_lists;
+pub mod max_trainable;
+pub mod movement_history;
+pub mod net_crop;
+pub mod next_village;
+pub mod plan_transfer;
+pub mod queue_completion;
+pub mod reports;
+pub mod resource_fields;
+pub mod send_troops_confirmation;
+pub mod trade_routes;
+pub mod upgrade_impact;
+pub mod wonder_progress;
+pub mod world_status;
+
+use anyhow::Result;
+
+// Queries read the game state without producing events.
+#[async_trait::async_trait]
+pub trait Query {
+    type Output: Send;
+
+    async fn run(&self) -> Result<Self::Output>;
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Query;
+use crate::{
+    app::jobs::{Job, JobTask},
+    game::models::ResourceGroup,
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MovementKind {
+    Attack,
+    Raid,
+    Reinforcement,
+    Return,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MovementOutcome {
+    pub units: u32,
+    pub bounty: ResourceGroup,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MovementEntry {
+    pub job_id: Uuid,
+    pub kind: MovementKind,
+    pub origin_village_id: u32,
+    pub target_village_id: u32,
+    pub outcome: MovementOutcome,
+    pub arrived_at: DateTime<Utc>,
+}
+
+impl MovementEntry {
+    // Returns None for jobs that are not army movements.
+    pub fn from_job(job: &Job) -> Option<Self> {
+        let (kind, army, target_village_id, bounty) = match &job.task {
+            JobTask::Attack {
+                army, village_id, ..
+            } => (MovementKind::Attack, army, *village_id, Default::default()),
+            JobTask::Raid {
+                army, village_id, ..
+            } => (MovementKind::Raid, army, *village_id, Default::default()),
+            JobTask::Reinforcement {
+                army, village_id, ..
+            } => (
+                MovementKind::Reinforcement,
+                army,
+                *village_id,
+                Default::default(),
+            ),
+            JobTask::ArmyReturn {
+                army,
+                resources,
+                village_id,
+            } => (MovementKind::Return, army, *village_id, resources.clone()),
+            _ => return None,
+        };
+
+        Some(Self {
+            job_id: job.id,
+            kind,
+            origin_village_id: job.village_id,
+            target_village_id,
+            outcome: MovementOutcome {
+                units: army.immensity(),
+                bounty,
+            },
+            arrived_at: job.ends_at(),
+        })
+    }
+}
+
+pub struct GetMovementHistory {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    page: u32,
+    per_page: u32,
+}
+
+impl GetMovementHistory {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, page: u32, per_page: u32) -> Self {
+        Self {
+            repo,
+            player_id,
+            page,
+            per_page,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetMovementHistory {
+    type Output = Vec<MovementEntry>;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let jobs = self.repo.get_done_jobs_by_player_id(self.player_id).await?;
+
+        Ok(movement_history(
+            &jobs,
+            self.player_id,
+            self.page,
+            self.per_page,
+        ))
+    }
+}
+
+// Returns the completed movements of a player, most recent first. Pages start from 1.
+pub fn movement_history(
+    jobs: &[Job],
+    player_id: Uuid,
+    page: u32,
+    per_page: u32,
+) -> Vec<MovementEntry> {
+    let mut entries: Vec<MovementEntry> = jobs
+        .iter()
+        .filter(|j| j.done && j.player_id == player_id)
+        .filter_map(MovementEntry::from_job)
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.arrived_at));
+
+    let skip = (page.max(1) - 1) as usize * per_page as usize;
+    entries
+        .into_iter()
+        .skip(skip)
+        .take(per_page as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::{movement_history, MovementKind};
+    use crate::{
+        app::jobs::{Job, JobTask},
+        game::models::{army::Army, buildings::BuildingName, Tribe},
+    };
+
+    fn raid_job(player_id: Uuid, minutes_ago: i64) -> Job {
+        let army = Army::new(
+            1,
+            player_id,
+            Tribe::Gaul,
+            [5, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let mut job = Job::new(
+            player_id,
+            1,
+            60,
+            JobTask::Raid {
+                army,
+                village_id: 2,
+                player_id: Uuid::new_v4(),
+            },
+        );
+        job.started_at -= Duration::minutes(minutes_ago);
+        job.done = true;
+        job
+    }
+
+    #[test]
+    fn test_movement_history_pagination() {
+        let player_id = Uuid::new_v4();
+        let jobs: Vec<Job> = (0..5).map(|i| raid_job(player_id, i * 10)).collect();
+
+        let first = movement_history(&jobs, player_id, 1, 2);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].job_id, jobs[0].id, "most recent first");
+        assert_eq!(first[1].job_id, jobs[1].id);
+        assert_eq!(first[0].kind, MovementKind::Raid);
+        assert_eq!(first[0].outcome.units, 5);
+
+        let last = movement_history(&jobs, player_id, 3, 2);
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].job_id, jobs[4].id);
+
+        assert!(movement_history(&jobs, player_id, 4, 2).is_empty());
+    }
+
+    #[test]
+    fn test_movement_history_only_player_movements() {
+        let player_id = Uuid::new_v4();
+        let mut jobs = vec![raid_job(player_id, 0), raid_job(Uuid::new_v4(), 5)];
+
+        let mut pending = raid_job(player_id, 10);
+        pending.done = false;
+        jobs.push(pending);
+
+        let mut upgrade = Job::new(
+            player_id,
+            1,
+            60,
+            JobTask::BuildingUpgrade {
+                slot_id: 1,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        upgrade.done = true;
+        jobs.push(upgrade);
+
+        let history = movement_history(&jobs, player_id, 1, 10);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].job_id, jobs[0].id);
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::Query;
+use crate::{
+    game::models::{village::Village, Player},
+    repository::Repository,
+};
+
+pub struct GetNetCrop {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    free_upkeep: u32,
+}
+
+impl GetNetCrop {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, free_upkeep: u32) -> Self {
+        Self {
+            repo,
+            village_id,
+            free_upkeep,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetNetCrop {
+    type Output = i64;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let player = self.repo.get_player_by_id(village.player_id).await?;
+
+        Ok(net_crop(&village, &player, self.free_upkeep, Utc::now()))
+    }
+}
+
+// Returns the hourly crop of the village after upkeep, net of the free upkeep the
+// owner is entitled to at the given time.
+fn net_crop(village: &Village, player: &Player, free_upkeep: u32, at: DateTime<Utc>) -> i64 {
+    village
+        .production
+        .net_crop(player.free_upkeep_at(free_upkeep, at))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::net_crop;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{map::Position, village::Village, Player, Tribe},
+    };
+
+    fn setup() -> (Village, Player) {
+        let player = Player {
+            protected_until: Some(Utc::now() + Duration::hours(72)),
+            ..test_player(Tribe::Roman)
+        };
+        let village = test_village(&player, Position { x: 10, y: 20 });
+        (village, player)
+    }
+
+    #[test]
+    fn test_free_upkeep_reduces_upkeep() {
+        let (village, player) = setup();
+        let now = Utc::now();
+        let upkeep = village.production.upkeep as i64;
+        let gross = village.production.effective.crop + upkeep;
+
+        assert_eq!(net_crop(&village, &player, 0, now), gross - upkeep);
+        assert_eq!(net_crop(&village, &player, 1, now), gross - upkeep + 1);
+        // the allowance never goes beyond the actual upkeep
+        assert_eq!(net_crop(&village, &player, 1_000, now), gross);
+    }
+
+    #[test]
+    fn test_free_upkeep_ends_with_protection() {
+        let (village, player) = setup();
+        let after_protection = player.protected_until.unwrap() + Duration::seconds(1);
+
+        assert_eq!(
+            net_crop(&village, &player, 10, after_protection),
+            village.production.effective.crop
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Query;
+use crate::{
+    game::models::{
+        army::{get_unit_by_name, UnitName},
+        village::{culture_points_needed, Village, SETTLERS_NEEDED, SETTLER_IDX},
+        Player, ResourceGroup,
+    },
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NextVillageRequirements {
+    pub culture_points_needed: u32,
+    pub culture_points_available: u32,
+    pub settlers_needed: u32,
+    // Settlers of the best stocked village, since they can't be merged across villages.
+    pub settlers_available: u32,
+    // Resources to train the settlers still missing.
+    pub settlers_cost: ResourceGroup,
+    pub expansion_slot_free: bool,
+}
+
+impl NextVillageRequirements {
+    pub fn is_ready(&self) -> bool {
+        self.culture_points_available >= self.culture_points_needed
+            && self.settlers_available >= self.settlers_needed
+            && self.expansion_slot_free
+    }
+}
+
+pub struct GetNextVillageRequirements {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+}
+
+impl GetNextVillageRequirements {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid) -> Self {
+        Self { repo, player_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetNextVillageRequirements {
+    type Output = NextVillageRequirements;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let player = self.repo.get_player_by_id(self.player_id).await?;
+        let villages = self.repo.get_villages_by_player_id(self.player_id).await?;
+
+        Ok(next_village_requirements(&player, &villages))
+    }
+}
+
+// Combines everything a player needs to found the next village.
+pub fn next_village_requirements(player: &Player, villages: &[Village]) -> NextVillageRequirements {
+    let settlers_available = villages
+        .iter()
+        .map(|v| v.army.units[SETTLER_IDX])
+        .max()
+        .unwrap_or(0);
+
+    let missing = SETTLERS_NEEDED.saturating_sub(settlers_available);
+    let settlers_cost = match get_unit_by_name(&player.tribe, &UnitName::Settler) {
+        Some((_, settler)) => {
+            let r = settler.cost.resources;
+            ResourceGroup::new(
+                r.lumber() * missing,
+                r.clay() * missing,
+                r.iron() * missing,
+                r.crop() * missing,
+            )
+        }
+        None => Default::default(),
+    };
+
+    NextVillageRequirements {
+        culture_points_needed: culture_points_needed(villages.len() as u32),
+        culture_points_available: player.culture_points,
+        settlers_needed: SETTLERS_NEEDED,
+        settlers_available,
+        settlers_cost,
+        expansion_slot_free: villages
+            .iter()
+            .any(|v| v.free_expansion_slots(villages) > 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::next_village_requirements;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::{Village, SETTLER_IDX},
+            Player, ResourceGroup, Tribe,
+        },
+    };
+
+    fn player(culture_points: u32) -> Player {
+        Player {
+            culture_points,
+            ..test_player(Tribe::Roman)
+        }
+    }
+
+    fn village(player: &Player) -> Village {
+        test_village(player, Position { x: 10, y: 20 })
+    }
+
+    // A village with a level 10 residence and the given settlers.
+    fn expanding_village(player: &Player, settlers: u32) -> Village {
+        let mut v = village(player);
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(10).unwrap(),
+        );
+        v.army.units[SETTLER_IDX] = settlers;
+        v
+    }
+
+    #[test]
+    fn test_ready_to_found() {
+        let player = player(2000);
+        let req = next_village_requirements(&player, &[expanding_village(&player, 3)]);
+
+        assert_eq!(req.culture_points_needed, 2000);
+        assert_eq!(req.settlers_available, 3);
+        assert_eq!(req.settlers_cost, ResourceGroup::default());
+        assert!(req.expansion_slot_free);
+        assert!(req.is_ready());
+    }
+
+    #[test]
+    fn test_missing_culture_points() {
+        let player = player(1999);
+        let req = next_village_requirements(&player, &[expanding_village(&player, 3)]);
+
+        assert_eq!(req.culture_points_available, 1999);
+        assert!(!req.is_ready());
+
+        // the third village costs more
+        let player = Player {
+            culture_points: 2000,
+            ..player
+        };
+        let villages = [expanding_village(&player, 3), village(&player)];
+        let req = next_village_requirements(&player, &villages);
+        assert_eq!(req.culture_points_needed, 8000);
+        assert!(!req.is_ready());
+    }
+
+    #[test]
+    fn test_missing_settlers() {
+        let player = player(2000);
+        let req = next_village_requirements(&player, &[expanding_village(&player, 1)]);
+
+        // two Roman settlers still to train
+        assert_eq!(req.settlers_available, 1);
+        assert_eq!(
+            req.settlers_cost,
+            ResourceGroup::new(9200, 8400, 11600, 8800)
+        );
+        assert!(!req.is_ready());
+    }
+
+    #[test]
+    fn test_missing_expansion_slot() {
+        let player = player(2000);
+        let mut v = village(&player);
+        v.army.units[SETTLER_IDX] = 3;
+        let req = next_village_requirements(&player, &[v]);
+
+        assert!(!req.expansion_slot_free);
+        assert!(!req.is_ready());
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{
+    game::models::{merchant::merchants_needed, village::Village, ResourceGroup},
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferPlan {
+    // Resources each merchant carries, Trade Office included.
+    pub merchant_capacity: u32,
+    pub merchants_needed: u32,
+    pub merchants_available: u32,
+    // Trips needed with every available merchant on the move, 0 without merchants.
+    pub trips: u32,
+    // True when the whole amount can be sent right away, in a single trip.
+    pub enough_merchants: bool,
+}
+
+pub struct PlanTransfer {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    amount: ResourceGroup,
+}
+
+impl PlanTransfer {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, amount: ResourceGroup) -> Self {
+        Self {
+            repo,
+            village_id,
+            amount,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for PlanTransfer {
+    type Output = TransferPlan;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+
+        Ok(plan_transfer(&village, &self.amount))
+    }
+}
+
+// Splits the amount across the village merchants.
+fn plan_transfer(village: &Village, amount: &ResourceGroup) -> TransferPlan {
+    let merchant_capacity = village.merchant_capacity();
+    let needed = merchants_needed(merchant_capacity, amount);
+    let available = village.merchants();
+    let trips = match available {
+        0 => 0,
+        _ => (needed + available - 1) / available,
+    };
+
+    TransferPlan {
+        merchant_capacity,
+        merchants_needed: needed,
+        merchants_available: available,
+        trips,
+        enough_merchants: needed <= available,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plan_transfer;
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::Position,
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        let mut village = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::Marketplace)
+                .at_level(2)
+                .unwrap(),
+        );
+        village
+    }
+
+    #[test]
+    fn test_transfer_needing_multiple_trips() {
+        let v = village();
+        // 2 Roman merchants carry 500 each
+        let plan = plan_transfer(&v, &ResourceGroup::new(1000, 1000, 500, 0));
+
+        assert_eq!(plan.merchant_capacity, 500);
+        assert_eq!(plan.merchants_needed, 5);
+        assert_eq!(plan.merchants_available, 2);
+        assert_eq!(plan.trips, 3);
+        assert!(!plan.enough_merchants);
+    }
+
+    #[test]
+    fn test_trade_office_raises_capacity() {
+        let mut v = village();
+        v.buildings.insert(
+            21,
+            Building::new(BuildingName::TradeOffice)
+                .at_level(20)
+                .unwrap(),
+        );
+        let plan = plan_transfer(&v, &ResourceGroup::new(1000, 1000, 500, 0));
+
+        assert_eq!(plan.merchant_capacity, 1500);
+        assert_eq!(plan.merchants_needed, 2);
+        assert_eq!(plan.trips, 1);
+        assert!(plan.enough_merchants);
+    }
+
+    #[test]
+    fn test_transfer_without_merchants() {
+        let mut v = village();
+        v.buildings.remove(&20);
+        let plan = plan_transfer(&v, &ResourceGroup::new(100, 0, 0, 0));
+
+        assert_eq!(plan.merchants_available, 0);
+        assert_eq!(plan.trips, 0);
+        assert!(!plan.enough_merchants);
+    }
+}
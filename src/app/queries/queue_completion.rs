@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{
+    app::jobs::{Job, JobTask},
+    game::models::Tribe,
+    repository::Repository,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum QueueLane {
+    // Romans build resource fields and village buildings at the same time.
+    ResourceFields,
+    Infrastructure,
+    Construction,
+    Training,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LaneCompletion {
+    pub lane: QueueLane,
+    pub completed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QueueCompletion {
+    // When the whole queue is done, None when there's nothing queued.
+    pub completed_at: Option<DateTime<Utc>>,
+    // Breakdown of the lanes working in parallel, only for Romans.
+    pub lanes: Vec<LaneCompletion>,
+}
+
+pub struct GetQueueCompletionTime {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl GetQueueCompletionTime {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetQueueCompletionTime {
+    type Output = QueueCompletion;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let jobs = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        Ok(queue_completion(&jobs, &village.tribe))
+    }
+}
+
+// Returns the queue lane of a build or training job.
+pub fn lane(task: &JobTask, tribe: &Tribe) -> Option<QueueLane> {
+    match task {
+        JobTask::BuildingUpgrade { slot_id, .. } | JobTask::BuildingDowngrade { slot_id, .. } => {
+            match tribe {
+                Tribe::Roman if *slot_id <= 18 => Some(QueueLane::ResourceFields),
+                Tribe::Roman => Some(QueueLane::Infrastructure),
+                _ => Some(QueueLane::Construction),
+            }
+        }
+        JobTask::TrainBarracks { .. }
+        | JobTask::TrainGreatBarracks { .. }
+        | JobTask::TrainStable { .. }
+        | JobTask::TrainGreatStable { .. }
+        | JobTask::TrainWorkshop { .. }
+        | JobTask::TrainGreatWorkshop { .. }
+        | JobTask::TrainExpansion { .. } => Some(QueueLane::Training),
+        _ => None,
+    }
+}
+
+// Returns true when the lane the task would run on is busy. Villages build one thing at a
+// time, Romans one resource field and one village building.
+pub fn queue_full(jobs: &[Job], task: &JobTask, tribe: &Tribe) -> bool {
+    let task_lane = match lane(task, tribe) {
+        Some(lane) => lane,
+        None => return false,
+    };
+
+    jobs.iter()
+        .filter(|j| !j.done)
+        .any(|j| lane(&j.task, tribe) == Some(task_lane))
+}
+
+// Returns when the last queued build or training job of the village completes.
+pub fn queue_completion(jobs: &[Job], tribe: &Tribe) -> QueueCompletion {
+    let mut lanes: Vec<LaneCompletion> = vec![];
+
+    for job in jobs.iter().filter(|j| !j.done) {
+        let lane = match lane(&job.task, tribe) {
+            Some(lane) => lane,
+            None => continue,
+        };
+        let ends_at = job.ends_at();
+        match lanes.iter_mut().find(|l| l.lane == lane) {
+            Some(l) => l.completed_at = l.completed_at.max(ends_at),
+            None => lanes.push(LaneCompletion {
+                lane,
+                completed_at: ends_at,
+            }),
+        }
+    }
+
+    let completed_at = lanes.iter().map(|l| l.completed_at).max();
+    if *tribe != Tribe::Roman {
+        lanes.clear();
+    }
+
+    QueueCompletion {
+        completed_at,
+        lanes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{queue_completion, queue_full, QueueLane};
+    use crate::{
+        app::jobs::{Job, JobTask},
+        game::models::{army::UnitName, buildings::BuildingName, Tribe},
+    };
+
+    fn upgrade(slot_id: u8, building_name: BuildingName, duration: u64) -> Job {
+        Job::new(
+            Uuid::new_v4(),
+            1,
+            duration,
+            JobTask::BuildingUpgrade {
+                slot_id,
+                building_name,
+                target_level: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_single_lane_completion() {
+        let mut jobs = vec![
+            upgrade(1, BuildingName::Woodcutter, 600),
+            upgrade(19, BuildingName::Warehouse, 1200),
+        ];
+        // done jobs are not part of the queue
+        jobs[1].done = true;
+        let last = jobs[0].ends_at();
+
+        let completion = queue_completion(&jobs, &Tribe::Teuton);
+        assert_eq!(completion.completed_at, Some(last));
+        assert!(completion.lanes.is_empty());
+
+        assert!(queue_completion(&[], &Tribe::Gaul).completed_at.is_none());
+    }
+
+    #[test]
+    fn test_roman_dual_lane_completion() {
+        let training = Job::new(
+            Uuid::new_v4(),
+            1,
+            300,
+            JobTask::TrainBarracks {
+                slot_id: 20,
+                unit: UnitName::Legionnaire,
+                quantity: 3,
+                time_per_unit_secs: 100,
+            },
+        );
+        let jobs = vec![
+            upgrade(1, BuildingName::Woodcutter, 600),
+            upgrade(2, BuildingName::ClayPit, 900),
+            upgrade(19, BuildingName::Warehouse, 1200),
+            training,
+        ];
+
+        let completion = queue_completion(&jobs, &Tribe::Roman);
+        assert_eq!(completion.completed_at, Some(jobs[2].ends_at()));
+
+        let lane_end = |lane: QueueLane| {
+            completion
+                .lanes
+                .iter()
+                .find(|l| l.lane == lane)
+                .map(|l| l.completed_at)
+        };
+        assert_eq!(lane_end(QueueLane::ResourceFields), Some(jobs[1].ends_at()));
+        assert_eq!(lane_end(QueueLane::Infrastructure), Some(jobs[2].ends_at()));
+        assert_eq!(lane_end(QueueLane::Training), Some(jobs[3].ends_at()));
+        assert_eq!(lane_end(QueueLane::Construction), None);
+    }
+
+    #[test]
+    fn test_roman_builds_a_field_and_a_building_together() {
+        let field = upgrade(1, BuildingName::Woodcutter, 600);
+        let building = upgrade(19, BuildingName::Warehouse, 1200);
+        let other_field = upgrade(2, BuildingName::ClayPit, 600);
+
+        let jobs = vec![field.clone()];
+        assert!(!queue_full(&jobs, &building.task, &Tribe::Roman));
+        assert!(queue_full(&jobs, &other_field.task, &Tribe::Roman));
+
+        let jobs = vec![field.clone(), building.clone()];
+        assert!(queue_full(&jobs, &other_field.task, &Tribe::Roman));
+        assert!(queue_full(&jobs, &building.task, &Tribe::Roman));
+    }
+
+    #[test]
+    fn test_other_tribes_build_one_thing_at_a_time() {
+        let mut jobs = vec![upgrade(1, BuildingName::Woodcutter, 600)];
+        let building = upgrade(19, BuildingName::Warehouse, 1200);
+
+        assert!(queue_full(&jobs, &building.task, &Tribe::Teuton));
+        assert!(queue_full(&jobs, &building.task, &Tribe::Gaul));
+
+        // the lane frees up once the job is done
+        jobs[0].done = true;
+        assert!(!queue_full(&jobs, &building.task, &Tribe::Teuton));
+        assert!(!queue_full(&[], &building.task, &Tribe::Teuton));
+    }
+}
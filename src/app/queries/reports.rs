@@ -0,0 +1,372 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Query;
+use crate::{
+    game::models::report::{Report, ReportFilter, ReportSort, ReportView},
+    repository::Repository,
+};
+
+pub struct GetReportDetail {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    report_id: Uuid,
+}
+
+impl GetReportDetail {
+    pub fn new(repo: Arc<dyn Repository>, player_id: Uuid, report_id: Uuid) -> Self {
+        Self {
+            repo,
+            player_id,
+            report_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetReportDetail {
+    type Output = ReportView;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let report = self.repo.get_report_by_id(self.report_id).await?;
+
+        // hidden reports look the same as missing ones
+        report
+            .view_for(self.player_id)
+            .ok_or_else(|| Error::msg("Report not found."))
+    }
+}
+
+// Most reports a single page can hold.
+pub const MAX_REPORTS_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportPage {
+    pub reports: Vec<ReportView>,
+    // Reports the player can see with the filter, over all the pages.
+    pub total: u32,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+pub struct ListReports {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    filter: ReportFilter,
+    page: u32,
+    per_page: u32,
+    sort: ReportSort,
+}
+
+impl ListReports {
+    // Pages start from 1.
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        filter: ReportFilter,
+        page: u32,
+        per_page: u32,
+        sort: ReportSort,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            filter,
+            page,
+            per_page,
+            sort,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for ListReports {
+    type Output = ReportPage;
+
+    async fn run(&self) -> Result<Self::Output> {
+        if self.page == 0 {
+            return Err(Error::msg("Pages start from 1."));
+        }
+        if self.per_page == 0 || self.per_page > MAX_REPORTS_PER_PAGE {
+            return Err(Error::msg(format!(
+                "Pages hold from 1 to {} reports.",
+                MAX_REPORTS_PER_PAGE
+            )));
+        }
+
+        let offset = (self.page - 1).saturating_mul(self.per_page);
+        let (reports, total) = self
+            .repo
+            .list_reports(
+                self.player_id,
+                &self.filter,
+                offset,
+                self.per_page,
+                self.sort,
+            )
+            .await?;
+
+        Ok(ReportPage {
+            reports: visible_reports(&reports, self.player_id),
+            total,
+            page: self.page,
+            per_page: self.per_page,
+        })
+    }
+}
+
+// Returns what the player can see of the given reports, skipping the hidden ones.
+pub fn visible_reports(reports: &[Report], player_id: Uuid) -> Vec<ReportView> {
+    reports
+        .iter()
+        .filter_map(|r| r.view_for(player_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::{GetReportDetail, ListReports, ReportPage};
+    use crate::{
+        app::queries::Query,
+        db::test_utils::setup_repo,
+        game::models::{
+            report::{
+                MerchantDelivery, Report, ReportAudience, ReportContent, ReportFilter, ReportKind,
+                ReportSort, ReportView, ScoutingIntel,
+            },
+            ResourceGroup,
+        },
+        repository::Repository,
+    };
+
+    // Stores deliveries of 1, 2, ... lumber to the player, one minute apart.
+    async fn deliveries(repo: &Arc<dyn Repository>, player_id: Uuid, count: u32) {
+        let start = Utc::now() - Duration::hours(1);
+        for i in 1..=count {
+            let mut report = Report::new(
+                Uuid::new_v4(),
+                1,
+                player_id,
+                2,
+                ReportAudience::Everyone,
+                ReportContent::Delivery(MerchantDelivery {
+                    delivered: ResourceGroup::new(i, 0, 0, 0),
+                    stored: ResourceGroup::default(),
+                }),
+            );
+            report.created_at = start + Duration::minutes(i as i64);
+            repo.add_report(report).await.unwrap();
+        }
+    }
+
+    // Returns the lumber delivered in each report of the page.
+    fn lumber(page: &ReportPage) -> Vec<u32> {
+        page.reports
+            .iter()
+            .map(|v| match v {
+                ReportView::Full(r) => match &r.content {
+                    ReportContent::Delivery(d) => d.delivered.lumber(),
+                    c => panic!("unexpected report content {:?}", c),
+                },
+                v => panic!("unexpected report view {:?}", v),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_reports_pages() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let player = Uuid::new_v4();
+        deliveries(&repo, player, 5).await;
+        let list = |page, per_page, sort| {
+            ListReports::new(
+                repo.clone(),
+                player,
+                ReportFilter::default(),
+                page,
+                per_page,
+                sort,
+            )
+        };
+
+        let first = list(1, 2, ReportSort::NewestFirst).run().await.unwrap();
+        assert_eq!(lumber(&first), vec![5, 4]);
+        assert_eq!(first.total, 5);
+
+        let last = list(3, 2, ReportSort::NewestFirst).run().await.unwrap();
+        assert_eq!(lumber(&last), vec![1], "the last page is not full");
+
+        let oldest = list(2, 2, ReportSort::OldestFirst).run().await.unwrap();
+        assert_eq!(lumber(&oldest), vec![3, 4]);
+
+        let all = list(1, 5, ReportSort::OldestFirst).run().await.unwrap();
+        assert_eq!(lumber(&all), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_page_past_the_end() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let player = Uuid::new_v4();
+        deliveries(&repo, player, 4).await;
+        let filter = ReportFilter::default();
+
+        let page = ListReports::new(
+            repo.clone(),
+            player,
+            filter.clone(),
+            3,
+            2,
+            ReportSort::NewestFirst,
+        )
+        .run()
+        .await
+        .unwrap();
+        assert!(page.reports.is_empty());
+        assert_eq!(page.total, 4);
+
+        let sort = ReportSort::NewestFirst;
+        assert!(
+            ListReports::new(repo.clone(), player, filter.clone(), 0, 2, sort)
+                .run()
+                .await
+                .is_err()
+        );
+        assert!(
+            ListReports::new(repo.clone(), player, filter.clone(), 1, 0, sort)
+                .run()
+                .await
+                .is_err()
+        );
+        assert!(
+            ListReports::new(repo.clone(), player, filter.clone(), 1, 101, sort)
+                .run()
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undetected_scouting_is_hidden_from_defender() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let report = Report::new(
+            attacker,
+            1,
+            defender,
+            2,
+            ReportAudience::Spy { detected: false },
+            ReportContent::Scouting(ScoutingIntel {
+                resources: ResourceGroup::new(10, 20, 30, 40),
+                units: [0; 10],
+                ..Default::default()
+            }),
+        );
+        repo.add_report(report.clone()).await.unwrap();
+
+        let page = ListReports::new(
+            repo.clone(),
+            attacker,
+            ReportFilter::default(),
+            1,
+            10,
+            ReportSort::NewestFirst,
+        )
+        .run()
+        .await
+        .unwrap();
+        assert_eq!(page.reports.len(), 1);
+        assert!(matches!(page.reports[0], ReportView::Full(_)));
+
+        let page = ListReports::new(
+            repo.clone(),
+            defender,
+            ReportFilter::default(),
+            1,
+            10,
+            ReportSort::NewestFirst,
+        )
+        .run()
+        .await
+        .unwrap();
+        assert!(page.reports.is_empty());
+        assert_eq!(page.total, 0, "hidden reports are not counted");
+
+        let detail = GetReportDetail::new(repo.clone(), attacker, report.id)
+            .run()
+            .await;
+        assert!(detail.is_ok());
+        let detail = GetReportDetail::new(repo.clone(), defender, report.id)
+            .run()
+            .await;
+        assert!(detail.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reports_filtered_by_kind_and_read() {
+        let repo: Arc<dyn Repository> = Arc::new(setup_repo().await);
+        let player = Uuid::new_v4();
+        deliveries(&repo, player, 3).await;
+
+        let scouting = Report::new(
+            player,
+            1,
+            Uuid::new_v4(),
+            2,
+            ReportAudience::Spy { detected: true },
+            ReportContent::Scouting(ScoutingIntel::default()),
+        );
+        repo.add_report(scouting.clone()).await.unwrap();
+
+        let list = |kind, unread_only| {
+            ListReports::new(
+                repo.clone(),
+                player,
+                ReportFilter {
+                    kind,
+                    unread_only,
+                    archived: false,
+                },
+                1,
+                10,
+                ReportSort::OldestFirst,
+            )
+        };
+
+        let trades = list(Some(ReportKind::Trade), false).run().await.unwrap();
+        assert_eq!(lumber(&trades), vec![1, 2, 3]);
+        assert_eq!(trades.total, 3);
+
+        let scoutings = list(Some(ReportKind::Scouting), false).run().await.unwrap();
+        assert_eq!(scoutings.total, 1);
+        let battles = list(Some(ReportKind::Battle), false).run().await.unwrap();
+        assert_eq!(battles.total, 0);
+
+        let (trades, _) = repo
+            .list_reports(
+                player,
+                &ReportFilter::default(),
+                0,
+                10,
+                ReportSort::OldestFirst,
+            )
+            .await
+            .unwrap();
+        repo.mark_report_read(trades[1].id, player).await.unwrap();
+        repo.mark_report_read(scouting.id, player).await.unwrap();
+
+        let unread = list(None, true).run().await.unwrap();
+        assert_eq!(lumber(&unread), vec![1, 3]);
+        assert_eq!(unread.total, 2);
+        let unread = list(Some(ReportKind::Scouting), true).run().await.unwrap();
+        assert_eq!(unread.total, 0);
+    }
+}
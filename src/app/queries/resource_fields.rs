@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{
+    app::jobs::{Job, JobTask},
+    game::models::{buildings::BuildingName, village::Village},
+    repository::Repository,
+};
+
+// Resource fields always occupy the first 18 slots of a village.
+const RESOURCE_SLOTS: u8 = 18;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FieldStatus {
+    Upgradable,
+    Upgrading,
+    MaxLevel,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResourceField {
+    pub slot_id: u8,
+    pub building: BuildingName,
+    pub level: u8,
+    pub production: u32,
+    pub status: FieldStatus,
+}
+
+pub struct GetResourceFields {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl GetResourceFields {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetResourceFields {
+    type Output = Vec<ResourceField>;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let jobs = self
+            .repo
+            .get_pending_jobs_by_village_id(self.village_id)
+            .await?;
+
+        Ok(resource_fields(&village, &jobs))
+    }
+}
+
+// Returns the resource fields of a village ordered by slot. Production includes the
+// village bonuses, the same way the effective production is calculated.
+pub fn resource_fields(village: &Village, pending_jobs: &[Job]) -> Vec<ResourceField> {
+    let bonus = &village.production.bonus;
+
+    (1..=RESOURCE_SLOTS)
+        .filter_map(|slot_id| {
+            let b = village.get_building_by_slot_id(slot_id)?;
+
+            let bonus_percent = match b.name {
+                BuildingName::Woodcutter => bonus.lumber,
+                BuildingName::ClayPit => bonus.clay,
+                BuildingName::IronMine => bonus.iron,
+                BuildingName::Cropland => bonus.crop,
+                _ => return None,
+            };
+            let production =
+                (b.value as f64 * ((bonus_percent as f64 / 100.0) + 1.0)).floor() as u32;
+
+            let upgrading = pending_jobs.iter().any(
+                |j| matches!(j.task, JobTask::BuildingUpgrade { slot_id: s, .. } if s == slot_id),
+            );
+            let status = if upgrading {
+                FieldStatus::Upgrading
+            } else if b.validate_upgrade().is_err() {
+                FieldStatus::MaxLevel
+            } else {
+                FieldStatus::Upgradable
+            };
+
+            Some(ResourceField {
+                slot_id,
+                building: b.name.clone(),
+                level: b.level,
+                production,
+                status,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resource_fields, FieldStatus};
+    use crate::{
+        app::jobs::{Job, JobTask},
+        db::test_utils::{test_player, test_village},
+        game::models::{buildings::BuildingName, map::Position, village::Village, Tribe},
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_resource_fields_with_mixed_levels() {
+        let mut v = village();
+        v.upgrade_building(1).unwrap();
+        v.upgrade_building(1).unwrap();
+        v.upgrade_building(5).unwrap();
+        v.upgrade_building(13).unwrap();
+
+        let fields = resource_fields(&v, &[]);
+        assert_eq!(fields.len(), 18, "main building is not a resource field");
+
+        assert_eq!(fields[0].slot_id, 1);
+        assert_eq!(fields[0].building, BuildingName::Woodcutter);
+        assert_eq!(fields[0].level, 2);
+        assert_eq!(fields[0].production, 9);
+
+        assert_eq!(fields[1].level, 0);
+        assert_eq!(fields[1].production, 2);
+
+        assert_eq!(fields[4].building, BuildingName::ClayPit);
+        assert_eq!(fields[4].level, 1);
+
+        assert_eq!(fields[12].building, BuildingName::Cropland);
+        assert_eq!(fields[12].level, 1);
+        assert_eq!(fields[12].production, 5);
+
+        // fields add up to the village production
+        let lumber: u32 = fields
+            .iter()
+            .filter(|f| f.building == BuildingName::Woodcutter)
+            .map(|f| f.production)
+            .sum();
+        assert_eq!(lumber, v.production.lumber);
+    }
+
+    #[test]
+    fn test_resource_fields_production_bonus() {
+        let mut v = village();
+        v.production.bonus.crop = 25;
+
+        let fields = resource_fields(&v, &[]);
+        assert_eq!(fields[0].production, 2);
+        assert_eq!(fields[12].production, 2);
+
+        v.upgrade_building(13).unwrap();
+        v.production.bonus.crop = 25;
+        let fields = resource_fields(&v, &[]);
+        assert_eq!(fields[12].production, 6);
+    }
+
+    #[test]
+    fn test_resource_fields_upgrade_status() {
+        let v = village();
+        let job = Job::new(
+            v.player_id,
+            v.id,
+            260,
+            JobTask::BuildingUpgrade {
+                slot_id: 3,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+
+        let fields = resource_fields(&v, &[job]);
+        assert_eq!(fields[2].status, FieldStatus::Upgrading);
+        assert_eq!(fields[0].status, FieldStatus::Upgradable);
+    }
+}
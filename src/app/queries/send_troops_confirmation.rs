@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Query;
+use crate::{
+    app::commands::send_troops::{movement_job, MovementKind},
+    game::models::{army::TroopSet, map::TravelSettings},
+    repository::Repository,
+};
+
+// What the player confirms before troops leave the Rally Point.
+#[derive(Debug, Clone, Serialize)]
+pub struct SendTroopsConfirmation {
+    pub kind: MovementKind,
+    pub village_id: u32,
+    pub target_village_id: u32,
+    pub target_player_id: Uuid,
+    pub units: TroopSet,
+    pub travel_secs: u64,
+    pub arrives_at: DateTime<Utc>,
+}
+
+pub struct GetSendTroopsConfirmation {
+    repo: Arc<dyn Repository>,
+    player_id: Uuid,
+    village_id: u32,
+    target_village_id: u32,
+    units: TroopSet,
+    kind: MovementKind,
+    travel: TravelSettings,
+}
+
+impl GetSendTroopsConfirmation {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        units: TroopSet,
+        kind: MovementKind,
+        travel: TravelSettings,
+    ) -> Self {
+        Self {
+            repo,
+            player_id,
+            village_id,
+            target_village_id,
+            units,
+            kind,
+            travel,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetSendTroopsConfirmation {
+    type Output = SendTroopsConfirmation;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        if village.player_id != self.player_id {
+            return Err(Error::msg("This village doesn't belong to the player."));
+        }
+        let target = self.repo.get_village_by_id(self.target_village_id).await?;
+
+        // the job is only built to know when it would arrive, nothing is sent yet
+        let job = movement_job(&village, &target, self.units, &self.kind, self.travel)?;
+
+        Ok(SendTroopsConfirmation {
+            kind: self.kind.clone(),
+            village_id: village.id,
+            target_village_id: target.id,
+            target_player_id: target.player_id,
+            units: self.units,
+            travel_secs: job.duration,
+            arrives_at: job.ends_at(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::GetSendTroopsConfirmation;
+    use crate::{
+        app::{commands::send_troops::MovementKind, queries::Query},
+        config::Config,
+        db::test_utils::{insert_valley, setup_repo},
+        game::models::{
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    #[tokio::test]
+    async fn test_confirmation_reflects_the_movement() {
+        let db = setup_repo().await;
+        let mut villages = vec![];
+        for (name, x) in [("alice", 1), ("bob", 4)] {
+            let position = Position { x, y: 0 };
+            insert_valley(&db, &position).await;
+            let player = db
+                .register_player(name.to_string(), Tribe::Gaul)
+                .await
+                .unwrap();
+            let valley = db
+                .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+                .await
+                .unwrap();
+            let village = Village::new(name.to_string(), &valley, &player, true);
+            db.found_village(village.clone(), None).await.unwrap();
+            villages.push(village);
+        }
+        let (mut home, target) = (villages[0].clone(), villages[1].clone());
+        home.army.units = [0, 0, 0, 5, 0, 0, 0, 0, 0, 0];
+        db.update_village(home.clone()).await.unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(db);
+
+        let travel = Config::default().travel_settings();
+        let units = [0, 0, 0, 5, 0, 0, 0, 0, 0, 0];
+        let confirmation = GetSendTroopsConfirmation::new(
+            repo.clone(),
+            home.player_id,
+            home.id,
+            target.id,
+            units,
+            MovementKind::Raid,
+            travel,
+        )
+        .run()
+        .await
+        .unwrap();
+
+        assert!(matches!(confirmation.kind, MovementKind::Raid));
+        assert_eq!(confirmation.target_player_id, target.player_id);
+        // Theutates Thunders walk 19 fields per hour: 3 fields take 568 seconds
+        assert_eq!(confirmation.travel_secs, 568);
+
+        let stored = repo.get_village_by_id(home.id).await.unwrap();
+        assert_eq!(stored.army.units, units, "nothing leaves the village");
+    }
+}
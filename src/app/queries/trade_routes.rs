@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::Query;
+use crate::{game::models::trade_route::TradeRoute, repository::Repository};
+
+pub struct ListTradeRoutes {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+}
+
+impl ListTradeRoutes {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32) -> Self {
+        Self { repo, village_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for ListTradeRoutes {
+    type Output = Vec<TradeRoute>;
+
+    async fn run(&self) -> Result<Self::Output> {
+        self.repo
+            .get_trade_routes_by_village_id(self.village_id)
+            .await
+    }
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{
+    game::models::buildings::{Building, BuildingName, MAIN_BUILDING_TIME_FACTOR},
+    repository::Repository,
+};
+
+// What the value of a building stands for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ImpactKind {
+    // Resources produced per hour.
+    Production,
+    // Percent bonus on the production of the matching resource fields.
+    ProductionBonus,
+    // Resources the village can store.
+    Storage,
+    // Resources hidden from raids.
+    HiddenResources,
+    // Percent bonus on the village defense.
+    Defense,
+    // Percent of the base training time.
+    TrainingTime,
+    // Percent of the base construction time.
+    ConstructionTime,
+    // Percent of the base troop speed on long distances.
+    TroopSpeed,
+    // Percent of the base merchant capacity.
+    MerchantCapacity,
+    // Nothing that can be measured, e.g. it only unlocks other buildings.
+    None,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildingUpgradeImpact {
+    pub building: BuildingName,
+    pub level: u8,
+    pub kind: ImpactKind,
+    pub current: u32,
+    pub next: u32,
+}
+
+impl BuildingUpgradeImpact {
+    pub fn delta(&self) -> i64 {
+        self.next as i64 - self.current as i64
+    }
+}
+
+pub struct GetBuildingUpgradeImpact {
+    repo: Arc<dyn Repository>,
+    village_id: u32,
+    slot_id: u8,
+}
+
+impl GetBuildingUpgradeImpact {
+    pub fn new(repo: Arc<dyn Repository>, village_id: u32, slot_id: u8) -> Self {
+        Self {
+            repo,
+            village_id,
+            slot_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetBuildingUpgradeImpact {
+    type Output = BuildingUpgradeImpact;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let village = self.repo.get_village_by_id(self.village_id).await?;
+        let building = village
+            .get_building_by_slot_id(self.slot_id)
+            .ok_or_else(|| Error::msg("No building on this slot."))?;
+
+        upgrade_impact(&building)
+    }
+}
+
+// Returns the effect of upgrading the building to its next level, it fails when the
+// building is already at its max level.
+pub fn upgrade_impact(building: &Building) -> Result<BuildingUpgradeImpact> {
+    let next = building.next_level()?;
+    let kind = impact_kind(&building.name);
+
+    let (current, next) = match kind {
+        ImpactKind::ConstructionTime => (
+            construction_time_percent(building.level),
+            construction_time_percent(next.level),
+        ),
+        ImpactKind::None => (0, 0),
+        _ => (building.value, next.value),
+    };
+
+    Ok(BuildingUpgradeImpact {
+        building: building.name.clone(),
+        level: building.level,
+        kind,
+        current,
+        next,
+    })
+}
+
+fn impact_kind(name: &BuildingName) -> ImpactKind {
+    match name {
+        BuildingName::Woodcutter
+        | BuildingName::ClayPit
+        | BuildingName::IronMine
+        | BuildingName::Cropland => ImpactKind::Production,
+        BuildingName::Sawmill
+        | BuildingName::Brickyard
+        | BuildingName::IronFoundry
+        | BuildingName::GrainMill
+        | BuildingName::Bakery => ImpactKind::ProductionBonus,
+        BuildingName::Warehouse
+        | BuildingName::Granary
+        | BuildingName::GreatWarehouse
+        | BuildingName::GreatGranary => ImpactKind::Storage,
+        BuildingName::Cranny => ImpactKind::HiddenResources,
+        BuildingName::CityWall | BuildingName::EarthWall | BuildingName::Palisade => {
+            ImpactKind::Defense
+        }
+        BuildingName::Barracks
+        | BuildingName::Stable
+        | BuildingName::Workshop
+        | BuildingName::GreatBarracks
+        | BuildingName::GreatStable
+        | BuildingName::GreatWorkshop => ImpactKind::TrainingTime,
+        BuildingName::MainBuilding => ImpactKind::ConstructionTime,
+        BuildingName::TournamentSquare => ImpactKind::TroopSpeed,
+        BuildingName::TradeOffice => ImpactKind::MerchantCapacity,
+        _ => ImpactKind::None,
+    }
+}
+
+// The Main Building speeds up construction with a factor that compounds on each level.
+fn construction_time_percent(level: u8) -> u32 {
+    (MAIN_BUILDING_TIME_FACTOR.powi(level.saturating_sub(1) as i32) * 100.0).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{upgrade_impact, ImpactKind};
+    use crate::game::models::buildings::{Building, BuildingName};
+
+    fn at_level(name: BuildingName, level: u8) -> Building {
+        Building::new(name).at_level(level).unwrap()
+    }
+
+    #[test]
+    fn test_resource_field_production_delta() {
+        let impact = upgrade_impact(&at_level(BuildingName::Woodcutter, 1)).unwrap();
+
+        assert_eq!(impact.kind, ImpactKind::Production);
+        assert_eq!((impact.current, impact.next), (5, 9));
+        assert_eq!(impact.delta(), 4);
+    }
+
+    #[test]
+    fn test_warehouse_storage_delta() {
+        let impact = upgrade_impact(&at_level(BuildingName::Warehouse, 4)).unwrap();
+
+        assert_eq!(impact.kind, ImpactKind::Storage);
+        assert_eq!((impact.current, impact.next), (3100, 4000));
+    }
+
+    #[test]
+    fn test_wall_defense_delta() {
+        let impact = upgrade_impact(&at_level(BuildingName::CityWall, 1)).unwrap();
+
+        assert_eq!(impact.kind, ImpactKind::Defense);
+        assert_eq!(impact.delta(), 3);
+    }
+
+    #[test]
+    fn test_barracks_training_time_shrinks() {
+        let impact = upgrade_impact(&at_level(BuildingName::Barracks, 4)).unwrap();
+
+        assert_eq!(impact.kind, ImpactKind::TrainingTime);
+        assert_eq!((impact.current, impact.next), (73, 66));
+        assert_eq!(impact.delta(), -7);
+    }
+
+    #[test]
+    fn test_main_building_construction_time_shrinks() {
+        let impact = upgrade_impact(&at_level(BuildingName::MainBuilding, 1)).unwrap();
+
+        assert_eq!(impact.kind, ImpactKind::ConstructionTime);
+        assert_eq!((impact.current, impact.next), (100, 96));
+    }
+
+    #[test]
+    fn test_max_level_has_no_impact() {
+        let warehouse = Building::new(BuildingName::Warehouse);
+        let max_level = warehouse.max_level();
+
+        assert!(upgrade_impact(&at_level(BuildingName::Warehouse, max_level)).is_err());
+    }
+}
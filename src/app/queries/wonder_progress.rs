@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::{game::models::buildings::WONDER_VICTORY_LEVEL, repository::Repository};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WonderProgress {
+    // Village with the highest Wonder of the World, None when nobody started one.
+    pub village_id: Option<u32>,
+    pub level: u8,
+    // The Wonder reached the last level and its owner won the game.
+    pub victory: bool,
+}
+
+pub struct GetWonderProgress {
+    repo: Arc<dyn Repository>,
+}
+
+impl GetWonderProgress {
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetWonderProgress {
+    type Output = WonderProgress;
+
+    async fn run(&self) -> Result<Self::Output> {
+        let highest = self.repo.get_highest_wonder().await?;
+
+        Ok(wonder_progress(highest))
+    }
+}
+
+fn wonder_progress(highest: Option<(u32, u8)>) -> WonderProgress {
+    match highest {
+        Some((village_id, level)) => WonderProgress {
+            village_id: Some(village_id),
+            level,
+            victory: level >= WONDER_VICTORY_LEVEL,
+        },
+        None => WonderProgress {
+            village_id: None,
+            level: 0,
+            victory: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{GetWonderProgress, WonderProgress};
+    use crate::{
+        app::queries::Query,
+        db::test_utils::{insert_valley, setup_repo},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    async fn wonder_village(repo: &dyn Repository, username: &str, x: i32, level: u8) -> u32 {
+        let position = Position { x, y: 0 };
+        let player = repo
+            .register_player(username.to_string(), Tribe::Teuton)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let mut village = Village::new(username.to_string(), &valley, &player, false);
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::WonderOfTheWorld)
+                .at_level(level)
+                .unwrap(),
+        );
+        repo.found_village(village.clone(), None).await.unwrap();
+        village.id
+    }
+
+    #[tokio::test]
+    async fn test_wonder_progress() {
+        let db = setup_repo().await;
+        for x in [1, 2] {
+            insert_valley(&db, &Position { x, y: 0 }).await;
+        }
+        let repo: Arc<dyn Repository> = Arc::new(db);
+        let query = GetWonderProgress::new(repo.clone());
+
+        assert_eq!(
+            query.run().await.unwrap(),
+            WonderProgress {
+                village_id: None,
+                level: 0,
+                victory: false,
+            }
+        );
+
+        wonder_village(repo.as_ref(), "alice", 1, 40).await;
+        let bob = wonder_village(repo.as_ref(), "bob", 2, 99).await;
+        let progress = query.run().await.unwrap();
+        assert_eq!(progress.village_id, Some(bob));
+        assert_eq!(progress.level, 99);
+        assert!(!progress.victory);
+
+        let mut village = repo.get_village_by_id(bob).await.unwrap();
+        village.upgrade_building(20).unwrap();
+        repo.update_village(village).await.unwrap();
+        let progress = query.run().await.unwrap();
+        assert_eq!(progress.level, 100);
+        assert!(progress.victory);
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::repository::Repository;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorldStatus {
+    pub players: u32,
+    pub villages: u32,
+    pub max_wonder_level: u8,
+    pub days_since_start: i64,
+    pub server_speed: u8,
+}
+
+pub struct GetWorldStatus {
+    repo: Arc<dyn Repository>,
+    world_started_at: DateTime<Utc>,
+    server_speed: u8,
+}
+
+impl GetWorldStatus {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        world_started_at: DateTime<Utc>,
+        server_speed: u8,
+    ) -> Self {
+        Self {
+            repo,
+            world_started_at,
+            server_speed,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Query for GetWorldStatus {
+    type Output = WorldStatus;
+
+    async fn run(&self) -> Result<Self::Output> {
+        Ok(WorldStatus {
+            players: self.repo.count_players().await?,
+            villages: self.repo.count_villages().await?,
+            max_wonder_level: self
+                .repo
+                .get_highest_wonder()
+                .await?
+                .map_or(0, |(_, level)| level),
+            days_since_start: (Utc::now() - self.world_started_at).num_days().max(0),
+            server_speed: self.server_speed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+
+    use super::GetWorldStatus;
+    use crate::{
+        app::queries::Query,
+        db::test_utils::{insert_valley, setup_repo},
+        game::models::{
+            buildings::{Building, BuildingName},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+        repository::Repository,
+    };
+
+    async fn found_village(repo: &dyn Repository, username: &str, x: i32, y: i32) -> Village {
+        let position = Position { x, y };
+        let player = repo
+            .register_player(username.to_string(), Tribe::Gaul)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let village = Village::new(username.to_string(), &valley, &player, false);
+        repo.found_village(village.clone(), None).await.unwrap();
+        village
+    }
+
+    #[tokio::test]
+    async fn test_world_status_counts() {
+        let db = setup_repo().await;
+        for (x, y) in [(1, 1), (2, 2), (3, 3)] {
+            insert_valley(&db, &Position { x, y }).await;
+        }
+        let repo: Arc<dyn Repository> = Arc::new(db);
+
+        let query = GetWorldStatus::new(repo.clone(), Utc::now() - Duration::days(3), 3);
+        let status = query.run().await.unwrap();
+        assert_eq!(status.players, 0);
+        assert_eq!(status.villages, 0);
+        assert_eq!(status.max_wonder_level, 0);
+        assert_eq!(status.days_since_start, 3);
+        assert_eq!(status.server_speed, 3);
+
+        let mut first = found_village(repo.as_ref(), "alice", 1, 1).await;
+        let mut second = found_village(repo.as_ref(), "bob", 2, 2).await;
+        found_village(repo.as_ref(), "carl", 3, 3).await;
+
+        first.buildings.insert(
+            20,
+            Building::new(BuildingName::WonderOfTheWorld)
+                .at_level(5)
+                .unwrap(),
+        );
+        repo.update_village(first).await.unwrap();
+        second.buildings.insert(
+            20,
+            Building::new(BuildingName::WonderOfTheWorld)
+                .at_level(12)
+                .unwrap(),
+        );
+        repo.update_village(second).await.unwrap();
+
+        let status = query.run().await.unwrap();
+        assert_eq!(status.players, 3);
+        assert_eq!(status.villages, 3);
+        assert_eq!(status.max_wonder_level, 12);
+    }
+}
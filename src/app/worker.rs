@@ -0,0 +1,427 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::{consumers::MainConsumer, events::GameEvent, jobs::JobTask};
+use crate::{
+    game::models::{
+        adventure::{should_spawn, spawn_adventure},
+        buildings::BuildingName,
+        hero::HeroStatus,
+        map::WORLD_MAX_SIZE,
+        village::Village,
+    },
+    repository::Repository,
+};
+
+// Periodic background tasks that don't belong to a single job.
+pub struct Worker {
+    repo: Arc<dyn Repository>,
+    consumer: MainConsumer,
+    tick_secs: u64,
+    free_upkeep: u32,
+}
+
+impl Worker {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        tick_secs: u64,
+        server_speed: u8,
+        free_upkeep: u32,
+    ) -> Self {
+        Self {
+            consumer: MainConsumer::new(repo.clone(), server_speed),
+            repo,
+            tick_secs,
+            free_upkeep,
+        }
+    }
+
+    // Runs the tasks every `tick_secs`. A failed tick is logged and tried again at the
+    // next one, so a single broken row doesn't stop the world.
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.tick_secs));
+
+        loop {
+            interval.tick().await;
+            let result = match self.tick(Utc::now()).await {
+                Ok(events) => self.consumer.process_events(events).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                tracing::error!("Worker tick failed: {}", err);
+            }
+        }
+    }
+
+    pub async fn tick(&self, now: DateTime<Utc>) -> Result<Vec<GameEvent>> {
+        let mut events = vec![];
+
+        self.regenerate_heroes(now).await?;
+        self.spawn_adventures(now).await?;
+        events.extend(self.expire_protections(now).await?);
+        self.award_culture_points(now).await?;
+        events.extend(self.starve_villages(now).await?);
+
+        Ok(events)
+    }
+
+    // Adds the daily culture points of all villages to their owners, once per day.
+    async fn award_culture_points(&self, now: DateTime<Utc>) -> Result<()> {
+        let day = now.format("%Y-%m-%d").to_string();
+
+        for player in self.repo.list_players().await? {
+            let amount = self
+                .repo
+                .get_villages_by_player_id(player.id)
+                .await?
+                .iter()
+                .map(|v| v.culture_points_production())
+                .sum();
+            self.repo
+                .award_culture_points(player.id, day.clone(), amount)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Starves the troops of villages that ran out of crop. Villages still starving
+    // without troops halt their construction queue.
+    async fn starve_villages(&self, now: DateTime<Utc>) -> Result<Vec<GameEvent>> {
+        let mut events = vec![];
+
+        for player in self.repo.list_players().await? {
+            let free_upkeep = player.free_upkeep_at(self.free_upkeep, now);
+
+            for mut village in self.repo.get_villages_by_player_id(player.id).await? {
+                if village.resources.crop() > 0 {
+                    continue;
+                }
+
+                let troops = troops_in(&village);
+                let starving = village.starve(free_upkeep);
+                if troops_in(&village) < troops {
+                    tracing::warn!("Troops starved in village {}", village.id);
+                    self.repo.update_village(village.clone()).await?;
+                }
+                if !starving {
+                    continue;
+                }
+
+                for job in self.repo.get_pending_jobs_by_village_id(village.id).await? {
+                    if let JobTask::BuildingUpgrade { .. } = job.task {
+                        events.push(GameEvent::JobCancelled { job_id: job.id });
+                    }
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    async fn regenerate_heroes(&self, now: DateTime<Utc>) -> Result<()> {
+        for mut hero in self.repo.list_heroes().await? {
+            let village = self.repo.get_village_by_id(hero.village_id).await?;
+            let mansion_level = village
+                .get_building_by_name(BuildingName::HeroMansion)
+                .map_or(0, |b| b.level);
+
+            if hero.regenerate(now, mansion_level) > 0 {
+                self.repo.update_hero(hero).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Places new adventures around the villages of living heroes, once their owners have
+    // a Hero Mansion.
+    async fn spawn_adventures(&self, now: DateTime<Utc>) -> Result<()> {
+        let world_size = self.repo.get_world_size().await?.unwrap_or(WORLD_MAX_SIZE);
+
+        for hero in self.repo.list_heroes().await? {
+            if hero.status == HeroStatus::Dead {
+                continue;
+            }
+            let village = self.repo.get_village_by_id(hero.village_id).await?;
+            if village
+                .get_building_by_name(BuildingName::HeroMansion)
+                .is_none()
+            {
+                continue;
+            }
+
+            let open = self
+                .repo
+                .get_adventures_by_player_id(hero.player_id)
+                .await?;
+            if !should_spawn(&open, now) {
+                continue;
+            }
+            let adventure = spawn_adventure(
+                hero.player_id,
+                &village,
+                world_size,
+                now,
+                &mut rand::thread_rng(),
+            );
+            self.repo.add_adventure(adventure).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn expire_protections(&self, now: DateTime<Utc>) -> Result<Vec<GameEvent>> {
+        let mut events = vec![];
+
+        for mut player in self.repo.get_players_with_expired_protection(now).await? {
+            player.protected_until = None;
+            events.push(GameEvent::ProtectionEnded {
+                player_id: player.id,
+            });
+            self.repo.update_player(player).await?;
+        }
+
+        Ok(events)
+    }
+}
+
+// Counts the troops eating in the village, reinforcements included.
+fn troops_in(village: &Village) -> u32 {
+    village.army.immensity()
+        + village
+            .reinforcements
+            .iter()
+            .map(|r| r.immensity())
+            .sum::<u32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+
+    use super::Worker;
+    use crate::{
+        app::{
+            commands::{attack::AttackCommand, Command},
+            events::GameEvent,
+            jobs::{Job, JobTask},
+        },
+        db::test_utils::{insert_hero, insert_valley, setup_repo},
+        game::{
+            battle::CataTargets,
+            models::{
+                adventure::ADVENTURE_SPAWN_INTERVAL_HOURS,
+                buildings::{Building, BuildingName},
+                hero::Hero,
+                map::{Position, TravelSettings, WORLD_MAX_SIZE},
+                village::Village,
+                Player, ResourceGroup, Tribe, BEGINNERS_PROTECTION_HOURS,
+            },
+        },
+        repository::Repository,
+    };
+
+    async fn found_village(repo: &Arc<dyn Repository>, player: &Player, x: i32, y: i32) -> Village {
+        let valley = repo
+            .get_valley_by_id(Position { x, y }.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        let village = Village::new("Village".to_string(), &valley, player, true);
+        repo.found_village(village.clone(), None).await.unwrap();
+        village
+    }
+
+    #[tokio::test]
+    async fn test_protection_expires() {
+        let repo = setup_repo().await;
+        for (x, y) in [(1, 1), (2, 2)] {
+            insert_valley(&repo, &Position { x, y }).await;
+        }
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let worker = Worker::new(repo.clone(), 60, 1, 0);
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let bob = repo
+            .register_player("bob".to_string(), Tribe::Teuton)
+            .await
+            .unwrap();
+        let target = found_village(&repo, &alice, 1, 1).await;
+        let mut home = found_village(&repo, &bob, 2, 2).await;
+        home.army.units[0] = 10;
+        home.buildings
+            .insert(39, Building::new(BuildingName::RallyPoint));
+        repo.update_village(home.clone()).await.unwrap();
+
+        let attack = AttackCommand::new(
+            repo.clone(),
+            home.id,
+            home.army.clone(),
+            CataTargets::default(),
+            target.id,
+            100,
+            TravelSettings::default(),
+        );
+        assert!(attack.run().await.is_err(), "alice is protected");
+
+        // still protected right before the deadline
+        let now = Utc::now();
+        assert!(worker.tick(now).await.unwrap().is_empty());
+
+        let later = now + Duration::hours(BEGINNERS_PROTECTION_HOURS) + Duration::minutes(1);
+        let events = worker.tick(later).await.unwrap();
+        assert_eq!(events.len(), 2);
+        for e in events {
+            assert!(matches!(
+                e,
+                GameEvent::ProtectionEnded { player_id } if player_id == alice.id || player_id == bob.id
+            ));
+        }
+        let alice = repo.get_player_by_id(alice.id).await.unwrap();
+        assert_eq!(alice.protected_until, None);
+
+        // expired protections are cleared only once
+        assert!(worker.tick(later).await.unwrap().is_empty());
+
+        assert!(attack.run().await.is_ok(), "alice can be attacked now");
+    }
+
+    #[tokio::test]
+    async fn test_culture_points_awarded_once_a_day() {
+        let repo = setup_repo().await;
+        insert_valley(&repo, &Position { x: 1, y: 1 }).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let mut village = found_village(&repo, &alice, 1, 1).await;
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::Residence).at_level(5).unwrap(),
+        );
+        repo.update_village(village.clone()).await.unwrap();
+        let daily = village.culture_points_production();
+        assert!(daily > 0);
+
+        let now = Utc::now();
+        let worker = Worker::new(repo.clone(), 60, 1, 0);
+        worker.tick(now).await.unwrap();
+        worker.tick(now).await.unwrap();
+        assert_eq!(
+            repo.get_player_by_id(alice.id)
+                .await
+                .unwrap()
+                .culture_points,
+            daily
+        );
+
+        // a restarted worker doesn't award the same day again
+        let restarted = Worker::new(repo.clone(), 60, 1, 0);
+        restarted.tick(now).await.unwrap();
+        assert_eq!(
+            repo.get_player_by_id(alice.id)
+                .await
+                .unwrap()
+                .culture_points,
+            daily
+        );
+
+        restarted.tick(now + Duration::days(1)).await.unwrap();
+        assert_eq!(
+            repo.get_player_by_id(alice.id)
+                .await
+                .unwrap()
+                .culture_points,
+            daily * 2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_starving_village_halts_construction() {
+        let repo = setup_repo().await;
+        insert_valley(&repo, &Position { x: 1, y: 1 }).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let mut village = found_village(&repo, &alice, 1, 1).await;
+        village.set_building_level(19, 20).unwrap();
+        village.army.units[0] = 10;
+        village.resources = ResourceGroup::new(750, 750, 750, 0);
+        repo.update_village(village.clone()).await.unwrap();
+
+        let job = Job::new(
+            alice.id,
+            village.id,
+            60,
+            JobTask::BuildingUpgrade {
+                slot_id: 1,
+                building_name: BuildingName::Woodcutter,
+                target_level: None,
+            },
+        );
+        repo.add_job(job.clone()).await.unwrap();
+
+        let worker = Worker::new(repo.clone(), 60, 1, 0);
+        let events = worker.tick(Utc::now()).await.unwrap();
+
+        let village = repo.get_village_by_id(village.id).await.unwrap();
+        assert_eq!(village.army.units[0], 0, "troops starved first");
+        assert!(village.net_crop_production() < 0);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, GameEvent::JobCancelled { job_id } if *job_id == job.id)));
+    }
+
+    #[tokio::test]
+    async fn test_adventures_spawn_with_hero_mansion() {
+        let repo = setup_repo().await;
+        let position = Position { x: 1, y: 1 };
+        insert_valley(&repo, &position).await;
+        let alice = repo
+            .register_player("alice".to_string(), Tribe::Gaul)
+            .await
+            .unwrap();
+        insert_hero(&repo, Hero::new(alice.id, position.to_id(WORLD_MAX_SIZE))).await;
+        let repo: Arc<dyn Repository> = Arc::new(repo);
+        let mut village = found_village(&repo, &alice, 1, 1).await;
+
+        let now = Utc::now();
+        let worker = Worker::new(repo.clone(), 60, 1, 0);
+        worker.tick(now).await.unwrap();
+        let adventures = repo.get_adventures_by_player_id(alice.id).await.unwrap();
+        assert!(adventures.is_empty(), "a Hero Mansion is needed");
+
+        village.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(1)
+                .unwrap(),
+        );
+        repo.update_village(village.clone()).await.unwrap();
+
+        worker.tick(now).await.unwrap();
+        worker.tick(now + Duration::hours(1)).await.unwrap();
+        let adventures = repo.get_adventures_by_player_id(alice.id).await.unwrap();
+        assert_eq!(adventures.len(), 1);
+
+        let next = now + Duration::hours(ADVENTURE_SPAWN_INTERVAL_HOURS);
+        worker.tick(next).await.unwrap();
+        let adventures = repo.get_adventures_by_player_id(alice.id).await.unwrap();
+        assert_eq!(adventures.len(), 2);
+        assert!(adventures
+            .iter()
+            .all(|a| a.position.grid_distance(&village.position, WORLD_MAX_SIZE) > 0));
+    }
+}
@@ -0,0 +1,479 @@
+use std::env;
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, FixedOffset, Timelike, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::jobs::RetryPolicy,
+    game::models::map::{TravelSettings, WORLD_MAX_SIZE},
+};
+
+// Game server settings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    // Players allowed to run admin commands.
+    pub admins: Vec<Uuid>,
+    // Seconds after departure during which an army movement can still be called back.
+    pub cancel_grace_secs: u64,
+    // Armies a single village can have on the move at the same time.
+    pub max_outgoing_movements: u32,
+    // Deleting the last village of a player deletes the account too, otherwise it's refused.
+    pub delete_account_with_last_village: bool,
+    // Crop upkeep that players under beginners' protection don't pay, 0 disables it.
+    pub free_upkeep: u32,
+    // Multiplier for production, construction and training speed, must be at least 1.
+    pub server_speed: u8,
+    // Multiplier for the cost of units trained in the Great Barracks, Stable and Workshop.
+    pub great_training_cost_factor: u32,
+    // Lets players rebalance their resources with the NPC merchant of the Marketplace.
+    pub npc_trade_enabled: bool,
+    // Start of the world, stored with the map when it's generated: later runs use the
+    // stored one.
+    pub world_started_at: DateTime<Utc>,
+    // Map extent from the center, it must match the size the stored map was generated with.
+    pub world_size: i32,
+    // Defensive bonus for attacks landing at night, disabled when None.
+    pub night_defense: Option<NightDefense>,
+    // Share of troops a winning attacker always loses when the target has any defending
+    // troops, from 0 (disabled) to 100.
+    pub min_attacker_losses_percent: f64,
+    // Times a job failing for transient reasons is run again before giving up.
+    pub max_retries: u32,
+    // Delay before the first retry of a job, it grows 4x on each following retry.
+    pub base_backoff_secs: u64,
+    // Seconds after which a job still marked as processing is considered lost by a crashed
+    // worker and is requeued.
+    pub stuck_job_timeout_secs: u64,
+    // Seconds between two checks for due jobs.
+    pub job_poll_secs: u64,
+    // Seconds between two runs of the periodic tasks: hero regeneration, adventures,
+    // protection expiry, culture points and starvation.
+    pub worker_tick_secs: u64,
+}
+
+impl Config {
+    // Loads settings from the environment, falling back to defaults.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+
+        if let Ok(speed) = env::var("SERVER_SPEED") {
+            config.server_speed = speed
+                .parse()
+                .map_err(|_| Error::msg("SERVER_SPEED must be a positive integer"))?;
+        }
+
+        if let Ok(factor) = env::var("GREAT_TRAINING_COST_FACTOR") {
+            config.great_training_cost_factor = factor
+                .parse()
+                .map_err(|_| Error::msg("GREAT_TRAINING_COST_FACTOR must be a positive integer"))?;
+        }
+
+        if let Ok(enabled) = env::var("NPC_TRADE_ENABLED") {
+            config.npc_trade_enabled = enabled
+                .parse()
+                .map_err(|_| Error::msg("NPC_TRADE_ENABLED must be true or false"))?;
+        }
+
+        if let Ok(started_at) = env::var("WORLD_STARTED_AT") {
+            config.world_started_at = DateTime::parse_from_rfc3339(&started_at)
+                .map_err(|_| Error::msg("WORLD_STARTED_AT must be a RFC 3339 date"))?
+                .with_timezone(&Utc);
+        }
+
+        if let Ok(grace) = env::var("CANCEL_GRACE_SECS") {
+            config.cancel_grace_secs = grace
+                .parse()
+                .map_err(|_| Error::msg("CANCEL_GRACE_SECS must be a positive integer"))?;
+        }
+
+        if let Ok(max) = env::var("MAX_OUTGOING_MOVEMENTS") {
+            config.max_outgoing_movements = max
+                .parse()
+                .map_err(|_| Error::msg("MAX_OUTGOING_MOVEMENTS must be a positive integer"))?;
+        }
+
+        if let Ok(delete) = env::var("DELETE_ACCOUNT_WITH_LAST_VILLAGE") {
+            config.delete_account_with_last_village = delete.parse().map_err(|_| {
+                Error::msg("DELETE_ACCOUNT_WITH_LAST_VILLAGE must be true or false")
+            })?;
+        }
+
+        if let Ok(upkeep) = env::var("FREE_UPKEEP") {
+            config.free_upkeep = upkeep
+                .parse()
+                .map_err(|_| Error::msg("FREE_UPKEEP must be a positive integer"))?;
+        }
+
+        if let Ok(admins) = env::var("ADMIN_PLAYER_IDS") {
+            config.admins = admins
+                .split(',')
+                .map(|id| id.trim())
+                .filter(|id| !id.is_empty())
+                .map(Uuid::parse_str)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| {
+                    Error::msg("ADMIN_PLAYER_IDS must be a comma separated list of ids")
+                })?;
+        }
+
+        if let Ok(size) = env::var("WORLD_SIZE") {
+            config.world_size = size
+                .parse()
+                .map_err(|_| Error::msg("WORLD_SIZE must be a positive integer"))?;
+        }
+
+        if let Ok(night) = env::var("NIGHT_DEFENSE") {
+            let mut night = NightDefense::parse(&night)?;
+            if let Ok(offset) = env::var("NIGHT_DEFENSE_UTC_OFFSET_SECS") {
+                night.utc_offset_secs = offset
+                    .parse()
+                    .map_err(|_| Error::msg("NIGHT_DEFENSE_UTC_OFFSET_SECS must be an integer"))?;
+            }
+            config.night_defense = Some(night);
+        }
+
+        if let Ok(percent) = env::var("MIN_ATTACKER_LOSSES_PERCENT") {
+            config.min_attacker_losses_percent = percent
+                .parse()
+                .map_err(|_| Error::msg("MIN_ATTACKER_LOSSES_PERCENT must be a number"))?;
+        }
+
+        if let Ok(retries) = env::var("MAX_RETRIES") {
+            config.max_retries = retries
+                .parse()
+                .map_err(|_| Error::msg("MAX_RETRIES must be a positive integer"))?;
+        }
+
+        if let Ok(backoff) = env::var("BASE_BACKOFF_SECS") {
+            config.base_backoff_secs = backoff
+                .parse()
+                .map_err(|_| Error::msg("BASE_BACKOFF_SECS must be a positive integer"))?;
+        }
+
+        if let Ok(timeout) = env::var("STUCK_JOB_TIMEOUT_SECS") {
+            config.stuck_job_timeout_secs = timeout
+                .parse()
+                .map_err(|_| Error::msg("STUCK_JOB_TIMEOUT_SECS must be a positive integer"))?;
+        }
+
+        if let Ok(poll) = env::var("JOB_POLL_SECS") {
+            config.job_poll_secs = poll
+                .parse()
+                .map_err(|_| Error::msg("JOB_POLL_SECS must be a positive integer"))?;
+        }
+
+        if let Ok(tick) = env::var("WORKER_TICK_SECS") {
+            config.worker_tick_secs = tick
+                .parse()
+                .map_err(|_| Error::msg("WORKER_TICK_SECS must be a positive integer"))?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.server_speed < 1 {
+            return Err(Error::msg("server speed must be at least 1"));
+        }
+
+        if self.great_training_cost_factor < 1 {
+            return Err(Error::msg("great training cost factor must be at least 1"));
+        }
+
+        if self.max_outgoing_movements < 1 {
+            return Err(Error::msg("max outgoing movements must be at least 1"));
+        }
+
+        if self.world_size < 1 {
+            return Err(Error::msg("world size must be at least 1"));
+        }
+
+        if let Some(night) = &self.night_defense {
+            if night.start_hour > 23 || night.end_hour > 23 {
+                return Err(Error::msg("night defense hours must be between 0 and 23"));
+            }
+            if night.multiplier < 1.0 {
+                return Err(Error::msg("night defense multiplier must be at least 1"));
+            }
+            // real timezones go from UTC-12 to UTC+14
+            if !(-12 * 3600..=14 * 3600).contains(&night.utc_offset_secs) {
+                return Err(Error::msg(
+                    "night defense UTC offset must be between -12 and +14 hours",
+                ));
+            }
+        }
+
+        if self.job_poll_secs < 1 {
+            return Err(Error::msg("job poll interval must be at least 1 second"));
+        }
+
+        if self.worker_tick_secs < 1 {
+            return Err(Error::msg("worker tick must be at least 1 second"));
+        }
+
+        if !(0.0..=100.0).contains(&self.min_attacker_losses_percent) {
+            return Err(Error::msg(
+                "min attacker losses percent must be between 0 and 100",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Checks the world size against the one stored at bootstrap, if the map exists yet.
+    pub fn check_world_size(&self, stored: Option<i32>) -> Result<()> {
+        match stored {
+            Some(size) if size != self.world_size => Err(Error::msg(format!(
+                "WORLD_SIZE is {} but the map was generated with size {}: set WORLD_SIZE={} or bootstrap a new map",
+                self.world_size, size, size
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries,
+            base_backoff_secs: self.base_backoff_secs,
+        }
+    }
+
+    pub fn travel_settings(&self) -> TravelSettings {
+        TravelSettings {
+            world_size: self.world_size,
+            server_speed: self.server_speed,
+        }
+    }
+
+    // Returns the multiplier to apply to the defense of a battle happening at the given time.
+    pub fn defense_multiplier_at(&self, at: DateTime<Utc>) -> f64 {
+        match &self.night_defense {
+            Some(night) if night.is_active_at(at) => night.multiplier,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            admins: vec![],
+            cancel_grace_secs: 90,
+            max_outgoing_movements: 100,
+            delete_account_with_last_village: false,
+            free_upkeep: 0,
+            server_speed: 1,
+            great_training_cost_factor: 3,
+            npc_trade_enabled: true,
+            world_started_at: Utc::now(),
+            world_size: WORLD_MAX_SIZE,
+            night_defense: None,
+            min_attacker_losses_percent: 0.0,
+            max_retries: 3,
+            base_backoff_secs: 1,
+            stuck_job_timeout_secs: 300,
+            job_poll_secs: 1,
+            worker_tick_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NightDefense {
+    // Window hours are in the server timezone, the end hour is excluded.
+    pub start_hour: u32,
+    pub end_hour: u32,
+    pub utc_offset_secs: i32,
+    pub multiplier: f64,
+}
+
+impl NightDefense {
+    // Parses a window in the `START-END:MULTIPLIER` form, e.g. `22-6:2` doubles the
+    // defense from 22:00 to 06:00. Hours are in UTC unless an offset is set.
+    pub fn parse(value: &str) -> Result<Self> {
+        let invalid = || Error::msg("NIGHT_DEFENSE must look like START-END:MULTIPLIER");
+        let (hours, multiplier) = value.trim().split_once(':').ok_or_else(invalid)?;
+        let (start_hour, end_hour) = hours.split_once('-').ok_or_else(invalid)?;
+
+        Ok(Self {
+            start_hour: start_hour.trim().parse().map_err(|_| invalid())?,
+            end_hour: end_hour.trim().parse().map_err(|_| invalid())?,
+            utc_offset_secs: 0,
+            multiplier: multiplier.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+
+    // Returns true if the given time falls into the night window, which can span midnight.
+    pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+        let offset = FixedOffset::east_opt(self.utc_offset_secs)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        let hour = at.with_timezone(&offset).hour();
+
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{Config, NightDefense};
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    fn night_config(utc_offset_secs: i32) -> Config {
+        Config {
+            night_defense: Some(NightDefense {
+                start_hour: 22,
+                end_hour: 6,
+                utc_offset_secs,
+                multiplier: 2.0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_server_speed_validation() {
+        assert!(Config::default().validate().is_ok());
+
+        let config = Config {
+            server_speed: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_great_training_cost_factor_validation() {
+        assert_eq!(Config::default().great_training_cost_factor, 3);
+
+        let config = Config {
+            great_training_cost_factor: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_min_attacker_losses_validation() {
+        assert_eq!(Config::default().min_attacker_losses_percent, 0.0);
+
+        let config = Config {
+            min_attacker_losses_percent: 5.0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = Config {
+            min_attacker_losses_percent: 101.0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_loop_intervals_validation() {
+        for config in [
+            Config {
+                job_poll_secs: 0,
+                ..Default::default()
+            },
+            Config {
+                worker_tick_secs: 0,
+                ..Default::default()
+            },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_world_size_matching_stored_map() {
+        let config = Config::default();
+        assert!(config.check_world_size(Some(config.world_size)).is_ok());
+
+        // a world without a map yet has nothing to disagree with
+        assert!(config.check_world_size(None).is_ok());
+    }
+
+    #[test]
+    fn test_world_size_mismatch() {
+        let config = Config {
+            world_size: 200,
+            ..Default::default()
+        };
+        assert!(config.check_world_size(Some(100)).is_err());
+
+        let config = Config {
+            world_size: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_night_defense_disabled_by_default() {
+        let config = Config::default();
+        assert_eq!(config.defense_multiplier_at(at(23, 0)), 1.0);
+    }
+
+    #[test]
+    fn test_attack_inside_night_window() {
+        let config = night_config(0);
+        assert_eq!(config.defense_multiplier_at(at(22, 0)), 2.0);
+        assert_eq!(config.defense_multiplier_at(at(0, 30)), 2.0);
+        assert_eq!(config.defense_multiplier_at(at(5, 59)), 2.0);
+
+        // 20:30 UTC is 22:30 on a server at UTC+2
+        let config = night_config(2 * 3600);
+        assert_eq!(config.defense_multiplier_at(at(20, 30)), 2.0);
+    }
+
+    #[test]
+    fn test_night_defense_parsing() {
+        let night = NightDefense::parse("22-6:2").unwrap();
+        assert_eq!((night.start_hour, night.end_hour), (22, 6));
+        assert_eq!(night.multiplier, 2.0);
+        assert_eq!(night.utc_offset_secs, 0);
+
+        assert!(NightDefense::parse("22-6").is_err());
+        assert!(NightDefense::parse("night:2").is_err());
+
+        let config = Config {
+            night_defense: Some(NightDefense::parse("22-24:2").unwrap()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        let config = Config {
+            night_defense: Some(NightDefense::parse("22-6:0.5").unwrap()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        assert!(night_config(14 * 3600).validate().is_ok());
+        assert!(night_config(-12 * 3600).validate().is_ok());
+        assert!(night_config(15 * 3600).validate().is_err());
+        assert!(night_config(-86400).validate().is_err());
+    }
+
+    #[test]
+    fn test_attack_outside_night_window() {
+        let config = night_config(0);
+        assert_eq!(config.defense_multiplier_at(at(6, 0)), 1.0);
+        assert_eq!(config.defense_multiplier_at(at(12, 0)), 1.0);
+        assert_eq!(config.defense_multiplier_at(at(21, 59)), 1.0);
+
+        // 23:00 UTC is 01:00 on a server at UTC+2, but 05:00 UTC is 07:00
+        let config = night_config(2 * 3600);
+        assert_eq!(config.defense_multiplier_at(at(5, 0)), 1.0);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod models;
 pub mod repository;
+#[cfg(test)]
+pub mod test_utils;
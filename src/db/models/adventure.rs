@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::{
+    adventure::{Adventure as GameAdventure, AdventureDifficulty, AdventureReward},
+    map::Position,
+};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "adventures")]
+pub struct Adventure {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub x: i32,
+    pub y: i32,
+    pub difficulty: Json<AdventureDifficulty>,
+    pub reward: Json<AdventureReward>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Adventure> for GameAdventure {
+    fn from(a: Adventure) -> Self {
+        Self {
+            id: a.id,
+            player_id: a.player_id,
+            position: Position { x: a.x, y: a.y },
+            difficulty: a.difficulty.as_ref().clone(),
+            reward: a.reward.as_ref().clone(),
+            created_at: a.created_at,
+        }
+    }
+}
+
+impl From<GameAdventure> for Adventure {
+    fn from(a: GameAdventure) -> Self {
+        Self {
+            id: a.id,
+            player_id: a.player_id,
+            x: a.position.x,
+            y: a.position.y,
+            difficulty: Json(a.difficulty),
+            reward: Json(a.reward),
+            created_at: a.created_at,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::audit::{AuditAction, AuditEntry as GameAuditEntry};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "audit_log")]
+pub struct AuditEntry {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub village_id: u32,
+    pub action: Json<AuditAction>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for GameAuditEntry {
+    fn from(e: AuditEntry) -> Self {
+        Self {
+            id: e.id,
+            admin_id: e.admin_id,
+            village_id: e.village_id,
+            action: e.action.as_ref().clone(),
+            created_at: e.created_at,
+        }
+    }
+}
+
+impl From<GameAuditEntry> for AuditEntry {
+    fn from(e: GameAuditEntry) -> Self {
+        Self {
+            id: e.id,
+            admin_id: e.admin_id,
+            village_id: e.village_id,
+            action: Json(e.action),
+            created_at: e.created_at,
+        }
+    }
+}
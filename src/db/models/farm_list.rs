@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::{army::TroopSet, farm_list::FarmList as GameFarmList, map::Position};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "farm_lists")]
+pub struct FarmList {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub name: String,
+    pub units: Json<TroopSet>,
+    pub targets: Json<Vec<Position>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<FarmList> for GameFarmList {
+    fn from(l: FarmList) -> Self {
+        Self {
+            id: l.id,
+            player_id: l.player_id,
+            village_id: l.village_id,
+            name: l.name,
+            units: *l.units.as_ref(),
+            targets: l.targets.as_ref().clone(),
+            created_at: l.created_at,
+        }
+    }
+}
+
+impl From<GameFarmList> for FarmList {
+    fn from(l: GameFarmList) -> Self {
+        Self {
+            id: l.id,
+            player_id: l.player_id,
+            village_id: l.village_id,
+            name: l.name,
+            units: Json(l.units),
+            targets: Json(l.targets),
+            created_at: l.created_at,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::hero::{Hero as GameHero, HeroAttributes, HeroItem, HeroStatus};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "heroes")]
+pub struct Hero {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub status: Json<HeroStatus>,
+    pub health: u8,
+    pub experience: u32,
+    pub strength: u32,
+    pub inventory: Json<Vec<HeroItem>>,
+    pub equipment: Json<Vec<HeroItem>>,
+    pub regenerated_at: DateTime<Utc>,
+    pub attributes: Json<HeroAttributes>,
+}
+
+impl From<Hero> for GameHero {
+    fn from(h: Hero) -> Self {
+        Self {
+            id: h.id,
+            player_id: h.player_id,
+            village_id: h.village_id,
+            status: h.status.as_ref().clone(),
+            health: h.health,
+            experience: h.experience,
+            strength: h.strength,
+            inventory: h.inventory.as_ref().clone(),
+            equipment: h.equipment.as_ref().clone(),
+            regenerated_at: h.regenerated_at,
+            attributes: h.attributes.as_ref().clone(),
+        }
+    }
+}
+
+impl From<GameHero> for Hero {
+    fn from(h: GameHero) -> Self {
+        Self {
+            id: h.id,
+            player_id: h.player_id,
+            village_id: h.village_id,
+            status: Json(h.status),
+            health: h.health,
+            experience: h.experience,
+            strength: h.strength,
+            inventory: Json(h.inventory),
+            equipment: Json(h.equipment),
+            regenerated_at: h.regenerated_at,
+            attributes: Json(h.attributes),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::app::jobs::{Job as AppJob, JobTask};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "jobs")]
+pub struct Job {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub task: Json<JobTask>,
+    pub duration: i64,
+    pub done: bool,
+    pub cancellable: bool,
+    pub started_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub failed: bool,
+    pub processing_since: Option<DateTime<Utc>>,
+}
+
+impl From<Job> for AppJob {
+    fn from(j: Job) -> Self {
+        Self {
+            id: j.id,
+            player_id: j.player_id,
+            village_id: j.village_id,
+            task: j.task.as_ref().clone(),
+            duration: j.duration as u64,
+            done: j.done,
+            cancellable: j.cancellable,
+            started_at: j.started_at,
+            attempts: j.attempts,
+            failed: j.failed,
+            processing_since: j.processing_since,
+        }
+    }
+}
+
+impl From<AppJob> for Job {
+    fn from(j: AppJob) -> Self {
+        Self {
+            id: j.id,
+            player_id: j.player_id,
+            village_id: j.village_id,
+            task: Json(j.task),
+            duration: j.duration as i64,
+            done: j.done,
+            cancellable: j.cancellable,
+            started_at: j.started_at,
+            attempts: j.attempts,
+            failed: j.failed,
+            processing_since: j.processing_since,
+        }
+    }
+}
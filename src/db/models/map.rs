@@ -4,8 +4,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
 use uuid::Uuid;
 
-use crate::game::models::map::{
-    MapField as GameMapField, MapFieldTopology, Oasis, Position, Valley,
+use crate::game::models::{
+    army::TroopSet,
+    map::{MapField as GameMapField, MapFieldTopology, Oasis, Position, Valley},
 };
 
 #[derive(Model, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -18,6 +19,8 @@ pub struct MapField {
     pub x: i32,
     pub y: i32,
     pub topology: Json<MapFieldTopology>,
+    // only oases have animals
+    pub animals: Option<Json<TroopSet>>,
 }
 
 impl From<MapField> for GameMapField {
@@ -63,6 +66,7 @@ impl TryFrom<MapField> for Oasis {
                     y: value.y,
                 },
                 topology,
+                animals: value.animals.map_or([0; 10], |a| *a.as_ref()),
             }),
             _ => Err(Error::msg("This map field is not an Oasis")),
         }
@@ -78,6 +82,7 @@ impl From<GameMapField> for MapField {
             x: f.position.x,
             y: f.position.y,
             topology: Json(f.topology),
+            animals: None,
         }
     }
 }
@@ -91,6 +96,7 @@ impl From<Valley> for MapField {
             x: valley.position.x,
             y: valley.position.y,
             topology: Json(MapFieldTopology::Valley(valley.topology)),
+            animals: None,
         }
     }
 }
@@ -104,6 +110,7 @@ impl From<Oasis> for MapField {
             x: oasis.position.x,
             y: oasis.position.y,
             topology: Json(MapFieldTopology::Oasis(oasis.topology)),
+            animals: Some(Json(oasis.animals)),
         }
     }
 }
@@ -1,3 +1,10 @@
+pub mod adventure;
+pub mod audit;
+pub mod farm_list;
+pub mod hero;
+pub mod job;
 pub mod map;
 pub mod player;
+pub mod report;
+pub mod trade_route;
 pub mod village;
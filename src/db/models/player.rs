@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use ormlite::model::*;
 use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
@@ -12,6 +13,8 @@ pub struct Player {
     pub id: Uuid,
     pub username: String,
     pub tribe: Json<Tribe>,
+    pub culture_points: u32,
+    pub protected_until: Option<DateTime<Utc>>,
 }
 
 impl From<Player> for crate::game::models::Player {
@@ -20,6 +23,20 @@ impl From<Player> for crate::game::models::Player {
             id: f.id,
             username: f.username,
             tribe: f.tribe.as_ref().clone(),
+            culture_points: f.culture_points,
+            protected_until: f.protected_until,
+        }
+    }
+}
+
+impl From<crate::game::models::Player> for Player {
+    fn from(p: crate::game::models::Player) -> Self {
+        Self {
+            id: p.id,
+            username: p.username,
+            tribe: Json(p.tribe),
+            culture_points: p.culture_points,
+            protected_until: p.protected_until,
         }
     }
 }
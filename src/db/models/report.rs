@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::report::{
+    Report as GameReport, ReportAudience, ReportContent, ReportKind,
+};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "reports")]
+pub struct Report {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub attacker_player_id: Uuid,
+    pub attacker_village_id: u32,
+    pub defender_player_id: Uuid,
+    pub defender_village_id: u32,
+    pub audience: Json<ReportAudience>,
+    pub content: Json<ReportContent>,
+    pub kind: Json<ReportKind>,
+    pub read_by_attacker: bool,
+    pub read_by_defender: bool,
+    pub archived_by_attacker: bool,
+    pub archived_by_defender: bool,
+    pub deleted_by_attacker: bool,
+    pub deleted_by_defender: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Report> for GameReport {
+    fn from(r: Report) -> Self {
+        Self {
+            id: r.id,
+            attacker_player_id: r.attacker_player_id,
+            attacker_village_id: r.attacker_village_id,
+            defender_player_id: r.defender_player_id,
+            defender_village_id: r.defender_village_id,
+            audience: r.audience.as_ref().clone(),
+            content: r.content.as_ref().clone(),
+            kind: *r.kind.as_ref(),
+            read_by_attacker: r.read_by_attacker,
+            read_by_defender: r.read_by_defender,
+            archived_by_attacker: r.archived_by_attacker,
+            archived_by_defender: r.archived_by_defender,
+            deleted_by_attacker: r.deleted_by_attacker,
+            deleted_by_defender: r.deleted_by_defender,
+            created_at: r.created_at,
+        }
+    }
+}
+
+impl From<GameReport> for Report {
+    fn from(r: GameReport) -> Self {
+        Self {
+            id: r.id,
+            attacker_player_id: r.attacker_player_id,
+            attacker_village_id: r.attacker_village_id,
+            defender_player_id: r.defender_player_id,
+            defender_village_id: r.defender_village_id,
+            audience: Json(r.audience),
+            content: Json(r.content),
+            kind: Json(r.kind),
+            read_by_attacker: r.read_by_attacker,
+            read_by_defender: r.read_by_defender,
+            archived_by_attacker: r.archived_by_attacker,
+            archived_by_defender: r.archived_by_defender,
+            deleted_by_attacker: r.deleted_by_attacker,
+            deleted_by_defender: r.deleted_by_defender,
+            created_at: r.created_at,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use ormlite::model::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::game::models::{trade_route::TradeRoute as GameTradeRoute, ResourceGroup};
+
+#[derive(Model, Serialize, Deserialize, Debug, Clone)]
+#[ormlite(table = "trade_routes")]
+pub struct TradeRoute {
+    #[ormlite(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub target_village_id: u32,
+    pub resources: Json<ResourceGroup>,
+    pub interval_secs: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TradeRoute> for GameTradeRoute {
+    fn from(r: TradeRoute) -> Self {
+        Self {
+            id: r.id,
+            player_id: r.player_id,
+            village_id: r.village_id,
+            target_village_id: r.target_village_id,
+            resources: r.resources.as_ref().clone(),
+            interval_secs: r.interval_secs as u64,
+            created_at: r.created_at,
+        }
+    }
+}
+
+impl From<GameTradeRoute> for TradeRoute {
+    fn from(r: GameTradeRoute) -> Self {
+        Self {
+            id: r.id,
+            player_id: r.player_id,
+            village_id: r.village_id,
+            target_village_id: r.target_village_id,
+            resources: Json(r.resources),
+            interval_secs: r.interval_secs as i64,
+            created_at: r.created_at,
+        }
+    }
+}
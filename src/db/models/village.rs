@@ -7,10 +7,13 @@ use uuid::Uuid;
 
 use crate::game::models::{
     army::Army,
+    artifact::Artifact,
     buildings::Building,
     map::{Oasis, Position},
-    village::{StockCapacity, Village as GameVillage, VillageProduction},
-    {SmithyUpgrades, Tribe},
+    village::{
+        ReinforcementPolicy, StockCapacity, TrapperState, Village as GameVillage, VillageProduction,
+    },
+    {ResourceGroup, SmithyUpgrades, Tribe},
 };
 
 #[derive(Model, Serialize, Deserialize, Debug, Clone)]
@@ -33,6 +36,12 @@ pub struct Village {
     pub is_capital: bool,
     pub smithy: Json<SmithyUpgrades>,
     pub stocks: Json<StockCapacity>,
+    pub resources: Json<ResourceGroup>,
+    pub reinforcement_policy: Json<ReinforcementPolicy>,
+    pub parent_village_id: Option<u32>,
+    pub trapper: Json<TrapperState>,
+    pub brewery_celebration_until: Option<DateTime<Utc>>,
+    pub artifacts: Json<Vec<Artifact>>,
     pub updated_at: DateTime<Utc>,
 }
 
@@ -52,8 +61,14 @@ impl From<Village> for GameVillage {
             loyalty: v.loyalty,
             production: v.production.as_ref().clone(),
             is_capital: v.is_capital,
-            smithy: v.smithy.as_ref().clone(),
+            smithy: *v.smithy.as_ref(),
             stocks: v.stocks.as_ref().clone(),
+            resources: v.resources.as_ref().clone(),
+            reinforcement_policy: v.reinforcement_policy.as_ref().clone(),
+            parent_village_id: v.parent_village_id,
+            trapper: v.trapper.as_ref().clone(),
+            brewery_celebration_until: v.brewery_celebration_until,
+            artifacts: v.artifacts.as_ref().clone(),
             updated_at: v.updated_at,
         }
     }
@@ -76,8 +91,14 @@ impl From<GameVillage> for Village {
             loyalty: v.loyalty,
             production: Json(v.production.clone()),
             is_capital: v.is_capital,
-            smithy: Json(v.smithy.clone()),
+            smithy: Json(v.smithy),
             stocks: Json(v.stocks.clone()),
+            resources: Json(v.resources.clone()),
+            reinforcement_policy: Json(v.reinforcement_policy.clone()),
+            parent_village_id: v.parent_village_id,
+            trapper: Json(v.trapper.clone()),
+            brewery_celebration_until: v.brewery_celebration_until,
+            artifacts: Json(v.artifacts.clone()),
             updated_at: Utc::now(),
         }
     }
@@ -1,19 +1,61 @@
 use std::env;
 
 use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
 use ormlite::{sqlite::SqlitePoolOptions, types::Json, Model, Pool};
-use sqlx::{pool::PoolConnection, Sqlite, SqlitePool, Transaction};
+use rand::{rngs::StdRng, SeedableRng};
+use sqlx::{pool::PoolConnection, sqlite::SqliteRow, FromRow, Sqlite, SqlitePool, Transaction};
 use uuid::Uuid;
 
-use super::models::{map::MapField, player::Player, village::Village};
+use super::models::{
+    adventure::Adventure, audit::AuditEntry, farm_list::FarmList, hero::Hero, job::Job,
+    map::MapField, player::Player, report::Report, trade_route::TradeRoute, village::Village,
+};
+use crate::app::jobs::Job as AppJob;
 use crate::game::models::{
-    map::{generate_new_map, Oasis, Quadrant, Valley},
-    village::Village as GameVillage,
-    Player as GamePlayer, Tribe,
+    adventure::Adventure as GameAdventure,
+    army::Army,
+    audit::AuditEntry as GameAuditEntry,
+    farm_list::FarmList as GameFarmList,
+    hero::Hero as GameHero,
+    map::{
+        generate_new_map, oasis_animals, select_valley, MapFieldTopology, Oasis, Quadrant, Valley,
+    },
+    report::{CombatPoints, Report as GameReport, ReportFilter, ReportSort},
+    trade_route::TradeRoute as GameTradeRoute,
+    village::{allowed_villages, Village as GameVillage, SETTLERS_NEEDED, SETTLER_IDX},
+    Player as GamePlayer, Tribe, BEGINNERS_PROTECTION_HOURS,
 };
 
 // use crate::game::models::village::Village;
 
+// A named savepoint opened with `Repository::savepoint`. The writes made in the
+// transaction after it was opened can be discarded with `rollback_to`, without
+// touching the ones made before.
+#[derive(Debug)]
+pub struct Savepoint {
+    name: String,
+}
+
+impl Savepoint {
+    // Discards the writes made since the savepoint was opened. The savepoint stays open,
+    // so it can be rolled back to again or released.
+    pub async fn rollback_to(&self, tx: &mut Transaction<'_, Sqlite>) -> Result<()> {
+        sqlx::query(&format!("ROLLBACK TO SAVEPOINT {}", self.name))
+            .execute(tx)
+            .await?;
+        Ok(())
+    }
+
+    // Closes the savepoint: its writes become part of the outer transaction.
+    pub async fn release(self, tx: &mut Transaction<'_, Sqlite>) -> Result<()> {
+        sqlx::query(&format!("RELEASE SAVEPOINT {}", self.name))
+            .execute(tx)
+            .await?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Repository {
     pool: SqlitePool,
@@ -40,69 +82,163 @@ impl Repository {
         Ok(conn)
     }
 
-    pub async fn begin_transaction(&self) -> Result<Transaction<Sqlite>> {
+    pub async fn begin_transaction(&self) -> Result<Transaction<'_, Sqlite>> {
         let tx = self.pool.begin().await?;
         Ok(tx)
     }
 
+    // Opens a savepoint in the transaction, to try a part of it on its own. The outer
+    // transaction stays with the caller, who still has to commit or roll it back.
+    pub async fn savepoint(tx: &mut Transaction<'_, Sqlite>) -> Result<Savepoint> {
+        let savepoint = Savepoint {
+            name: format!("sp_{}", Uuid::new_v4().simple()),
+        };
+        sqlx::query(&format!("SAVEPOINT {}", savepoint.name))
+            .execute(tx)
+            .await?;
+        Ok(savepoint)
+    }
+
+    // Inserts a single row. Writes made through ormlite return the stored row and sqlite
+    // commits them only once the statement is reset, which can happen after the call
+    // returned: they run in a transaction, so they're visible to the other connections
+    // right away.
+    async fn insert_row<M>(&self, row: M) -> Result<()>
+    where
+        M: for<'m> Model<'m, Sqlite> + for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let mut tx = self.begin_transaction().await?;
+        row.insert(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Updates all the fields of a single row, committed right away as in `insert_row`.
+    async fn update_row<M>(&self, row: M) -> Result<()>
+    where
+        M: for<'m> Model<'m, Sqlite> + Send,
+    {
+        let mut tx = self.begin_transaction().await?;
+        row.update_all_fields(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     async fn new_connection_pool(url: &str) -> Result<Pool<Sqlite>> {
         Ok(SqlitePoolOptions::new()
             .max_connections(20)
-            .connect(&url)
+            .connect(url)
             .await?)
     }
 }
 #[async_trait::async_trait]
 impl crate::repository::Repository for Repository {
-    async fn bootstrap_new_map(&self, size: u32) -> Result<()> {
+    async fn bootstrap_new_map(&self, size: u32, started_at: DateTime<Utc>) -> Result<()> {
         let map = generate_new_map(size as i32);
         let mut tx = self.begin_transaction().await?;
 
         print!("Generating a map of {} fields... ", size * size * 4);
+        let mut rng = StdRng::from_entropy();
         for f in map {
-            let fm: MapField = f.into();
+            let mut fm: MapField = f.into();
+            // oases start guarded by wild animals
+            if let MapFieldTopology::Oasis(topology) = fm.topology.as_ref() {
+                fm.animals = Some(Json(oasis_animals(topology, &mut rng)));
+            }
             fm.insert(&mut tx).await?;
         }
+
+        // remember the size the map was generated with, to catch config changes later, and
+        // when the world started, so that restarts don't move it
+        sqlx::query(
+            "INSERT OR REPLACE INTO world_config (id, world_size, created_at) VALUES (1, ?, ?)",
+        )
+        .bind(size)
+        .bind(started_at)
+        .execute(&mut tx)
+        .await?;
         tx.commit().await?;
         println!("done!");
 
         Ok(())
     }
 
-    async fn get_unoccupied_valley(&self, quadrant: Option<Quadrant>) -> Result<Valley> {
+    async fn get_world_size(&self) -> Result<Option<i32>> {
         let mut conn = self.get_pool_connection().await?;
-        let query = match quadrant {
-            Some(Quadrant::NorthEast) => {
-                MapField::query("SELECT * FROM map_fields WHERE player_id IS NULL AND village_id IS NULL AND x >= 0 AND y >= 0 AND topology = '{\"Valley\":[4,4,4,6]}' ORDER BY
-	RANDOM()")
-            }
-            Some(Quadrant::EastSouth) => {
-                MapField::query("SELECT * FROM map_fields WHERE player_id IS NULL AND village_id IS NULL AND x >= 0 AND y < 0 AND topology = '{\"Valley\":[4,4,4,6]}' ORDER BY
-	RANDOM()" )
-            }
-            Some(Quadrant::SouthWest) => {
-                MapField::query("SELECT * FROM map_fields WHERE player_id IS NULL AND village_id IS NULL AND x < 0 AND y < 0 AND topology = '{\"Valley\":[4,4,4,6]}' ORDER BY
-	RANDOM()")
-            }
-            Some(Quadrant::WestNorth) => {
-                MapField::query("SELECT * FROM map_fields WHERE player_id IS NULL AND village_id IS NULL AND x >= 0 AND y < 0 AND topology = '{\"Valley\":[4,4,4,6]}' ORDER BY
-	RANDOM()")
-            }
-            None => { MapField::query("SELECT * FROM map_fields WHERE player_id IS NULL AND village_id IS NULL AND topology = '{\"Valley\":[4,4,4,6]}' ORDER BY RANDOM()") }
+        let row: Option<(i32,)> =
+            sqlx::query_as("SELECT world_size FROM world_config WHERE id = 1")
+                .fetch_optional(&mut conn)
+                .await?;
+
+        Ok(row.map(|(size,)| size))
+    }
+
+    async fn get_world_started_at(&self) -> Result<Option<DateTime<Utc>>> {
+        let mut conn = self.get_pool_connection().await?;
+        let row: Option<(DateTime<Utc>,)> =
+            sqlx::query_as("SELECT created_at FROM world_config WHERE id = 1")
+                .fetch_optional(&mut conn)
+                .await?;
+
+        Ok(row.map(|(started_at,)| started_at))
+    }
+
+    async fn get_unoccupied_valley(
+        &self,
+        quadrant: Option<Quadrant>,
+        seed: Option<u64>,
+    ) -> Result<Valley> {
+        let mut conn = self.get_pool_connection().await?;
+        let quadrant_condition = match quadrant {
+            Some(Quadrant::NorthEast) => "AND x >= 0 AND y >= 0",
+            Some(Quadrant::EastSouth) => "AND x >= 0 AND y < 0",
+            Some(Quadrant::SouthWest) => "AND x < 0 AND y < 0",
+            Some(Quadrant::WestNorth) => "AND x < 0 AND y >= 0",
+            None => "",
         };
-        let valley = query.fetch_one(&mut conn).await?;
+        let condition = format!(
+            "player_id IS NULL AND village_id IS NULL {} AND topology = '{{\"Valley\":[4,4,4,6]}}'",
+            quadrant_condition
+        );
 
-        Ok(valley.try_into()?)
+        // with a seed, the pick is reproducible and doesn't depend on the db ordering
+        match seed {
+            Some(seed) => {
+                let fields =
+                    MapField::query(&format!("SELECT * FROM map_fields WHERE {}", condition))
+                        .fetch_all(&mut conn)
+                        .await?;
+                let valleys = fields
+                    .into_iter()
+                    .map(|f| f.try_into())
+                    .collect::<Result<Vec<Valley>>>()?;
+
+                select_valley(valleys, seed).ok_or_else(|| Error::msg("No unoccupied valleys."))
+            }
+            None => {
+                let field = MapField::query(&format!(
+                    "SELECT * FROM map_fields WHERE {} ORDER BY RANDOM()",
+                    condition
+                ))
+                .fetch_one(&mut conn)
+                .await?;
+
+                Ok(field.try_into()?)
+            }
+        }
     }
 
     async fn register_player(&self, username: String, tribe: Tribe) -> Result<GamePlayer> {
         let mut tx = self.begin_transaction().await?;
 
-        if let Ok(_) = Player::query("SELECT * FROM players WHERE username = ?")
+        if Player::query("SELECT * FROM players WHERE username = ?")
             .bind(username.clone())
             // FIXME this method is better to lookup records by their columns `.fetch_optional(&mut tx)`
             .fetch_one(&mut tx)
             .await
+            .is_ok()
         {
             return Err(Error::msg("Username already used."));
         }
@@ -111,6 +247,8 @@ impl crate::repository::Repository for Repository {
             id: Uuid::new_v4(),
             username,
             tribe: Json(tribe),
+            culture_points: 0,
+            protected_until: Some(Utc::now() + Duration::hours(BEGINNERS_PROTECTION_HOURS)),
         };
         player.clone().insert(&mut tx).await?;
 
@@ -139,6 +277,95 @@ impl crate::repository::Repository for Repository {
         Ok(player.into())
     }
 
+    async fn update_player(&self, player: GamePlayer) -> Result<()> {
+        let player: Player = player.into();
+        self.update_row(player).await
+    }
+
+    async fn get_players_with_expired_protection(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<GamePlayer>> {
+        let mut conn = self.get_pool_connection().await?;
+        let players = Player::query(
+            "SELECT * FROM players WHERE protected_until IS NOT NULL AND protected_until <= ?",
+        )
+        .bind(now)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(players.into_iter().map(|p| p.into()).collect())
+    }
+
+    async fn list_players(&self) -> Result<Vec<GamePlayer>> {
+        let mut conn = self.get_pool_connection().await?;
+        let players = Player::query("SELECT * FROM players")
+            .fetch_all(&mut conn)
+            .await?;
+
+        Ok(players.into_iter().map(|p| p.into()).collect())
+    }
+
+    async fn award_culture_points(
+        &self,
+        player_id: Uuid,
+        day: String,
+        amount: u32,
+    ) -> Result<bool> {
+        let mut tx = self.begin_transaction().await?;
+
+        // the award is recorded once per day, so running it again changes nothing
+        let recorded = sqlx::query(
+            "INSERT OR IGNORE INTO culture_point_awards (player_id, day, amount) VALUES (?, ?, ?)",
+        )
+        .bind(player_id)
+        .bind(day)
+        .bind(amount)
+        .execute(&mut tx)
+        .await?;
+        if recorded.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE players SET culture_points = culture_points + ? WHERE id = ?")
+            .bind(amount)
+            .bind(player_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn count_players(&self) -> Result<u32> {
+        let mut conn = self.get_pool_connection().await?;
+        let (count,): (u32,) = sqlx::query_as("SELECT COUNT(*) FROM players")
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn count_villages(&self) -> Result<u32> {
+        let mut conn = self.get_pool_connection().await?;
+        let (count,): (u32,) = sqlx::query_as("SELECT COUNT(*) FROM villages")
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn get_highest_wonder(&self) -> Result<Option<(u32, u8)>> {
+        let mut conn = self.get_pool_connection().await?;
+        let wonder: Option<(u32, u8)> = sqlx::query_as(
+            "SELECT villages.id, json_extract(b.value, '$.level') AS level FROM villages, json_each(villages.buildings) b WHERE json_extract(b.value, '$.name') = 'WonderOfTheWorld' ORDER BY level DESC LIMIT 1",
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(wonder)
+    }
+
     async fn get_village_by_id(&self, village_id: u32) -> Result<GameVillage> {
         let mut conn = self.get_pool_connection().await?;
         let village = Village::query("SELECT * FROM villages WHERE id = ?")
@@ -149,6 +376,158 @@ impl crate::repository::Repository for Repository {
         Ok(village.into())
     }
 
+    async fn get_defending_armies(&self, village_id: u32) -> Result<Vec<Army>> {
+        let mut conn = self.get_pool_connection().await?;
+        let (army, reinforcements): (Json<Army>, Json<Vec<Army>>) =
+            sqlx::query_as("SELECT army, reinforcements FROM villages WHERE id = ?")
+                .bind(village_id)
+                .fetch_one(&mut conn)
+                .await?;
+
+        let mut armies = vec![army.0];
+        armies.extend(reinforcements.0);
+        Ok(armies)
+    }
+
+    async fn get_villages_by_player_id(&self, player_id: Uuid) -> Result<Vec<GameVillage>> {
+        let mut conn = self.get_pool_connection().await?;
+        let villages = Village::query("SELECT * FROM villages WHERE player_id = ?")
+            .bind(player_id)
+            .fetch_all(&mut conn)
+            .await?;
+
+        Ok(villages.into_iter().map(|v| v.into()).collect())
+    }
+
+    async fn found_village(
+        &self,
+        village: GameVillage,
+        settlers_village_id: Option<u32>,
+    ) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+
+        // settlers are consumed only if they're still there, so they can't be spent twice
+        if let Some(settlers_village_id) = settlers_village_id {
+            let path = format!("$.units[{}]", SETTLER_IDX);
+            let consumed = sqlx::query(
+                "UPDATE villages SET army = json_set(army, ?1, json_extract(army, ?1) - ?2) WHERE id = ?3 AND json_extract(army, ?1) >= ?2",
+            )
+            .bind(&path)
+            .bind(SETTLERS_NEEDED)
+            .bind(settlers_village_id)
+            .execute(&mut tx)
+            .await?;
+
+            if consumed.rows_affected() != 1 {
+                return Err(Error::msg("Not enough settlers."));
+            }
+        }
+
+        // claim the valley only if it's still free, so that concurrent requests can't both succeed
+        let claimed = sqlx::query(
+            "UPDATE map_fields SET player_id = ?, village_id = ? WHERE x = ? AND y = ? AND player_id IS NULL AND village_id IS NULL",
+        )
+        .bind(village.player_id)
+        .bind(village.id)
+        .bind(village.position.x)
+        .bind(village.position.y)
+        .execute(&mut tx)
+        .await?;
+
+        if claimed.rows_affected() != 1 {
+            return Err(Error::msg("Valley already occupied."));
+        }
+
+        // culture points are checked again here, where the write lock is held, so that
+        // concurrent requests can't found more villages than allowed
+        let (culture_points,): (u32,) =
+            sqlx::query_as("SELECT culture_points FROM players WHERE id = ?")
+                .bind(village.player_id)
+                .fetch_one(&mut tx)
+                .await?;
+        let (villages,): (u32,) =
+            sqlx::query_as("SELECT COUNT(*) FROM villages WHERE player_id = ?")
+                .bind(village.player_id)
+                .fetch_one(&mut tx)
+                .await?;
+        if villages >= allowed_villages(culture_points) {
+            return Err(Error::msg("Not enough culture points for a new village."));
+        }
+
+        let village: Village = village.into();
+        village.insert(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn update_village(&self, village: GameVillage) -> Result<()> {
+        let village: Village = village.into();
+        self.update_row(village).await
+    }
+
+    async fn conquer_village(&self, village: GameVillage) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+
+        // the valley and the annexed oases both point to the village
+        sqlx::query("UPDATE map_fields SET player_id = ? WHERE village_id = ?")
+            .bind(village.player_id)
+            .bind(village.id)
+            .execute(&mut tx)
+            .await?;
+
+        let village: Village = village.into();
+        village.update_all_fields(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_village(&self, village_id: u32) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+
+        let deleted = sqlx::query("DELETE FROM villages WHERE id = ?")
+            .bind(village_id)
+            .execute(&mut tx)
+            .await?;
+        if deleted.rows_affected() != 1 {
+            return Err(Error::msg("Village not found."));
+        }
+
+        // the tile goes back to be a free valley
+        sqlx::query(
+            "UPDATE map_fields SET player_id = NULL, village_id = NULL WHERE village_id = ?",
+        )
+        .bind(village_id)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("DELETE FROM trade_routes WHERE village_id = ?1 OR target_village_id = ?1")
+            .bind(village_id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM farm_lists WHERE village_id = ?")
+            .bind(village_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_player(&self, player_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        for query in [
+            "DELETE FROM heroes WHERE player_id = ?",
+            "DELETE FROM combat_points WHERE player_id = ?",
+            "DELETE FROM players WHERE id = ?",
+        ] {
+            sqlx::query(query).bind(player_id).execute(&mut tx).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     async fn get_valley_by_id(&self, valley_id: u32) -> Result<Valley> {
         let mut conn = self.get_pool_connection().await?;
         let valley = MapField::query("SELECT * FROM map_fields WHERE id = ?")
@@ -168,4 +547,632 @@ impl crate::repository::Repository for Repository {
 
         Ok(oasis.try_into()?)
     }
+
+    async fn update_oasis(&self, oasis: Oasis) -> Result<()> {
+        let oasis: MapField = oasis.into();
+        self.update_row(oasis).await
+    }
+
+    async fn get_hero_by_player_id(&self, player_id: Uuid) -> Result<GameHero> {
+        let mut conn = self.get_pool_connection().await?;
+        let hero = Hero::query("SELECT * FROM heroes WHERE player_id = ?")
+            .bind(player_id)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(hero.into())
+    }
+
+    async fn update_hero(&self, hero: GameHero) -> Result<()> {
+        let hero: Hero = hero.into();
+        self.update_row(hero).await
+    }
+
+    async fn list_heroes(&self) -> Result<Vec<GameHero>> {
+        let mut conn = self.get_pool_connection().await?;
+        let heroes = Hero::query("SELECT * FROM heroes")
+            .fetch_all(&mut conn)
+            .await?;
+
+        Ok(heroes.into_iter().map(|h| h.into()).collect())
+    }
+
+    async fn add_adventure(&self, adventure: GameAdventure) -> Result<()> {
+        let adventure: Adventure = adventure.into();
+        self.insert_row(adventure).await
+    }
+
+    async fn get_adventure_by_id(&self, adventure_id: Uuid) -> Result<GameAdventure> {
+        let mut conn = self.get_pool_connection().await?;
+        let adventure = Adventure::query("SELECT * FROM adventures WHERE id = ?")
+            .bind(adventure_id)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(adventure.into())
+    }
+
+    async fn get_adventures_by_player_id(&self, player_id: Uuid) -> Result<Vec<GameAdventure>> {
+        let mut conn = self.get_pool_connection().await?;
+        let adventures =
+            Adventure::query("SELECT * FROM adventures WHERE player_id = ? ORDER BY created_at")
+                .bind(player_id)
+                .fetch_all(&mut conn)
+                .await?;
+
+        Ok(adventures.into_iter().map(|a| a.into()).collect())
+    }
+
+    async fn remove_adventure(&self, adventure_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query("DELETE FROM adventures WHERE id = ?")
+            .bind(adventure_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn add_job(&self, job: AppJob) -> Result<()> {
+        let job: Job = job.into();
+        self.insert_row(job).await
+    }
+
+    async fn get_job_by_id(&self, job_id: Uuid) -> Result<AppJob> {
+        let mut conn = self.get_pool_connection().await?;
+        let job = Job::query("SELECT * FROM jobs WHERE id = ?")
+            .bind(job_id)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(job.into())
+    }
+
+    async fn update_job(&self, job: AppJob) -> Result<()> {
+        let job: Job = job.into();
+        self.update_row(job).await
+    }
+
+    async fn remove_job(&self, job_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query("DELETE FROM jobs WHERE id = ?")
+            .bind(job_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn mark_job_done(&self, job_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query("UPDATE jobs SET done = 1 WHERE id = ?")
+            .bind(job_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_done_jobs_by_player_id(&self, player_id: Uuid) -> Result<Vec<AppJob>> {
+        let mut conn = self.get_pool_connection().await?;
+        let jobs = Job::query(
+            "SELECT * FROM jobs WHERE player_id = ? AND done = 1 ORDER BY started_at DESC",
+        )
+        .bind(player_id)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(jobs.into_iter().map(|j| j.into()).collect())
+    }
+
+    async fn get_pending_jobs_by_village_id(&self, village_id: u32) -> Result<Vec<AppJob>> {
+        let mut conn = self.get_pool_connection().await?;
+        let jobs = Job::query(
+            "SELECT * FROM jobs WHERE village_id = ? AND done = 0 AND failed = 0 ORDER BY started_at ASC",
+        )
+        .bind(village_id)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(jobs.into_iter().map(|j| j.into()).collect())
+    }
+
+    async fn get_stuck_jobs(&self, processing_before: DateTime<Utc>) -> Result<Vec<AppJob>> {
+        let mut conn = self.get_pool_connection().await?;
+        let jobs = Job::query(
+            "SELECT * FROM jobs WHERE done = 0 AND failed = 0 AND processing_since IS NOT NULL AND processing_since < ?",
+        )
+        .bind(processing_before)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(jobs.into_iter().map(|j| j.into()).collect())
+    }
+
+    async fn get_due_jobs(&self, now: DateTime<Utc>) -> Result<Vec<AppJob>> {
+        let mut conn = self.get_pool_connection().await?;
+        let jobs = Job::query(
+            "SELECT * FROM jobs WHERE done = 0 AND failed = 0 AND processing_since IS NULL",
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        // the end time isn't stored, it's derived from the start time and the duration
+        let mut jobs: Vec<AppJob> = jobs
+            .into_iter()
+            .map(AppJob::from)
+            .filter(|j| j.ends_at() <= now)
+            .collect();
+        jobs.sort_by_key(|j| j.ends_at());
+
+        Ok(jobs)
+    }
+
+    async fn add_report(&self, report: GameReport) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+
+        // points are added as reports come in, so rankings never re-scan the reports
+        let (attack, defense) = report.combat_points();
+        for (player_id, attack, defense) in [
+            (report.attacker_player_id, attack, 0),
+            (report.defender_player_id, 0, defense),
+        ] {
+            if attack == 0 && defense == 0 {
+                continue;
+            }
+            sqlx::query(
+                "INSERT INTO combat_points (player_id, attack, defense) VALUES (?, ?, ?) ON CONFLICT(player_id) DO UPDATE SET attack = attack + excluded.attack, defense = defense + excluded.defense",
+            )
+            .bind(player_id)
+            .bind(attack)
+            .bind(defense)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        let report: Report = report.into();
+        report.insert(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_report_by_id(&self, report_id: Uuid) -> Result<GameReport> {
+        let mut conn = self.get_pool_connection().await?;
+        let report = Report::query("SELECT * FROM reports WHERE id = ?")
+            .bind(report_id)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(report.into())
+    }
+
+    async fn get_reports_by_ids(&self, report_ids: &[Uuid]) -> Result<Vec<GameReport>> {
+        let mut conn = self.get_pool_connection().await?;
+        let mut reports = vec![];
+        for report_id in report_ids {
+            let report = Report::query("SELECT * FROM reports WHERE id = ?")
+                .bind(report_id)
+                .fetch_optional(&mut conn)
+                .await?;
+            reports.extend(report.map(|r| r.into()));
+        }
+
+        Ok(reports)
+    }
+
+    async fn get_reports_by_player_id(&self, player_id: Uuid) -> Result<Vec<GameReport>> {
+        let mut conn = self.get_pool_connection().await?;
+        let reports = Report::query(
+            "SELECT * FROM reports WHERE attacker_player_id = ? OR defender_player_id = ? ORDER BY created_at DESC",
+        )
+        .bind(player_id)
+        .bind(player_id)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(reports.into_iter().map(|r| r.into()).collect())
+    }
+
+    async fn list_reports(
+        &self,
+        player_id: Uuid,
+        filter: &ReportFilter,
+        offset: u32,
+        limit: u32,
+        sort: ReportSort,
+    ) -> Result<(Vec<GameReport>, u32)> {
+        // defenders don't see scouting reports when the scouts went unnoticed
+        let visible = "((attacker_player_id = ?1 AND NOT deleted_by_attacker) OR (defender_player_id = ?1 AND NOT deleted_by_defender AND COALESCE(json_extract(audience, '$.Spy.detected'), 1) = 1)) \
+            AND (?2 IS NULL OR kind = ?2) \
+            AND (?3 = 0 OR NOT ((attacker_player_id = ?1 AND read_by_attacker) OR (defender_player_id = ?1 AND read_by_defender))) \
+            AND ((attacker_player_id = ?1 AND archived_by_attacker) OR (defender_player_id = ?1 AND archived_by_defender)) = ?4";
+        let kind = filter.kind.map(|k| serde_json::to_string(&k)).transpose()?;
+        let order = match sort {
+            ReportSort::NewestFirst => "DESC",
+            ReportSort::OldestFirst => "ASC",
+        };
+
+        let mut conn = self.get_pool_connection().await?;
+        let (total,): (u32,) =
+            sqlx::query_as(&format!("SELECT COUNT(*) FROM reports WHERE {}", visible))
+                .bind(player_id)
+                .bind(&kind)
+                .bind(filter.unread_only)
+                .bind(filter.archived)
+                .fetch_one(&mut conn)
+                .await?;
+        let reports = Report::query(&format!(
+            "SELECT * FROM reports WHERE {} ORDER BY created_at {}, id LIMIT ?5 OFFSET ?6",
+            visible, order
+        ))
+        .bind(player_id)
+        .bind(&kind)
+        .bind(filter.unread_only)
+        .bind(filter.archived)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok((reports.into_iter().map(|r| r.into()).collect(), total))
+    }
+
+    async fn mark_report_read(&self, report_id: Uuid, player_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query(
+            "UPDATE reports SET read_by_attacker = read_by_attacker OR attacker_player_id = ?2, read_by_defender = read_by_defender OR defender_player_id = ?2 WHERE id = ?1",
+        )
+        .bind(report_id)
+        .bind(player_id)
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn archive_reports(&self, report_ids: &[Uuid], player_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        for report_id in report_ids {
+            sqlx::query(
+                "UPDATE reports SET archived_by_attacker = archived_by_attacker OR attacker_player_id = ?2, archived_by_defender = archived_by_defender OR defender_player_id = ?2 WHERE id = ?1",
+            )
+            .bind(report_id)
+            .bind(player_id)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_reports(&self, report_ids: &[Uuid], player_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        for report_id in report_ids {
+            sqlx::query(
+                "UPDATE reports SET deleted_by_attacker = deleted_by_attacker OR attacker_player_id = ?2, deleted_by_defender = deleted_by_defender OR defender_player_id = ?2 WHERE id = ?1",
+            )
+            .bind(report_id)
+            .bind(player_id)
+            .execute(&mut tx)
+            .await?;
+
+            // the report is gone once nobody can see it anymore
+            sqlx::query(
+                "DELETE FROM reports WHERE id = ? AND deleted_by_attacker AND (deleted_by_defender OR COALESCE(json_extract(audience, '$.Spy.detected'), 1) = 0)",
+            )
+            .bind(report_id)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, entry: GameAuditEntry) -> Result<()> {
+        let entry: AuditEntry = entry.into();
+        self.insert_row(entry).await
+    }
+
+    async fn get_audit_entries_by_village_id(
+        &self,
+        village_id: u32,
+    ) -> Result<Vec<GameAuditEntry>> {
+        let mut conn = self.get_pool_connection().await?;
+        let entries =
+            AuditEntry::query("SELECT * FROM audit_log WHERE village_id = ? ORDER BY created_at")
+                .bind(village_id)
+                .fetch_all(&mut conn)
+                .await?;
+
+        Ok(entries.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn get_combat_points(&self, player_id: Uuid) -> Result<CombatPoints> {
+        let mut conn = self.get_pool_connection().await?;
+        let row: Option<(u32, u32)> =
+            sqlx::query_as("SELECT attack, defense FROM combat_points WHERE player_id = ?")
+                .bind(player_id)
+                .fetch_optional(&mut conn)
+                .await?;
+
+        Ok(
+            row.map_or_else(Default::default, |(attack, defense)| CombatPoints {
+                attack,
+                defense,
+            }),
+        )
+    }
+
+    async fn add_trade_route(&self, route: GameTradeRoute) -> Result<()> {
+        let route: TradeRoute = route.into();
+        self.insert_row(route).await
+    }
+
+    async fn get_trade_route_by_id(&self, route_id: Uuid) -> Result<Option<GameTradeRoute>> {
+        let mut conn = self.get_pool_connection().await?;
+        let route = TradeRoute::query("SELECT * FROM trade_routes WHERE id = ?")
+            .bind(route_id)
+            .fetch_optional(&mut conn)
+            .await?;
+
+        Ok(route.map(|r| r.into()))
+    }
+
+    async fn get_trade_routes_by_village_id(&self, village_id: u32) -> Result<Vec<GameTradeRoute>> {
+        let mut conn = self.get_pool_connection().await?;
+        let routes = TradeRoute::query(
+            "SELECT * FROM trade_routes WHERE village_id = ? ORDER BY created_at",
+        )
+        .bind(village_id)
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(routes.into_iter().map(|r| r.into()).collect())
+    }
+
+    async fn remove_trade_route(&self, route_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query("DELETE FROM trade_routes WHERE id = ?")
+            .bind(route_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn add_farm_list(&self, list: GameFarmList) -> Result<()> {
+        let list: FarmList = list.into();
+        self.insert_row(list).await
+    }
+
+    async fn get_farm_list_by_id(&self, list_id: Uuid) -> Result<Option<GameFarmList>> {
+        let mut conn = self.get_pool_connection().await?;
+        let list = FarmList::query("SELECT * FROM farm_lists WHERE id = ?")
+            .bind(list_id)
+            .fetch_optional(&mut conn)
+            .await?;
+
+        Ok(list.map(|l| l.into()))
+    }
+
+    async fn get_farm_lists_by_village_id(&self, village_id: u32) -> Result<Vec<GameFarmList>> {
+        let mut conn = self.get_pool_connection().await?;
+        let lists =
+            FarmList::query("SELECT * FROM farm_lists WHERE village_id = ? ORDER BY created_at")
+                .bind(village_id)
+                .fetch_all(&mut conn)
+                .await?;
+
+        Ok(lists.into_iter().map(|l| l.into()).collect())
+    }
+
+    async fn update_farm_list(&self, list: GameFarmList) -> Result<()> {
+        let list: FarmList = list.into();
+        self.update_row(list).await
+    }
+
+    async fn remove_farm_list(&self, list_id: Uuid) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        sqlx::query("DELETE FROM farm_lists WHERE id = ?")
+            .bind(list_id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use ormlite::Model;
+
+    use crate::db::{
+        models::map::MapField,
+        test_utils::{insert_oasis, insert_valley, insert_village, setup_repo},
+    };
+    use crate::game::{
+        battle::total_defense_points,
+        models::{
+            army::Army,
+            map::{MapFieldTopology, Position, WORLD_MAX_SIZE},
+            village::Village,
+            Tribe,
+        },
+    };
+    use crate::repository::Repository;
+
+    #[tokio::test]
+    async fn test_bootstrap_stores_world_size_and_start() {
+        let repo = setup_repo().await;
+        assert_eq!(repo.get_world_size().await.unwrap(), None);
+        assert_eq!(repo.get_world_started_at().await.unwrap(), None);
+
+        let started_at = Utc::now() - Duration::days(12);
+        repo.bootstrap_new_map(3, started_at).await.unwrap();
+        assert_eq!(repo.get_world_size().await.unwrap(), Some(3));
+        assert_eq!(repo.get_world_started_at().await.unwrap(), Some(started_at));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_seeds_oasis_animals() {
+        let repo = setup_repo().await;
+        repo.bootstrap_new_map(10, Utc::now()).await.unwrap();
+
+        let mut conn = repo.get_pool_connection().await.unwrap();
+        let fields = MapField::select().fetch_all(&mut conn).await.unwrap();
+        for f in fields {
+            match f.topology.as_ref() {
+                MapFieldTopology::Oasis(_) => assert!(f.animals.is_some()),
+                MapFieldTopology::Valley(_) => assert!(f.animals.is_none()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_oasis_animals() {
+        let repo = setup_repo().await;
+        let position = Position { x: 3, y: 4 };
+        insert_oasis(&repo, &position, [5, 3, 0, 0, 0, 0, 0, 0, 0, 0]).await;
+
+        let mut oasis = repo
+            .get_oasis_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+        assert_eq!(oasis.animals, [5, 3, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(!oasis.is_cleared());
+
+        oasis.animals = [0; 10];
+        repo.update_oasis(oasis.clone()).await.unwrap();
+        let oasis = repo.get_oasis_by_id(oasis.id).await.unwrap();
+        assert!(oasis.is_cleared());
+    }
+
+    #[tokio::test]
+    async fn test_savepoint_rollback_keeps_the_outer_transaction() {
+        let repo = setup_repo().await;
+        let (before, lost, after) = (
+            Position { x: 3, y: 4 },
+            Position { x: 5, y: 6 },
+            Position { x: 7, y: 8 },
+        );
+        for position in [&before, &lost, &after] {
+            insert_valley(&repo, position).await;
+        }
+        let claim = "UPDATE map_fields SET village_id = id WHERE id = ?";
+        let claimed = |position: &Position| {
+            let id = position.to_id(WORLD_MAX_SIZE);
+            let repo = repo.clone();
+            async move { repo.get_valley_by_id(id).await.unwrap().village_id == Some(id) }
+        };
+
+        let mut tx = repo.begin_transaction().await.unwrap();
+        sqlx::query(claim)
+            .bind(before.to_id(WORLD_MAX_SIZE))
+            .execute(&mut tx)
+            .await
+            .unwrap();
+
+        let savepoint = super::Repository::savepoint(&mut tx).await.unwrap();
+        sqlx::query(claim)
+            .bind(lost.to_id(WORLD_MAX_SIZE))
+            .execute(&mut tx)
+            .await
+            .unwrap();
+        savepoint.rollback_to(&mut tx).await.unwrap();
+
+        sqlx::query(claim)
+            .bind(after.to_id(WORLD_MAX_SIZE))
+            .execute(&mut tx)
+            .await
+            .unwrap();
+        savepoint.release(&mut tx).await.unwrap();
+
+        // nothing is visible until the outer transaction commits
+        assert!(!claimed(&before).await);
+        tx.commit().await.unwrap();
+
+        assert!(claimed(&before).await);
+        assert!(!claimed(&lost).await);
+        assert!(claimed(&after).await);
+    }
+
+    #[tokio::test]
+    async fn test_village_updates_are_visible_right_away() {
+        let repo = setup_repo().await;
+        let village = insert_village(&repo, "alice", Tribe::Roman, &Position { x: 3, y: 4 }).await;
+
+        // reads go through any connection of the pool
+        for loyalty in 0..200 {
+            let mut village = repo.get_village_by_id(village.id).await.unwrap();
+            village.loyalty = (loyalty % 100) as u8;
+            repo.update_village(village.clone()).await.unwrap();
+
+            let stored = repo.get_village_by_id(village.id).await.unwrap();
+            assert_eq!(stored.loyalty, village.loyalty);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_defending_armies() {
+        let repo = setup_repo().await;
+        let position = Position { x: 3, y: 4 };
+        insert_valley(&repo, &position).await;
+
+        let defender = repo
+            .register_player("alice".to_string(), Tribe::Roman)
+            .await
+            .unwrap();
+        let ally = repo
+            .register_player("bob".to_string(), Tribe::Gaul)
+            .await
+            .unwrap();
+        let valley = repo
+            .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+            .await
+            .unwrap();
+
+        let mut village = Village::new("Alice".to_string(), &valley, &defender, true);
+        village.army.units = [50, 10, 0, 0, 0, 0, 0, 0, 0, 0];
+        village.reinforcements = vec![
+            Army::new(
+                1,
+                ally.id,
+                Tribe::Gaul,
+                [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0; 10],
+            ),
+            Army::new(
+                2,
+                ally.id,
+                Tribe::Gaul,
+                [0, 0, 0, 0, 20, 0, 0, 0, 0, 0],
+                [0; 10],
+            ),
+        ];
+        repo.found_village(village.clone(), None).await.unwrap();
+
+        let armies = repo.get_defending_armies(village.id).await.unwrap();
+        assert_eq!(armies.len(), 3);
+        assert_eq!(armies[0].units, village.army.units);
+        assert_eq!(armies[2].units, village.reinforcements[1].units);
+
+        let (infantry, cavalry) = total_defense_points(&armies);
+        let expected = village
+            .defending_armies()
+            .iter()
+            .map(|a| a.defense_points())
+            .fold((0, 0), |(i, c), (ai, ac)| (i + ai, c + ac));
+        assert_eq!((infantry, cavalry), expected);
+        assert!(infantry > village.army.defense_points().0);
+    }
 }
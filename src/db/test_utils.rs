@@ -0,0 +1,113 @@
+use ormlite::{model::*, types::Json};
+use uuid::Uuid;
+
+use super::{
+    models::{hero::Hero, map::MapField},
+    repository::Repository,
+};
+use crate::game::models::{
+    army::TroopSet,
+    hero::Hero as GameHero,
+    map::{MapFieldTopology, OasisTopology, Position, Valley, ValleyTopology, WORLD_MAX_SIZE},
+    village::Village,
+    Player, Tribe,
+};
+use crate::repository::Repository as _;
+
+// Returns a new player without culture points or protection, for the tests that don't
+// need it stored.
+pub fn test_player(tribe: Tribe) -> Player {
+    Player {
+        id: Uuid::new_v4(),
+        username: "pavonz".to_string(),
+        tribe,
+        culture_points: 0,
+        protected_until: None,
+    }
+}
+
+// Returns a new capital of the player on a 4-4-4-6 valley at the given position, without
+// storing either of them.
+pub fn test_village(player: &Player, position: Position) -> Village {
+    let valley = Valley {
+        id: position.to_id(WORLD_MAX_SIZE),
+        position,
+        topology: ValleyTopology(4, 4, 4, 6),
+        player_id: None,
+        village_id: None,
+    };
+    Village::new("Gino".to_string(), &valley, player, true)
+}
+
+// Returns a repository on a fresh, migrated sqlite database.
+pub async fn setup_repo() -> Repository {
+    let path = std::env::temp_dir().join(format!("parabellum-{}.db", Uuid::new_v4()));
+    let repo = Repository::new(format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    let mut conn = repo.get_pool_connection().await.unwrap();
+    sqlx::migrate!("./migrations").run(&mut conn).await.unwrap();
+    repo
+}
+
+// Adds an unoccupied valley to the map.
+pub async fn insert_valley(repo: &Repository, position: &Position) {
+    let valley = MapField {
+        id: position.to_id(WORLD_MAX_SIZE),
+        player_id: None,
+        village_id: None,
+        x: position.x,
+        y: position.y,
+        topology: Json(MapFieldTopology::Valley(ValleyTopology(4, 4, 4, 6))),
+        animals: None,
+    };
+    let mut tx = repo.begin_transaction().await.unwrap();
+    valley.insert(&mut tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+// Adds an unoccupied lumber oasis, guarded by the given animals, to the map.
+pub async fn insert_oasis(repo: &Repository, position: &Position, animals: TroopSet) {
+    let oasis = MapField {
+        id: position.to_id(WORLD_MAX_SIZE),
+        player_id: None,
+        village_id: None,
+        x: position.x,
+        y: position.y,
+        topology: Json(MapFieldTopology::Oasis(OasisTopology::Lumber)),
+        animals: Some(Json(animals)),
+    };
+    let mut tx = repo.begin_transaction().await.unwrap();
+    oasis.insert(&mut tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+// Stores the hero, as players don't get one on registration yet.
+pub async fn insert_hero(repo: &Repository, hero: GameHero) {
+    let hero: Hero = hero.into();
+    let mut tx = repo.begin_transaction().await.unwrap();
+    hero.insert(&mut tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+// Registers a player and founds their capital on a new valley at the given position.
+// The village is named after the player.
+pub async fn insert_village(
+    repo: &Repository,
+    username: &str,
+    tribe: Tribe,
+    position: &Position,
+) -> Village {
+    insert_valley(repo, position).await;
+    let player = repo
+        .register_player(username.to_string(), tribe)
+        .await
+        .unwrap();
+    let valley = repo
+        .get_valley_by_id(position.to_id(WORLD_MAX_SIZE))
+        .await
+        .unwrap();
+    let village = Village::new(username.to_string(), &valley, &player, true);
+    repo.found_village(village.clone(), None).await.unwrap();
+    village
+}
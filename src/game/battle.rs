@@ -1,13 +1,16 @@
+use chrono::{DateTime, Utc};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::models::{
-    army::Army,
-    buildings::{Building, BuildingName},
-    village::Village,
+    army::{Army, UnitName},
+    buildings::{tribe_wall, Building, BuildingName},
+    map::Oasis,
+    village::{Village, CHIEF_IDX},
     Tribe,
 };
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct CataTargets(pub Option<BuildingName>, pub Option<BuildingName>);
 
 impl CataTargets {
@@ -47,11 +50,19 @@ pub struct Battle {
     pub is_normal: bool,
     pub is_scouting: bool,
     pub cata_targets: CataTargets,
+    // Server-wide defense multiplier (e.g. night bonus), 1.0 means no bonus.
+    pub defense_multiplier: f64,
+    // Minimum share of troops a winning attacker loses against any defending troop, 0 disables it.
+    pub min_attacker_losses_percent: f64,
+    // Set when the attacker chiefs took the village over.
+    pub conquered: bool,
+    // When the armies meet, timed bonuses apply if they're active at this time.
+    pub fought_at: DateTime<Utc>,
     state: BattleState,
 }
 
 impl Battle {
-    pub fn new<'b>(
+    pub fn new(
         attacker_army: Army,
         attacker_village: Village,
         defender_village: Village,
@@ -66,16 +77,20 @@ impl Battle {
             is_normal,
             is_scouting,
             cata_targets,
+            defense_multiplier: 1.0,
+            min_attacker_losses_percent: 0.0,
+            conquered: false,
+            fought_at: Utc::now(),
             state: Default::default(),
         }
     }
 
     // Calculates a battle between two armies. Kirilloid's formulas.
     pub fn combat(&mut self) {
+        self.apply_traps();
         self.calculate_battle_points();
 
         if !self.is_scouting && self.is_normal {
-            self.apply_catapults_damage();
             self.apply_rams_damage();
         }
 
@@ -88,7 +103,23 @@ impl Battle {
 
         self.calculate_immensity_factor();
         self.calculate_losses_percent();
+        self.apply_min_attacker_losses();
         self.apply_losses();
+
+        // Catapults fire once the fight is over, only the survivors of a won battle
+        if !self.is_scouting && self.is_normal && self.state.atk_won {
+            self.apply_catapults_damage();
+            self.apply_loyalty_damage();
+        }
+    }
+
+    // Trappers catch attackers before the fight: captured units don't fight and don't
+    // die, they stay in the defender village until they're freed.
+    fn apply_traps(&mut self) {
+        if self.is_scouting {
+            return;
+        }
+        self.defender_village.trap(&mut self.attacker_army);
     }
 
     // Calculates attacker and defender points, including Smithy upgrades and bonuses.
@@ -102,6 +133,7 @@ impl Battle {
             for r in self.defender_village.reinforcements.clone() {
                 self.state.def_points += r.scouting_defense_points()
             }
+            return;
         }
 
         // Calculate the Cavalry Attacking Power (CAP) and Infantry Attacking Power (IAP)
@@ -110,89 +142,54 @@ impl Battle {
         // against Infantry (IDP)
         let infantry_atk_points: u32;
         (infantry_atk_points, cavalry_atk_points) = self.attacker_army.attack_points();
+        let bonus = self.attacker_village.attack_bonus(self.fought_at);
+        let infantry_atk_points = (infantry_atk_points as f64 * bonus).round() as u32;
+        let cavalry_atk_points = (cavalry_atk_points as f64 * bonus).round() as u32;
 
-        let mut cavalry_def_points: u32;
-        let mut infantry_def_points: u32;
-        (infantry_def_points, cavalry_def_points) = self.defender_village.army.defense_points();
-
-        // Include reinforcements defensive power
-        for r in self.defender_village.reinforcements.clone() {
-            let rcdp: u32;
-            let ridp: u32;
-            (ridp, rcdp) = r.defense_points();
-            infantry_def_points += ridp;
-            cavalry_def_points += rcdp;
-        }
+        // Garrison and reinforcements defend together
+        let (infantry_def_points, cavalry_def_points) =
+            total_defense_points(&self.defender_village.defending_armies());
 
         // Calculate the total offensive and defensive power.
         self.state.atk_points = infantry_atk_points + cavalry_atk_points;
-        let cavalry_atk_percent: f64 = cavalry_atk_points as f64 / self.state.atk_points as f64;
-        let infantry_atk_percent: f64 = infantry_atk_points as f64 / self.state.atk_points as f64;
-
-        self.state.def_points = (infantry_def_points as f64 * infantry_atk_percent
-            + cavalry_def_points as f64 * cavalry_atk_percent)
-            .floor() as u32;
-
-        // Each village has a basic defense value of 10
-        self.state.def_points += 10;
+        self.state.def_points = weighted_defense_points(
+            (infantry_atk_points, cavalry_atk_points),
+            (infantry_def_points, cavalry_def_points),
+        );
 
         // Battle bonuses: order matters!
-        self.apply_palace_defense();
+        self.state.def_points += village_base_defense(&self.defender_village);
         self.apply_wall_bonus();
-    }
-
-    // Palace/Residence have some defense value
-    fn apply_palace_defense(&mut self) {
-        self.state.def_points += match self.defender_village.get_palace_or_residence() {
-            Some((building, _)) => (building.level * building.level) as u32 * 2,
-            None => 0 as u32,
-        };
+        self.apply_defense_multiplier();
     }
 
     // Walls give a percentual bonus to total defense.
     fn apply_wall_bonus(&mut self) {
-        if let Some(wall) = self.defender_village.get_wall() {
-            let tribe_bonus: f64 = match self.defender_village.tribe {
-                Tribe::Roman => 1.030,
-                Tribe::Gaul => 1.025,
-                Tribe::Teuton => 1.020,
-                _ => 1.0,
-            };
-
-            let bonus = tribe_bonus.powf(wall.level as f64);
-
-            self.state.def_points = (self.state.def_points as f64 * bonus).floor() as u32
-        }
+        let bonus = wall_bonus(&self.defender_village);
+        self.state.def_points = (self.state.def_points as f64 * bonus).floor() as u32
+    }
 
-        // bonus := math.Pow(tribeBonus, float64(level))
-        // b.state.defPoints = uint64(math.Floor(float64(b.state.defPoints) * bonus))
+    // Some servers boost defense during given hours of the day.
+    fn apply_defense_multiplier(&mut self) {
+        self.state.def_points = (self.state.def_points as f64 * self.defense_multiplier) as u32;
     }
 
     // Morale changes when defender's account population is lower than attacker's one.
     fn apply_defender_morale_bonus(&mut self) {
-        let atk_pop = self.attacker_village.population;
-        let def_pop = self.defender_village.population;
-
-        let mut bonus = (atk_pop as f64 / def_pop as f64).powf(0.2).floor();
-
-        // Special case: if attacker has fewer points than defender (including all bonuses), and has more population, formula changes:
-        if self.state.atk_points < self.state.def_points {
-            bonus = (atk_pop as f64 / def_pop as f64)
-                .powf(0.2 * (self.state.atk_points as f64 / self.state.def_points as f64));
-        }
-
-        // Morale bonus never goes beyond +50% regardless of defender's population
-        if bonus > 1.5 {
-            bonus = 1.5
-        }
-
+        let bonus = defender_morale_bonus(
+            self.attacker_village.population,
+            self.defender_village.population,
+            self.state.atk_points,
+            self.state.def_points,
+        );
         self.state.def_points = (self.state.def_points as f64 * bonus) as u32;
     }
 
     // Determine winner and loser of this battle.
     fn calculate_outcome(&mut self) {
         // A single unit with less than 83 attack power will always die regardless of defenses
-        let lone_attack = self.attacker_army.immensity() == 1 && self.state.atk_points < 83;
+        let lone_attack =
+            !self.is_scouting && self.attacker_army.immensity() == 1 && self.state.atk_points < 83;
 
         // Determine the winner and loser of the battle
         if self.state.atk_points >= self.state.def_points && !lone_attack {
@@ -210,75 +207,113 @@ impl Battle {
 
     // Calculates the total number of troops involved in the battle.
     fn calculate_immensity_factor(&mut self) {
-        let immensity =
-            (self.attacker_army.immensity() + self.defender_village.army.immensity()) as f64; // + self.defender_village.reinforcements.immensity();
-        if self.is_scouting || immensity < 1000.0 {
-            self.state.immensity_factor = 1.5;
-            return;
-        }
-
-        self.state.immensity_factor = 2.0 * (1.8592f64 - immensity.powf(0.015))
+        let defenders: u32 = self
+            .defender_village
+            .defending_armies()
+            .iter()
+            .map(|a| a.immensity())
+            .sum();
+        self.state.immensity_factor = match self.is_scouting {
+            true => 1.5,
+            false => immensity_factor(self.attacker_army.immensity() + defenders),
+        };
     }
 
     // Calculates the losses percentuals of both sides.
     fn calculate_losses_percent(&mut self) {
-        self.state.winner_losses_percent = (self.state.loser_points as f64
-            / self.state.winner_points as f64)
-            .powf(self.state.immensity_factor)
-            / 100.0;
-
-        // in normal attacks, loser loses everything
-        if self.is_normal {
-            self.state.loser_losses_percent = 100.0;
-
-            // in case of spying and defender has lost, it won't lose any troop
-            if self.is_scouting && self.state.atk_won {
-                self.state.loser_losses_percent = 0.0;
-            }
+        let (winner_losses, loser_losses) = losses_percent(
+            self.state.winner_points,
+            self.state.loser_points,
+            self.state.immensity_factor,
+            self.is_normal,
+        );
+        self.state.winner_losses_percent = winner_losses;
+        self.state.loser_losses_percent = loser_losses;
+
+        // in case of spying and defender has lost, it won't lose any troop
+        if self.is_normal && self.is_scouting && self.state.atk_won {
+            self.state.loser_losses_percent = 0.0;
+        }
+    }
+
+    // Some servers make a winning attacker always pay something, but only when the target
+    // had troops to fight back: the village base defense alone doesn't count.
+    fn apply_min_attacker_losses(&mut self) {
+        if !self.state.atk_won || self.is_scouting {
             return;
         }
 
-        // for raid attacks
-        self.state.winner_losses_percent =
-            self.state.winner_losses_percent / (100.0 + self.state.winner_losses_percent);
-        self.state.loser_losses_percent = 100.0 - self.state.winner_losses_percent
+        let defenders: u32 = self
+            .defender_village
+            .defending_armies()
+            .iter()
+            .map(|a| a.immensity())
+            .sum();
+        if defenders == 0 {
+            return;
+        }
+
+        self.state.winner_losses_percent = self
+            .state
+            .winner_losses_percent
+            .max(self.min_attacker_losses_percent);
     }
 
-    // Apply the losses percentuals on both armies.
+    // Apply the losses percentuals on both armies. Garrison and reinforcements are on
+    // the same side, so they lose the same share of troops.
     fn apply_losses(&mut self) {
-        if self.state.atk_won {
-            self.attacker_army
-                .apply_losses(self.state.winner_losses_percent);
-            self.defender_village
-                .army
-                .apply_losses(self.state.loser_losses_percent);
-            self.state.reinforcement_losses_percent = self.state.loser_losses_percent;
-        } else {
-            self.attacker_army
-                .apply_losses(self.state.loser_losses_percent);
-            self.defender_village
-                .army
-                .apply_losses(self.state.loser_losses_percent);
-            self.state.reinforcement_losses_percent = self.state.winner_losses_percent;
-        }
+        let (attacker_losses, defender_losses) = match self.state.atk_won {
+            true => (
+                self.state.winner_losses_percent,
+                self.state.loser_losses_percent,
+            ),
+            false => (
+                self.state.loser_losses_percent,
+                self.state.winner_losses_percent,
+            ),
+        };
+
+        let attacker_upkeep = self.attacker_army.upkeep();
+        let defenders_upkeep = self.defenders_upkeep();
+
+        self.attacker_army.apply_losses(attacker_losses);
+        self.state.reinforcement_losses_percent = defender_losses;
 
-        let mut reinforcements: Vec<Army> = vec![];
+        // only scouts fight scouts
+        for army in std::iter::once(&mut self.defender_village.army)
+            .chain(self.defender_village.reinforcements.iter_mut())
+        {
+            match self.is_scouting {
+                true => army.apply_scouting_losses(defender_losses),
+                false => army.apply_losses(defender_losses),
+            }
+        }
 
-        for r in self.defender_village.reinforcements.clone() {
-            let mut updated = r.clone();
-            updated.apply_losses(self.state.reinforcement_losses_percent);
-            reinforcements.push(updated);
+        // heroes gain a point of experience for each crop of upkeep killed
+        let attacker_killed = attacker_upkeep - self.attacker_army.upkeep();
+        let defenders_killed = defenders_upkeep - self.defenders_upkeep();
+        if let Some(hero) = self.attacker_army.hero.as_mut() {
+            hero.add_experience(defenders_killed);
+        }
+        if let Some(hero) = self.defender_village.army.hero.as_mut() {
+            hero.add_experience(attacker_killed);
         }
+    }
 
-        self.defender_village.reinforcements = reinforcements;
+    fn defenders_upkeep(&self) -> u32 {
+        self.defender_village
+            .defending_armies()
+            .iter()
+            .map(|a| a.upkeep())
+            .sum()
     }
 
     // Catas and rams
 
-    // Applies damage to buildings when hit by catapults.
+    // Applies damage to buildings when hit by the surviving catapults.
     fn apply_catapults_damage(&mut self) {
-        let working_catas = self.get_working_siege_units(self.attacker_army.unit_amount(7));
-        if working_catas <= 0 {
+        let working_catas = self.attacker_army.unit_amount(7);
+        if working_catas == 0 || self.defender_village.buildings.is_empty() {
             return;
         }
         let morale = self.get_siege_morale();
@@ -299,33 +334,41 @@ impl Battle {
         match (atk_rally_point, working_catas) {
             (20, 20..) => (),
             (10..=19, 20..) => self.cata_targets.1 = Some(self.get_random_defender_building().name),
+            (10.., _) => {
+                self.cata_targets.1 = None;
+            }
             (_, 20..) => {
                 self.cata_targets.0 = Some(self.get_random_defender_building().name);
                 self.cata_targets.1 = Some(self.get_random_defender_building().name)
             }
             (_, _) => {
+                self.cata_targets.0 = Some(self.get_random_defender_building().name);
                 self.cata_targets.1 = None;
             }
         };
 
-        // FIXME: use real slot id!
-        let slot_id: u8 = 1;
-        for building_name in self.cata_targets.targets() {
-            if let Some(b) = self.defender_village.get_building_by_name(building_name) {
-                let cata_needed =
-                    self.get_siege_units_needed(morale, b.level, cata_smithy, buildings_durability);
-
-                if working_catas / self.cata_targets.targets().len() as u32 >= cata_needed {
-                    // Destroy building
-                    let _ = self.defender_village.destroy_building(slot_id);
-                } else {
-                    // Downgrade building level by a certain damage
-                    let new_lvl =
-                        self.calculate_building_level_damage(b.level, working_catas, cata_needed);
-                    let _ = self
-                        .defender_village
-                        .downgrade_building_to_level(slot_id, new_lvl);
+        let targets = self.cata_targets.targets();
+        let catas_per_target = split_siege_units(working_catas, targets.len());
+
+        for (building_name, catas) in targets.into_iter().zip(catas_per_target) {
+            // any wall stands for the defender's own one
+            let building_name = match building_name {
+                BuildingName::CityWall | BuildingName::EarthWall | BuildingName::Palisade => {
+                    match tribe_wall(&self.defender_village.tribe) {
+                        Some(wall) => wall,
+                        None => continue,
+                    }
                 }
+                name => name,
+            };
+
+            // catapults aimed at a missing building are wasted
+            if let Some(slot_id) = self
+                .defender_village
+                .get_building_slot_by_name(building_name)
+            {
+                let damage = siege_damage_points(catas, cata_smithy, morale, buildings_durability);
+                self.damage_building(slot_id, damage);
             }
         }
         // TODO: buildings are damaged, but we need to include this outcome in BattleReport
@@ -334,43 +377,73 @@ impl Battle {
     // Applies damage to wall when hit by rams.
     fn apply_rams_damage(&mut self) {
         let working_rams = self.get_working_siege_units(self.attacker_army.unit_amount(6));
-        if working_rams <= 0 {
+        if working_rams == 0 {
             return;
         }
+        let wall_slot = match tribe_wall(&self.defender_village.tribe)
+            .and_then(|wall| self.defender_village.get_building_slot_by_name(wall))
+        {
+            Some(slot_id) => slot_id,
+            None => return,
+        };
         let morale = self.get_siege_morale();
         let ram_smithy = self.attacker_army.smithy[6];
         let buildings_durability = self.defender_village.get_buildings_durability();
-        let mut wall_level = 0;
 
-        if let Some(wall) = self.defender_village.get_wall() {
-            wall_level = wall.level;
-        }
+        let damage = siege_damage_points(working_rams, ram_smithy, morale, buildings_durability);
+        self.damage_building(wall_slot, damage);
+    }
 
-        let rams_needed =
-            self.get_siege_units_needed(morale, wall_level, ram_smithy, buildings_durability);
+    // Downgrades the building on the given slot by the damage points, or destroys it.
+    fn damage_building(&mut self, slot_id: u8, damage: f64) {
+        let level = match self.defender_village.get_building_by_slot_id(slot_id) {
+            Some(b) => b.level,
+            None => return,
+        };
 
-        // FIXME: use real slot id!
-        let slot_id = 1;
-        if working_rams >= rams_needed {
-            // Destroy building
+        let new_lvl = level_after_siege_damage(level, damage);
+        if new_lvl == 0 {
             let _ = self.defender_village.destroy_building(slot_id);
-        } else {
-            // Downgrade wall level by a certain damage
-            let new_lvl =
-                self.calculate_building_level_damage(wall_level, working_rams, rams_needed);
+        } else if new_lvl < level {
             let _ = self
                 .defender_village
                 .downgrade_building_to_level(slot_id, new_lvl);
         }
-        // TODO: wall is damaged, but we need to include this outcome in BattleReport
     }
 
     // Returns a random building from defender's village to be used as catapult target.
-    // FIXME: should return Building name + slot_id (for applying damage to specific one)
     fn get_random_defender_building(&self) -> Building {
         let mut rng = rand::thread_rng();
-        let idx = rng.gen_range(0..self.defender_village.buildings.len()) as u8;
-        self.defender_village.buildings[&idx].clone()
+        let idx = rng.gen_range(0..self.defender_village.buildings.len());
+        self.defender_village
+            .buildings
+            .values()
+            .nth(idx)
+            .cloned()
+            .unwrap()
+    }
+
+    // Surviving chiefs lower the loyalty of the village, until the attacker takes it over.
+    // Capitals can't be conquered, and a Residence or Palace must be knocked down first.
+    // The chiefs that conquer a village stay there.
+    fn apply_loyalty_damage(&mut self) {
+        let chiefs = self.attacker_army.units[CHIEF_IDX];
+        if chiefs == 0
+            || self.defender_village.is_capital
+            || self.defender_village.get_palace_or_residence().is_some()
+        {
+            return;
+        }
+
+        let damage = loyalty_damage(&self.attacker_army.tribe, chiefs, &mut rand::thread_rng());
+        self.defender_village.loyalty =
+            (self.defender_village.loyalty as u32).saturating_sub(damage) as u8;
+
+        if self.defender_village.loyalty == 0 {
+            self.attacker_army.units[CHIEF_IDX] = 0;
+            self.defender_village.conquer(&self.attacker_village);
+            self.conquered = true;
+        }
     }
 
     // Calculates working catapults/rams based on battle points.
@@ -393,27 +466,1018 @@ impl Battle {
         let atk_pop = self.attacker_village.population;
         let def_pop = self.defender_village.population;
         // 100% ≤ morale ≤ 300%
-        (atk_pop as f64 / def_pop as f64)
-            .powf(0.3)
-            .max(1.0)
-            .min(3.0)
-    }
-
-    // Calculates amount of catapults/rams needed to destroy a building/wall.
-    fn get_siege_units_needed(&self, morale: f64, level: u8, smithy: u8, durability: u16) -> u32 {
-        ((morale * ((level * level) + level + 1) as f64 / (8 * smithy as u16 / durability) as f64)
-            + 0.5)
-            .round() as u32
-    }
-
-    // Determine the damage caused by a siege unit to a building.
-    fn calculate_building_level_damage(
-        &self,
-        level: u8,
-        working_units: u32,
-        units_needed: u32,
-    ) -> u8 {
-        let damage_percent = (working_units * 100) as f64 / units_needed as f64;
-        (level as f64 * damage_percent).floor() as u8
+        (atk_pop as f64 / def_pop as f64).powf(0.3).clamp(1.0, 3.0)
+    }
+}
+
+// Siege damage model (Kirilloid's).
+//
+// Working catapults/rams deal an amount of damage points:
+//
+//   damage = units * 8 * 1.0205^smithy_level / (durability * morale)
+//
+// while knocking a building from level `l` down to level 0 costs `l² + l + 1` points.
+// A building is destroyed when the damage covers the cost of its current level, otherwise
+// it drops to the lowest level it can afford, that is the lowest `n` where
+// `cost(level) - cost(n) <= damage`. Since the cost grows quadratically, top levels of big
+// buildings absorb much more damage than lower ones (diminishing returns on high levels).
+
+// Defense of a village against pure infantry and pure cavalry attacks, before morale
+// and server-wide bonuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DefenseStrength {
+    pub infantry: u32,
+    pub cavalry: u32,
+}
+
+// Returns the defense of a village with all its troops, reinforcements and heroes
+// included, computed the same way battles do.
+pub fn defense_strength(village: &Village) -> DefenseStrength {
+    let (infantry, cavalry) = total_defense_points(&village.defending_armies());
+    let base = village_base_defense(village);
+    let bonus = wall_bonus(village);
+
+    DefenseStrength {
+        infantry: ((infantry + base) as f64 * bonus).floor() as u32,
+        cavalry: ((cavalry + base) as f64 * bonus).floor() as u32,
+    }
+}
+
+// Each village has a basic defense value of 10, Palace/Residence add some more.
+fn village_base_defense(village: &Village) -> u32 {
+    let palace = match village.get_palace_or_residence() {
+        Some((building, _)) => (building.level as u32).pow(2) * 2,
+        None => 0_u32,
+    };
+
+    10 + palace
+}
+
+// Returns the multiplier walls apply to the total defense.
+fn wall_bonus(village: &Village) -> f64 {
+    match village.get_wall() {
+        Some(wall) => tribe_wall_bonus(&village.tribe, wall.level),
+        None => 1.0,
+    }
+}
+
+// Returns the multiplier of the wall of the given tribe at the given level, that is
+// `1 + value / 100` of that level. Levels above the maximum count as the maximum one.
+fn tribe_wall_bonus(tribe: &Tribe, level: u8) -> f64 {
+    match (tribe_wall(tribe), level) {
+        (Some(name), 1..) => {
+            let wall = Building::new(name);
+            let level = level.min(wall.max_level());
+            wall.at_level(level)
+                .map_or(1.0, |wall| 1.0 + wall.value as f64 / 100.0)
+        }
+        _ => 1.0,
+    }
+}
+
+// Returns the exponent applied to the points ratio to get losses: the more troops are
+// involved, the lower it gets.
+fn immensity_factor(immensity: u32) -> f64 {
+    if immensity < 1000 {
+        return 1.5;
+    }
+    2.0 * (1.8592f64 - (immensity as f64).powf(0.015))
+}
+
+// Returns the losses percentuals of the winner and of the loser. In normal attacks the
+// loser loses everything and the winner `(loser_points / winner_points) ^ factor`, in
+// raids the two sides share the losses.
+fn losses_percent(
+    winner_points: u32,
+    loser_points: u32,
+    immensity_factor: f64,
+    is_normal: bool,
+) -> (f64, f64) {
+    let ratio = match winner_points {
+        0 => 0.0,
+        _ => (loser_points as f64 / winner_points as f64).powf(immensity_factor),
+    };
+
+    match is_normal {
+        true => (ratio * 100.0, 100.0),
+        false => {
+            let winner = ratio * 100.0 / (1.0 + ratio);
+            (winner, 100.0 - winner)
+        }
+    }
+}
+
+// Returns the multiplier of the defense points against an attacker with a bigger account
+// population: `(atk_pop / def_pop) ^ 0.2`, with the exponent scaled down by
+// `atk_points / def_points` when the attack is weaker. It's between 1 and 1.5.
+fn defender_morale_bonus(atk_pop: u32, def_pop: u32, atk_points: u32, def_points: u32) -> f64 {
+    if atk_pop <= def_pop {
+        return 1.0;
+    }
+
+    let ratio = atk_pop as f64 / def_pop.max(1) as f64;
+    let exponent = match atk_points < def_points {
+        true => 0.2 * atk_points as f64 / def_points as f64,
+        false => 0.2,
+    };
+    ratio.powf(exponent).clamp(1.0, 1.5)
+}
+
+// Result of a normal attack resolved by `resolve_battle`, with the surviving armies.
+#[derive(Debug, Clone)]
+pub struct BattleOutcome {
+    pub attacker_won: bool,
+    pub attacker: Army,
+    pub defenders: Vec<Army>,
+    // Resources the surviving attackers can carry away.
+    pub loot_capacity: u64,
+}
+
+impl BattleOutcome {
+    pub fn attacker_survivors(&self) -> Vec<(UnitName, u32)> {
+        self.attacker.units_by_name()
+    }
+
+    // Survivors of each defending army, in the same order they were given.
+    pub fn defender_survivors(&self) -> Vec<Vec<(UnitName, u32)>> {
+        self.defenders.iter().map(|d| d.units_by_name()).collect()
+    }
+}
+
+// Resolves a normal attack between armies only, without morale, siege weapons or
+// buildings other than the wall of the given defender tribe. The loser loses
+// everything, the winner loses `(loser_points / winner_points) ^ immensity_factor`.
+pub fn resolve_battle(
+    attacker: &Army,
+    defenders: &[Army],
+    wall_level: u8,
+    tribe: Tribe,
+) -> BattleOutcome {
+    let (infantry_atk, cavalry_atk) = attacker.attack_points();
+    let atk_points = infantry_atk + cavalry_atk;
+    let troops_def =
+        weighted_defense_points((infantry_atk, cavalry_atk), total_defense_points(defenders));
+    // every village has a basic defense of 10
+    let def_points =
+        ((troops_def + 10) as f64 * tribe_wall_bonus(&tribe, wall_level)).floor() as u32;
+
+    // A single unit with less than 83 attack power will always die regardless of defenses
+    let lone_attack = attacker.immensity() == 1 && atk_points < 83;
+    let attacker_won = atk_points >= def_points && !lone_attack;
+    let (winner_points, loser_points) = match attacker_won {
+        true => (atk_points, def_points),
+        false => (def_points, atk_points),
+    };
+
+    let immensity = attacker.immensity() + defenders.iter().map(|d| d.immensity()).sum::<u32>();
+    let (winner_losses, loser_losses) = losses_percent(
+        winner_points,
+        loser_points,
+        immensity_factor(immensity),
+        true,
+    );
+    let (attacker_losses, defender_losses) = match attacker_won {
+        true => (winner_losses, loser_losses),
+        false => (loser_losses, winner_losses),
+    };
+
+    let mut attacker = attacker.clone();
+    attacker.apply_losses(attacker_losses);
+    let defenders: Vec<Army> = defenders
+        .iter()
+        .map(|d| {
+            let mut d = d.clone();
+            d.apply_losses(defender_losses);
+            d
+        })
+        .collect();
+
+    BattleOutcome {
+        attacker_won,
+        loot_capacity: attacker.carry_capacity(),
+        attacker,
+        defenders,
+    }
+}
+
+// Fights the animals guarding an oasis. Animals have no walls nor morale, so the
+// battle is just about troops. Returns true if the attacker has won.
+pub fn oasis_battle(attacker: &mut Army, oasis: &mut Oasis, is_normal: bool) -> bool {
+    let mut animals = oasis.animals_army();
+
+    let (infantry_atk, cavalry_atk) = attacker.attack_points();
+    let atk_points = infantry_atk + cavalry_atk;
+    let def_points = weighted_defense_points((infantry_atk, cavalry_atk), animals.defense_points());
+
+    let atk_won = atk_points > 0 && atk_points >= def_points;
+    let (winner_points, loser_points) = match atk_won {
+        true => (atk_points, def_points),
+        false => (def_points, atk_points),
+    };
+    let (winner_losses, loser_losses) = losses_percent(
+        winner_points,
+        loser_points,
+        immensity_factor(attacker.immensity() + animals.immensity()),
+        is_normal,
+    );
+
+    match atk_won {
+        true => {
+            attacker.apply_losses(winner_losses);
+            animals.apply_losses(loser_losses);
+        }
+        false => {
+            attacker.apply_losses(loser_losses);
+            animals.apply_losses(winner_losses);
+        }
+    }
+    oasis.animals = animals.units;
+
+    atk_won
+}
+
+// Sums the infantry and cavalry defense points of all the armies defending a village.
+pub fn total_defense_points(armies: &[Army]) -> (u32, u32) {
+    armies.iter().fold((0, 0), |(infantry, cavalry), army| {
+        let (army_infantry, army_cavalry) = army.defense_points();
+        (infantry + army_infantry, cavalry + army_cavalry)
+    })
+}
+
+// Returns the defense points opposing an attack: the infantry and cavalry defense of all
+// the defenders are weighted by the share of infantry and cavalry in the attack, so
+// cavalry defenders shine against horses and infantry defenders against foot soldiers.
+// Without attack points, only the infantry defense counts.
+pub fn weighted_defense_points(attack: (u32, u32), defense: (u32, u32)) -> u32 {
+    let (infantry_atk, cavalry_atk) = attack;
+    let (infantry_def, cavalry_def) = defense;
+    let atk_points = infantry_atk + cavalry_atk;
+    if atk_points == 0 {
+        return infantry_def;
+    }
+
+    let infantry_share = infantry_atk as f64 / atk_points as f64;
+    let cavalry_share = cavalry_atk as f64 / atk_points as f64;
+    (infantry_def as f64 * infantry_share + cavalry_def as f64 * cavalry_share).floor() as u32
+}
+
+// Returns the damage points dealt by the given amount of working siege units.
+pub fn siege_damage_points(units: u32, smithy_level: u8, morale: f64, durability: u16) -> f64 {
+    let upgrade = 1.0205f64.powi(smithy_level as i32);
+    units as f64 * 8.0 * upgrade / (durability.max(1) as f64 * morale.max(1.0))
+}
+
+// Returns the level of a building after it has received the given damage points.
+pub fn level_after_siege_damage(level: u8, damage: f64) -> u8 {
+    let cost = |l: u8| -> f64 {
+        let l = l as f64;
+        l * l + l + 1.0
+    };
+
+    if damage >= cost(level) {
+        return 0;
+    }
+
+    (0..=level)
+        .find(|&l| cost(level) - cost(l) <= damage)
+        .unwrap_or(level)
+}
+
+// Returns the loyalty points taken away by the given chiefs, each one rolls its own
+// damage: Senators 20-30, Chiefs and Chieftains 20-25.
+pub fn loyalty_damage<R: Rng>(tribe: &Tribe, chiefs: u32, rng: &mut R) -> u32 {
+    let max = match tribe {
+        Tribe::Roman => 30,
+        _ => 25,
+    };
+    (0..chiefs).map(|_| rng.gen_range(20..=max)).sum()
+}
+
+// Splits siege units evenly across targets, the first target gets the remainder.
+pub fn split_siege_units(units: u32, targets: usize) -> Vec<u32> {
+    if targets == 0 {
+        return vec![];
+    }
+
+    let share = units / targets as u32;
+    let mut split = vec![share; targets];
+    split[0] += units % targets as u32;
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::{
+        defender_morale_bonus, defense_strength, level_after_siege_damage, losses_percent,
+        oasis_battle, resolve_battle, siege_damage_points, split_siege_units, total_defense_points,
+        tribe_wall_bonus, weighted_defense_points, Battle, CataTargets,
+    };
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::{Army, UnitName},
+            buildings::{Building, BuildingName},
+            hero::{experience_for_level, Hero, HeroAttributes},
+            map::{Oasis, OasisTopology, Position},
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village(x: i32, y: i32, tribe: Tribe) -> Village {
+        test_village(&test_player(tribe), Position { x, y })
+    }
+
+    fn battle(defense_multiplier: f64) -> Battle {
+        let attacker = village(10, 10, Tribe::Teuton);
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.army.units[0] = 100;
+
+        let mut army = attacker.army.clone();
+        army.units[0] = 100;
+
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            defender,
+            true,
+            false,
+            CataTargets::default(),
+        );
+        battle.defense_multiplier = defense_multiplier;
+        battle
+    }
+
+    #[test]
+    fn test_defense_multiplier_applies_to_defense_points() {
+        let mut day = battle(1.0);
+        day.calculate_battle_points();
+
+        let mut night = battle(2.0);
+        night.calculate_battle_points();
+
+        assert_eq!(night.state.atk_points, day.state.atk_points);
+        assert_eq!(night.state.def_points, day.state.def_points * 2);
+    }
+
+    #[test]
+    fn test_morale_bonus_follows_population_ratio() {
+        // a stronger attack with twice the population
+        let bonus = defender_morale_bonus(200, 100, 1000, 500);
+        assert!((bonus - 2f64.powf(0.2)).abs() < 1e-9);
+
+        // a weaker one lowers the exponent
+        let bonus = defender_morale_bonus(200, 100, 500, 1000);
+        assert!((bonus - 2f64.powf(0.1)).abs() < 1e-9);
+
+        // no bonus against smaller accounts, never more than +50%
+        assert_eq!(defender_morale_bonus(100, 200, 1000, 500), 1.0);
+        assert_eq!(defender_morale_bonus(100, 100, 1000, 500), 1.0);
+        assert_eq!(defender_morale_bonus(100_000, 1, 1000, 500), 1.5);
+        assert_eq!(defender_morale_bonus(100, 0, 1000, 500), 1.5);
+    }
+
+    #[test]
+    fn test_morale_raises_defense_against_bigger_accounts() {
+        let mut even = battle(1.0);
+        even.attacker_village.population = even.defender_village.population;
+        even.calculate_battle_points();
+        even.apply_defender_morale_bonus();
+
+        let mut bigger = battle(1.0);
+        bigger.attacker_village.population = bigger.defender_village.population * 2;
+        bigger.calculate_battle_points();
+        bigger.apply_defender_morale_bonus();
+
+        // 4000 attack points against 4010 defense points
+        assert_eq!(bigger.state.atk_points, even.state.atk_points);
+        let def = even.state.def_points as f64;
+        assert!(bigger.state.def_points as f64 > def * 1.1);
+        assert!((bigger.state.def_points as f64) < def * 1.15);
+    }
+
+    #[test]
+    fn test_losses_percent() {
+        let (winner, loser) = losses_percent(1000, 250, 1.5, true);
+        assert!((winner - 12.5).abs() < 1e-9);
+        assert_eq!(loser, 100.0);
+
+        // raids split the losses
+        let (winner, loser) = losses_percent(1000, 250, 1.5, false);
+        assert!((winner - 12.5 / 1.125).abs() < 1e-9);
+        assert!((winner + loser - 100.0).abs() < 1e-9);
+
+        assert_eq!(losses_percent(0, 0, 1.5, true), (0.0, 100.0));
+    }
+
+    // 1000 Clubswingers and some Catapults against an empty village, with a level 20
+    // Rally Point to pick the targets.
+    fn siege_battle(
+        catapults: u32,
+        targets: CataTargets,
+        buildings: &[(u8, BuildingName, u8)],
+    ) -> Battle {
+        let mut attacker = village(10, 10, Tribe::Teuton);
+        attacker.buildings.insert(
+            19,
+            Building::new(BuildingName::RallyPoint)
+                .at_level(20)
+                .unwrap(),
+        );
+        let mut army = attacker.army.clone();
+        army.units[0] = 1000;
+        army.units[7] = catapults;
+
+        let mut defender = village(20, 20, Tribe::Gaul);
+        for (slot_id, name, level) in buildings {
+            defender.buildings.insert(
+                *slot_id,
+                Building::new(name.clone()).at_level(*level).unwrap(),
+            );
+        }
+        // no morale penalty on catapults
+        attacker.population = defender.population;
+
+        Battle::new(army, attacker, defender, true, false, targets)
+    }
+
+    #[test]
+    fn test_catapults_damage_building_after_battle() {
+        let target = CataTargets(Some(BuildingName::Warehouse), None);
+
+        let mut battle = siege_battle(5, target.clone(), &[(20, BuildingName::Warehouse, 10)]);
+        battle.combat();
+        assert_eq!(battle.attacker_army.units[7], 5);
+        let warehouse = battle.defender_village.get_building_by_slot_id(20).unwrap();
+        assert_eq!(warehouse.level, 8);
+
+        let mut battle = siege_battle(20, target, &[(20, BuildingName::Warehouse, 10)]);
+        battle.combat();
+        assert!(battle
+            .defender_village
+            .get_building_by_slot_id(20)
+            .is_none());
+    }
+
+    #[test]
+    fn test_catapults_split_across_two_targets() {
+        let buildings = [
+            (20, BuildingName::Warehouse, 15),
+            (21, BuildingName::Granary, 15),
+        ];
+
+        let targets = CataTargets(Some(BuildingName::Warehouse), Some(BuildingName::Granary));
+        let mut battle = siege_battle(40, targets, &buildings);
+        battle.combat();
+        for slot_id in [20, 21] {
+            let building = battle.defender_village.get_building_by_slot_id(slot_id);
+            assert_eq!(building.unwrap().level, 9);
+        }
+
+        // half of the catapults aim at a missing building and are wasted
+        let targets = CataTargets(Some(BuildingName::Warehouse), Some(BuildingName::Academy));
+        let mut battle = siege_battle(40, targets, &buildings);
+        battle.combat();
+        let warehouse = battle.defender_village.get_building_by_slot_id(20).unwrap();
+        assert_eq!(warehouse.level, 9);
+        let granary = battle.defender_village.get_building_by_slot_id(21).unwrap();
+        assert_eq!(granary.level, 15);
+    }
+
+    #[test]
+    fn test_catapults_target_the_wall() {
+        // aiming at a City Wall hits the Gaul Palisade
+        let targets = CataTargets(Some(BuildingName::CityWall), None);
+        let mut battle = siege_battle(5, targets, &[(40, BuildingName::Palisade, 10)]);
+        battle.combat();
+        let wall = battle.defender_village.get_wall().unwrap();
+        assert_eq!(wall.level, 8);
+    }
+
+    #[test]
+    fn test_lost_battle_leaves_buildings_untouched() {
+        let target = CataTargets(Some(BuildingName::Warehouse), None);
+        let mut battle = siege_battle(20, target, &[(20, BuildingName::Warehouse, 10)]);
+        battle.attacker_army.units[0] = 0;
+        battle.defender_village.army.units[0] = 1000;
+        battle.combat();
+
+        assert_eq!(battle.attacker_army.units[7], 0);
+        let warehouse = battle.defender_village.get_building_by_slot_id(20).unwrap();
+        assert_eq!(warehouse.level, 10);
+    }
+
+    // A Teuton army with the given chiefs against an empty village, one wave each call.
+    fn chiefs_wave(defender: Village, chiefs: u32) -> Battle {
+        let attacker = village(10, 10, Tribe::Teuton);
+        let mut army = attacker.army.clone();
+        army.units[0] = 1000;
+        army.units[8] = chiefs;
+
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            defender,
+            true,
+            false,
+            CataTargets::default(),
+        );
+        battle.combat();
+        battle
+    }
+
+    #[test]
+    fn test_chiefs_lower_loyalty_wave_after_wave() {
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.is_capital = false;
+        defender.resources = ResourceGroup::new(100, 200, 300, 400);
+        let buildings = defender.buildings.len();
+
+        // two chiefs take away 40-50 loyalty points each wave
+        let battle = chiefs_wave(defender, 2);
+        assert!(!battle.conquered);
+        assert!((50..=60).contains(&battle.defender_village.loyalty));
+        assert_eq!(battle.attacker_army.units[8], 2);
+
+        let battle = chiefs_wave(battle.defender_village, 2);
+        assert!(battle.defender_village.loyalty <= 20);
+        let battle = match battle.conquered {
+            true => battle,
+            false => chiefs_wave(battle.defender_village, 2),
+        };
+
+        assert!(battle.conquered);
+        let conquered = &battle.defender_village;
+        assert_eq!(conquered.player_id, battle.attacker_village.player_id);
+        assert_eq!(conquered.army.player_id, battle.attacker_village.player_id);
+        assert_eq!(
+            conquered.parent_village_id,
+            Some(battle.attacker_village.id)
+        );
+        assert_eq!(conquered.loyalty, 0);
+        assert!(!conquered.is_capital);
+        assert_eq!(conquered.buildings.len(), buildings);
+        assert_eq!(conquered.resources, ResourceGroup::new(100, 200, 300, 400));
+        assert_eq!(
+            battle.attacker_army.units[8], 0,
+            "chiefs stay in the village"
+        );
+    }
+
+    #[test]
+    fn test_conquest_when_loyalty_reaches_zero() {
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.is_capital = false;
+
+        // a single Chief takes away 20-25 points
+        defender.loyalty = 26;
+        let battle = chiefs_wave(defender.clone(), 1);
+        assert!(!battle.conquered);
+        assert!((1..=6).contains(&battle.defender_village.loyalty));
+
+        defender.loyalty = 20;
+        let battle = chiefs_wave(defender.clone(), 1);
+        assert!(battle.conquered);
+        assert_eq!(
+            battle.defender_village.player_id,
+            battle.attacker_village.player_id
+        );
+    }
+
+    #[test]
+    fn test_capitals_and_residences_resist_chiefs() {
+        let capital = village(20, 20, Tribe::Gaul);
+        let battle = chiefs_wave(capital, 5);
+        assert!(!battle.conquered);
+        assert_eq!(battle.defender_village.loyalty, 100);
+
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.is_capital = false;
+        defender.buildings.insert(
+            25,
+            Building::new(BuildingName::Residence).at_level(1).unwrap(),
+        );
+        let battle = chiefs_wave(defender, 5);
+        assert!(!battle.conquered);
+        assert_eq!(battle.defender_village.loyalty, 100);
+    }
+
+    #[test]
+    fn test_trapped_attackers_skip_the_fight() {
+        let attacker = village(10, 10, Tribe::Teuton);
+        let mut army = attacker.army.clone();
+        army.units[0] = 25;
+
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.buildings.insert(
+            20,
+            Building::new(BuildingName::Trapper).at_level(1).unwrap(),
+        );
+
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            defender,
+            false,
+            false,
+            CataTargets::default(),
+        );
+        battle.combat();
+
+        // 10 are caught, the other 15 face the empty village
+        assert_eq!(battle.attacker_army.units[0], 15);
+        assert_eq!(battle.defender_village.trapper.used(), 10);
+    }
+
+    #[test]
+    fn test_reinforcements_defend_empty_garrison() {
+        let attacker = village(10, 10, Tribe::Teuton);
+        let mut army = attacker.army.clone();
+        army.units[0] = 100;
+
+        let mut defender = village(20, 20, Tribe::Gaul);
+        assert_eq!(defender.army.immensity(), 0);
+        defender.reinforcements.push(Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Gaul,
+            [200, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        ));
+
+        // without reinforcements the attacker walks in
+        let mut undefended = Battle::new(
+            army.clone(),
+            attacker.clone(),
+            village(20, 20, Tribe::Gaul),
+            true,
+            false,
+            CataTargets::default(),
+        );
+        undefended.combat();
+        assert!(undefended.state.atk_won);
+
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            defender,
+            true,
+            false,
+            CataTargets::default(),
+        );
+        battle.combat();
+
+        assert!(!battle.state.atk_won, "reinforcements hold the village");
+        assert_eq!(battle.attacker_army.immensity(), 0);
+
+        let survivors = battle.defender_village.reinforcements[0].units[0];
+        assert!(survivors > 0 && survivors < 200, "reinforcements fought");
+        assert_eq!(battle.defender_village.army.immensity(), 0);
+    }
+
+    #[test]
+    fn test_hero_bonus_and_experience() {
+        let attacker = village(10, 20, Tribe::Teuton);
+        let mut army = attacker.army.clone();
+        army.units[0] = 1000;
+        let plain = army.attack_points();
+
+        let mut hero = Hero::new(attacker.player_id, attacker.id);
+        hero.add_experience(experience_for_level(10));
+        hero.reallocate_points(HeroAttributes {
+            off_bonus: 50,
+            ..Default::default()
+        })
+        .unwrap();
+        army.hero = Some(hero.clone());
+
+        // 10% more on the troops, plus the hero itself
+        let (infantry, cavalry) = army.attack_points();
+        assert_eq!(infantry, plain.0 * 110 / 100 + hero.attack_points());
+        assert_eq!(cavalry, plain.1);
+
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.army.units[0] = 10;
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            defender,
+            true,
+            false,
+            CataTargets::default(),
+        );
+        battle.combat();
+
+        // every phalanx killed is worth its upkeep
+        assert!(battle.state.atk_won);
+        let hero = battle.attacker_army.hero.unwrap();
+        assert_eq!(hero.experience, experience_for_level(10) + 10);
+    }
+
+    #[test]
+    fn test_brewery_celebration_boosts_attack() {
+        let mut attacker = village(10, 20, Tribe::Teuton);
+        attacker.buildings.insert(
+            21,
+            Building::new(BuildingName::Brewery).at_level(10).unwrap(),
+        );
+        let mut army = attacker.army.clone();
+        army.units[0] = 100;
+        let (infantry, _) = army.attack_points();
+
+        let now = Utc::now();
+        attacker.brewery_celebration_until = Some(now + Duration::hours(1));
+        let mut battle = Battle::new(
+            army,
+            attacker,
+            village(20, 20, Tribe::Gaul),
+            true,
+            false,
+            CataTargets::default(),
+        );
+
+        battle.fought_at = now;
+        battle.calculate_battle_points();
+        assert_eq!(battle.state.atk_points, infantry * 110 / 100);
+
+        battle.fought_at = now + Duration::hours(2);
+        battle.calculate_battle_points();
+        assert_eq!(battle.state.atk_points, infantry);
+    }
+
+    #[test]
+    fn test_defense_strength_matches_battle_points() {
+        let mut defender = village(20, 20, Tribe::Gaul);
+        defender.army.units[0] = 100;
+        defender.army.hero = Some(Hero::new(defender.player_id, defender.id));
+        defender.reinforcements.push(Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Roman,
+            [0, 50, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        ));
+        defender.buildings.insert(
+            40,
+            Building::new(BuildingName::Palisade).at_level(10).unwrap(),
+        );
+        defender.buildings.insert(
+            25,
+            Building::new(BuildingName::Residence).at_level(20).unwrap(),
+        );
+        let strength = defense_strength(&defender);
+
+        let attacker = village(10, 10, Tribe::Teuton);
+        // Clubswingers are infantry, Teutonic Knights cavalry
+        for (idx, expected) in [(0, strength.infantry), (5, strength.cavalry)] {
+            let mut army = attacker.army.clone();
+            army.units[idx] = 100;
+
+            let mut battle = Battle::new(
+                army,
+                attacker.clone(),
+                defender.clone(),
+                true,
+                false,
+                CataTargets::default(),
+            );
+            battle.calculate_battle_points();
+            assert_eq!(battle.state.def_points, expected);
+        }
+        assert!(strength.infantry != strength.cavalry);
+    }
+
+    #[test]
+    fn test_siege_damage_scales_with_quantity() {
+        assert_eq!(
+            level_after_siege_damage(10, siege_damage_points(5, 0, 1.0, 1)),
+            8
+        );
+        assert_eq!(
+            level_after_siege_damage(10, siege_damage_points(10, 0, 1.0, 1)),
+            5
+        );
+        assert_eq!(
+            level_after_siege_damage(10, siege_damage_points(20, 0, 1.0, 1)),
+            0
+        );
+
+        // the same amount of catapults takes down fewer levels on a bigger building
+        assert_eq!(
+            level_after_siege_damage(20, siege_damage_points(10, 0, 1.0, 1)),
+            18
+        );
+        assert_eq!(
+            level_after_siege_damage(20, siege_damage_points(20, 0, 1.0, 1)),
+            16
+        );
+        assert_eq!(
+            level_after_siege_damage(20, siege_damage_points(40, 0, 1.0, 1)),
+            10
+        );
+        assert_eq!(
+            level_after_siege_damage(20, siege_damage_points(60, 0, 1.0, 1)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_siege_damage_scales_with_smithy_level() {
+        let base = siege_damage_points(20, 0, 1.0, 1);
+        let upgraded = siege_damage_points(20, 20, 1.0, 1);
+        assert!(upgraded > base);
+        assert!((upgraded / base - 1.0205f64.powi(20)).abs() < 1e-9);
+
+        assert_eq!(level_after_siege_damage(20, base), 16);
+        assert_eq!(level_after_siege_damage(20, upgraded), 13);
+
+        // morale and Stonemason's durability reduce damage
+        assert!(siege_damage_points(20, 0, 2.0, 1) < base);
+        assert!(siege_damage_points(20, 0, 1.0, 2) < base);
+    }
+
+    #[test]
+    fn test_siege_units_split_across_targets() {
+        assert_eq!(split_siege_units(41, 2), vec![21, 20]);
+        assert_eq!(split_siege_units(40, 1), vec![40]);
+        assert_eq!(split_siege_units(40, 0), Vec::<u32>::new());
+
+        // 40 catas on a single level 15 building destroy it, split on two they don't
+        let full = siege_damage_points(40, 0, 1.0, 1);
+        let half = siege_damage_points(20, 0, 1.0, 1);
+        assert_eq!(level_after_siege_damage(15, full), 0);
+        assert_eq!(level_after_siege_damage(15, half), 9);
+    }
+
+    fn oasis(animals: [u32; 10]) -> Oasis {
+        Oasis {
+            id: 42,
+            player_id: None,
+            village_id: None,
+            position: Position { x: 11, y: 20 },
+            topology: OasisTopology::Lumber,
+            animals,
+        }
+    }
+
+    #[test]
+    fn test_oasis_battle_clears_animals() {
+        let mut oasis = oasis([10, 5, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut army = Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Roman,
+            [0, 0, 0, 0, 100, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        assert!(oasis_battle(&mut army, &mut oasis, true));
+        assert!(oasis.is_cleared());
+        assert!(army.units[4] > 95);
+    }
+
+    #[test]
+    fn test_oasis_battle_lost_against_animals() {
+        let mut oasis = oasis([0, 0, 0, 0, 0, 0, 20, 0, 0, 0]);
+        let mut army = Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Roman,
+            [5, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        assert!(!oasis_battle(&mut army, &mut oasis, true));
+        assert_eq!(army.immensity(), 0);
+        assert!(!oasis.is_cleared());
+    }
+
+    #[test]
+    fn test_resolve_battle_legionnaires_against_phalanxes() {
+        let attacker = Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Roman,
+            [1000, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let defender = Army::new(
+            2,
+            Uuid::new_v4(),
+            Tribe::Gaul,
+            [500, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+
+        let outcome = resolve_battle(&attacker, std::slice::from_ref(&defender), 0, Tribe::Gaul);
+
+        // 40000 attack points against 500 * 40 + 10 defense points
+        assert!(outcome.attacker_won);
+        assert_eq!(
+            outcome.attacker_survivors(),
+            vec![(UnitName::Legionnaire, 643)]
+        );
+        assert_eq!(outcome.defender_survivors(), vec![vec![]]);
+        assert_eq!(outcome.loot_capacity, 643 * 50);
+
+        // a high wall turns the battle around
+        let outcome = resolve_battle(
+            &attacker,
+            &[defender.clone(), defender.clone()],
+            20,
+            Tribe::Gaul,
+        );
+        assert!(!outcome.attacker_won);
+        assert!(outcome.attacker_survivors().is_empty());
+        assert_eq!(outcome.loot_capacity, 0);
+        assert!(outcome.defender_survivors()[0][0].1 > 0);
+
+        // walls above the maximum level fight as a maximum level one
+        let over = resolve_battle(
+            &attacker,
+            std::slice::from_ref(&defender),
+            u8::MAX,
+            Tribe::Gaul,
+        );
+        let max = resolve_battle(&attacker, &[defender], 20, Tribe::Gaul);
+        assert_eq!(over.attacker_won, max.attacker_won);
+        assert_eq!(over.attacker_survivors(), max.attacker_survivors());
+    }
+
+    #[test]
+    fn test_wall_defense_multiplier() {
+        for tribe in [Tribe::Roman, Tribe::Gaul, Tribe::Teuton] {
+            assert_eq!(tribe_wall_bonus(&tribe, 0), 1.0);
+        }
+
+        assert!((tribe_wall_bonus(&Tribe::Roman, 10) - 1.34).abs() < 1e-9);
+        assert!((tribe_wall_bonus(&Tribe::Gaul, 10) - 1.28).abs() < 1e-9);
+        assert!((tribe_wall_bonus(&Tribe::Teuton, 10) - 1.22).abs() < 1e-9);
+
+        assert!((tribe_wall_bonus(&Tribe::Roman, 20) - 1.81).abs() < 1e-9);
+        assert!((tribe_wall_bonus(&Tribe::Gaul, 20) - 1.64).abs() < 1e-9);
+        assert!((tribe_wall_bonus(&Tribe::Teuton, 20) - 1.49).abs() < 1e-9);
+
+        // tribes without walls get no bonus
+        assert_eq!(tribe_wall_bonus(&Tribe::Nature, 20), 1.0);
+
+        // levels above the maximum count as the maximum
+        for tribe in [Tribe::Roman, Tribe::Gaul, Tribe::Teuton] {
+            assert_eq!(
+                tribe_wall_bonus(&tribe, u8::MAX),
+                tribe_wall_bonus(&tribe, 20)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_defense_against_infantry_and_cavalry() {
+        let player_id = Uuid::new_v4();
+        // 100 phalanxes (40/50) and 100 druidriders (115/55)
+        let defenders = [
+            Army::new(
+                1,
+                player_id,
+                Tribe::Gaul,
+                [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0; 10],
+            ),
+            Army::new(
+                2,
+                Uuid::new_v4(),
+                Tribe::Gaul,
+                [0, 0, 0, 0, 100, 0, 0, 0, 0, 0],
+                [0; 10],
+            ),
+        ];
+        let defense = total_defense_points(&defenders);
+        assert_eq!(defense, (40 * 100 + 115 * 100, 50 * 100 + 55 * 100));
+
+        let infantry = Army::new(
+            3,
+            player_id,
+            Tribe::Roman,
+            [100, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let cavalry = Army::new(
+            3,
+            player_id,
+            Tribe::Roman,
+            [0, 0, 0, 0, 0, 100, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let infantry_def = weighted_defense_points(infantry.attack_points(), defense);
+        let cavalry_def = weighted_defense_points(cavalry.attack_points(), defense);
+
+        assert_eq!(infantry_def, 15500);
+        assert_eq!(cavalry_def, 10500);
+
+        // a mixed attack gets a defense in between, by its share of cavalry attack
+        assert_eq!(weighted_defense_points((3000, 1000), defense), 14250);
+        assert_eq!(weighted_defense_points((0, 0), defense), 15500);
     }
 }
@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+// Errors of game rules that callers may want to tell apart.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GameError {
+    #[error("Target ({x}|{y}) is out of the map range")]
+    TargetOutOfRange { x: i32, y: i32 },
+    #[error("Smithy level {level} is out of range")]
+    InvalidSmithyLevel { level: u8 },
+}
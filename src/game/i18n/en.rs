@@ -0,0 +1,112 @@
+// English names, keyed by the `Debug` name of each variant.
+pub static EN: &[(&str, &str)] = &[
+    ("buildings.Woodcutter", "Woodcutter"),
+    ("buildings.ClayPit", "Clay Pit"),
+    ("buildings.IronMine", "Iron Mine"),
+    ("buildings.Cropland", "Cropland"),
+    ("buildings.Sawmill", "Sawmill"),
+    ("buildings.Brickyard", "Brickyard"),
+    ("buildings.IronFoundry", "Iron Foundry"),
+    ("buildings.GrainMill", "Grain Mill"),
+    ("buildings.Bakery", "Bakery"),
+    ("buildings.Warehouse", "Warehouse"),
+    ("buildings.Granary", "Granary"),
+    ("buildings.Smithy", "Smithy"),
+    ("buildings.TournamentSquare", "Tournament Square"),
+    ("buildings.MainBuilding", "Main Building"),
+    ("buildings.RallyPoint", "Rally Point"),
+    ("buildings.Marketplace", "Marketplace"),
+    ("buildings.Embassy", "Embassy"),
+    ("buildings.Barracks", "Barracks"),
+    ("buildings.Stable", "Stable"),
+    ("buildings.Workshop", "Workshop"),
+    ("buildings.Academy", "Academy"),
+    ("buildings.Cranny", "Cranny"),
+    ("buildings.TownHall", "Town Hall"),
+    ("buildings.Residence", "Residence"),
+    ("buildings.Palace", "Palace"),
+    ("buildings.Treasury", "Treasury"),
+    ("buildings.TradeOffice", "Trade Office"),
+    ("buildings.GreatBarracks", "Great Barracks"),
+    ("buildings.GreatStable", "Great Stable"),
+    ("buildings.CityWall", "City Wall"),
+    ("buildings.EarthWall", "Earth Wall"),
+    ("buildings.Palisade", "Palisade"),
+    ("buildings.StonemansionLodge", "Stonemason's Lodge"),
+    ("buildings.Brewery", "Brewery"),
+    ("buildings.Trapper", "Trapper"),
+    ("buildings.HeroMansion", "Hero's Mansion"),
+    ("buildings.GreatWarehouse", "Great Warehouse"),
+    ("buildings.GreatGranary", "Great Granary"),
+    ("buildings.WonderOfTheWorld", "Wonder of the World"),
+    ("buildings.HorseDrinkingTrough", "Horse Drinking Trough"),
+    ("buildings.GreatWorkshop", "Great Workshop"),
+    ("units.Legionnaire", "Legionnaire"),
+    ("units.Praetorian", "Praetorian"),
+    ("units.Imperian", "Imperian"),
+    ("units.EquitesLegati", "Equites Legati"),
+    ("units.EquitesImperatoris", "Equites Imperatoris"),
+    ("units.EquitesCaesaris", "Equites Caesaris"),
+    ("units.BatteringRam", "Battering Ram"),
+    ("units.FireCatapult", "Fire Catapult"),
+    ("units.Senator", "Senator"),
+    ("units.Settler", "Settler"),
+    ("units.Maceman", "Maceman"),
+    ("units.Spearman", "Spearman"),
+    ("units.Axeman", "Axeman"),
+    ("units.Scout", "Scout"),
+    ("units.Paladin", "Paladin"),
+    ("units.TeutonicKnight", "Teutonic Knight"),
+    ("units.Ram", "Ram"),
+    ("units.Catapult", "Catapult"),
+    ("units.Chief", "Chief"),
+    ("units.Phalanx", "Phalanx"),
+    ("units.Swordsman", "Swordsman"),
+    ("units.Pathfinder", "Pathfinder"),
+    ("units.TheutatesThunder", "Theutates Thunder"),
+    ("units.Druidrider", "Druidrider"),
+    ("units.Haeduan", "Haeduan"),
+    ("units.Trebuchet", "Trebuchet"),
+    ("units.Chieftain", "Chieftain"),
+    ("units.Rat", "Rat"),
+    ("units.Spider", "Spider"),
+    ("units.Serpent", "Serpent"),
+    ("units.Bat", "Bat"),
+    ("units.WildBoar", "Wild Boar"),
+    ("units.Wolf", "Wolf"),
+    ("units.Bear", "Bear"),
+    ("units.Crocodile", "Crocodile"),
+    ("units.Tiger", "Tiger"),
+    ("units.Elephant", "Elephant"),
+    ("units.Pikeman", "Pikeman"),
+    ("units.ThornedWarrior", "Thorned Warrior"),
+    ("units.Guardsman", "Guardsman"),
+    ("units.BirdsOfPrey", "Birds of Prey"),
+    ("units.Axerider", "Axerider"),
+    ("units.NatarianKnight", "Natarian Knight"),
+    ("units.Warelephant", "War Elephant"),
+    ("units.Ballista", "Ballista"),
+    ("units.NatarianEmperor", "Natarian Emperor"),
+    ("units.Mercenary", "Mercenary"),
+    ("units.Bowman", "Bowman"),
+    ("units.Spotter", "Spotter"),
+    ("units.SteppeRider", "Steppe Rider"),
+    ("units.Marksman", "Marksman"),
+    ("units.Marauder", "Marauder"),
+    ("units.Logades", "Logades"),
+    ("units.SlaveMilitia", "Slave Militia"),
+    ("units.AshWarden", "Ash Warden"),
+    ("units.KhopeshWarrior", "Khopesh Warrior"),
+    ("units.SopduExplorer", "Sopdu Explorer"),
+    ("units.AnhurGuard", "Anhur Guard"),
+    ("units.ReshephChariot", "Resheph Chariot"),
+    ("units.StoneCatapult", "Stone Catapult"),
+    ("units.Nomarch", "Nomarch"),
+    ("units.Hoplite", "Hoplite"),
+    ("units.Sentinel", "Sentinel"),
+    ("units.Shieldsman", "Shieldsman"),
+    ("units.TwinsteelTherion", "Twinsteel Therion"),
+    ("units.ElpidaRider", "Elpida Rider"),
+    ("units.CorinthianCrusher", "Corinthian Crusher"),
+    ("units.Ephor", "Ephor"),
+];
@@ -0,0 +1,192 @@
+mod en;
+
+use super::models::{army::UnitName, buildings::BuildingName};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Translates a key into the default locale, falling back to the key itself.
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::game::i18n::translate($crate::game::i18n::DEFAULT_LOCALE, $key)
+            .map(str::to_string)
+            .unwrap_or_else(|| $key.to_string())
+    };
+}
+
+// Returns the translation of a key in the given locale, if any.
+pub fn translate(locale: &str, key: &str) -> Option<&'static str> {
+    let table = match locale {
+        "en" => en::EN,
+        _ => return None,
+    };
+
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+pub fn building_display_name(name: &BuildingName) -> String {
+    t!(&format!("buildings.{:?}", name))
+}
+
+pub fn unit_display_name(name: &UnitName) -> String {
+    t!(&format!("units.{:?}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{building_display_name, translate, unit_display_name};
+    use crate::game::models::{army::UnitName, buildings::BuildingName};
+
+    #[test]
+    fn test_every_building_has_an_english_name() {
+        let names = [
+            BuildingName::Woodcutter,
+            BuildingName::ClayPit,
+            BuildingName::IronMine,
+            BuildingName::Cropland,
+            BuildingName::Sawmill,
+            BuildingName::Brickyard,
+            BuildingName::IronFoundry,
+            BuildingName::GrainMill,
+            BuildingName::Bakery,
+            BuildingName::Warehouse,
+            BuildingName::Granary,
+            BuildingName::Smithy,
+            BuildingName::TournamentSquare,
+            BuildingName::MainBuilding,
+            BuildingName::RallyPoint,
+            BuildingName::Marketplace,
+            BuildingName::Embassy,
+            BuildingName::Barracks,
+            BuildingName::Stable,
+            BuildingName::Workshop,
+            BuildingName::Academy,
+            BuildingName::Cranny,
+            BuildingName::TownHall,
+            BuildingName::Residence,
+            BuildingName::Palace,
+            BuildingName::Treasury,
+            BuildingName::TradeOffice,
+            BuildingName::GreatBarracks,
+            BuildingName::GreatStable,
+            BuildingName::CityWall,
+            BuildingName::EarthWall,
+            BuildingName::Palisade,
+            BuildingName::StonemansionLodge,
+            BuildingName::Brewery,
+            BuildingName::Trapper,
+            BuildingName::HeroMansion,
+            BuildingName::GreatWarehouse,
+            BuildingName::GreatGranary,
+            BuildingName::WonderOfTheWorld,
+            BuildingName::HorseDrinkingTrough,
+            BuildingName::GreatWorkshop,
+        ];
+
+        for name in names {
+            let key = format!("buildings.{:?}", name);
+            assert!(
+                translate("en", &key).map_or(false, |v| !v.is_empty()),
+                "missing name for {}",
+                key
+            );
+        }
+        assert_eq!(
+            building_display_name(&BuildingName::MainBuilding),
+            "Main Building"
+        );
+    }
+
+    #[test]
+    fn test_every_unit_has_an_english_name() {
+        let names = [
+            UnitName::Legionnaire,
+            UnitName::Praetorian,
+            UnitName::Imperian,
+            UnitName::EquitesLegati,
+            UnitName::EquitesImperatoris,
+            UnitName::EquitesCaesaris,
+            UnitName::BatteringRam,
+            UnitName::FireCatapult,
+            UnitName::Senator,
+            UnitName::Settler,
+            UnitName::Maceman,
+            UnitName::Spearman,
+            UnitName::Axeman,
+            UnitName::Scout,
+            UnitName::Paladin,
+            UnitName::TeutonicKnight,
+            UnitName::Ram,
+            UnitName::Catapult,
+            UnitName::Chief,
+            UnitName::Phalanx,
+            UnitName::Swordsman,
+            UnitName::Pathfinder,
+            UnitName::TheutatesThunder,
+            UnitName::Druidrider,
+            UnitName::Haeduan,
+            UnitName::Trebuchet,
+            UnitName::Chieftain,
+            UnitName::Rat,
+            UnitName::Spider,
+            UnitName::Serpent,
+            UnitName::Bat,
+            UnitName::WildBoar,
+            UnitName::Wolf,
+            UnitName::Bear,
+            UnitName::Crocodile,
+            UnitName::Tiger,
+            UnitName::Elephant,
+            UnitName::Pikeman,
+            UnitName::ThornedWarrior,
+            UnitName::Guardsman,
+            UnitName::BirdsOfPrey,
+            UnitName::Axerider,
+            UnitName::NatarianKnight,
+            UnitName::Warelephant,
+            UnitName::Ballista,
+            UnitName::NatarianEmperor,
+            UnitName::Mercenary,
+            UnitName::Bowman,
+            UnitName::Spotter,
+            UnitName::SteppeRider,
+            UnitName::Marksman,
+            UnitName::Marauder,
+            UnitName::Logades,
+            UnitName::SlaveMilitia,
+            UnitName::AshWarden,
+            UnitName::KhopeshWarrior,
+            UnitName::SopduExplorer,
+            UnitName::AnhurGuard,
+            UnitName::ReshephChariot,
+            UnitName::StoneCatapult,
+            UnitName::Nomarch,
+            UnitName::Hoplite,
+            UnitName::Sentinel,
+            UnitName::Shieldsman,
+            UnitName::TwinsteelTherion,
+            UnitName::ElpidaRider,
+            UnitName::CorinthianCrusher,
+            UnitName::Ephor,
+        ];
+
+        for name in names {
+            let key = format!("units.{:?}", name);
+            assert!(
+                translate("en", &key).map_or(false, |v| !v.is_empty()),
+                "missing name for {}",
+                key
+            );
+        }
+        assert_eq!(
+            unit_display_name(&UnitName::EquitesLegati),
+            "Equites Legati"
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_falls_back_to_key() {
+        assert_eq!(translate("xx", "buildings.Woodcutter"), None);
+        assert_eq!(t!("buildings.Unknown"), "buildings.Unknown");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod battle;
+pub mod error;
+pub mod i18n;
 pub mod models;
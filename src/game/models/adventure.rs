@@ -0,0 +1,328 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    hero::{Hero, HeroItem, HeroStatus, ItemBonus, ItemSlot},
+    map::Position,
+    village::Village,
+    ResourceGroup,
+};
+
+// Minimum time between two adventures spawned for the same player.
+pub const ADVENTURE_SPAWN_INTERVAL_HOURS: i64 = 8;
+// Adventures a player can have on the map at the same time.
+pub const MAX_OPEN_ADVENTURES: usize = 3;
+// Max distance, in fields, of a new adventure from the hero's village.
+pub const ADVENTURE_RANGE: i32 = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AdventureDifficulty {
+    Normal,
+    Hard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AdventureReward {
+    Resources(ResourceGroup),
+    Experience(u32),
+    Item(HeroItem),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Adventure {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub position: Position,
+    pub difficulty: AdventureDifficulty,
+    pub reward: AdventureReward,
+    pub created_at: DateTime<Utc>,
+}
+
+// What happened to the hero during an adventure. The reward is lost when the hero dies.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AdventureOutcome {
+    pub position: Position,
+    pub difficulty: AdventureDifficulty,
+    pub health_lost: u8,
+    pub reward: Option<AdventureReward>,
+}
+
+impl Adventure {
+    pub fn new(
+        player_id: Uuid,
+        position: Position,
+        difficulty: AdventureDifficulty,
+        reward: AdventureReward,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            player_id,
+            position,
+            difficulty,
+            reward,
+            created_at,
+        }
+    }
+
+    // Applies the damage taken by the hero and brings it back home with the reward.
+    // Experience and items go to the hero, resources are left to the caller to store
+    // in the village. A hero taking as much damage as its health dies out there.
+    pub fn resolve(&self, hero: &mut Hero, damage: u8) -> AdventureOutcome {
+        let health_lost = damage.min(hero.health);
+        hero.health -= health_lost;
+
+        let reward = if hero.health == 0 {
+            hero.status = HeroStatus::Dead;
+            None
+        } else {
+            hero.status = HeroStatus::Home;
+            match &self.reward {
+                AdventureReward::Experience(xp) => {
+                    hero.add_experience(*xp);
+                }
+                AdventureReward::Item(item) => hero.inventory.push(item.clone()),
+                AdventureReward::Resources(_) => (),
+            }
+            Some(self.reward.clone())
+        };
+
+        AdventureOutcome {
+            position: self.position.clone(),
+            difficulty: self.difficulty.clone(),
+            health_lost,
+            reward,
+        }
+    }
+}
+
+// Returns true when a new adventure is due, given the ones the player still has open.
+pub fn should_spawn(open: &[Adventure], now: DateTime<Utc>) -> bool {
+    let interval = Duration::hours(ADVENTURE_SPAWN_INTERVAL_HOURS);
+
+    open.len() < MAX_OPEN_ADVENTURES && open.iter().all(|a| now - a.created_at >= interval)
+}
+
+// Places a new adventure around the village, one hard adventure out of four.
+pub fn spawn_adventure<R: Rng>(
+    player_id: Uuid,
+    village: &Village,
+    world_size: i32,
+    now: DateTime<Utc>,
+    rng: &mut R,
+) -> Adventure {
+    let position = loop {
+        let dx = rng.gen_range(-ADVENTURE_RANGE..=ADVENTURE_RANGE);
+        let dy = rng.gen_range(-ADVENTURE_RANGE..=ADVENTURE_RANGE);
+        if dx == 0 && dy == 0 {
+            continue;
+        }
+        let p = Position {
+            x: village.position.x + dx,
+            y: village.position.y + dy,
+        };
+        if let Ok(p) = p.normalize(world_size) {
+            break p;
+        }
+    };
+
+    let difficulty = match rng.gen_ratio(1, 4) {
+        true => AdventureDifficulty::Hard,
+        false => AdventureDifficulty::Normal,
+    };
+    let multiplier = match difficulty {
+        AdventureDifficulty::Normal => 1,
+        AdventureDifficulty::Hard => 3,
+    };
+    let reward = match rng.gen_range(0..3) {
+        0 => AdventureReward::Resources(ResourceGroup::new(
+            100 * multiplier,
+            100 * multiplier,
+            100 * multiplier,
+            100 * multiplier,
+        )),
+        1 => AdventureReward::Experience(10 * multiplier),
+        _ => AdventureReward::Item(random_item(rng)),
+    };
+
+    Adventure::new(player_id, position, difficulty, reward, now)
+}
+
+// Returns the damage taken by the hero. Normal adventures only kill heroes that are
+// already badly wounded, hard ones can kill a hero in full health.
+pub fn adventure_damage<R: Rng>(difficulty: &AdventureDifficulty, rng: &mut R) -> u8 {
+    match difficulty {
+        AdventureDifficulty::Normal => rng.gen_range(0..=25),
+        AdventureDifficulty::Hard => rng.gen_range(30..=110),
+    }
+}
+
+fn random_item<R: Rng>(rng: &mut R) -> HeroItem {
+    let (name, slot, bonus) = match rng.gen_range(0..3) {
+        0 => (
+            "Helmet of awareness",
+            ItemSlot::Helmet,
+            ItemBonus {
+                regeneration: 5,
+                ..Default::default()
+            },
+        ),
+        1 => (
+            "Short sword",
+            ItemSlot::RightHand,
+            ItemBonus {
+                attack: 500,
+                ..Default::default()
+            },
+        ),
+        _ => (
+            "Boots of the wanderer",
+            ItemSlot::Shoes,
+            ItemBonus {
+                speed: 1,
+                ..Default::default()
+            },
+        ),
+    };
+
+    HeroItem::new(name.to_string(), slot, bonus)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use rand::{rngs::StdRng, SeedableRng};
+    use uuid::Uuid;
+
+    use super::{
+        adventure_damage, should_spawn, spawn_adventure, Adventure, AdventureDifficulty,
+        AdventureReward, ADVENTURE_RANGE, ADVENTURE_SPAWN_INTERVAL_HOURS, MAX_OPEN_ADVENTURES,
+    };
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            hero::{Hero, HeroItem, HeroStatus, ItemBonus, ItemSlot},
+            map::{Position, WORLD_MAX_SIZE},
+            village::Village,
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn village() -> Village {
+        test_village(&test_player(Tribe::Gaul), Position { x: 10, y: 20 })
+    }
+
+    fn adventure(difficulty: AdventureDifficulty, reward: AdventureReward) -> Adventure {
+        Adventure::new(
+            Uuid::new_v4(),
+            Position { x: 12, y: 22 },
+            difficulty,
+            reward,
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn test_spawn_cadence() {
+        let now = Utc::now();
+        let interval = Duration::hours(ADVENTURE_SPAWN_INTERVAL_HOURS);
+        let spawned_at = |at| {
+            let mut a = adventure(AdventureDifficulty::Normal, AdventureReward::Experience(10));
+            a.created_at = at;
+            a
+        };
+
+        assert!(should_spawn(&[], now));
+        assert!(!should_spawn(&[spawned_at(now - Duration::hours(1))], now));
+        assert!(should_spawn(&[spawned_at(now - interval)], now));
+
+        let full: Vec<Adventure> = (0..MAX_OPEN_ADVENTURES)
+            .map(|_| spawned_at(now - interval * 2))
+            .collect();
+        assert!(!should_spawn(&full, now));
+    }
+
+    #[test]
+    fn test_spawned_adventures_are_near_the_village() {
+        let village = village();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..100 {
+            let a = spawn_adventure(
+                village.player_id,
+                &village,
+                WORLD_MAX_SIZE,
+                Utc::now(),
+                &mut rng,
+            );
+            assert_ne!(a.position, village.position);
+            assert!((a.position.x - village.position.x).abs() <= ADVENTURE_RANGE);
+            assert!((a.position.y - village.position.y).abs() <= ADVENTURE_RANGE);
+        }
+    }
+
+    #[test]
+    fn test_rewards_go_to_the_hero() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.status = HeroStatus::Away;
+
+        let outcome = adventure(AdventureDifficulty::Normal, AdventureReward::Experience(10))
+            .resolve(&mut hero, 20);
+        assert_eq!(outcome.health_lost, 20);
+        assert_eq!(outcome.reward, Some(AdventureReward::Experience(10)));
+        assert_eq!(hero.health, 80);
+        assert_eq!(hero.experience, 10);
+        assert_eq!(hero.status, HeroStatus::Home);
+
+        let item = HeroItem::new(
+            "Short sword".to_string(),
+            ItemSlot::RightHand,
+            ItemBonus::default(),
+        );
+        adventure(
+            AdventureDifficulty::Normal,
+            AdventureReward::Item(item.clone()),
+        )
+        .resolve(&mut hero, 0);
+        assert_eq!(hero.inventory, vec![item]);
+
+        // resources are stored in the village by the caller
+        let resources = ResourceGroup::new(100, 100, 100, 100);
+        let outcome = adventure(
+            AdventureDifficulty::Normal,
+            AdventureReward::Resources(resources.clone()),
+        )
+        .resolve(&mut hero, 0);
+        assert_eq!(outcome.reward, Some(AdventureReward::Resources(resources)));
+        assert_eq!(hero.experience, 10);
+    }
+
+    #[test]
+    fn test_hard_adventures_can_kill_the_hero() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let normal: Vec<u8> = (0..200)
+            .map(|_| adventure_damage(&AdventureDifficulty::Normal, &mut rng))
+            .collect();
+        let hard: Vec<u8> = (0..200)
+            .map(|_| adventure_damage(&AdventureDifficulty::Hard, &mut rng))
+            .collect();
+        assert!(
+            normal.iter().all(|d| *d < 100),
+            "normal ones spare a healthy hero"
+        );
+        assert!(hard.iter().any(|d| *d >= 100), "hard ones can be lethal");
+        assert!(hard.iter().any(|d| *d < 100), "but not always");
+
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.status = HeroStatus::Away;
+        let outcome = adventure(AdventureDifficulty::Hard, AdventureReward::Experience(30))
+            .resolve(&mut hero, 110);
+        assert_eq!(outcome.health_lost, 100);
+        assert_eq!(outcome.reward, None);
+        assert_eq!(hero.health, 0);
+        assert_eq!(hero.status, HeroStatus::Dead);
+        assert_eq!(hero.experience, 0);
+    }
+}
@@ -2,7 +2,15 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{Cost, ResourceGroup, SmithyUpgrades, Tribe};
+use super::{
+    buildings::BuildingName, hero::Hero, scale_time, Cost, ResourceGroup, SmithyUpgrades, Tribe,
+};
+use crate::game::error::GameError;
+
+// Smithy levels count the upgrades done: 0 is a unit never upgraded, 20 the last upgrade.
+pub const SMITHY_MAX_LEVEL: u8 = 20;
+// Each level of the training building cuts training times by this factor.
+pub const TRAINING_TIME_FACTOR: f64 = 0.9;
 
 #[derive(Debug, Clone)]
 pub enum UnitRole {
@@ -15,7 +23,7 @@ pub enum UnitRole {
     Settler,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UnitGroup {
     Infantry,
     Cavalry,
@@ -23,7 +31,10 @@ pub enum UnitGroup {
     Expansion,
 }
 
-#[derive(Debug, Clone)]
+// Some names are shared by more tribes with different stats (eg: Teuton and Gaul Ram,
+// Settler), so unit data must always be looked up along with the tribe. Names are
+// stored with jobs, renaming them breaks the jobs in flight.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum UnitName {
     // Romans
     Legionnaire,
@@ -114,6 +125,12 @@ pub struct Army {
     pub tribe: Tribe,
     pub units: TroopSet,
     pub smithy: SmithyUpgrades,
+    #[serde(default)]
+    pub hero: Option<Hero>,
+    // Units caught in enemy traps: they still belong to the army, but can't be sent
+    // anywhere until they're freed.
+    #[serde(default)]
+    pub trapped: TroopSet,
 }
 
 impl Army {
@@ -130,6 +147,8 @@ impl Army {
             tribe,
             units,
             smithy,
+            hero: None,
+            trapped: [0; 10],
         }
     }
 
@@ -157,6 +176,27 @@ impl Army {
         total
     }
 
+    // Returns the quantity of each unit type in the army, empty slots excluded.
+    pub fn units_by_name(&self) -> Vec<(UnitName, u32)> {
+        let units = get_tribe_units(self.tribe.clone());
+        self.units
+            .into_iter()
+            .enumerate()
+            .filter(|(_, quantity)| *quantity > 0)
+            .map(|(idx, quantity)| (units[idx].name.clone(), quantity))
+            .collect()
+    }
+
+    // Returns how many resources the army can carry back home.
+    pub fn carry_capacity(&self) -> u64 {
+        let units = get_tribe_units(self.tribe.clone());
+        self.units
+            .into_iter()
+            .enumerate()
+            .map(|(idx, quantity)| units[idx].capacity as u64 * quantity as u64)
+            .sum()
+    }
+
     pub fn attack_points(&self) -> (u32, u32) {
         let mut infantry_points: u32 = 0;
         let mut cavalry_points: u32 = 0;
@@ -171,6 +211,15 @@ impl Army {
                 _ => infantry_points += smithy_improvement * quantity,
             }
         }
+
+        if let Some(hero) = &self.hero {
+            infantry_points = (infantry_points as f64 * hero.off_bonus()).round() as u32;
+            cavalry_points = (cavalry_points as f64 * hero.off_bonus()).round() as u32;
+            match hero.is_mounted() {
+                true => cavalry_points += hero.attack_points(),
+                false => infantry_points += hero.attack_points(),
+            }
+        }
         (infantry_points, cavalry_points)
     }
 
@@ -187,6 +236,13 @@ impl Army {
             infantry_points += smithy_infantry * quantity;
             cavalry_points += smithy_cavalry * quantity;
         }
+
+        if let Some(hero) = &self.hero {
+            infantry_points = (infantry_points as f64 * hero.def_bonus()).round() as u32;
+            cavalry_points = (cavalry_points as f64 * hero.def_bonus()).round() as u32;
+            infantry_points += hero.defense_points();
+            cavalry_points += hero.defense_points();
+        }
         (infantry_points, cavalry_points)
     }
 
@@ -204,34 +260,69 @@ impl Army {
         }
     }
 
+    // Returns the units free to leave, trapped ones excluded.
+    pub fn available_units(&self) -> TroopSet {
+        let mut units = self.units;
+        for (idx, quantity) in self.trapped.into_iter().enumerate() {
+            units[idx] = units[idx].saturating_sub(quantity);
+        }
+        units
+    }
+
     // Returns a new Army which has been extracted from the current one.
     pub fn deploy(&mut self, set: TroopSet) -> Result<TroopSet> {
+        let available = self.available_units();
+        if set.iter().zip(available.iter()).any(|(q, a)| q > a) {
+            return Err(anyhow!("The number of available units is not enough"));
+        }
         for (idx, quantity) in set.into_iter().enumerate() {
-            if self.units[idx] > quantity {
-                self.units[idx] -= quantity;
-            } else {
-                return Err(anyhow!("The number of available units is not enough"));
-            }
+            self.units[idx] -= quantity;
+        }
+        Ok(set)
+    }
+
+    // Puts back into the current army the units (and hero) of another one.
+    pub fn merge(&mut self, other: Army) {
+        for (idx, quantity) in other.units.into_iter().enumerate() {
+            self.units[idx] += quantity;
+        }
+        if other.hero.is_some() {
+            self.hero = other.hero;
         }
-        Ok(set.clone())
     }
 
-    // Returns the actual speed of the Army by taking the speed of slowest unit.
+    // Returns the actual speed of the Army by taking the speed of slowest unit (hero included).
     pub fn speed(&self) -> u8 {
-        let mut speed: u8 = 0;
+        let mut speed: Option<u8> = self.hero.as_ref().map(|h| h.speed());
         for (idx, quantity) in self.units.into_iter().enumerate() {
             if quantity > 0 {
                 let u = self.get_unit(idx as u8).unwrap();
-                if u.speed < speed {
-                    speed = u.speed;
-                }
+                speed = Some(speed.map_or(u.speed, |s| s.min(u.speed)));
             }
         }
-        speed
+        speed.unwrap_or(0)
+    }
+
+    // Kills the given share of scouts only, other units don't take part in scouting.
+    pub fn apply_scouting_losses(&mut self, percent: f64) {
+        if let Some(idx) = self.scout_idx() {
+            let quantity = self.units[idx];
+            self.units[idx] = quantity - ((quantity as f64) * percent / 100.0).floor() as u32;
+        }
+    }
+
+    // Returns the slot of the tribe scouts, not every tribe has them in the same slot.
+    pub fn scout_idx(&self) -> Option<usize> {
+        get_tribe_units(self.tribe.clone())
+            .iter()
+            .position(|u| matches!(u.role, UnitRole::Scout))
     }
 
     fn scouting_points(&self, base_points: u8) -> u32 {
-        let idx: usize = 3;
+        let idx = match self.scout_idx() {
+            Some(idx) => idx,
+            None => return 0,
+        };
         let quantity = self.units[idx];
         let unit = self.get_unit(idx as u8).unwrap();
         let smithy_improvement = self.apply_smithy_upgrade(unit.clone(), idx, base_points as u32);
@@ -239,11 +330,20 @@ impl Army {
     }
 
     fn apply_smithy_upgrade(&self, unit: Unit, idx: usize, combat_value: u32) -> u32 {
-        let level: i32 = self.smithy[idx as usize].into();
-        ((combat_value as f64)
-            + ((combat_value + 300 * unit.cost.upkeep) as f64 / 7.0)
-                * ((1.007f64).powi(level.try_into().unwrap()) - 1.0).floor()) as u32
+        // levels beyond the max can only come from broken data, they count as the max
+        let factor = smithy_upgrade_factor(self.smithy[idx].min(SMITHY_MAX_LEVEL))
+            .expect("smithy level is clamped to the max");
+        ((combat_value as f64) + ((combat_value + 300 * unit.cost.upkeep) as f64 / 7.0) * factor)
+            as u32
+    }
+}
+
+// Returns the growth factor of combat values given by the Smithy at the given level.
+pub fn smithy_upgrade_factor(level: u8) -> Result<f64, GameError> {
+    if level > SMITHY_MAX_LEVEL {
+        return Err(GameError::InvalidSmithyLevel { level });
     }
+    Ok(1.007f64.powi(level as i32) - 1.0)
 }
 
 #[derive(Debug, Clone)]
@@ -259,6 +359,54 @@ pub struct Unit {
     pub cost: Cost,
 }
 
+impl Unit {
+    // Returns the resources needed to train the given quantity, multiplied by the cost
+    // factor of the training building.
+    pub fn training_cost(&self, quantity: u32, cost_factor: u32) -> ResourceGroup {
+        let multiplier = quantity.saturating_mul(cost_factor);
+        let cost = &self.cost.resources;
+        ResourceGroup::new(
+            cost.lumber().saturating_mul(multiplier),
+            cost.clay().saturating_mul(multiplier),
+            cost.iron().saturating_mul(multiplier),
+            cost.crop().saturating_mul(multiplier),
+        )
+    }
+
+    // Returns the seconds needed to train one unit, reduced by the level of the training
+    // building and by the given factor (e.g. artifacts), then scaled by the server speed.
+    pub fn training_time_secs(&self, building_level: u8, factor: f64, server_speed: u8) -> u32 {
+        let reduction = TRAINING_TIME_FACTOR.powi(building_level.saturating_sub(1) as i32) * factor;
+
+        scale_time(self.cost.build_time, reduction, server_speed)
+    }
+
+    pub fn stats(&self) -> UnitStats {
+        UnitStats {
+            attack: self.attack,
+            defense_infantry: self.defense_infantry,
+            defense_cavalry: self.defense_cavalry,
+            speed: self.speed,
+            capacity: self.capacity,
+            upkeep: self.cost.upkeep,
+        }
+    }
+}
+
+// Combat, movement and upkeep figures of a unit, without its training costs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitStats {
+    pub attack: u32,
+    pub defense_infantry: u32,
+    pub defense_cavalry: u32,
+    // Fields per hour.
+    pub speed: u8,
+    // Resources carried back from raids.
+    pub capacity: u32,
+    // Crop consumed per hour.
+    pub upkeep: u32,
+}
+
 static ROMAN_UNITS: TribeUnits = [
     Unit {
         name: UnitName::Legionnaire,
@@ -267,7 +415,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 40,
         defense_infantry: 35,
         defense_cavalry: 50,
-        speed: 12,
+        speed: 6,
         capacity: 50,
         cost: Cost {
             resources: ResourceGroup::new(120, 100, 150, 30),
@@ -282,7 +430,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 30,
         defense_infantry: 65,
         defense_cavalry: 35,
-        speed: 10,
+        speed: 5,
         capacity: 20,
         cost: Cost {
             resources: ResourceGroup::new(100, 130, 160, 70),
@@ -297,7 +445,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 70,
         defense_infantry: 40,
         defense_cavalry: 25,
-        speed: 14,
+        speed: 7,
         capacity: 50,
         cost: Cost {
             resources: ResourceGroup::new(150, 160, 210, 80),
@@ -312,7 +460,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 20,
         defense_cavalry: 10,
-        speed: 32,
+        speed: 16,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(140, 160, 20, 40),
@@ -327,7 +475,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 120,
         defense_infantry: 65,
         defense_cavalry: 50,
-        speed: 28,
+        speed: 14,
         capacity: 100,
         cost: Cost {
             resources: ResourceGroup::new(550, 440, 320, 100),
@@ -342,7 +490,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 180,
         defense_infantry: 80,
         defense_cavalry: 105,
-        speed: 20,
+        speed: 10,
         capacity: 70,
         cost: Cost {
             resources: ResourceGroup::new(550, 640, 800, 180),
@@ -357,7 +505,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 60,
         defense_infantry: 30,
         defense_cavalry: 75,
-        speed: 8,
+        speed: 4,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(900, 360, 500, 70),
@@ -372,7 +520,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 75,
         defense_infantry: 60,
         defense_cavalry: 10,
-        speed: 6,
+        speed: 3,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(950, 1350, 600, 90),
@@ -387,7 +535,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 50,
         defense_infantry: 40,
         defense_cavalry: 30,
-        speed: 8,
+        speed: 4,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(30750, 27200, 45000, 37500),
@@ -402,7 +550,7 @@ static ROMAN_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 80,
         defense_cavalry: 80,
-        speed: 10,
+        speed: 5,
         capacity: 3000,
         cost: Cost {
             resources: ResourceGroup::new(4600, 4200, 5800, 4400),
@@ -420,7 +568,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 40,
         defense_infantry: 20,
         defense_cavalry: 5,
-        speed: 14,
+        speed: 7,
         capacity: 60,
         cost: Cost {
             resources: ResourceGroup::new(95, 75, 40, 40),
@@ -435,7 +583,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 10,
         defense_infantry: 35,
         defense_cavalry: 60,
-        speed: 14,
+        speed: 7,
         capacity: 40,
         cost: Cost {
             resources: ResourceGroup::new(145, 70, 85, 40),
@@ -450,7 +598,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 60,
         defense_infantry: 30,
         defense_cavalry: 30,
-        speed: 12,
+        speed: 6,
         capacity: 50,
         cost: Cost {
             resources: ResourceGroup::new(130, 120, 170, 70),
@@ -465,7 +613,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 10,
         defense_cavalry: 5,
-        speed: 18,
+        speed: 9,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(160, 100, 50, 50),
@@ -475,12 +623,12 @@ static TEUTON_UNITS: TribeUnits = [
     },
     Unit {
         name: UnitName::Paladin,
-        role: UnitRole::Infantry,
-        group: UnitGroup::Infantry,
+        role: UnitRole::Cavalry,
+        group: UnitGroup::Cavalry,
         attack: 55,
         defense_infantry: 100,
         defense_cavalry: 40,
-        speed: 20,
+        speed: 10,
         capacity: 110,
         cost: Cost {
             resources: ResourceGroup::new(370, 270, 290, 75),
@@ -490,12 +638,12 @@ static TEUTON_UNITS: TribeUnits = [
     },
     Unit {
         name: UnitName::TeutonicKnight,
-        role: UnitRole::Infantry,
-        group: UnitGroup::Infantry,
+        role: UnitRole::Cavalry,
+        group: UnitGroup::Cavalry,
         attack: 150,
         defense_infantry: 50,
         defense_cavalry: 75,
-        speed: 18,
+        speed: 9,
         capacity: 80,
         cost: Cost {
             resources: ResourceGroup::new(450, 515, 480, 80),
@@ -510,7 +658,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 65,
         defense_infantry: 30,
         defense_cavalry: 80,
-        speed: 8,
+        speed: 4,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(1000, 300, 350, 70),
@@ -525,7 +673,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 50,
         defense_infantry: 60,
         defense_cavalry: 10,
-        speed: 6,
+        speed: 3,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(900, 1200, 600, 60),
@@ -540,7 +688,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 40,
         defense_infantry: 60,
         defense_cavalry: 40,
-        speed: 8,
+        speed: 4,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(35500, 26600, 25000, 27200),
@@ -555,7 +703,7 @@ static TEUTON_UNITS: TribeUnits = [
         attack: 10,
         defense_infantry: 80,
         defense_cavalry: 80,
-        speed: 10,
+        speed: 5,
         capacity: 3000,
         cost: Cost {
             resources: ResourceGroup::new(5800, 4400, 4600, 5200),
@@ -573,7 +721,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 15,
         defense_infantry: 40,
         defense_cavalry: 50,
-        speed: 14,
+        speed: 7,
         capacity: 35,
         cost: Cost {
             resources: ResourceGroup::new(100, 130, 55, 30),
@@ -588,7 +736,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 65,
         defense_infantry: 35,
         defense_cavalry: 20,
-        speed: 12,
+        speed: 6,
         capacity: 45,
         cost: Cost {
             resources: ResourceGroup::new(140, 150, 185, 60),
@@ -603,7 +751,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 20,
         defense_cavalry: 10,
-        speed: 34,
+        speed: 17,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(170, 150, 20, 40),
@@ -618,7 +766,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 100,
         defense_infantry: 25,
         defense_cavalry: 40,
-        speed: 38,
+        speed: 19,
         capacity: 75,
         cost: Cost {
             resources: ResourceGroup::new(350, 450, 230, 60),
@@ -633,7 +781,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 45,
         defense_infantry: 115,
         defense_cavalry: 55,
-        speed: 32,
+        speed: 16,
         capacity: 35,
         cost: Cost {
             resources: ResourceGroup::new(360, 330, 280, 120),
@@ -648,7 +796,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 140,
         defense_infantry: 60,
         defense_cavalry: 165,
-        speed: 26,
+        speed: 13,
         capacity: 65,
         cost: Cost {
             resources: ResourceGroup::new(500, 620, 675, 170),
@@ -663,7 +811,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 50,
         defense_infantry: 30,
         defense_cavalry: 105,
-        speed: 8,
+        speed: 4,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(950, 555, 330, 75),
@@ -678,7 +826,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 70,
         defense_infantry: 45,
         defense_cavalry: 10,
-        speed: 6,
+        speed: 3,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(960, 1450, 630, 90),
@@ -693,7 +841,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 40,
         defense_infantry: 50,
         defense_cavalry: 50,
-        speed: 10,
+        speed: 5,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(30750, 45400, 31000, 37500),
@@ -708,7 +856,7 @@ static GAUL_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 80,
         defense_cavalry: 80,
-        speed: 10,
+        speed: 5,
         capacity: 3000,
         cost: Cost {
             resources: ResourceGroup::new(4400, 5600, 4200, 3900),
@@ -726,7 +874,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 10,
         defense_infantry: 25,
         defense_cavalry: 20,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 100, 100),
@@ -741,7 +889,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 20,
         defense_infantry: 35,
         defense_cavalry: 40,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -756,7 +904,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 60,
         defense_infantry: 40,
         defense_cavalry: 60,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -771,7 +919,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 80,
         defense_infantry: 66,
         defense_cavalry: 50,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -786,7 +934,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 50,
         defense_infantry: 70,
         defense_cavalry: 33,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -801,7 +949,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 100,
         defense_infantry: 80,
         defense_cavalry: 70,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -816,7 +964,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 250,
         defense_infantry: 140,
         defense_cavalry: 200,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -831,7 +979,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 450,
         defense_infantry: 380,
         defense_cavalry: 240,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -846,7 +994,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 200,
         defense_infantry: 170,
         defense_cavalry: 250,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -861,7 +1009,7 @@ static NATURE_UNITS: TribeUnits = [
         attack: 600,
         defense_infantry: 440,
         defense_cavalry: 520,
-        speed: 40,
+        speed: 20,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -879,7 +1027,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 20,
         defense_infantry: 35,
         defense_cavalry: 50,
-        speed: 12,
+        speed: 6,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -894,7 +1042,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 65,
         defense_infantry: 30,
         defense_cavalry: 10,
-        speed: 14,
+        speed: 7,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -909,7 +1057,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 100,
         defense_infantry: 90,
         defense_cavalry: 75,
-        speed: 12,
+        speed: 6,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -924,7 +1072,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 0,
         defense_infantry: 10,
         defense_cavalry: 0,
-        speed: 50,
+        speed: 25,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -939,7 +1087,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 155,
         defense_infantry: 80,
         defense_cavalry: 50,
-        speed: 28,
+        speed: 14,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -954,7 +1102,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 170,
         defense_infantry: 140,
         defense_cavalry: 80,
-        speed: 24,
+        speed: 12,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -969,7 +1117,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 250,
         defense_infantry: 120,
         defense_cavalry: 150,
-        speed: 10,
+        speed: 5,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -984,7 +1132,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 60,
         defense_infantry: 45,
         defense_cavalry: 10,
-        speed: 6,
+        speed: 3,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -999,7 +1147,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 80,
         defense_infantry: 50,
         defense_cavalry: 50,
-        speed: 10,
+        speed: 5,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -1014,7 +1162,7 @@ static NATAR_UNITS: TribeUnits = [
         attack: 30,
         defense_infantry: 40,
         defense_cavalry: 40,
-        speed: 10,
+        speed: 5,
         capacity: 0,
         cost: Cost {
             resources: ResourceGroup::new(0, 0, 0, 0),
@@ -1024,6 +1172,36 @@ static NATAR_UNITS: TribeUnits = [
     },
 ];
 
+// Returns the group of units trained in the building, and whether it's one of the great
+// buildings training the same units at a higher cost.
+pub fn training_group(building: &BuildingName) -> Option<(UnitGroup, bool)> {
+    match building {
+        BuildingName::Barracks => Some((UnitGroup::Infantry, false)),
+        BuildingName::GreatBarracks => Some((UnitGroup::Infantry, true)),
+        BuildingName::Stable => Some((UnitGroup::Cavalry, false)),
+        BuildingName::GreatStable => Some((UnitGroup::Cavalry, true)),
+        BuildingName::Workshop => Some((UnitGroup::Siege, false)),
+        BuildingName::GreatWorkshop => Some((UnitGroup::Siege, true)),
+        BuildingName::Residence | BuildingName::Palace => Some((UnitGroup::Expansion, false)),
+        _ => None,
+    }
+}
+
+// Returns the index and the data of a unit within its tribe units.
+pub fn get_unit_by_name(tribe: &Tribe, name: &UnitName) -> Option<(u8, Unit)> {
+    get_tribe_units(tribe.clone())
+        .into_iter()
+        .enumerate()
+        .find(|(_, u)| &u.name == name)
+        .map(|(idx, u)| (idx as u8, u))
+}
+
+// Returns the stats of a unit. The tribe is needed because some names are shared by
+// units with different stats.
+pub fn unit_stats(tribe: &Tribe, name: &UnitName) -> Option<UnitStats> {
+    get_unit_by_name(tribe, name).map(|(_, u)| u.stats())
+}
+
 fn get_tribe_units(tribe: Tribe) -> TribeUnits {
     match tribe {
         Tribe::Roman => ROMAN_UNITS.clone(),
@@ -1033,3 +1211,105 @@ fn get_tribe_units(tribe: Tribe) -> TribeUnits {
         Tribe::Natar => NATAR_UNITS.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{
+        get_tribe_units, get_unit_by_name, smithy_upgrade_factor, unit_stats, Army, UnitName,
+        UnitStats, SMITHY_MAX_LEVEL,
+    };
+    use crate::game::{error::GameError, models::Tribe};
+
+    #[test]
+    fn test_siege_units_resolve_to_their_tribe() {
+        let siege = |tribe: Tribe, name: UnitName| {
+            let (idx, unit) = get_unit_by_name(&tribe, &name).unwrap();
+            (idx, unit.attack, unit.cost.build_time)
+        };
+
+        // rams and catapults sit on the same slots for every tribe
+        assert_eq!(siege(Tribe::Roman, UnitName::BatteringRam), (6, 60, 1533));
+        assert_eq!(siege(Tribe::Teuton, UnitName::Ram), (6, 65, 1400));
+        assert_eq!(siege(Tribe::Gaul, UnitName::Ram), (6, 50, 1667));
+        assert_eq!(siege(Tribe::Roman, UnitName::FireCatapult).0, 7);
+        assert_eq!(siege(Tribe::Teuton, UnitName::Catapult).0, 7);
+        assert_eq!(siege(Tribe::Gaul, UnitName::Trebuchet).0, 7);
+
+        // names of other tribes are not found
+        assert!(get_unit_by_name(&Tribe::Gaul, &UnitName::Catapult).is_none());
+        assert!(get_unit_by_name(&Tribe::Roman, &UnitName::Ram).is_none());
+    }
+
+    #[test]
+    fn test_unit_stats() {
+        assert_eq!(
+            unit_stats(&Tribe::Roman, &UnitName::Legionnaire),
+            Some(UnitStats {
+                attack: 40,
+                defense_infantry: 35,
+                defense_cavalry: 50,
+                speed: 6,
+                capacity: 50,
+                upkeep: 1,
+            })
+        );
+        assert_ne!(
+            unit_stats(&Tribe::Teuton, &UnitName::Ram),
+            unit_stats(&Tribe::Gaul, &UnitName::Ram)
+        );
+        assert_eq!(unit_stats(&Tribe::Roman, &UnitName::Phalanx), None);
+
+        for tribe in [Tribe::Roman, Tribe::Teuton, Tribe::Gaul] {
+            for unit in get_tribe_units(tribe.clone()) {
+                let stats = unit_stats(&tribe, &unit.name).unwrap();
+                assert!(stats.speed > 0, "{:?} doesn't move", unit.name);
+                assert!(stats.upkeep > 0, "{:?} doesn't eat", unit.name);
+                assert!(
+                    stats.attack + stats.defense_infantry + stats.defense_cavalry > 0,
+                    "{:?} can't fight",
+                    unit.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_smithy_levels_range() {
+        assert_eq!(smithy_upgrade_factor(0), Ok(0.0));
+        assert!(smithy_upgrade_factor(19).unwrap() < smithy_upgrade_factor(20).unwrap());
+        assert_eq!(SMITHY_MAX_LEVEL, 20);
+        assert_eq!(
+            smithy_upgrade_factor(21),
+            Err(GameError::InvalidSmithyLevel { level: 21 })
+        );
+    }
+
+    #[test]
+    fn test_broken_smithy_levels_dont_panic() {
+        let mut smithy = [0; 10];
+        smithy[0] = 20;
+        let top = Army::new(1, Uuid::new_v4(), Tribe::Roman, [10; 10], smithy);
+        smithy[0] = 255;
+        let broken = Army::new(1, Uuid::new_v4(), Tribe::Roman, [10; 10], smithy);
+
+        assert_eq!(broken.attack_points(), top.attack_points());
+        assert_eq!(broken.defense_points(), top.defense_points());
+    }
+
+    #[test]
+    fn test_smithy_upgrade_raises_attack() {
+        let mut units = [0; 10];
+        units[0] = 100;
+        let base = Army::new(1, Uuid::new_v4(), Tribe::Roman, units, [0; 10]);
+        let mut smithy = [0; 10];
+        smithy[0] = 20;
+        let upgraded = Army::new(1, Uuid::new_v4(), Tribe::Roman, units, smithy);
+
+        // a legionnaire attacks with 40, plus (40 + 300 * 1) / 7 * (1.007^20 - 1) = 7.27
+        assert_eq!(base.attack_points(), (4_000, 0));
+        assert_eq!(upgraded.attack_points(), (4_700, 0));
+        assert!(upgraded.defense_points() > base.defense_points());
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ArtifactSize {
+    // Works only in the village holding it.
+    Small,
+    // Works in every village of the owner, with a weaker effect.
+    Large,
+    // Works in every village of the owner, as strong as a small one.
+    Unique,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ArtifactEffect {
+    // Boosts the resource production.
+    Production,
+    // Speeds up the training of troops.
+    Trainer,
+    // Makes buildings and walls sturdier against catapults and rams.
+    BuildingDurability,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Artifact {
+    Boost {
+        size: ArtifactSize,
+        effect: ArtifactEffect,
+    },
+    // Has no effect of its own, it's needed to build a Wonder of the World in the
+    // village holding it.
+    ConstructionPlan,
+}
+
+impl Artifact {
+    pub fn is_account_wide(&self) -> bool {
+        matches!(
+            self,
+            Artifact::Boost {
+                size: ArtifactSize::Large | ArtifactSize::Unique,
+                ..
+            }
+        )
+    }
+
+    // Returns the multiplier of the given effect in a village, whether it's the holder or
+    // another village of the owner, 1.0 when the artifact doesn't reach it. Trainers
+    // multiply the training time, the others the value they boost.
+    pub fn multiplier(&self, effect: ArtifactEffect, holder: bool) -> f64 {
+        let size = match self {
+            Artifact::Boost { size, effect: e } if *e == effect => *size,
+            _ => return 1.0,
+        };
+        if !holder && !self.is_account_wide() {
+            return 1.0;
+        }
+
+        match (effect, size) {
+            (ArtifactEffect::Production, ArtifactSize::Large) => 1.15,
+            (ArtifactEffect::Production, _) => 1.25,
+            (ArtifactEffect::Trainer, ArtifactSize::Large) => 0.75,
+            (ArtifactEffect::Trainer, _) => 0.5,
+            (ArtifactEffect::BuildingDurability, ArtifactSize::Small) => 4.0,
+            (ArtifactEffect::BuildingDurability, ArtifactSize::Large) => 3.0,
+            (ArtifactEffect::BuildingDurability, ArtifactSize::Unique) => 5.0,
+        }
+    }
+}
+
+// Returns the strongest multiplier of the effect among the given artifacts, 1.0 when none
+// of them has it. The same effect doesn't stack.
+pub fn strongest_multiplier(artifacts: &[Artifact], effect: ArtifactEffect, holder: bool) -> f64 {
+    let multipliers = artifacts.iter().map(|a| a.multiplier(effect, holder));
+    match effect {
+        ArtifactEffect::Trainer => multipliers.fold(1.0, f64::min),
+        _ => multipliers.fold(1.0, f64::max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strongest_multiplier, Artifact, ArtifactEffect, ArtifactSize};
+
+    fn boost(size: ArtifactSize, effect: ArtifactEffect) -> Artifact {
+        Artifact::Boost { size, effect }
+    }
+
+    #[test]
+    fn test_small_artifacts_work_only_in_the_holder() {
+        let small = boost(ArtifactSize::Small, ArtifactEffect::Trainer);
+        let large = boost(ArtifactSize::Large, ArtifactEffect::Trainer);
+
+        assert_eq!(small.multiplier(ArtifactEffect::Trainer, true), 0.5);
+        assert_eq!(small.multiplier(ArtifactEffect::Trainer, false), 1.0);
+        assert_eq!(large.multiplier(ArtifactEffect::Trainer, false), 0.75);
+        assert_eq!(small.multiplier(ArtifactEffect::Production, true), 1.0);
+        assert_eq!(
+            Artifact::ConstructionPlan.multiplier(ArtifactEffect::Production, true),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_same_effect_does_not_stack() {
+        let artifacts = vec![
+            boost(ArtifactSize::Large, ArtifactEffect::BuildingDurability),
+            boost(ArtifactSize::Unique, ArtifactEffect::BuildingDurability),
+            boost(ArtifactSize::Large, ArtifactEffect::Trainer),
+            boost(ArtifactSize::Unique, ArtifactEffect::Trainer),
+        ];
+
+        assert_eq!(
+            strongest_multiplier(&artifacts, ArtifactEffect::BuildingDurability, true),
+            5.0
+        );
+        assert_eq!(
+            strongest_multiplier(&artifacts, ArtifactEffect::Trainer, true),
+            0.5
+        );
+        assert_eq!(
+            strongest_multiplier(&artifacts, ArtifactEffect::Production, true),
+            1.0
+        );
+        assert_eq!(
+            strongest_multiplier(&[], ArtifactEffect::Trainer, true),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{buildings::BuildingName, ResourceGroup};
+
+// Changes made by admins to fix broken game states.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum AuditAction {
+    SetVillageResources {
+        from: ResourceGroup,
+        to: ResourceGroup,
+    },
+    SetBuildingLevel {
+        slot_id: u8,
+        building_name: BuildingName,
+        from: u8,
+        to: u8,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub village_id: u32,
+    pub action: AuditAction,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(admin_id: Uuid, village_id: u32, action: AuditAction) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            admin_id,
+            village_id,
+            action,
+            created_at: Utc::now(),
+        }
+    }
+}
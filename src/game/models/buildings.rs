@@ -1,8 +1,16 @@
 use anyhow::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{artifact::Artifact, scale_time, Cost, ResourceGroup, Tribe};
 
-use super::{Cost, ResourceGroup, Tribe};
+// Each Main Building level cuts construction times by this factor.
+pub const MAIN_BUILDING_TIME_FACTOR: f64 = 0.964;
+// The first Wonder of the World reaching this level wins the game.
+pub const WONDER_VICTORY_LEVEL: u8 = 100;
 
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub enum BuildingGroup {
@@ -11,7 +19,7 @@ pub enum BuildingGroup {
     Military,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub enum BuildingName {
     Woodcutter,
     ClayPit,
@@ -52,7 +60,6 @@ pub enum BuildingName {
     GreatWarehouse,
     GreatGranary,
     WonderOfTheWorld,
-    AncientConstructionPlan,
     HorseDrinkingTrough,
     GreatWorkshop,
 }
@@ -85,16 +92,7 @@ impl Building {
             return Err(Error::msg("error"));
         }
 
-        let level = self.level + 1;
-        let data = building.data[level as usize].clone();
-
-        Ok(Self {
-            name: self.name.clone(),
-            group: building.group.clone(),
-            culture_points: data.5.clone(),
-            level: level.clone(),
-            value: data.6.clone(),
-        })
+        self.at_level(self.level + 1)
     }
 
     pub fn at_level(&self, mut level: u8) -> Result<Self> {
@@ -107,10 +105,8 @@ impl Building {
 
         // check starting levels
         let mut lvl_idx = level;
-        if level > 0 {
-            if self.group != BuildingGroup::Resources {
-                lvl_idx -= 1;
-            }
+        if level > 0 && self.group != BuildingGroup::Resources {
+            lvl_idx -= 1;
         }
 
         let data = building.data[lvl_idx as usize].clone();
@@ -118,55 +114,62 @@ impl Building {
         Ok(Self {
             name: self.name.clone(),
             group: building.group.clone(),
-            culture_points: data.5.clone(),
-            level: level.clone(),
-            value: data.6.clone(),
+            culture_points: data.5,
+            level,
+            value: data.6,
         })
     }
 
+    // Returns the total population and culture points of the building, summing the
+    // increments of every level up to the current one.
+    pub fn get_cumulative_stats(&self) -> (u32, u32) {
+        let table = cumulative_stats_table(&self.name);
+
+        // levels beyond the static data get the stats of the last known level
+        table[(self.level as usize).min(table.len() - 1)]
+    }
+
     pub fn validate_build(
         &self,
         tribe: &Tribe,
         village_buildings: &HashMap<u8, Building>,
         is_capital: bool,
+        artifacts: &[Artifact],
     ) -> Result<()> {
         let data = get_building_data(self.name.clone()).unwrap();
 
         // tribe constraint (if any)?
         if !data.rules.tribes.is_empty() {
-            let ok = data.rules.tribes.contains(&tribe);
+            let ok = data.rules.tribes.contains(tribe);
             if !ok {
                 return Err(Error::msg("not compatible with village tribe"));
             }
         }
 
-        if is_capital
-            && data
-                .rules
-                .constraints
-                .contains(&BuildingConstraint::NonCapital)
-        {
-            return Err(Error::msg("can't build in capital"));
-        }
+        self.validate_capital(is_capital)?;
 
-        if !is_capital
-            && data
-                .rules
-                .constraints
-                .contains(&BuildingConstraint::OnlyCapital)
+        if data
+            .rules
+            .constraints
+            .contains(&BuildingConstraint::ConstructionPlan)
+            && !artifacts.contains(&Artifact::ConstructionPlan)
         {
-            return Err(Error::msg("can be built only in capital"));
+            return Err(Error::msg(
+                "a construction plan must be held by the village",
+            ));
         }
 
-        for (_, vb) in village_buildings {
-            // building requirements (if any) - Part 2
-
-            for req in data.rules.requirements {
-                if vb.name == req.0 && vb.level == req.1 {
-                    return Err(Error::msg("missing building requirements"));
-                }
+        // building requirements (if any)
+        for req in data.rules.requirements {
+            let met = village_buildings
+                .values()
+                .any(|vb| vb.name == req.0 && vb.level >= req.1);
+            if !met {
+                return Err(Error::msg("missing building requirements"));
             }
+        }
 
+        for vb in village_buildings.values() {
             for conflict in data.rules.conflicts {
                 if vb.name == conflict.0 {
                     return Err(Error::msg("conflicts with X"));
@@ -191,6 +194,38 @@ impl Building {
         Ok(())
     }
 
+    // Checks the building against the capital constraints, if any.
+    pub fn validate_capital(&self, is_capital: bool) -> Result<()> {
+        let data = get_building_data(self.name.clone()).unwrap();
+
+        if is_capital
+            && data
+                .rules
+                .constraints
+                .contains(&BuildingConstraint::NonCapital)
+        {
+            return Err(Error::msg("can't build in capital"));
+        }
+
+        if !is_capital
+            && data
+                .rules
+                .constraints
+                .contains(&BuildingConstraint::OnlyCapital)
+        {
+            return Err(Error::msg("can be built only in capital"));
+        }
+
+        Ok(())
+    }
+
+    pub fn max_level(&self) -> u8 {
+        get_building_data(self.name.clone())
+            .unwrap()
+            .rules
+            .max_level
+    }
+
     pub fn validate_upgrade(&self) -> Result<()> {
         let data = get_building_data(self.name.clone()).unwrap();
 
@@ -207,10 +242,8 @@ impl Building {
 
         let mut level = self.level;
 
-        if level > 0 {
-            if self.group != BuildingGroup::Resources {
-                level -= 1;
-            }
+        if level > 0 && self.group != BuildingGroup::Resources {
+            level -= 1;
         }
 
         let data = building.data[level as usize].clone();
@@ -218,7 +251,88 @@ impl Building {
         Cost {
             resources: ResourceGroup::new(data.0, data.1, data.2, data.3),
             upkeep: data.4,
-            build_time: data.6,
+            build_time: data.7,
+        }
+    }
+
+    // Returns the seconds needed to build the current level, reduced by the Main Building
+    // and scaled by the server speed.
+    pub fn calculate_build_time_secs(&self, main_building_level: u8, server_speed: u8) -> u32 {
+        let reduction =
+            MAIN_BUILDING_TIME_FACTOR.powi(main_building_level.saturating_sub(1) as i32);
+
+        scale_time(self.cost().build_time, reduction, server_speed)
+    }
+}
+
+// Cumulative (population, culture points) of each building indexed by level, computed
+// once per building on first use.
+type CumulativeStatsTable = Arc<Vec<(u32, u32)>>;
+
+static CUMULATIVE_STATS: Mutex<Option<HashMap<BuildingName, CumulativeStatsTable>>> =
+    Mutex::new(None);
+
+fn cumulative_stats_table(name: &BuildingName) -> CumulativeStatsTable {
+    let mut tables = CUMULATIVE_STATS.lock().unwrap();
+
+    tables
+        .get_or_insert_with(HashMap::new)
+        .entry(name.clone())
+        .or_insert_with(|| {
+            let building = get_building_data(name.clone()).unwrap();
+
+            // resource fields data starts at level 0, other buildings at level 1
+            let first_level_idx = match building.group {
+                BuildingGroup::Resources => 1,
+                _ => 0,
+            };
+
+            let mut table = vec![(0, 0)];
+            for data in building.data.iter().skip(first_level_idx) {
+                let (population, culture_points) = table[table.len() - 1];
+                table.push((population + data.4, culture_points + data.5 as u32));
+            }
+            Arc::new(table)
+        })
+        .clone()
+}
+
+// Returns the wall the given tribe can build, according to the tribes allowed by each
+// wall rules.
+pub fn tribe_wall(tribe: &Tribe) -> Option<BuildingName> {
+    [
+        BuildingName::CityWall,
+        BuildingName::EarthWall,
+        BuildingName::Palisade,
+    ]
+    .into_iter()
+    .find(|name| {
+        get_building_data(name.clone())
+            .unwrap()
+            .rules
+            .tribes
+            .contains(tribe)
+    })
+}
+
+// Returns every building needed to unlock the given one, including the requirements of
+// its requirements. Each building appears once, with the highest level needed, and
+// always after the buildings it depends on.
+pub fn requirement_chain(name: BuildingName) -> Vec<(BuildingName, u8)> {
+    let mut chain: Vec<(BuildingName, u8)> = vec![];
+    collect_requirements(name, &mut chain);
+    chain
+}
+
+fn collect_requirements(name: BuildingName, chain: &mut Vec<(BuildingName, u8)>) {
+    let building = get_building_data(name).unwrap();
+
+    for req in building.rules.requirements {
+        collect_requirements(req.0.clone(), chain);
+
+        match chain.iter_mut().find(|(n, _)| *n == req.0) {
+            Some((_, level)) => *level = (*level).max(req.1),
+            None => chain.push((req.0.clone(), req.1)),
         }
     }
 }
@@ -231,6 +345,8 @@ struct BuildingValueData(u32, u32, u32, u32, u32, u16, u32, u32);
 enum BuildingConstraint {
     OnlyCapital,
     NonCapital,
+    // needs a construction plan held by the village
+    ConstructionPlan,
 }
 
 #[derive(Debug, Clone)]
@@ -298,9 +414,7 @@ fn get_building_data(name: BuildingName) -> Result<BuildingData> {
         BuildingName::GreatGranary => Ok(GREAT_GRANARY.clone()),
         BuildingName::GreatWarehouse => Ok(GREAT_WAREHOUSE.clone()),
         BuildingName::HorseDrinkingTrough => Ok(HORSE_DRINKING_TROUGH.clone()),
-        BuildingName::WonderOfTheWorld => Ok(WONDER_OF_THW_WORLD.clone()),
-        // FIXME: artifacts and construction plans deserve another category
-        BuildingName::AncientConstructionPlan => Ok(WONDER_OF_THW_WORLD.clone()),
+        BuildingName::WonderOfTheWorld => Ok(WONDER_OF_THE_WORLD.clone()),
     }
 }
 
@@ -356,14 +470,14 @@ static CLAY_PIT: BuildingData = BuildingData {
         BuildingValueData(8080, 4040, 8080, 5050, 2, 6, 200, 37690),
         BuildingValueData(13500, 6750, 13500, 8435, 2, 7, 280, 60510),
         BuildingValueData(22540, 11270, 22540, 14090, 2, 9, 375, 97010),
-        BuildingValueData(37645, 18820, 37645, 23525, 2, 11, 11495, 155420),
-        BuildingValueData(62865, 31430, 62865, 39290, 2, 13, 13635, 248870),
-        BuildingValueData(104985, 52490, 104985, 65615, 2, 15, 15800, 398390),
-        BuildingValueData(175320, 87660, 175320, 109575, 3, 18, 181000, 637620),
-        BuildingValueData(292790, 146395, 292790, 182995, 3, 22, 221300, 1020390),
-        BuildingValueData(488955, 244480, 488955, 305600, 3, 27, 271600, 1632820),
-        BuildingValueData(816555, 408280, 816555, 510350, 3, 32, 322000, 2612710),
-        BuildingValueData(1363650, 681825, 1363650, 852280, 3, 38, 382450, 4180540),
+        BuildingValueData(37645, 18820, 37645, 23525, 2, 11, 495, 155420),
+        BuildingValueData(62865, 31430, 62865, 39290, 2, 13, 635, 248870),
+        BuildingValueData(104985, 52490, 104985, 65615, 2, 15, 800, 398390),
+        BuildingValueData(175320, 87660, 175320, 109575, 3, 18, 1000, 637620),
+        BuildingValueData(292790, 146395, 292790, 182995, 3, 22, 1300, 1020390),
+        BuildingValueData(488955, 244480, 488955, 305600, 3, 27, 1600, 1632820),
+        BuildingValueData(816555, 408280, 816555, 510350, 3, 32, 2000, 2612710),
+        BuildingValueData(1363650, 681825, 1363650, 852280, 3, 38, 2450, 4180540),
     ],
     group: BuildingGroup::Resources,
     rules: BuildingRules {
@@ -391,14 +505,14 @@ static IRON_MINE: BuildingData = BuildingData {
         BuildingValueData(10105, 8080, 3030, 6060, 2, 6, 200, 53500),
         BuildingValueData(16870, 13500, 5060, 10125, 3, 7, 280, 85800),
         BuildingValueData(28175, 22540, 8455, 16905, 3, 9, 375, 137470),
-        BuildingValueData(47055, 37645, 14115, 28230, 3, 11, 11495, 220160),
-        BuildingValueData(78580, 62865, 23575, 47150, 3, 13, 13635, 352450),
-        BuildingValueData(131230, 104985, 39370, 78740, 3, 15, 15800, 564120),
-        BuildingValueData(219155, 175320, 65745, 131490, 3, 18, 181000, 902760),
-        BuildingValueData(365985, 292790, 109795, 219590, 3, 22, 221300, 145546),
-        BuildingValueData(611195, 488955, 183360, 366715, 3, 27, 271600, 2311660),
-        BuildingValueData(1020695, 816555, 306210, 612420, 3, 32, 322000, 3698850),
-        BuildingValueData(1704565, 1363650, 511370, 1022740, 3, 38, 382450, 5918370),
+        BuildingValueData(47055, 37645, 14115, 28230, 3, 11, 495, 220160),
+        BuildingValueData(78580, 62865, 23575, 47150, 3, 13, 635, 352450),
+        BuildingValueData(131230, 104985, 39370, 78740, 3, 15, 800, 564120),
+        BuildingValueData(219155, 175320, 65745, 131490, 3, 18, 1000, 902760),
+        BuildingValueData(365985, 292790, 109795, 219590, 3, 22, 1300, 1444660),
+        BuildingValueData(611195, 488955, 183360, 366715, 3, 27, 1600, 2311660),
+        BuildingValueData(1020695, 816555, 306210, 612420, 3, 32, 2000, 3698850),
+        BuildingValueData(1704565, 1363650, 511370, 1022740, 3, 38, 2450, 5918370),
     ],
     group: BuildingGroup::Resources,
     rules: BuildingRules {
@@ -1635,7 +1749,7 @@ static GREAT_WORKSHOP: BuildingData = BuildingData {
     },
 };
 
-static WONDER_OF_THW_WORLD: BuildingData = BuildingData {
+static WONDER_OF_THE_WORLD: BuildingData = BuildingData {
     data: &[
         BuildingValueData(66700, 69050, 72200, 13200, 1, 0, 0, 18000),
         BuildingValueData(68535, 70950, 74185, 13565, 1, 0, 0, 18850),
@@ -1740,23 +1854,220 @@ static WONDER_OF_THW_WORLD: BuildingData = BuildingData {
     ],
     group: BuildingGroup::Infrastructure,
     rules: BuildingRules {
-        requirements: &[BuildingRequirement(
-            BuildingName::AncientConstructionPlan,
-            0,
-        )],
+        requirements: &[],
         conflicts: &[],
         tribes: &[],
         max_level: 100,
-        constraints: &[],
+        constraints: &[BuildingConstraint::ConstructionPlan],
         allow_multiple: false,
     },
 };
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use super::{get_building_data, requirement_chain, Building, BuildingGroup, BuildingName};
+    use crate::game::models::{artifact::Artifact, Tribe};
+
+    const ALL_BUILDINGS: [BuildingName; 41] = [
+        BuildingName::Woodcutter,
+        BuildingName::ClayPit,
+        BuildingName::IronMine,
+        BuildingName::Cropland,
+        BuildingName::Sawmill,
+        BuildingName::Brickyard,
+        BuildingName::IronFoundry,
+        BuildingName::GrainMill,
+        BuildingName::Bakery,
+        BuildingName::Warehouse,
+        BuildingName::Granary,
+        BuildingName::Smithy,
+        BuildingName::TournamentSquare,
+        BuildingName::MainBuilding,
+        BuildingName::RallyPoint,
+        BuildingName::Marketplace,
+        BuildingName::Embassy,
+        BuildingName::Barracks,
+        BuildingName::Stable,
+        BuildingName::Workshop,
+        BuildingName::Academy,
+        BuildingName::Cranny,
+        BuildingName::TownHall,
+        BuildingName::Residence,
+        BuildingName::Palace,
+        BuildingName::Treasury,
+        BuildingName::TradeOffice,
+        BuildingName::GreatBarracks,
+        BuildingName::GreatStable,
+        BuildingName::CityWall,
+        BuildingName::EarthWall,
+        BuildingName::Palisade,
+        BuildingName::StonemansionLodge,
+        BuildingName::Brewery,
+        BuildingName::Trapper,
+        BuildingName::HeroMansion,
+        BuildingName::GreatWarehouse,
+        BuildingName::GreatGranary,
+        BuildingName::WonderOfTheWorld,
+        BuildingName::HorseDrinkingTrough,
+        BuildingName::GreatWorkshop,
+    ];
+
+    // Sums the stats level by level, the way they were computed before the lookup table.
+    fn looped_cumulative_stats(name: BuildingName, level: u8) -> (u32, u32) {
+        let building = get_building_data(name).unwrap();
+        let first_level_idx = match building.group {
+            BuildingGroup::Resources => 1,
+            _ => 0,
+        };
+
+        building
+            .data
+            .iter()
+            .skip(first_level_idx)
+            .take(level as usize)
+            .fold((0, 0), |(population, culture_points), data| {
+                (population + data.4, culture_points + data.5 as u32)
+            })
+    }
+
     #[test]
     fn test_new_building() {
         // test level, upkeep and value at level 0, 1, and 2
         // resources will start at level 0 and upkeep 0, the others will have level 1 and relative upkeep
     }
+
+    #[test]
+    fn test_build_time_secs() {
+        let building = Building::new(BuildingName::Warehouse);
+        let base = building.cost().build_time;
+
+        assert_eq!(building.calculate_build_time_secs(1, 1), base);
+        assert_eq!(building.calculate_build_time_secs(1, 3), base / 3);
+        assert!(building.calculate_build_time_secs(20, 1) < base);
+
+        // zero speed never divides by zero, it falls back to speed 1
+        assert_eq!(building.calculate_build_time_secs(1, 0), base);
+        assert_eq!(building.calculate_build_time_secs(0, 0), base);
+    }
+
+    #[test]
+    fn test_resource_fields_grow_with_level() {
+        for name in [
+            BuildingName::Woodcutter,
+            BuildingName::ClayPit,
+            BuildingName::IronMine,
+            BuildingName::Cropland,
+        ] {
+            let field = Building::new(name.clone());
+            for level in 1..20 {
+                let (this, next) = (
+                    field.at_level(level).unwrap(),
+                    field.at_level(level + 1).unwrap(),
+                );
+                let (cost, next_cost) = (this.cost(), next.cost());
+
+                assert!(
+                    next_cost.build_time > cost.build_time,
+                    "{:?} build time at level {}",
+                    name,
+                    level + 1
+                );
+                assert!(
+                    next_cost.resources.total() > cost.resources.total()
+                        && next_cost.resources.covers(&cost.resources),
+                    "{:?} cost at level {}",
+                    name,
+                    level + 1
+                );
+                assert!(
+                    next.value > this.value,
+                    "{:?} production at level {}",
+                    name,
+                    level + 1
+                );
+                // every resource field produces the same amount at the same level
+                assert_eq!(
+                    next.value,
+                    Building::new(BuildingName::Woodcutter)
+                        .at_level(level + 1)
+                        .unwrap()
+                        .value,
+                    "{:?} production at level {}",
+                    name,
+                    level + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_requirement_chain_deep() {
+        assert_eq!(
+            requirement_chain(BuildingName::Workshop),
+            vec![
+                (BuildingName::MainBuilding, 5),
+                (BuildingName::RallyPoint, 1),
+                (BuildingName::Barracks, 3),
+                (BuildingName::Academy, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_requirement_chain_shallow() {
+        assert_eq!(
+            requirement_chain(BuildingName::Sawmill),
+            vec![
+                (BuildingName::Woodcutter, 10),
+                (BuildingName::MainBuilding, 5)
+            ]
+        );
+        assert!(requirement_chain(BuildingName::MainBuilding).is_empty());
+    }
+
+    #[test]
+    fn test_wonder_requires_construction_plan() {
+        let wonder = Building::new(BuildingName::WonderOfTheWorld);
+        let mut buildings: HashMap<u8, Building> = HashMap::new();
+        buildings.insert(19, Building::new(BuildingName::MainBuilding));
+
+        assert!(wonder
+            .validate_build(&Tribe::Roman, &buildings, false, &[])
+            .is_err());
+        assert!(wonder
+            .validate_build(
+                &Tribe::Roman,
+                &buildings,
+                false,
+                &[Artifact::ConstructionPlan]
+            )
+            .is_ok());
+
+        // the plan is an item, not a building to build first
+        assert!(requirement_chain(BuildingName::WonderOfTheWorld).is_empty());
+    }
+
+    #[test]
+    fn test_cumulative_stats_match_looped_values() {
+        for name in ALL_BUILDINGS {
+            let building = Building::new(name.clone());
+            let max_level = get_building_data(name.clone()).unwrap().rules.max_level;
+
+            for level in 0..=max_level {
+                let b = Building {
+                    level,
+                    ..building.clone()
+                };
+                assert_eq!(
+                    b.get_cumulative_stats(),
+                    looped_cumulative_stats(name.clone(), level),
+                    "{:?} at level {}",
+                    name,
+                    level
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,60 @@
+use super::{scale_time, ResourceGroup};
+
+// Resources needed to hold a small or a big celebration in the Town Hall.
+pub const SMALL_CELEBRATION_COST: ResourceGroup = ResourceGroup::new(6400, 6650, 5940, 1340);
+pub const BIG_CELEBRATION_COST: ResourceGroup = ResourceGroup::new(29700, 33250, 32000, 6700);
+// Share of the cost given back when a celebration is cancelled before it starts.
+pub const CANCELLED_CELEBRATION_REFUND_PERCENT: u32 = 75;
+// Culture points given to the village owner when a Town Hall celebration ends.
+pub const SMALL_CELEBRATION_CULTURE_POINTS: u32 = 500;
+pub const BIG_CELEBRATION_CULTURE_POINTS: u32 = 2000;
+// Town Hall level needed to hold big celebrations.
+pub const BIG_CELEBRATION_TOWN_HALL_LEVEL: u8 = 10;
+// Duration of a celebration with a level 1 Town Hall, higher levels make it shorter.
+pub const SMALL_CELEBRATION_TIME_SECS: u32 = 86_400;
+pub const BIG_CELEBRATION_TIME_SECS: u32 = 216_000;
+// Duration of a Brewery celebration, whatever the Brewery level.
+pub const BREWERY_CELEBRATION_TIME_SECS: u32 = 259_200;
+// Attack bonus, in percent, given by each Brewery level while celebrating.
+pub const BREWERY_ATTACK_BONUS_PER_LEVEL: u32 = 1;
+// Speed lost, in percent, by troops leaving the village while celebrating.
+pub const BREWERY_SPEED_PENALTY_PERCENT: u32 = 20;
+
+// Returns the cost of a Town Hall celebration.
+// TODO: Brewery celebrations have no costs of their own yet, they cost as small ones.
+pub fn celebration_cost(big: bool) -> ResourceGroup {
+    match big {
+        true => BIG_CELEBRATION_COST,
+        false => SMALL_CELEBRATION_COST,
+    }
+}
+
+// Returns the resources refunded for a celebration cancelled before it starts.
+pub fn celebration_refund(cost: &ResourceGroup) -> ResourceGroup {
+    let refund = |amount: u32| amount * CANCELLED_CELEBRATION_REFUND_PERCENT / 100;
+    ResourceGroup::new(
+        refund(cost.lumber()),
+        refund(cost.clay()),
+        refund(cost.iron()),
+        refund(cost.crop()),
+    )
+}
+
+// Returns the culture points awarded by a Town Hall celebration.
+pub fn celebration_culture_points(big: bool) -> u32 {
+    match big {
+        true => BIG_CELEBRATION_CULTURE_POINTS,
+        false => SMALL_CELEBRATION_CULTURE_POINTS,
+    }
+}
+
+// Returns how long a Town Hall celebration lasts, each level past the first one
+// shortens it by 3.6%.
+pub fn celebration_time_secs(big: bool, town_hall_level: u8, speed: u8) -> u32 {
+    let base = match big {
+        true => BIG_CELEBRATION_TIME_SECS,
+        false => SMALL_CELEBRATION_TIME_SECS,
+    };
+    let factor = 0.964f64.powi(town_hall_level.max(1) as i32 - 1);
+    scale_time(base, factor, speed)
+}
@@ -0,0 +1,120 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{army::TroopSet, map::Position};
+
+// Most targets a single farm list can hold.
+pub const MAX_FARM_LIST_TARGETS: usize = 100;
+
+// Targets raided all together from a village, each one with the same troops.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FarmList {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub name: String,
+    pub units: TroopSet,
+    pub targets: Vec<Position>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FarmList {
+    pub fn new(player_id: Uuid, village_id: u32, name: String, units: TroopSet) -> Result<Self> {
+        let mut list = Self {
+            id: Uuid::new_v4(),
+            player_id,
+            village_id,
+            name: String::new(),
+            units: [0; 10],
+            targets: vec![],
+            created_at: Utc::now(),
+        };
+        list.update(name, units)?;
+
+        Ok(list)
+    }
+
+    pub fn update(&mut self, name: String, units: TroopSet) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::msg("Farm lists need a name"));
+        }
+        if units.iter().all(|q| *q == 0) {
+            return Err(Error::msg("Farm lists need at least one unit to send"));
+        }
+
+        self.name = name.to_string();
+        self.units = units;
+        Ok(())
+    }
+
+    pub fn add_target(&mut self, position: Position) -> Result<()> {
+        if self.targets.contains(&position) {
+            return Err(Error::msg("This target is already in the farm list"));
+        }
+        if self.targets.len() >= MAX_FARM_LIST_TARGETS {
+            return Err(Error::msg("The farm list is full"));
+        }
+
+        self.targets.push(position);
+        Ok(())
+    }
+
+    pub fn remove_target(&mut self, position: &Position) -> Result<()> {
+        let len = self.targets.len();
+        self.targets.retain(|t| t != position);
+        if self.targets.len() == len {
+            return Err(Error::msg("This target is not in the farm list"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{FarmList, MAX_FARM_LIST_TARGETS};
+    use crate::game::models::map::Position;
+
+    fn farm_list() -> FarmList {
+        FarmList::new(
+            Uuid::new_v4(),
+            1,
+            "Farms".to_string(),
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_new_farm_list() {
+        let list = farm_list();
+        assert_eq!(list.name, "Farms");
+        assert!(list.targets.is_empty());
+
+        assert!(FarmList::new(Uuid::new_v4(), 1, " ".to_string(), [1; 10]).is_err());
+        assert!(FarmList::new(Uuid::new_v4(), 1, "Farms".to_string(), [0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_farm_list_targets() {
+        let mut list = farm_list();
+        let target = Position { x: 1, y: 2 };
+
+        list.add_target(target.clone()).unwrap();
+        assert!(list.add_target(target.clone()).is_err(), "no duplicates");
+
+        list.remove_target(&target).unwrap();
+        assert!(list.targets.is_empty());
+        assert!(list.remove_target(&target).is_err());
+
+        for x in 0..MAX_FARM_LIST_TARGETS as i32 {
+            list.add_target(Position { x, y: 0 }).unwrap();
+        }
+        assert!(list.add_target(Position { x: -1, y: 0 }).is_err());
+    }
+}
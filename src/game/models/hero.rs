@@ -0,0 +1,496 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ResourceGroup;
+
+// Speed of a hero without any equipment, in fields per hour.
+pub const HERO_BASE_SPEED: u8 = 7;
+// Health points regenerated daily by a hero without any bonus.
+pub const HERO_BASE_REGENERATION: u8 = 10;
+// Attribute points earned on each level up.
+pub const HERO_POINTS_PER_LEVEL: u32 = 5;
+pub const HERO_MAX_LEVEL: u8 = 100;
+// Fighting strength added by each point spent on strength.
+pub const HERO_STRENGTH_PER_POINT: u32 = 80;
+// Attack or defense bonus, in percent, given to the hero's army by each point.
+pub const HERO_BONUS_PER_POINT: f64 = 0.2;
+// Hourly production of each resource added by each point spent on production.
+pub const HERO_PRODUCTION_PER_POINT: u32 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HeroStatus {
+    Home,
+    Away,
+    Dead,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Hero {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub status: HeroStatus,
+    pub health: u8,
+    pub experience: u32,
+    pub strength: u32,
+    pub inventory: Vec<HeroItem>,
+    pub equipment: Vec<HeroItem>,
+    pub regenerated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub attributes: HeroAttributes,
+}
+
+// How the hero's attribute points are spent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HeroAttributes {
+    pub strength: u32,
+    pub off_bonus: u32,
+    pub def_bonus: u32,
+    pub production: u32,
+}
+
+impl HeroAttributes {
+    pub fn total(&self) -> u32 {
+        self.strength + self.off_bonus + self.def_bonus + self.production
+    }
+}
+
+// Returns the total experience needed to reach the given level: 50 for level 1,
+// 150 for level 2, 300 for level 3 and so on.
+pub fn experience_for_level(level: u8) -> u32 {
+    25 * level as u32 * (level as u32 + 1)
+}
+
+impl Hero {
+    pub fn new(player_id: Uuid, village_id: u32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            player_id,
+            village_id,
+            status: HeroStatus::Home,
+            health: 100,
+            experience: 0,
+            strength: 100,
+            inventory: vec![],
+            equipment: vec![],
+            regenerated_at: Utc::now(),
+            attributes: HeroAttributes::default(),
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        (1..=HERO_MAX_LEVEL)
+            .take_while(|l| self.experience >= experience_for_level(*l))
+            .last()
+            .unwrap_or(0)
+    }
+
+    // Adds experience to a living hero and returns the levels gained.
+    pub fn add_experience(&mut self, experience: u32) -> u8 {
+        if self.status == HeroStatus::Dead {
+            return 0;
+        }
+        let level = self.level();
+        self.experience += experience;
+        self.level() - level
+    }
+
+    // Returns the attribute points earned so far, spent or not.
+    pub fn attribute_points(&self) -> u32 {
+        self.level() as u32 * HERO_POINTS_PER_LEVEL
+    }
+
+    pub fn unspent_points(&self) -> u32 {
+        self.attribute_points()
+            .saturating_sub(self.attributes.total())
+    }
+
+    // Replaces how the attribute points are spent, as long as they've been earned.
+    pub fn reallocate_points(&mut self, attributes: HeroAttributes) -> Result<()> {
+        if attributes.total() > self.attribute_points() {
+            return Err(Error::msg("The hero doesn't have enough attribute points"));
+        }
+        self.attributes = attributes;
+
+        Ok(())
+    }
+
+    pub fn fighting_strength(&self) -> u32 {
+        self.strength + self.attributes.strength * HERO_STRENGTH_PER_POINT
+    }
+
+    // Returns the multiplier applied to the attack of the troops the hero goes with.
+    pub fn off_bonus(&self) -> f64 {
+        1.0 + self.attributes.off_bonus as f64 * HERO_BONUS_PER_POINT / 100.0
+    }
+
+    // Returns the multiplier applied to the defense of the troops the hero stays with.
+    pub fn def_bonus(&self) -> f64 {
+        1.0 + self.attributes.def_bonus as f64 * HERO_BONUS_PER_POINT / 100.0
+    }
+
+    // Returns the hourly resources produced in the hero's village.
+    pub fn production(&self) -> ResourceGroup {
+        let amount = self.attributes.production * HERO_PRODUCTION_PER_POINT;
+        ResourceGroup::new(amount, amount, amount, amount)
+    }
+
+    // Returns the health points regenerated daily, boosted by the Hero Mansion level and items.
+    pub fn daily_regeneration(&self, mansion_level: u8) -> u32 {
+        HERO_BASE_REGENERATION as u32 + mansion_level as u32 + self.items_regeneration()
+    }
+
+    // Regenerates health for the time passed since the last regeneration, up to 100.
+    // Heroes only heal while alive and at home. Returns the health points gained.
+    pub fn regenerate(&mut self, now: DateTime<Utc>, mansion_level: u8) -> u8 {
+        if self.status != HeroStatus::Home || self.health == 0 || self.health >= 100 {
+            self.regenerated_at = now;
+            return 0;
+        }
+
+        let daily = self.daily_regeneration(mansion_level) as i64;
+        let elapsed = (now - self.regenerated_at).num_seconds().max(0);
+        let gained = (daily * elapsed / 86_400).min((100 - self.health) as i64);
+
+        if gained == 0 {
+            return 0;
+        }
+
+        self.health += gained as u8;
+        if self.health == 100 {
+            self.regenerated_at = now;
+        } else {
+            // keep the leftover time for the next partial health point
+            self.regenerated_at += Duration::seconds(gained * 86_400 / daily);
+        }
+
+        gained as u8
+    }
+
+    // Moves an item from the inventory to its equipment slot. An item already
+    // equipped in the same slot goes back to the inventory.
+    pub fn equip(&mut self, item_id: Uuid) -> Result<()> {
+        let idx = match self.inventory.iter().position(|i| i.id == item_id) {
+            Some(idx) => idx,
+            None => return Err(Error::msg("Item not found in the hero inventory")),
+        };
+        let item = self.inventory.remove(idx);
+
+        if let Some(equipped) = self.unequip_slot(&item.slot) {
+            self.inventory.push(equipped);
+        }
+        self.equipment.push(item);
+
+        Ok(())
+    }
+
+    // Moves the item equipped in the given slot back to the inventory.
+    pub fn unequip(&mut self, slot: ItemSlot) -> Result<()> {
+        match self.unequip_slot(&slot) {
+            Some(item) => {
+                self.inventory.push(item);
+                Ok(())
+            }
+            None => Err(Error::msg("No item equipped in this slot")),
+        }
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        self.equipment.iter().any(|i| i.slot == ItemSlot::Horse)
+    }
+
+    pub fn attack_points(&self) -> u32 {
+        self.fighting_strength() + self.equipment.iter().map(|i| i.bonus.attack).sum::<u32>()
+    }
+
+    pub fn defense_points(&self) -> u32 {
+        self.fighting_strength() + self.equipment.iter().map(|i| i.bonus.defense).sum::<u32>()
+    }
+
+    pub fn speed(&self) -> u8 {
+        HERO_BASE_SPEED + self.equipment.iter().map(|i| i.bonus.speed).sum::<u8>()
+    }
+
+    // Returns the health points regenerated daily thanks to the equipment.
+    pub fn items_regeneration(&self) -> u32 {
+        self.equipment
+            .iter()
+            .map(|i| i.bonus.regeneration as u32)
+            .sum()
+    }
+
+    fn unequip_slot(&mut self, slot: &ItemSlot) -> Option<HeroItem> {
+        let idx = self.equipment.iter().position(|i| &i.slot == slot)?;
+        Some(self.equipment.remove(idx))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ItemSlot {
+    Helmet,
+    Body,
+    LeftHand,
+    RightHand,
+    Shoes,
+    Horse,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ItemBonus {
+    pub attack: u32,
+    pub defense: u32,
+    pub speed: u8,
+    pub regeneration: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HeroItem {
+    pub id: Uuid,
+    pub name: String,
+    pub slot: ItemSlot,
+    pub bonus: ItemBonus,
+}
+
+impl HeroItem {
+    pub fn new(name: String, slot: ItemSlot, bonus: ItemBonus) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            slot,
+            bonus,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::{
+        experience_for_level, Hero, HeroAttributes, HeroItem, HeroStatus, ItemBonus, ItemSlot,
+        HERO_BASE_SPEED,
+    };
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            map::{travel_time_secs, Position, TravelSettings},
+            ResourceGroup, Tribe,
+        },
+    };
+
+    fn horse(speed: u8) -> HeroItem {
+        HeroItem::new(
+            "Horse".to_string(),
+            ItemSlot::Horse,
+            ItemBonus {
+                speed,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_equip_speed_item_affects_travel_time() {
+        let position = Position { x: 0, y: 0 };
+        let player = test_player(Tribe::Gaul);
+        let village = test_village(&player, position.clone());
+        let target = Position { x: 60, y: 80 };
+
+        let mut hero = Hero::new(player.id, village.id);
+        let item = horse(7);
+        hero.inventory.push(item.clone());
+
+        let on_foot = travel_time_secs(
+            &village.position,
+            &target,
+            hero.speed(),
+            TravelSettings::default(),
+        );
+
+        hero.equip(item.id).unwrap();
+        assert_eq!(hero.speed(), HERO_BASE_SPEED + 7);
+        assert!(hero.is_mounted());
+        assert!(hero.inventory.is_empty());
+        let mounted = travel_time_secs(
+            &village.position,
+            &target,
+            hero.speed(),
+            TravelSettings::default(),
+        );
+        assert!(mounted < on_foot, "mounted hero is faster");
+
+        hero.unequip(ItemSlot::Horse).unwrap();
+        assert_eq!(hero.speed(), HERO_BASE_SPEED);
+        assert_eq!(hero.inventory.len(), 1);
+        assert_eq!(
+            travel_time_secs(
+                &village.position,
+                &target,
+                hero.speed(),
+                TravelSettings::default()
+            ),
+            on_foot
+        );
+    }
+
+    #[test]
+    fn test_equip_swaps_items_in_same_slot() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        let slow = horse(3);
+        let fast = horse(10);
+        hero.inventory.push(slow.clone());
+        hero.inventory.push(fast.clone());
+
+        hero.equip(slow.id).unwrap();
+        hero.equip(fast.id).unwrap();
+
+        assert_eq!(hero.equipment, vec![fast]);
+        assert_eq!(hero.inventory, vec![slow]);
+        assert!(hero.equip(Uuid::new_v4()).is_err());
+        assert!(hero.unequip(ItemSlot::Helmet).is_err());
+    }
+
+    #[test]
+    fn test_items_affect_combat_points() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        let sword = HeroItem::new(
+            "Short sword".to_string(),
+            ItemSlot::RightHand,
+            ItemBonus {
+                attack: 500,
+                ..Default::default()
+            },
+        );
+        let armor = HeroItem::new(
+            "Light armor".to_string(),
+            ItemSlot::Body,
+            ItemBonus {
+                defense: 300,
+                regeneration: 10,
+                ..Default::default()
+            },
+        );
+        hero.inventory.push(sword.clone());
+        hero.inventory.push(armor.clone());
+        hero.equip(sword.id).unwrap();
+        hero.equip(armor.id).unwrap();
+
+        assert_eq!(hero.attack_points(), 600);
+        assert_eq!(hero.defense_points(), 400);
+        assert_eq!(hero.items_regeneration(), 10);
+    }
+
+    #[test]
+    fn test_regeneration_over_time() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.health = 50;
+        let start = hero.regenerated_at;
+
+        // base regeneration: 10 health points per day
+        assert_eq!(hero.regenerate(start + Duration::hours(12), 0), 5);
+        assert_eq!(hero.health, 55);
+
+        // partial points are kept for the next tick
+        assert_eq!(hero.regenerate(start + Duration::hours(13), 0), 0);
+        assert_eq!(hero.regenerate(start + Duration::hours(15), 0), 1);
+        assert_eq!(hero.health, 56);
+
+        // Hero Mansion level 10 doubles it
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.health = 50;
+        let start = hero.regenerated_at;
+        assert_eq!(hero.regenerate(start + Duration::hours(12), 10), 10);
+        assert_eq!(hero.health, 60);
+    }
+
+    #[test]
+    fn test_regeneration_is_capped() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.health = 95;
+        let start = hero.regenerated_at;
+
+        assert_eq!(hero.regenerate(start + Duration::days(3), 0), 5);
+        assert_eq!(hero.health, 100);
+        assert_eq!(hero.regenerate(start + Duration::days(6), 0), 0);
+        assert_eq!(hero.health, 100);
+    }
+
+    #[test]
+    fn test_no_regeneration_while_away_or_dead() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.health = 50;
+        hero.status = HeroStatus::Away;
+        let start = hero.regenerated_at;
+
+        assert_eq!(hero.regenerate(start + Duration::days(1), 0), 0);
+        assert_eq!(hero.health, 50);
+
+        // time spent away doesn't count once back home
+        hero.status = HeroStatus::Home;
+        assert_eq!(hero.regenerate(start + Duration::days(1), 0), 0);
+        assert_eq!(hero.regenerate(start + Duration::days(2), 0), 10);
+
+        hero.health = 0;
+        hero.status = HeroStatus::Dead;
+        assert_eq!(hero.regenerate(start + Duration::days(5), 0), 0);
+        assert_eq!(hero.health, 0);
+    }
+
+    #[test]
+    fn test_level_up_thresholds() {
+        assert_eq!(experience_for_level(1), 50);
+        assert_eq!(experience_for_level(2), 150);
+        assert_eq!(experience_for_level(3), 300);
+
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        assert_eq!(hero.level(), 0);
+        assert_eq!(hero.add_experience(49), 0);
+        assert_eq!(hero.add_experience(1), 1);
+        assert_eq!(hero.level(), 1);
+        assert_eq!(hero.add_experience(250), 2, "levels can be skipped");
+        assert_eq!(hero.level(), 3);
+        assert_eq!(hero.attribute_points(), 15);
+
+        hero.status = HeroStatus::Dead;
+        assert_eq!(hero.add_experience(1000), 0);
+        assert_eq!(hero.experience, 300);
+    }
+
+    #[test]
+    fn test_reallocate_attribute_points() {
+        let mut hero = Hero::new(Uuid::new_v4(), 1);
+        hero.add_experience(150);
+
+        let attributes = HeroAttributes {
+            strength: 5,
+            off_bonus: 2,
+            def_bonus: 1,
+            production: 2,
+        };
+        hero.reallocate_points(attributes.clone()).unwrap();
+        assert_eq!(hero.unspent_points(), 0);
+        assert_eq!(hero.attack_points(), 100 + 5 * 80);
+        assert_eq!(hero.defense_points(), 100 + 5 * 80);
+        assert!((hero.off_bonus() - 1.004).abs() < 1e-9);
+        assert!((hero.def_bonus() - 1.002).abs() < 1e-9);
+        assert_eq!(hero.production(), ResourceGroup::new(12, 12, 12, 12));
+
+        let too_many = HeroAttributes {
+            strength: 11,
+            ..Default::default()
+        };
+        assert!(hero.reallocate_points(too_many).is_err());
+        assert_eq!(hero.attributes, attributes);
+
+        // points can be moved around freely
+        let all_in = HeroAttributes {
+            production: 10,
+            ..Default::default()
+        };
+        hero.reallocate_points(all_in).unwrap();
+        assert_eq!(hero.attack_points(), 100);
+    }
+}
@@ -1,8 +1,14 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::village::ProductionBonus;
+use super::{
+    army::{Army, TroopSet},
+    scale_time,
+    village::ProductionBonus,
+    Tribe,
+};
+use crate::game::error::GameError;
 
 // FIXME: use config
 pub const WORLD_MAX_SIZE: i32 = 100;
@@ -70,8 +76,46 @@ impl Position {
         ((world_size - self.y) * (world_size * 2 + 1) + (world_size + self.x + 1)) as u32
     }
 
+    // Wraps coordinates crossing the map edges around to the other side. Coordinates
+    // farther than a whole map lap can't come from the map itself, so they're rejected.
+    pub fn normalize(&self, world_size: i32) -> Result<Position, GameError> {
+        let side = 2 * world_size + 1;
+        let wrap = |c: i32| match c {
+            c if (-world_size..=world_size).contains(&c) => Some(c),
+            c if c > world_size && c - side <= world_size => Some(c - side),
+            c if c < -world_size && c + side >= -world_size => Some(c + side),
+            _ => None,
+        };
+
+        match (wrap(self.x), wrap(self.y)) {
+            (Some(x), Some(y)) => Ok(Position { x, y }),
+            _ => Err(GameError::TargetOutOfRange {
+                x: self.x,
+                y: self.y,
+            }),
+        }
+    }
+
     // Returns the distance between two points.
     pub fn distance(&self, position: &Position, world_size: i32) -> u32 {
+        self.exact_distance(position, world_size) as u32
+    }
+
+    // Returns how many fields apart two points are on the grid, counting diagonal steps
+    // as one and crossing the map edges when it's shorter.
+    pub fn grid_distance(&self, position: &Position, world_size: i32) -> u32 {
+        let side = 2 * world_size + 1;
+        let wrap = |diff: i32| match diff.abs() {
+            d if d > world_size => side - d,
+            d => d,
+        };
+
+        wrap(self.x - position.x).max(wrap(self.y - position.y)) as u32
+    }
+
+    // Returns the straight line distance between two points, crossing the map edges
+    // when it's shorter.
+    pub fn exact_distance(&self, position: &Position, world_size: i32) -> f64 {
         let mut x_diff = (self.x - position.x).abs();
         let mut y_diff = (self.y - position.y).abs();
 
@@ -83,10 +127,39 @@ impl Position {
             y_diff = (2 * world_size + 1) - y_diff;
         }
 
-        (((x_diff * x_diff) + (y_diff * y_diff)) as f64).sqrt() as u32
+        (((x_diff * x_diff) + (y_diff * y_diff)) as f64).sqrt()
     }
 }
 
+// Map extent and server speed travel times depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TravelSettings {
+    pub world_size: i32,
+    pub server_speed: u8,
+}
+
+impl Default for TravelSettings {
+    fn default() -> Self {
+        Self {
+            world_size: WORLD_MAX_SIZE,
+            server_speed: 1,
+        }
+    }
+}
+
+// Returns the seconds an army takes to go from a position to another, at the pace of its
+// slowest unit. Speed is in fields per hour.
+pub fn travel_time_secs(
+    from: &Position,
+    to: &Position,
+    slowest_unit_speed: u8,
+    settings: TravelSettings,
+) -> u32 {
+    let hours = from.exact_distance(to, settings.world_size) / slowest_unit_speed.max(1) as f64;
+    // rounding down before and after dividing by the speed gives the same seconds
+    scale_time((hours * 3600.0) as u32, 1.0, settings.server_speed)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum OasisTopology {
     Lumber,
@@ -106,9 +179,22 @@ pub struct Oasis {
     pub village_id: Option<u32>,
     pub position: Position,
     pub topology: OasisTopology,
+    // Wild animals guarding the oasis, as Nature units.
+    #[serde(default)]
+    pub animals: TroopSet,
 }
 
 impl Oasis {
+    // Returns the animals as an army, to fight them in battles.
+    pub fn animals_army(&self) -> Army {
+        Army::new(self.id, Uuid::nil(), Tribe::Nature, self.animals, [0; 10])
+    }
+
+    // An oasis can be annexed only once all its animals have been killed.
+    pub fn is_cleared(&self) -> bool {
+        self.animals.iter().all(|&a| a == 0)
+    }
+
     pub fn bonus(&self) -> ProductionBonus {
         let mut lumber: u8 = 0;
         let mut clay: u8 = 0;
@@ -155,6 +241,7 @@ impl TryFrom<MapField> for Oasis {
                 village_id: value.village_id,
                 position: value.position,
                 topology,
+                animals: [0; 10],
             }),
             _ => Err("This map field is not an Oasis"),
         }
@@ -210,7 +297,7 @@ pub fn generate_new_map(world_size: i32) -> Vec<MapField> {
             let position = Position { x, y };
             let id = position.to_id(world_size);
 
-            if (x == y && x == 0) || (x == y && x == world_size) || (x == y && x == -world_size) {
+            if x == y && (x == 0 || x == world_size || x == -world_size) {
                 map.push(MapField {
                     player_id: None,
                     village_id: None,
@@ -375,12 +462,55 @@ pub fn generate_new_map(world_size: i32) -> Vec<MapField> {
     map
 }
 
+// Returns a random garrison of wild animals for a new oasis. Each kind of oasis is
+// guarded by its own animals, crop ones by the bigger beasts.
+pub fn oasis_animals<R: Rng>(topology: &OasisTopology, rng: &mut R) -> TroopSet {
+    // (Nature unit index, maximum amount)
+    let kinds: &[(usize, u32)] = match topology {
+        OasisTopology::Lumber => &[(0, 20), (1, 15), (4, 10), (5, 5)],
+        OasisTopology::Clay => &[(0, 20), (1, 15), (2, 10), (3, 5)],
+        OasisTopology::Iron => &[(0, 20), (1, 15), (3, 10), (4, 5)],
+        OasisTopology::Crop => &[(0, 20), (2, 15), (3, 10), (5, 5)],
+        OasisTopology::LumberCrop => &[(0, 25), (1, 20), (4, 15), (5, 10), (6, 3)],
+        OasisTopology::ClayCrop => &[(0, 25), (1, 20), (2, 15), (3, 10), (6, 3)],
+        OasisTopology::IronCrop => &[(0, 25), (1, 20), (3, 15), (4, 10), (6, 3)],
+        OasisTopology::Crop50 => &[(0, 30), (2, 20), (6, 10), (7, 10), (8, 5), (9, 3)],
+    };
+
+    let mut animals = [0; 10];
+    for &(idx, max) in kinds {
+        animals[idx] = rng.gen_range(1..=max);
+    }
+    animals
+}
+
+// Orders valleys by a score derived from the seed and their position. The same seed
+// always gives the same order, no matter how candidates are sorted, and removing a
+// valley doesn't change the order of the others.
+pub fn rank_valleys(mut valleys: Vec<Valley>, seed: u64) -> Vec<Valley> {
+    valleys.sort_by_cached_key(|v| {
+        let score: u64 = StdRng::seed_from_u64(seed ^ v.id as u64).gen();
+        (score, v.position.x, v.position.y)
+    });
+    valleys
+}
+
+// Picks a valley among the candidates, deterministically for a given seed.
+pub fn select_valley(valleys: Vec<Valley>, seed: u64) -> Option<Valley> {
+    rank_valleys(valleys, seed).into_iter().next()
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
-    use super::{generate_new_map, MapFieldTopology, OasisTopology, ValleyTopology};
-    use crate::game::models::map::Position;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{
+        generate_new_map, oasis_animals, rank_valleys, select_valley, travel_time_secs,
+        MapFieldTopology, OasisTopology, TravelSettings, Valley, ValleyTopology,
+    };
+    use crate::game::{error::GameError, models::map::Position};
 
     #[test]
     fn test_position_id() {
@@ -388,6 +518,22 @@ mod tests {
         assert_eq!(p.to_id(100), 14587);
     }
 
+    #[test]
+    fn test_position_normalize() {
+        let world_size = 100;
+
+        let p = Position { x: 14, y: -28 };
+        assert_eq!(p.normalize(world_size), Ok(p.clone()));
+        assert_eq!(
+            Position { x: 101, y: -102 }.normalize(world_size),
+            Ok(Position { x: -100, y: 99 })
+        );
+        assert_eq!(
+            Position { x: 1000, y: 0 }.normalize(world_size),
+            Err(GameError::TargetOutOfRange { x: 1000, y: 0 })
+        );
+    }
+
     #[test]
     fn test_position_distance() {
         let world_size = 200;
@@ -400,6 +546,89 @@ mod tests {
         assert_eq!(p.distance(&Position { x: 200, y: 200 }, world_size), 268);
     }
 
+    #[test]
+    fn test_position_grid_distance() {
+        let world_size = 200;
+        let p = Position { x: 10, y: 10 };
+
+        assert_eq!(p.grid_distance(&Position { x: 13, y: 7 }, world_size), 3);
+        assert_eq!(p.grid_distance(&Position { x: 10, y: 14 }, world_size), 4);
+        // across the map edges
+        let edge = Position { x: 200, y: 0 };
+        assert_eq!(
+            edge.grid_distance(&Position { x: -199, y: 2 }, world_size),
+            2
+        );
+    }
+
+    #[test]
+    fn test_travel_time_straight_line() {
+        let settings = TravelSettings::default();
+        let from = Position { x: 0, y: 0 };
+
+        // 10 fields at 6 fields per hour
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: 0, y: 10 }, 6, settings),
+            6000
+        );
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: -10, y: 0 }, 6, settings),
+            6000
+        );
+
+        let fast = TravelSettings {
+            server_speed: 3,
+            ..settings
+        };
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: 0, y: 10 }, 6, fast),
+            2000
+        );
+    }
+
+    #[test]
+    fn test_travel_time_diagonal() {
+        let settings = TravelSettings::default();
+        let from = Position { x: 0, y: 0 };
+
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: 3, y: 4 }, 6, settings),
+            3000
+        );
+        // the distance isn't rounded to whole fields
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: 1, y: 1 }, 6, settings),
+            848
+        );
+    }
+
+    #[test]
+    fn test_travel_time_across_map_edges() {
+        let settings = TravelSettings {
+            world_size: 50,
+            server_speed: 1,
+        };
+
+        // opposite edges are next to each other
+        let from = Position { x: -50, y: 0 };
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: 50, y: 0 }, 6, settings),
+            600
+        );
+        let from = Position { x: 48, y: -49 };
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: -50, y: 49 }, 6, settings),
+            2545
+        );
+
+        // the same positions are far apart on a larger map
+        let larger = TravelSettings::default();
+        assert_eq!(
+            travel_time_secs(&from, &Position { x: -50, y: 49 }, 6, larger),
+            83155
+        );
+    }
+
     #[test]
     fn test_generate_new_map() {
         let world_size = 100;
@@ -443,4 +672,63 @@ mod tests {
         }
         println!("Total: {}", valleys.values().sum::<u32>());
     }
+
+    fn valleys() -> Vec<Valley> {
+        let mut valleys = vec![];
+        for x in -3..3 {
+            for y in -3..3 {
+                let position = Position { x, y };
+                valleys.push(Valley {
+                    id: position.to_id(100),
+                    position,
+                    topology: ValleyTopology(4, 4, 4, 6),
+                    player_id: None,
+                    village_id: None,
+                });
+            }
+        }
+        valleys
+    }
+
+    #[test]
+    fn test_select_valley_with_seed() {
+        let first = select_valley(valleys(), 42).unwrap();
+        let again = select_valley(valleys(), 42).unwrap();
+        assert_eq!(first.position, again.position);
+
+        // candidates order doesn't matter
+        let mut reversed = valleys();
+        reversed.reverse();
+        assert_eq!(
+            select_valley(reversed, 42).unwrap().position,
+            first.position
+        );
+
+        assert!(select_valley(vec![], 42).is_none());
+    }
+
+    #[test]
+    fn test_select_valley_after_taken_one() {
+        let ranked = rank_valleys(valleys(), 7);
+
+        let remaining: Vec<Valley> = valleys()
+            .into_iter()
+            .filter(|v| v.position != ranked[0].position)
+            .collect();
+        let next = select_valley(remaining, 7).unwrap();
+
+        assert_eq!(next.position, ranked[1].position);
+    }
+
+    #[test]
+    fn test_oasis_animals() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let animals = oasis_animals(&OasisTopology::Lumber, &mut rng);
+        assert!(animals[0] > 0 && animals[1] > 0 && animals[4] > 0 && animals[5] > 0);
+        assert_eq!(animals[9], 0);
+
+        let animals = oasis_animals(&OasisTopology::Crop50, &mut rng);
+        assert!(animals[9] > 0 && animals[9] <= 3);
+    }
 }
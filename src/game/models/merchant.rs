@@ -0,0 +1,43 @@
+use super::{ResourceGroup, Tribe};
+
+// Returns how many fields per hour merchants of the given tribe travel.
+pub fn merchant_speed(tribe: &Tribe) -> u8 {
+    match tribe {
+        Tribe::Roman => 16,
+        Tribe::Gaul => 24,
+        Tribe::Teuton => 12,
+        _ => 16,
+    }
+}
+
+// Returns how many resources a single merchant of the given tribe can carry.
+pub fn merchant_capacity(tribe: &Tribe) -> u32 {
+    match tribe {
+        Tribe::Roman => 500,
+        Tribe::Gaul => 750,
+        Tribe::Teuton => 1000,
+        _ => 500,
+    }
+}
+
+// Returns how many merchants carrying `capacity` resources each are needed to carry
+// the given resources.
+pub fn merchants_needed(capacity: u32, resources: &ResourceGroup) -> u32 {
+    (resources.total() + capacity - 1) / capacity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merchant_capacity, merchants_needed};
+    use crate::game::models::{ResourceGroup, Tribe};
+
+    #[test]
+    fn test_merchants_needed() {
+        let resources = ResourceGroup::new(500, 500, 500, 0);
+        let needed = |tribe| merchants_needed(merchant_capacity(&tribe), &resources);
+        assert_eq!(needed(Tribe::Roman), 3);
+        assert_eq!(needed(Tribe::Gaul), 2);
+        assert_eq!(needed(Tribe::Teuton), 2);
+        assert_eq!(merchants_needed(500, &ResourceGroup::default()), 0);
+    }
+}
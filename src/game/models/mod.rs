@@ -1,11 +1,24 @@
+pub mod adventure;
 pub mod army;
+pub mod artifact;
+pub mod audit;
 pub mod buildings;
+pub mod celebration;
+pub mod farm_list;
+pub mod hero;
 pub mod map;
+pub mod merchant;
+pub mod report;
+pub mod trade_route;
 pub mod village;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+// How long new players are protected from attacks.
+pub const BEGINNERS_PROTECTION_HOURS: i64 = 72;
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Tribe {
     Roman,
@@ -22,13 +35,62 @@ pub struct Cost {
     pub build_time: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ResourceGroup(u32, u32, u32, u32);
 
 impl ResourceGroup {
     pub const fn new(lumber: u32, clay: u32, iron: u32, crop: u32) -> Self {
         Self(lumber, clay, iron, crop)
     }
+
+    pub fn lumber(&self) -> u32 {
+        self.0
+    }
+
+    pub fn clay(&self) -> u32 {
+        self.1
+    }
+
+    pub fn iron(&self) -> u32 {
+        self.2
+    }
+
+    pub fn crop(&self) -> u32 {
+        self.3
+    }
+
+    pub fn total(&self) -> u32 {
+        self.0 + self.1 + self.2 + self.3
+    }
+
+    // Returns true if every resource is at least the same amount of the other group.
+    pub fn covers(&self, other: &ResourceGroup) -> bool {
+        self.0 >= other.0 && self.1 >= other.1 && self.2 >= other.2 && self.3 >= other.3
+    }
+
+    pub fn add(&mut self, other: &ResourceGroup) {
+        self.0 += other.0;
+        self.1 += other.1;
+        self.2 += other.2;
+        self.3 += other.3;
+    }
+
+    // Subtracts the other group, never going below zero.
+    pub fn sub(&mut self, other: &ResourceGroup) {
+        self.0 = self.0.saturating_sub(other.0);
+        self.1 = self.1.saturating_sub(other.1);
+        self.2 = self.2.saturating_sub(other.2);
+        self.3 = self.3.saturating_sub(other.3);
+    }
+}
+
+// Scales a base duration by a reduction factor and the speed, always rounding down and
+// never going below one second. Construction, demolition, training, celebration and
+// travel times all go through here, so that players never see the same duration rounded
+// in different ways. Durations picked by players, like trade route intervals, don't.
+pub fn scale_time(base_secs: u32, factor: f64, speed: u8) -> u32 {
+    let secs = (base_secs as f64 * factor / speed.max(1) as f64).floor() as u32;
+    secs.max(1)
 }
 
 pub type SmithyUpgrades = [u8; 10];
@@ -38,4 +100,50 @@ pub struct Player {
     pub id: Uuid,
     pub username: String,
     pub tribe: Tribe,
+    // Culture points accumulated so far, needed to found or conquer new villages.
+    pub culture_points: u32,
+    // New players can't be attacked until then.
+    pub protected_until: Option<DateTime<Utc>>,
+}
+
+impl Player {
+    pub fn is_protected_at(&self, at: DateTime<Utc>) -> bool {
+        self.protected_until.map_or(false, |until| at < until)
+    }
+
+    // Returns the crop upkeep the player doesn't pay at the given time: the server
+    // allowance while under beginners' protection, nothing afterwards.
+    pub fn free_upkeep_at(&self, allowance: u32, at: DateTime<Utc>) -> u32 {
+        match self.is_protected_at(at) {
+            true => allowance,
+            false => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scale_time;
+
+    #[test]
+    fn test_scale_time_rounds_down() {
+        assert_eq!(scale_time(1000, 0.964, 1), 964);
+        assert_eq!(scale_time(1000, 0.964, 2), 482);
+        assert_eq!(scale_time(1000, 0.964, 3), 321);
+        assert_eq!(scale_time(1001, 1.0, 2), 500);
+
+        // flooring once or after each step gives the same result
+        for speed in 1..=10u8 {
+            let once = scale_time(1234, 0.9, speed);
+            let twice = (1234.0f64 * 0.9).floor() as u32 / speed as u32;
+            assert_eq!(once, twice.max(1));
+        }
+    }
+
+    #[test]
+    fn test_scale_time_never_below_one_second() {
+        assert_eq!(scale_time(2, 1.0, 3), 1);
+        assert_eq!(scale_time(0, 1.0, 1), 1);
+        assert_eq!(scale_time(100, 1.0, 0), 100, "zero speed falls back to 1");
+    }
 }
@@ -0,0 +1,440 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    adventure::AdventureOutcome,
+    army::{Army, TroopSet},
+    buildings::BuildingName,
+    village::Village,
+    ResourceGroup,
+};
+
+// Who can read a report.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReportAudience {
+    // Both the attacker and the defender get the full report.
+    Everyone,
+    // Only the scouting player gets the intel, the defender gets a notice if the scouts
+    // were detected and nothing otherwise.
+    Spy { detected: bool },
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScoutingIntel {
+    pub resources: ResourceGroup,
+    pub units: TroopSet,
+    // Name and level of each building in the village.
+    #[serde(default)]
+    pub buildings: Vec<(BuildingName, u8)>,
+    // Scouts killed by the defender, when all of them die nothing is learned.
+    #[serde(default)]
+    pub scouts_lost: u32,
+}
+
+impl ScoutingIntel {
+    // Reads stocks, garrison and buildings of the scouted village.
+    pub fn gather(target: &Village, scouts_lost: u32) -> Self {
+        let mut buildings: Vec<(u8, BuildingName, u8)> = target
+            .buildings
+            .iter()
+            .map(|(slot, b)| (*slot, b.name.clone(), b.level))
+            .collect();
+        buildings.sort_by_key(|(slot, _, _)| *slot);
+
+        Self {
+            resources: target.resources.clone(),
+            units: target.army.units,
+            buildings: buildings
+                .into_iter()
+                .map(|(_, name, level)| (name, level))
+                .collect(),
+            scouts_lost,
+        }
+    }
+}
+
+// Troops lost on each side, the defender side includes reinforcements.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BattleCasualties {
+    pub attacker_losses: Army,
+    pub defender_losses: Vec<Army>,
+    // Troops sent by the attacker, before traps and losses.
+    #[serde(default)]
+    pub attacker_sent: TroopSet,
+    // Attackers caught in the defender traps, they're not counted as losses.
+    #[serde(default)]
+    pub attacker_trapped: TroopSet,
+    // Defending armies as they were before the battle, garrison first.
+    #[serde(default)]
+    pub defenders_before: Vec<Army>,
+    #[serde(default)]
+    pub loot: ResourceGroup,
+    // Wall level before and after the battle, None when there's no wall.
+    #[serde(default)]
+    pub wall_levels: Option<(u8, u8)>,
+}
+
+impl BattleCasualties {
+    // Compares both sides before and after the battle. Defending armies keep their
+    // order through the battle, so they're matched by position.
+    pub fn new(
+        sent: &Army,
+        survivors: &Army,
+        defender_before: &Village,
+        defender_after: &Village,
+        loot: ResourceGroup,
+    ) -> Self {
+        let prisoners = |village: &Village| {
+            village
+                .trapper
+                .prisoners
+                .iter()
+                .find(|p| p.village_id == sent.village_id)
+                .map_or([0; 10], |p| p.units)
+        };
+        let attacker_trapped = units_lost(&prisoners(defender_after), &prisoners(defender_before));
+
+        let mut attacker_losses = sent.clone();
+        attacker_losses.units = units_lost(&sent.units, &survivors.units);
+        attacker_losses.units = units_lost(&attacker_losses.units, &attacker_trapped);
+
+        let defenders_before = defender_before.defending_armies();
+        let defenders_after = defender_after.defending_armies();
+        let defender_losses = defenders_before
+            .iter()
+            .enumerate()
+            .map(|(idx, before)| {
+                let mut lost = before.clone();
+                lost.units = match defenders_after.get(idx) {
+                    Some(after) => units_lost(&before.units, &after.units),
+                    None => before.units,
+                };
+                lost
+            })
+            .collect();
+
+        let wall_levels = match (defender_before.get_wall(), defender_after.get_wall()) {
+            (Some(before), after) => Some((before.level, after.map_or(0, |w| w.level))),
+            _ => None,
+        };
+
+        Self {
+            attacker_losses,
+            defender_losses,
+            attacker_sent: sent.units,
+            attacker_trapped,
+            defenders_before,
+            loot,
+            wall_levels,
+        }
+    }
+}
+
+fn units_lost(before: &TroopSet, after: &TroopSet) -> TroopSet {
+    let mut lost = [0; 10];
+    for (idx, quantity) in before.iter().enumerate() {
+        lost[idx] = quantity.saturating_sub(after[idx]);
+    }
+    lost
+}
+
+// Resources brought by merchants, along with the stocks of the receiving village as
+// they were right after the delivery.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MerchantDelivery {
+    pub delivered: ResourceGroup,
+    pub stored: ResourceGroup,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ReportContent {
+    Scouting(ScoutingIntel),
+    Battle(Box<BattleCasualties>),
+    Delivery(MerchantDelivery),
+    Adventure(AdventureOutcome),
+}
+
+// Points earned by killing enemy troops, used for rankings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CombatPoints {
+    pub attack: u32,
+    pub defense: u32,
+}
+
+// What a report is about, to filter the lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReportKind {
+    Battle,
+    Raid,
+    Reinforcement,
+    Scouting,
+    Trade,
+    Adventure,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Report {
+    pub id: Uuid,
+    pub attacker_player_id: Uuid,
+    pub attacker_village_id: u32,
+    pub defender_player_id: Uuid,
+    pub defender_village_id: u32,
+    pub audience: ReportAudience,
+    pub content: ReportContent,
+    pub kind: ReportKind,
+    // Each side reads, archives and deletes the report on its own.
+    pub read_by_attacker: bool,
+    pub read_by_defender: bool,
+    pub archived_by_attacker: bool,
+    pub archived_by_defender: bool,
+    pub deleted_by_attacker: bool,
+    pub deleted_by_defender: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// What a player is allowed to see of a report.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ReportView {
+    Full(Box<Report>),
+    // The defender only learns that its village has been scouted, and from where.
+    ScoutingNotice {
+        report_id: Uuid,
+        attacker_village_id: u32,
+        defender_village_id: u32,
+        read: bool,
+        created_at: DateTime<Utc>,
+    },
+}
+
+// Narrows a list of reports, the default keeps all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReportFilter {
+    pub kind: Option<ReportKind>,
+    pub unread_only: bool,
+    // Lists only the archived reports, which are left out otherwise.
+    pub archived: bool,
+}
+
+// Order of the reports in a list, by creation time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReportSort {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+impl Report {
+    pub fn new(
+        attacker_player_id: Uuid,
+        attacker_village_id: u32,
+        defender_player_id: Uuid,
+        defender_village_id: u32,
+        audience: ReportAudience,
+        content: ReportContent,
+    ) -> Self {
+        // raids have the same content of battles, their processor sets the kind
+        let kind = match content {
+            ReportContent::Scouting(_) => ReportKind::Scouting,
+            ReportContent::Battle(_) => ReportKind::Battle,
+            ReportContent::Delivery(_) => ReportKind::Trade,
+            ReportContent::Adventure(_) => ReportKind::Adventure,
+        };
+
+        Self {
+            id: Uuid::new_v4(),
+            attacker_player_id,
+            attacker_village_id,
+            defender_player_id,
+            defender_village_id,
+            audience,
+            content,
+            kind,
+            read_by_attacker: false,
+            read_by_defender: false,
+            archived_by_attacker: false,
+            archived_by_defender: false,
+            deleted_by_attacker: false,
+            deleted_by_defender: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_read_by(&self, player_id: Uuid) -> bool {
+        (player_id == self.attacker_player_id && self.read_by_attacker)
+            || (player_id == self.defender_player_id && self.read_by_defender)
+    }
+
+    pub fn is_archived_by(&self, player_id: Uuid) -> bool {
+        (player_id == self.attacker_player_id && self.archived_by_attacker)
+            || (player_id == self.defender_player_id && self.archived_by_defender)
+    }
+
+    // Returns the points earned by the attacker and the defender in this report. Every
+    // killed unit is worth its crop upkeep, so bigger troops give more points. All the
+    // defense points go to the owner of the defending village.
+    pub fn combat_points(&self) -> (u32, u32) {
+        match &self.content {
+            ReportContent::Battle(casualties) => {
+                let attack = casualties.defender_losses.iter().map(|a| a.upkeep()).sum();
+                let defense = casualties.attacker_losses.upkeep();
+                (attack, defense)
+            }
+            ReportContent::Scouting(_)
+            | ReportContent::Delivery(_)
+            | ReportContent::Adventure(_) => (0, 0),
+        }
+    }
+
+    // Returns the part of the report the given player can see, None if it's hidden or
+    // the player deleted it.
+    pub fn view_for(&self, player_id: Uuid) -> Option<ReportView> {
+        let is_attacker = player_id == self.attacker_player_id && !self.deleted_by_attacker;
+        let is_defender = player_id == self.defender_player_id && !self.deleted_by_defender;
+
+        match self.audience {
+            ReportAudience::Everyone if is_attacker || is_defender => {
+                Some(ReportView::Full(Box::new(self.clone())))
+            }
+            ReportAudience::Spy { .. } if is_attacker => {
+                Some(ReportView::Full(Box::new(self.clone())))
+            }
+            ReportAudience::Spy { detected: true } if is_defender => {
+                Some(ReportView::ScoutingNotice {
+                    report_id: self.id,
+                    attacker_village_id: self.attacker_village_id,
+                    defender_village_id: self.defender_village_id,
+                    read: self.read_by_defender,
+                    created_at: self.created_at,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{
+        BattleCasualties, Report, ReportAudience, ReportContent, ReportView, ScoutingIntel,
+    };
+    use crate::game::models::{army::Army, ResourceGroup, Tribe};
+
+    fn scouting_report(attacker: Uuid, defender: Uuid, detected: bool) -> Report {
+        Report::new(
+            attacker,
+            1,
+            defender,
+            2,
+            ReportAudience::Spy { detected },
+            ReportContent::Scouting(ScoutingIntel {
+                resources: ResourceGroup::new(100, 200, 300, 400),
+                units: [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn test_scouting_player_sees_full_intel() {
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+
+        for detected in [true, false] {
+            let report = scouting_report(attacker, defender, detected);
+            match report.view_for(attacker) {
+                Some(ReportView::Full(r)) => match r.content {
+                    ReportContent::Scouting(intel) => {
+                        assert_eq!(intel.resources, ResourceGroup::new(100, 200, 300, 400));
+                        assert_eq!(intel.units[0], 10);
+                    }
+                    c => panic!("expected scouting intel, got {:?}", c),
+                },
+                other => panic!("expected the full report, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_defender_sees_only_detection_notice() {
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let report = scouting_report(attacker, defender, true);
+        match report.view_for(defender) {
+            Some(ReportView::ScoutingNotice {
+                report_id,
+                attacker_village_id,
+                ..
+            }) => {
+                assert_eq!(report_id, report.id);
+                assert_eq!(attacker_village_id, 1);
+            }
+            other => panic!("expected a scouting notice, got {:?}", other),
+        }
+
+        let report = scouting_report(attacker, defender, false);
+        assert!(report.view_for(defender).is_none());
+    }
+
+    #[test]
+    fn test_outsiders_see_nothing() {
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut report = scouting_report(attacker, defender, true);
+        assert!(report.view_for(Uuid::new_v4()).is_none());
+
+        report.audience = ReportAudience::Everyone;
+        assert!(matches!(
+            report.view_for(defender),
+            Some(ReportView::Full(_))
+        ));
+        assert!(report.view_for(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn test_battle_combat_points() {
+        let (attacker, defender) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // 10 Phalanxes (1 crop each) and 5 Theutates Thunders (2 crop each) killed,
+        // against 20 Clubswingers (1 crop each)
+        let report = Report::new(
+            attacker,
+            1,
+            defender,
+            2,
+            ReportAudience::Everyone,
+            ReportContent::Battle(Box::new(BattleCasualties {
+                attacker_losses: Army::new(
+                    1,
+                    attacker,
+                    Tribe::Teuton,
+                    [20, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0; 10],
+                ),
+                defender_losses: vec![
+                    Army::new(
+                        2,
+                        defender,
+                        Tribe::Gaul,
+                        [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                        [0; 10],
+                    ),
+                    Army::new(
+                        3,
+                        Uuid::new_v4(),
+                        Tribe::Gaul,
+                        [0, 0, 0, 5, 0, 0, 0, 0, 0, 0],
+                        [0; 10],
+                    ),
+                ],
+                attacker_sent: [20, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                attacker_trapped: [0; 10],
+                defenders_before: vec![],
+                loot: ResourceGroup::default(),
+                wall_levels: None,
+            })),
+        );
+
+        assert_eq!(report.combat_points(), (20, 20));
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ResourceGroup;
+
+// Shortest time between two shipments of the same route.
+pub const MIN_TRADE_ROUTE_INTERVAL_SECS: u64 = 3600;
+
+// Resources shipped over and over from a village to another, each time the interval
+// expires.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TradeRoute {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub village_id: u32,
+    pub target_village_id: u32,
+    pub resources: ResourceGroup,
+    pub interval_secs: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TradeRoute {
+    pub fn new(
+        player_id: Uuid,
+        village_id: u32,
+        target_village_id: u32,
+        resources: ResourceGroup,
+        interval_secs: u64,
+    ) -> Result<Self> {
+        if village_id == target_village_id {
+            return Err(Error::msg("A trade route needs two different villages"));
+        }
+        if resources.total() == 0 {
+            return Err(Error::msg("No resources to send"));
+        }
+        if interval_secs < MIN_TRADE_ROUTE_INTERVAL_SECS {
+            return Err(Error::msg("Trade routes can run at most once per hour"));
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            player_id,
+            village_id,
+            target_village_id,
+            resources,
+            interval_secs,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::TradeRoute;
+    use crate::game::models::ResourceGroup;
+
+    #[test]
+    fn test_new_trade_route() {
+        let resources = ResourceGroup::new(100, 100, 0, 0);
+        assert!(TradeRoute::new(Uuid::new_v4(), 1, 2, resources.clone(), 3600).is_ok());
+
+        assert!(TradeRoute::new(Uuid::new_v4(), 1, 1, resources.clone(), 3600).is_err());
+        assert!(TradeRoute::new(Uuid::new_v4(), 1, 2, resources, 60).is_err());
+        assert!(TradeRoute::new(Uuid::new_v4(), 1, 2, ResourceGroup::default(), 3600).is_err());
+    }
+}
@@ -5,12 +5,59 @@ use std::collections::HashMap;
 use uuid::Uuid;
 
 use super::{
-    army::Army,
-    buildings::{Building, BuildingGroup, BuildingName},
-    map::{Oasis, Position, Valley, WORLD_MAX_SIZE},
-    {Player, SmithyUpgrades, Tribe},
+    army::{Army, TroopSet},
+    artifact::{strongest_multiplier, Artifact, ArtifactEffect},
+    buildings::{tribe_wall, Building, BuildingGroup, BuildingName},
+    celebration::{BREWERY_ATTACK_BONUS_PER_LEVEL, BREWERY_SPEED_PENALTY_PERCENT},
+    map::{Oasis, Position, Valley},
+    merchant::merchant_capacity,
+    {Player, ResourceGroup, SmithyUpgrades, Tribe},
 };
 
+// Resources available in a newly founded village.
+const STARTING_RESOURCES: ResourceGroup = ResourceGroup::new(750, 750, 750, 750);
+
+// Chiefs come right before settlers in each tribe.
+pub const CHIEF_IDX: usize = 8;
+// Settlers are always the last unit of each tribe.
+pub const SETTLER_IDX: usize = 9;
+// Settlers consumed to found a new village.
+pub const SETTLERS_NEEDED: u32 = 3;
+
+// Gauls hide twice as much in their crannies.
+const GAUL_CRANNY_MULTIPLIER: u32 = 2;
+// Share of the cranny capacity Teuton raiders can still reach.
+const TEUTON_CRANNY_BYPASS_PERCENT: u32 = 20;
+
+// Oases can be annexed within this many fields from the village, a 7x7 square.
+pub const OASIS_ANNEX_RANGE: u32 = 3;
+
+// Armies that can be on the move at the same time for each Rally Point level.
+const MOVEMENTS_PER_RALLY_POINT_LEVEL: u32 = 5;
+
+// Main Building level needed to tear down buildings.
+pub const DEMOLITION_MAIN_BUILDING_LEVEL: u8 = 10;
+// Time to tear down one level of any building, before the server speed.
+pub const DEMOLITION_TIME_SECS: u32 = 600;
+
+// Returns the culture points a player must have to own one more village than the given
+// ones, following the curve of the classic servers: 2000, 8000, 20000, 39000...
+pub fn culture_points_needed(villages: u32) -> u32 {
+    if villages == 0 {
+        return 0;
+    }
+    (1.6 * (villages as f64).powf(2.3)).round() as u32 * 1000
+}
+
+// Returns how many villages a player can own with the given culture points.
+pub fn allowed_villages(culture_points: u32) -> u32 {
+    let mut villages = 1;
+    while culture_points_needed(villages) <= culture_points {
+        villages += 1;
+    }
+    villages
+}
+
 // TODO: add standalone rally point? Not yet
 // TODO: add standalone wall? Not yet
 // TODO: track reinforcements to other villages? -> better to have a table for armies
@@ -31,16 +78,25 @@ pub struct Village {
     pub is_capital: bool,
     pub smithy: SmithyUpgrades,
     pub stocks: StockCapacity,
+    pub resources: ResourceGroup,
+    pub reinforcement_policy: ReinforcementPolicy,
+    // Village whose settlers founded this one, taking one of its expansion slots.
+    pub parent_village_id: Option<u32>,
+    pub trapper: TrapperState,
+    // End of the Brewery celebration going on, if any.
+    pub brewery_celebration_until: Option<DateTime<Utc>>,
+    // Artifacts and construction plans held by the village.
+    pub artifacts: Vec<Artifact>,
     pub updated_at: DateTime<Utc>,
 }
 
 impl Village {
     pub fn new(name: String, valley: &Valley, player: &Player, is_capital: bool) -> Self {
         let position = valley.position.clone();
-        let village_id = position.to_id(WORLD_MAX_SIZE);
+        let village_id = valley.id;
         let army = Army::new(
             village_id,
-            player.id.clone(),
+            player.id,
             player.tribe.clone(),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -52,7 +108,7 @@ impl Village {
             id: village_id,
             name,
             position,
-            player_id: player.id.clone(),
+            player_id: player.id,
             tribe: player.tribe.clone(),
             buildings: HashMap::new(),
             oases: vec![],
@@ -64,6 +120,12 @@ impl Village {
             is_capital,
             smithy,
             stocks: Default::default(),
+            resources: STARTING_RESOURCES,
+            reinforcement_policy: Default::default(),
+            parent_village_id: None,
+            trapper: Default::default(),
+            brewery_celebration_until: None,
+            artifacts: vec![],
             updated_at: Utc::now(),
         };
 
@@ -88,26 +150,32 @@ impl Village {
 
         let building = Building::new(name);
 
-        building.validate_build(&self.tribe, &self.buildings, self.is_capital)?;
+        building.validate_build(
+            &self.tribe,
+            &self.buildings,
+            self.is_capital,
+            &self.artifacts,
+        )?;
         self.buildings.insert(slot_id, building);
         self.update_state();
 
         Ok(())
     }
 
-    pub fn upgrade_building(&mut self, slot_id: u8) -> Result<()> {
+    // Upgrades the building on the given slot to the next level and returns it.
+    pub fn upgrade_building(&mut self, slot_id: u8) -> Result<Building> {
         match self.get_building_by_slot_id(slot_id) {
             Some(b) => match b.validate_upgrade() {
                 Ok(_) => {
-                    let next = b.next_level().unwrap();
-                    self.buildings.insert(slot_id, next);
+                    let next = b.next_level()?;
+                    self.buildings.insert(slot_id, next.clone());
                     self.update_state();
+                    Ok(next)
                 }
-                Err(msg) => return Err(Error::msg(msg)),
+                Err(msg) => Err(Error::msg(msg)),
             },
-            None => return Err(Error::msg("No buildings found on this slot")),
+            None => Err(Error::msg("No buildings found on this slot")),
         }
-        Ok(())
     }
 
     pub fn downgrade_building_to_level(&mut self, slot_id: u8, level: u8) -> Result<()> {
@@ -122,15 +190,70 @@ impl Village {
         Ok(())
     }
 
+    // Sets the building on the given slot straight to a level, without costs or build
+    // times. Level 0 destroys it, as catapults would do.
+    pub fn set_building_level(&mut self, slot_id: u8, level: u8) -> Result<()> {
+        let building = self
+            .get_building_by_slot_id(slot_id)
+            .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+        if level > building.max_level() {
+            return Err(Error::msg("The level is beyond the building max level."));
+        }
+
+        match level {
+            0 => self.destroy_building(slot_id),
+            _ => self.downgrade_building_to_level(slot_id, level),
+        }
+    }
+
+    // Checks the building on the given slot can lose a level: the Main Building must be
+    // big enough to lead the works.
+    pub fn validate_demolition(&self, slot_id: u8) -> Result<Building> {
+        let main_building_level = self
+            .get_building_by_name(BuildingName::MainBuilding)
+            .map_or(0, |b| b.level);
+        if main_building_level < DEMOLITION_MAIN_BUILDING_LEVEL {
+            return Err(Error::msg(format!(
+                "Main Building level {} is needed to demolish buildings.",
+                DEMOLITION_MAIN_BUILDING_LEVEL
+            )));
+        }
+
+        self.demolishable_building(slot_id)
+    }
+
+    // Tears down one level of the building on the given slot and returns its new level.
+    // Level 1 buildings leave an empty slot, resource fields stay at level 0.
+    pub fn demolish_building(&mut self, slot_id: u8) -> Result<u8> {
+        let level = self.demolishable_building(slot_id)?.level - 1;
+        self.set_building_level(slot_id, level)?;
+        Ok(level)
+    }
+
+    // The Main Building can't tear itself down below level 1.
+    fn demolishable_building(&self, slot_id: u8) -> Result<Building> {
+        let building = self
+            .get_building_by_slot_id(slot_id)
+            .ok_or_else(|| Error::msg("No buildings found on this slot"))?;
+        if building.level == 0 {
+            return Err(Error::msg("This building has nothing left to demolish."));
+        }
+        if building.name == BuildingName::MainBuilding && building.level <= 1 {
+            return Err(Error::msg("The Main Building can't be demolished."));
+        }
+
+        Ok(building)
+    }
+
     pub fn destroy_building(&mut self, slot_id: u8) -> Result<()> {
         match self.get_building_by_slot_id(slot_id) {
             Some(b) => {
                 if b.group == BuildingGroup::Resources {
-                    b.at_level(0)?;
-                    self.update_state();
+                    self.buildings.insert(slot_id, b.at_level(0)?);
                 } else {
                     self.buildings.remove(&slot_id);
                 }
+                self.update_state();
             }
             None => return Err(Error::msg("No buildings found on this slot")),
         };
@@ -146,12 +269,21 @@ impl Village {
         self.buildings
             .clone()
             .values()
-            .into_iter()
             .filter(|&x| x.name == name)
             .cloned()
             .max_by(|x, y| x.level.cmp(&y.level))
     }
 
+    // Returns the slot of a building in the village, picking the highest level one in case
+    // of multiple buildings of same type.
+    pub fn get_building_slot_by_name(&self, name: BuildingName) -> Option<u8> {
+        self.buildings
+            .iter()
+            .filter(|(_, b)| b.name == name)
+            .max_by(|(_, x), (_, y)| x.level.cmp(&y.level))
+            .map(|(slot_id, _)| *slot_id)
+    }
+
     pub fn get_palace_or_residence(&self) -> Option<(Building, BuildingName)> {
         if let Some(palace) = self.get_building_by_name(BuildingName::Palace) {
             return Some((palace, BuildingName::Palace));
@@ -162,60 +294,499 @@ impl Village {
         None
     }
 
+    // Hands the village over to the owner of the conquering village, taking one of its
+    // expansion slots. Buildings, resources, oases and upgrades stay as they are, the
+    // loyalty starts over from zero.
+    pub fn conquer(&mut self, conqueror: &Village) {
+        self.player_id = conqueror.player_id;
+        self.army = Army::new(
+            self.id,
+            self.player_id,
+            self.tribe.clone(),
+            [0; 10],
+            [0; 10],
+        );
+        self.reinforcements.retain(|r| r.immensity() > 0);
+        for oasis in self.oases.iter_mut() {
+            oasis.player_id = Some(conqueror.player_id);
+        }
+        self.is_capital = false;
+        self.parent_village_id = Some(conqueror.id);
+        self.loyalty = 0;
+        self.update_state();
+    }
+
+    // Returns how many villages can be founded or conquered from this village.
+    pub fn expansion_slots(&self) -> u8 {
+        match self.get_palace_or_residence() {
+            Some((palace, BuildingName::Palace)) => match palace.level {
+                20 => 3,
+                15..=19 => 2,
+                10..=14 => 1,
+                _ => 0,
+            },
+            Some((residence, _)) => match residence.level {
+                20 => 2,
+                10..=19 => 1,
+                _ => 0,
+            },
+            None => 0,
+        }
+    }
+
+    // Returns the expansion slots not taken yet by villages founded from this one.
+    pub fn free_expansion_slots(&self, villages: &[Village]) -> u8 {
+        let taken = villages
+            .iter()
+            .filter(|v| v.parent_village_id == Some(self.id))
+            .count();
+        self.expansion_slots().saturating_sub(taken as u8)
+    }
+
+    // Returns how many armies the village can have on the move at the same time, which
+    // grows with the Rally Point level. Without a Rally Point no army can leave.
+    pub fn max_outgoing_movements(&self) -> u32 {
+        self.get_building_by_name(BuildingName::RallyPoint)
+            .map_or(0, |rp| rp.level as u32 * MOVEMENTS_PER_RALLY_POINT_LEVEL)
+    }
+
+    // Each Marketplace level gives a merchant.
+    pub fn merchants(&self) -> u32 {
+        self.get_building_by_name(BuildingName::Marketplace)
+            .map_or(0, |b| b.level as u32)
+    }
+
+    // Returns how many resources a merchant can carry, the Trade Office raises it.
+    pub fn merchant_capacity(&self) -> u32 {
+        let base = merchant_capacity(&self.tribe);
+        self.get_building_by_name(BuildingName::TradeOffice)
+            .map_or(base, |b| base * b.value / 100)
+    }
+
+    // Adds resources to the village stocks, capped at warehouse and granary capacity.
+    // A Wonder of the World can receive more than its stocks can hold, since the
+    // resources are used to build the wonder itself.
+    pub fn deposit_resources(&mut self, resources: &ResourceGroup) {
+        self.resources.add(resources);
+
+        if self
+            .get_building_by_name(BuildingName::WonderOfTheWorld)
+            .is_none()
+        {
+            self.resources = ResourceGroup::new(
+                self.resources.lumber().min(self.stocks.warehouse),
+                self.resources.clay().min(self.stocks.warehouse),
+                self.resources.iron().min(self.stocks.warehouse),
+                self.resources.crop().min(self.stocks.granary),
+            );
+        }
+    }
+
+    // Returns how much of each resource the village can hold, summing up every warehouse
+    // and granary, great ones included. A village without them still has some room.
+    pub fn storage_capacity(&self) -> ResourceGroup {
+        let (mut warehouse, mut granary) = (0, 0);
+        for b in self.buildings.values() {
+            match b.name {
+                BuildingName::Warehouse | BuildingName::GreatWarehouse => warehouse += b.value,
+                BuildingName::Granary | BuildingName::GreatGranary => granary += b.value,
+                _ => continue,
+            }
+        }
+
+        let base = StockCapacity::default();
+        if warehouse == 0 {
+            warehouse = base.warehouse;
+        }
+        if granary == 0 {
+            granary = base.granary;
+        }
+
+        ResourceGroup::new(warehouse, warehouse, warehouse, granary)
+    }
+
+    pub fn granary_capacity(&self) -> u32 {
+        self.storage_capacity().crop()
+    }
+
+    // Returns the traps of the village, summed across its trappers.
+    pub fn traps(&self) -> u32 {
+        self.buildings
+            .values()
+            .filter(|b| b.name == BuildingName::Trapper)
+            .map(|b| b.value)
+            .sum()
+    }
+
+    // Catches attacking units in the free traps before they can fight. When there are
+    // fewer traps than attackers, each unit type fills them in proportion to its size.
+    // Returns the captured units, which are kept in the village until they're freed.
+    pub fn trap(&mut self, army: &mut Army) -> TroopSet {
+        let free = self.traps().saturating_sub(self.trapper.used()) as u64;
+        let total = army.immensity() as u64;
+        if free == 0 || total == 0 {
+            return [0; 10];
+        }
+
+        let mut captured = army.units;
+        if total > free {
+            captured = army.units.map(|q| (q as u64 * free / total) as u32);
+            // hand out the traps lost to rounding
+            let mut left = free - captured.iter().sum::<u32>() as u64;
+            for (caught, quantity) in captured.iter_mut().zip(army.units.iter()) {
+                if left > 0 && *caught < *quantity {
+                    *caught += 1;
+                    left -= 1;
+                }
+            }
+        }
+
+        for (idx, quantity) in captured.iter().enumerate() {
+            army.units[idx] -= quantity;
+        }
+        self.trapper.imprison(Army::new(
+            army.village_id,
+            army.player_id,
+            army.tribe.clone(),
+            captured,
+            army.smithy,
+        ));
+
+        captured
+    }
+
+    // Frees every prisoner, emptying the traps. The freed armies are returned so they
+    // can head back home.
+    pub fn release_prisoners(&mut self) -> Vec<Army> {
+        std::mem::take(&mut self.trapper.prisoners)
+    }
+
+    // Returns the resources raiders of the given tribe can take: what's hidden in the
+    // crannies and what's piled up beyond warehouse and granary capacity (e.g. for a
+    // Wonder of the World) are out of reach.
+    pub fn lootable_resources(&self, attacker_tribe: &Tribe) -> ResourceGroup {
+        let mut lootable = ResourceGroup::new(
+            self.resources.lumber().min(self.stocks.warehouse),
+            self.resources.clay().min(self.stocks.warehouse),
+            self.resources.iron().min(self.stocks.warehouse),
+            self.resources.crop().min(self.stocks.granary),
+        );
+        lootable.sub(&self.protected_resources(attacker_tribe));
+        lootable
+    }
+
+    // Returns the resources the crannies hide from raiders of the given tribe, up to
+    // what's actually stored.
+    pub fn protected_resources(&self, attacker_tribe: &Tribe) -> ResourceGroup {
+        let mut capacity: u32 = self
+            .buildings
+            .values()
+            .filter(|b| b.name == BuildingName::Cranny)
+            .map(|b| b.value)
+            .sum();
+        if self.tribe == Tribe::Gaul {
+            capacity *= GAUL_CRANNY_MULTIPLIER;
+        }
+        if *attacker_tribe == Tribe::Teuton {
+            capacity -= capacity * TEUTON_CRANNY_BYPASS_PERCENT / 100;
+        }
+
+        ResourceGroup::new(
+            self.resources.lumber().min(capacity),
+            self.resources.clay().min(capacity),
+            self.resources.iron().min(capacity),
+            self.resources.crop().min(capacity),
+        )
+    }
+
+    // Replaces the village stocks, which must fit into warehouse and granary.
+    pub fn set_resources(&mut self, resources: ResourceGroup) -> Result<()> {
+        if resources.lumber() > self.stocks.warehouse
+            || resources.clay() > self.stocks.warehouse
+            || resources.iron() > self.stocks.warehouse
+            || resources.crop() > self.stocks.granary
+        {
+            return Err(Error::msg("Resources exceed the village stocks capacity."));
+        }
+        self.resources = resources;
+
+        Ok(())
+    }
+
+    // Returns how many oases the village can hold: the Hero's Mansion unlocks one at
+    // level 10, 15 and 20.
+    pub fn max_oases(&self) -> usize {
+        match self.get_building_by_name(BuildingName::HeroMansion) {
+            Some(mansion) => match mansion.level {
+                20 => 3,
+                15..=19 => 2,
+                10..=14 => 1,
+                _ => 0,
+            },
+            None => 0,
+        }
+    }
+
+    // Takes control of an oasis, which must have been cleared of its animals first and
+    // lie close enough to the village.
+    pub fn annex_oasis(&mut self, oasis: &mut Oasis, world_size: i32) -> Result<()> {
+        if oasis.player_id.is_some() {
+            return Err(Error::msg("The oasis already belongs to someone else"));
+        }
+        if !oasis.is_cleared() {
+            return Err(Error::msg("The oasis must be cleared of animals first"));
+        }
+        if self.position.grid_distance(&oasis.position, world_size) > OASIS_ANNEX_RANGE {
+            return Err(Error::msg("The oasis is too far from the village"));
+        }
+        if self.oases.len() >= self.max_oases() {
+            return Err(Error::msg(
+                "The Hero's Mansion can't hold more oases, upgrade it first",
+            ));
+        }
+
+        oasis.player_id = Some(self.player_id);
+        oasis.village_id = Some(self.id);
+        self.oases.push(oasis.clone());
+        self.update_state();
+
+        Ok(())
+    }
+
+    // Returns the troops at home and free to be sent. Armies on the move have already
+    // left the garrison, while trapped units stay in it but can't leave.
+    pub fn sendable_troops(&self) -> TroopSet {
+        self.army.available_units()
+    }
+
+    // Returns the garrison followed by every reinforcement stationed in the village.
+    pub fn defending_armies(&self) -> Vec<Army> {
+        let mut armies = vec![self.army.clone()];
+        armies.extend(self.reinforcements.iter().cloned());
+        armies
+    }
+
+    // Returns true if the village lets in reinforcements sent by the given player.
+    pub fn accepts_reinforcements_from(&self, player_id: Uuid) -> bool {
+        match self.reinforcement_policy {
+            ReinforcementPolicy::None => false,
+            // TODO: include alliance members once alliances exist
+            ReinforcementPolicy::AllyOnly => player_id == self.player_id,
+            ReinforcementPolicy::Anyone => true,
+        }
+    }
+
+    pub fn is_brewery_celebrating(&self, at: DateTime<Utc>) -> bool {
+        self.brewery_celebration_until
+            .map_or(false, |until| at < until)
+    }
+
+    // Returns the multiplier on the attack of troops from this village: a Brewery
+    // celebration gives 1% for each Brewery level.
+    pub fn attack_bonus(&self, at: DateTime<Utc>) -> f64 {
+        if !self.is_brewery_celebrating(at) {
+            return 1.0;
+        }
+        let level = self
+            .get_building_by_name(BuildingName::Brewery)
+            .map_or(0, |b| b.level);
+        1.0 + (level as u32 * BREWERY_ATTACK_BONUS_PER_LEVEL) as f64 / 100.0
+    }
+
+    // Returns the speed of an army leaving this village. Troops are slower while the
+    // Brewery celebration is going on.
+    pub fn army_speed(&self, army: &Army, at: DateTime<Utc>) -> u8 {
+        let speed = army.speed();
+        if !self.is_brewery_celebrating(at) {
+            return speed;
+        }
+        (speed as u32 * (100 - BREWERY_SPEED_PENALTY_PERCENT) / 100).max(1) as u8
+    }
+
+    // Removes resources from the village stocks, failing if they're not enough.
+    pub fn withdraw_resources(&mut self, resources: &ResourceGroup) -> Result<()> {
+        if !self.resources.covers(resources) {
+            return Err(Error::msg("Not enough resources in the village"));
+        }
+        self.resources.sub(resources);
+
+        Ok(())
+    }
+
     // Returns the current wall, if any, according to the tribe.
     pub fn get_wall(&self) -> Option<Building> {
-        match self.tribe {
-            Tribe::Roman => self.get_building_by_name(BuildingName::CityWall),
-            Tribe::Teuton => self.get_building_by_name(BuildingName::EarthWall),
-            Tribe::Gaul => self.get_building_by_name(BuildingName::Palisade),
-            _ => None,
-        }
+        tribe_wall(&self.tribe).and_then(|name| self.get_building_by_name(name))
     }
 
     pub fn get_buildings_durability(&self) -> u16 {
-        match self.get_building_by_name(BuildingName::StonemansionLodge) {
+        let durability = match self.get_building_by_name(BuildingName::StonemansionLodge) {
             Some(b) => b.value as u16,
             None => 1,
+        };
+        let multiplier = self.artifact_multiplier(ArtifactEffect::BuildingDurability);
+
+        (durability as f64 * multiplier) as u16
+    }
+
+    // Returns the multiplier given to the effect by the artifacts held here. Large and
+    // unique artifacts held by other villages of the owner are not counted.
+    pub fn artifact_multiplier(&self, effect: ArtifactEffect) -> f64 {
+        strongest_multiplier(&self.artifacts, effect, true)
+    }
+
+    // Returns the population of the village, given by the cumulative population of each building.
+    pub fn population(&self) -> u32 {
+        self.buildings
+            .values()
+            .map(|b| b.get_cumulative_stats().0)
+            .sum()
+    }
+
+    // Returns the culture points the village produces each day, given by the cumulative
+    // culture points of each building.
+    pub fn culture_points_production(&self) -> u32 {
+        self.buildings
+            .values()
+            .map(|b| b.get_cumulative_stats().1)
+            .sum()
+    }
+
+    // Returns the hourly crop left once buildings and troops staying here are fed, it's
+    // negative when the village eats more than it produces.
+    pub fn net_crop_production(&self) -> i64 {
+        self.production.effective.crop
+    }
+
+    // Once the granary is empty, troops staying in the village starve, the hungriest
+    // first, until the crop production covers the upkeep again. Troops away eat
+    // elsewhere and are spared. Returns true when the village still starves with no
+    // troops left, so only its buildings can be blamed.
+    pub fn starve(&mut self, free_upkeep: u32) -> bool {
+        self.update_state();
+        if self.resources.crop() > 0 {
+            return false;
+        }
+
+        while self.production.net_crop(free_upkeep) < 0 {
+            let deficit = self.production.net_crop(free_upkeep).unsigned_abs() as u32;
+            let mut armies: Vec<&mut Army> = vec![&mut self.army];
+            armies.extend(self.reinforcements.iter_mut());
+
+            // (army, unit index, upkeep) of the hungriest unit still alive
+            let hungriest = armies
+                .iter()
+                .enumerate()
+                .flat_map(|(a, army)| {
+                    (0..10u8)
+                        .filter(|&idx| army.unit_amount(idx) > 0)
+                        .filter_map(move |idx| Some((a, idx, army.get_unit(idx).ok()?.cost.upkeep)))
+                })
+                .max_by_key(|(_, _, upkeep)| *upkeep);
+
+            match hungriest {
+                Some((a, idx, upkeep)) => {
+                    let army = &mut armies[a];
+                    let upkeep = upkeep.max(1);
+                    let dead = army.units[idx as usize].min((deficit + upkeep - 1) / upkeep);
+                    army.units[idx as usize] -= dead;
+                }
+                None => return true,
+            }
+
+            self.reinforcements.retain(|r| r.immensity() > 0);
+            self.update_state();
+        }
+
+        false
+    }
+
+    // Returns the hourly production of each resource, before upkeep, going through the
+    // production pipeline: base fields, processing buildings, oases and then the given
+    // extra modifiers (hero, timed effects) in their stage order.
+    pub fn resource_production(
+        &self,
+        extra: &[ProductionModifier],
+        at: DateTime<Utc>,
+    ) -> ResourceGroup {
+        let base = ResourceGroup::new(
+            self.production.lumber,
+            self.production.clay,
+            self.production.iron,
+            self.production.crop,
+        );
+
+        let mut modifiers = vec![
+            ProductionModifier::ProcessingBuildings(self.processing_buildings_bonus()),
+            ProductionModifier::Oases(self.oases_bonus()),
+            ProductionModifier::Artifact(self.artifact_multiplier(ArtifactEffect::Production)),
+        ];
+        modifiers.extend(extra.iter().cloned());
+        // stable sort: modifiers of the same stage keep their order
+        modifiers.sort_by_key(|m| m.stage());
+
+        let production = modifiers
+            .iter()
+            .fold(ProductionRates::from(&base), |rates, m| {
+                m.apply(&base, rates, at)
+            });
+        production.floor()
+    }
+
+    // Percentual bonus of Sawmill, Brickyard, Iron Foundry, Grain Mill and Bakery.
+    fn processing_buildings_bonus(&self) -> ProductionBonus {
+        // the Bakery only works along with a Grain Mill
+        let grain_mill = self.get_building_by_name(BuildingName::GrainMill).is_some();
+
+        let mut bonus = ProductionBonus::default();
+        for b in self.buildings.values() {
+            let value = b.value as u8;
+            match b.name {
+                BuildingName::Sawmill => bonus.lumber = bonus.lumber.saturating_add(value),
+                BuildingName::Brickyard => bonus.clay = bonus.clay.saturating_add(value),
+                BuildingName::IronFoundry => bonus.iron = bonus.iron.saturating_add(value),
+                BuildingName::GrainMill => bonus.crop = bonus.crop.saturating_add(value),
+                BuildingName::Bakery if grain_mill => bonus.crop = bonus.crop.saturating_add(value),
+                _ => continue,
+            }
         }
+        bonus
     }
 
-    pub fn calculate_travel_time_secs(&self, position: Position, speed: u8) -> u32 {
-        let distance = self.position.distance(&position, 100);
-        (distance as f64 / speed as f64).floor() as u32
+    // Percentual bonus of the annexed oases.
+    fn oases_bonus(&self) -> ProductionBonus {
+        let mut bonus = ProductionBonus::default();
+        for o in self.oases.iter() {
+            bonus.add(&o.bonus());
+        }
+        bonus
     }
+
     // Updates the village stats (population, production, bonuses from buildings and oases, etc).
     fn update_state(&mut self) {
-        self.population = 0;
+        self.population = self.population();
         self.production = Default::default();
-        self.stocks = Default::default();
+        let capacity = self.storage_capacity();
+        self.stocks = StockCapacity {
+            warehouse: capacity.lumber(),
+            granary: capacity.crop(),
+        };
 
         // data from infrastructures
         for (_, b) in self.buildings.clone() {
-            self.population += b.cost().upkeep;
-
             match b.name {
                 BuildingName::Woodcutter => self.production.lumber += b.value,
                 BuildingName::ClayPit => self.production.clay += b.value,
                 BuildingName::IronMine => self.production.iron += b.value,
                 BuildingName::Cropland => self.production.crop += b.value,
-                BuildingName::Sawmill => self.production.bonus.lumber += b.value as u8,
-                BuildingName::Brickyard => self.production.bonus.clay += b.value as u8,
-                BuildingName::IronFoundry => self.production.bonus.iron += b.value as u8,
-                BuildingName::GrainMill => self.production.bonus.crop += b.value as u8,
-                BuildingName::Bakery => self.production.bonus.crop += b.value as u8,
-                BuildingName::Warehouse => self.stocks.warehouse += b.value,
-                BuildingName::Granary => self.stocks.granary += b.value,
                 _ => continue,
             }
         }
 
         self.production.upkeep += self.population;
 
-        // oases production bonuses
-        for o in self.oases.clone() {
-            let oasis_bonus = o.bonus();
-            self.production.bonus.add(&oasis_bonus)
-        }
+        // processing buildings and oases bonuses both apply to the base production
+        self.production.bonus = self.processing_buildings_bonus();
+        self.production.bonus.add(&self.oases_bonus());
 
         // armies upkeep
         self.production.upkeep += self.army.upkeep();
@@ -266,6 +837,15 @@ impl Village {
     }
 }
 
+// Who can send reinforcements to a village. Refused armies go back home.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReinforcementPolicy {
+    None,
+    AllyOnly,
+    #[default]
+    Anyone,
+}
+
 // Gross production of a village with upkeep and bonuses values ready to apply.
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct VillageProduction {
@@ -280,16 +860,21 @@ pub struct VillageProduction {
 
 impl VillageProduction {
     pub fn calculate_effective_production(&mut self) {
-        let mut ep: VillageEffectiveProduction = Default::default();
+        let crop = (self.crop as f64 * ((self.bonus.crop as f64 / 100.0) + 1.0)).floor() as i64;
 
-        ep.lumber =
-            ((self.lumber as f64) * ((self.bonus.lumber as f64 / 100.0) + 1.0)).floor() as u32;
-        ep.clay = (self.clay as f64 * ((self.bonus.clay as f64 / 100.0) + 1.0)).floor() as u32;
-        ep.iron = (self.iron as f64 * ((self.bonus.iron as f64 / 100.0) + 1.0)).floor() as u32;
-        ep.crop = (self.crop as f64 * ((self.bonus.crop as f64 / 100.0) + 1.0)).floor() as i64;
-        ep.crop -= self.upkeep as i64;
+        self.effective = VillageEffectiveProduction {
+            lumber: ((self.lumber as f64) * ((self.bonus.lumber as f64 / 100.0) + 1.0)).floor()
+                as u32,
+            clay: (self.clay as f64 * ((self.bonus.clay as f64 / 100.0) + 1.0)).floor() as u32,
+            iron: (self.iron as f64 * ((self.bonus.iron as f64 / 100.0) + 1.0)).floor() as u32,
+            crop: crop - self.upkeep as i64,
+        };
+    }
 
-        self.effective = ep;
+    // Returns the effective crop once the given free upkeep is taken off. The allowance
+    // can cover the upkeep, but never adds crop on its own.
+    pub fn net_crop(&self, free_upkeep: u32) -> i64 {
+        self.effective.crop + free_upkeep.min(self.upkeep) as i64
     }
 }
 
@@ -312,10 +897,131 @@ pub struct ProductionBonus {
 
 impl ProductionBonus {
     pub fn add(&mut self, bonus: &ProductionBonus) {
-        self.lumber = bonus.lumber;
-        self.clay = bonus.clay;
-        self.iron = bonus.iron;
-        self.crop = bonus.crop;
+        self.lumber = self.lumber.saturating_add(bonus.lumber);
+        self.clay = self.clay.saturating_add(bonus.clay);
+        self.iron = self.iron.saturating_add(bonus.iron);
+        self.crop = self.crop.saturating_add(bonus.crop);
+    }
+}
+
+// A source of resource production. Modifiers are applied in stage order, so each one
+// composes with the previous ones the same way every time.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ProductionModifier {
+    // Percentual bonus of Sawmill, Brickyard, Iron Foundry, Grain Mill and Bakery,
+    // calculated on the base production of the fields.
+    ProcessingBuildings(ProductionBonus),
+    // Percentual bonus of the annexed oases, also calculated on the base production.
+    Oases(ProductionBonus),
+    // Hourly resources produced by a hero set to production mode.
+    Hero(ResourceGroup),
+    // Percentual boost of the whole production, active until the given time.
+    Timed {
+        bonus: ProductionBonus,
+        until: DateTime<Utc>,
+    },
+    // Multiplier of the whole production given by an artifact.
+    Artifact(f64),
+}
+
+impl ProductionModifier {
+    fn stage(&self) -> u8 {
+        match self {
+            ProductionModifier::ProcessingBuildings(_) => 0,
+            ProductionModifier::Oases(_) => 1,
+            ProductionModifier::Hero(_) => 2,
+            ProductionModifier::Timed { .. } => 3,
+            ProductionModifier::Artifact(_) => 4,
+        }
+    }
+
+    // Applies the modifier to the production calculated so far.
+    fn apply(
+        &self,
+        base: &ResourceGroup,
+        rates: ProductionRates,
+        at: DateTime<Utc>,
+    ) -> ProductionRates {
+        match self {
+            ProductionModifier::ProcessingBuildings(bonus) | ProductionModifier::Oases(bonus) => {
+                rates.add(&ProductionRates::from(base).percent(bonus))
+            }
+            ProductionModifier::Hero(resources) => rates.add(&ProductionRates::from(resources)),
+            ProductionModifier::Timed { bonus, until } if at < *until => {
+                rates.add(&rates.percent(bonus))
+            }
+            ProductionModifier::Timed { .. } => rates,
+            ProductionModifier::Artifact(multiplier) => rates.scale(*multiplier),
+        }
+    }
+}
+
+// Hourly production while it goes through the modifiers, rounded down only at the end.
+#[derive(Debug, Clone, Copy)]
+struct ProductionRates([f64; 4]);
+
+impl ProductionRates {
+    fn from(resources: &ResourceGroup) -> Self {
+        Self([
+            resources.lumber() as f64,
+            resources.clay() as f64,
+            resources.iron() as f64,
+            resources.crop() as f64,
+        ])
+    }
+
+    fn add(self, other: &ProductionRates) -> Self {
+        let mut rates = self.0;
+        for (rate, other) in rates.iter_mut().zip(other.0) {
+            *rate += other;
+        }
+        Self(rates)
+    }
+
+    fn scale(self, multiplier: f64) -> Self {
+        Self(self.0.map(|r| r * multiplier))
+    }
+
+    fn percent(&self, bonus: &ProductionBonus) -> Self {
+        let [lumber, clay, iron, crop] = self.0;
+        Self([
+            lumber * bonus.lumber as f64 / 100.0,
+            clay * bonus.clay as f64 / 100.0,
+            iron * bonus.iron as f64 / 100.0,
+            crop * bonus.crop as f64 / 100.0,
+        ])
+    }
+
+    fn floor(&self) -> ResourceGroup {
+        let [lumber, clay, iron, crop] = self.0.map(|r| r.floor() as u32);
+        ResourceGroup::new(lumber, clay, iron, crop)
+    }
+}
+
+// Enemy troops caught in the village traps, one army for each village they come from.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TrapperState {
+    pub prisoners: Vec<Army>,
+}
+
+impl TrapperState {
+    // Returns the traps holding a prisoner.
+    pub fn used(&self) -> u32 {
+        self.prisoners.iter().map(|a| a.immensity()).sum()
+    }
+
+    fn imprison(&mut self, army: Army) {
+        if army.immensity() == 0 {
+            return;
+        }
+        match self
+            .prisoners
+            .iter_mut()
+            .find(|p| p.village_id == army.village_id)
+        {
+            Some(prisoners) => prisoners.merge(army),
+            None => self.prisoners.push(army),
+        }
     }
 }
 
@@ -325,6 +1031,16 @@ pub struct StockCapacity {
     granary: u32,
 }
 
+impl StockCapacity {
+    pub fn warehouse(&self) -> u32 {
+        self.warehouse
+    }
+
+    pub fn granary(&self) -> u32 {
+        self.granary
+    }
+}
+
 impl Default for StockCapacity {
     fn default() -> Self {
         Self {
@@ -337,15 +1053,24 @@ impl Default for StockCapacity {
 
 #[cfg(test)]
 mod tests {
+    use chrono::{Duration, Utc};
     use uuid::Uuid;
 
-    use crate::game::models::{
-        buildings::BuildingName,
-        map::{Position, Valley, ValleyTopology},
-        Player, Tribe,
+    use crate::{
+        db::test_utils::{test_player, test_village},
+        game::models::{
+            army::Army,
+            artifact::{Artifact, ArtifactEffect, ArtifactSize},
+            buildings::{Building, BuildingName},
+            map::{
+                travel_time_secs, Oasis, OasisTopology, Position, TravelSettings, Valley,
+                ValleyTopology, WORLD_MAX_SIZE,
+            },
+            ResourceGroup, Tribe,
+        },
     };
 
-    use super::Village;
+    use super::{allowed_villages, ProductionBonus, ProductionModifier, Village};
 
     #[test]
     fn test_new_village() {
@@ -358,11 +1083,7 @@ mod tests {
             village_id: None,
         };
 
-        let player = Player {
-            id: Uuid::new_v4(),
-            username: "pavonz".to_string(),
-            tribe: Tribe::Roman,
-        };
+        let player = test_player(Tribe::Roman);
         let v = Village::new("Gino".to_string(), &valley, &player, true);
 
         // has same id of the valley
@@ -402,4 +1123,775 @@ mod tests {
         assert_eq!(v.stocks.warehouse, 800, "stock warehouse");
         assert_eq!(v.stocks.granary, 800, "stock granary");
     }
+
+    #[test]
+    fn test_population_follows_building_levels() {
+        let mut v = test_village(&test_player(Tribe::Teuton), Position { x: 0, y: 0 });
+        assert_eq!(v.population, 2, "main building level 1");
+
+        // woodcutter on slot 1: level 1 (+2), level 2 (+1), level 3 (+1)
+        for _ in 0..3 {
+            v.upgrade_building(1).unwrap();
+        }
+        assert_eq!(v.population, 6, "woodcutter level 3");
+
+        // main building on slot 19: level 2 (+1), level 3 (+1)
+        v.upgrade_building(19).unwrap();
+        v.upgrade_building(19).unwrap();
+        assert_eq!(v.population, 8, "main building level 3");
+        assert_eq!(v.population, v.population());
+
+        // catapulted down
+        v.downgrade_building_to_level(1, 1).unwrap();
+        assert_eq!(v.population, 6, "woodcutter back to level 1");
+
+        v.destroy_building(1).unwrap();
+        assert_eq!(v.population, 4, "woodcutter destroyed");
+        assert_eq!(v.get_building_by_slot_id(1).unwrap().level, 0);
+
+        v.destroy_building(19).unwrap();
+        assert_eq!(v.population, 0, "main building destroyed");
+        assert!(v.get_building_by_slot_id(19).is_none());
+        assert_eq!(v.production.upkeep, 0);
+    }
+
+    fn new_village() -> Village {
+        test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 })
+    }
+
+    #[test]
+    fn test_deposit_resources_capped_by_stocks() {
+        let mut v = new_village();
+        v.deposit_resources(&ResourceGroup::new(30, 30, 30, 30));
+        assert_eq!(v.resources, ResourceGroup::new(780, 780, 780, 780));
+
+        v.deposit_resources(&ResourceGroup::new(1000, 0, 0, 1000));
+        assert_eq!(v.resources, ResourceGroup::new(800, 780, 780, 800));
+    }
+
+    #[test]
+    fn test_storage_capacity_sums_every_building() {
+        let mut v = new_village();
+        assert_eq!(v.storage_capacity(), ResourceGroup::new(800, 800, 800, 800));
+
+        // a second warehouse can be built once the first one is at max level
+        let full = Building::new(BuildingName::Warehouse).at_level(20).unwrap();
+        let second = Building::new(BuildingName::Warehouse).at_level(3).unwrap();
+        let great = Building::new(BuildingName::GreatWarehouse);
+        let granary = Building::new(BuildingName::Granary);
+        let capacity = full.value + second.value + great.value;
+        v.buildings.insert(20, full);
+        v.buildings.insert(21, second);
+        v.buildings.insert(22, great);
+        v.buildings.insert(23, granary);
+        v.update_state();
+
+        assert_eq!(
+            v.storage_capacity(),
+            ResourceGroup::new(capacity, capacity, capacity, 1200)
+        );
+        assert_eq!(v.granary_capacity(), 1200);
+        assert_eq!(v.stocks.warehouse(), capacity);
+        assert_eq!(v.stocks.granary(), 1200);
+
+        v.deposit_resources(&ResourceGroup::new(100_000, 0, 0, 100_000));
+        assert_eq!(v.resources, ResourceGroup::new(capacity, 750, 750, 1200));
+    }
+
+    fn village_with_crannies(tribe: Tribe, levels: &[u8]) -> Village {
+        let mut v = new_village();
+        v.tribe = tribe;
+        for (i, level) in levels.iter().enumerate() {
+            let cranny = Building::new(BuildingName::Cranny)
+                .at_level(*level)
+                .unwrap();
+            v.buildings.insert(20 + i as u8, cranny);
+        }
+        v.update_state();
+        v
+    }
+
+    #[test]
+    fn test_crannies_hide_resources() {
+        // crannies at level 1 and 3 hide 100 and 170 resources
+        let v = village_with_crannies(Tribe::Roman, &[1, 3]);
+        assert_eq!(
+            v.protected_resources(&Tribe::Roman),
+            ResourceGroup::new(270, 270, 270, 270)
+        );
+        assert_eq!(
+            v.lootable_resources(&Tribe::Roman),
+            ResourceGroup::new(480, 480, 480, 480)
+        );
+
+        // nothing is hidden beyond what's stored
+        let mut v = village_with_crannies(Tribe::Roman, &[1, 3]);
+        v.resources = ResourceGroup::new(50, 750, 750, 0);
+        assert_eq!(
+            v.protected_resources(&Tribe::Roman),
+            ResourceGroup::new(50, 270, 270, 0)
+        );
+    }
+
+    #[test]
+    fn test_gaul_crannies_hide_double() {
+        let v = village_with_crannies(Tribe::Gaul, &[1, 3]);
+        assert_eq!(
+            v.protected_resources(&Tribe::Roman),
+            ResourceGroup::new(540, 540, 540, 540)
+        );
+    }
+
+    #[test]
+    fn test_teutons_raid_through_crannies() {
+        let v = village_with_crannies(Tribe::Roman, &[1, 3]);
+        assert_eq!(
+            v.protected_resources(&Tribe::Teuton),
+            ResourceGroup::new(216, 216, 216, 216)
+        );
+
+        let v = village_with_crannies(Tribe::Gaul, &[1, 3]);
+        assert_eq!(
+            v.protected_resources(&Tribe::Teuton),
+            ResourceGroup::new(432, 432, 432, 432)
+        );
+    }
+
+    fn village_with_trapper(level: u8) -> Village {
+        let mut v = new_village();
+        v.tribe = Tribe::Gaul;
+        let trapper = Building::new(BuildingName::Trapper)
+            .at_level(level)
+            .unwrap();
+        v.buildings.insert(20, trapper);
+        v
+    }
+
+    fn attackers(village_id: u32, units: [u32; 10]) -> Army {
+        Army::new(village_id, Uuid::new_v4(), Tribe::Teuton, units, [0; 10])
+    }
+
+    #[test]
+    fn test_traps_fewer_than_attackers() {
+        let mut v = village_with_trapper(1);
+        assert_eq!(v.traps(), 10);
+
+        let mut army = attackers(1, [30, 0, 10, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(v.trap(&mut army), [8, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(army.units, [22, 0, 8, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(v.trapper.used(), 10);
+
+        // traps are full
+        let mut army = attackers(2, [30, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(v.trap(&mut army), [0; 10]);
+        assert_eq!(army.units[0], 30);
+    }
+
+    #[test]
+    fn test_traps_exceeding_attackers() {
+        let mut v = village_with_trapper(3);
+        assert_eq!(v.traps(), 35);
+
+        let mut army = attackers(1, [5, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(v.trap(&mut army), [5, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(army.immensity(), 0);
+
+        // prisoners from the same village share the same cell
+        let mut army = attackers(1, [10, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        v.trap(&mut army);
+        assert_eq!(v.trapper.prisoners.len(), 1);
+        assert_eq!(
+            v.trapper.prisoners[0].units,
+            [15, 0, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(v.trapper.used(), 18);
+    }
+
+    #[test]
+    fn test_release_prisoners_frees_traps() {
+        let mut v = village_with_trapper(1);
+        v.trap(&mut attackers(1, [4, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+        v.trap(&mut attackers(2, [20, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+
+        let freed = v.release_prisoners();
+        assert_eq!(freed.len(), 2);
+        assert_eq!(freed[0].village_id, 1);
+        assert_eq!(freed[0].units[0], 4);
+        assert_eq!(freed[1].units[0], 6);
+        assert_eq!(v.trapper.used(), 0);
+
+        let mut army = attackers(3, [20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(v.trap(&mut army)[0], 10);
+    }
+
+    #[test]
+    fn test_deposit_resources_to_wonder_of_the_world() {
+        let mut v = new_village();
+        v.buildings
+            .insert(20, Building::new(BuildingName::WonderOfTheWorld));
+
+        // allied supplies pile up beyond the stocks capacity for the construction
+        v.deposit_resources(&ResourceGroup::new(10000, 10000, 10000, 5000));
+        v.deposit_resources(&ResourceGroup::new(10000, 0, 0, 0));
+        assert_eq!(v.resources, ResourceGroup::new(20750, 10750, 10750, 5750));
+    }
+
+    #[test]
+    fn test_gaul_cavalry_arrives_before_roman_cavalry() {
+        let village = new_village();
+        let target = Position { x: 40, y: -20 };
+
+        // Equites Imperatoris against Theutates Thunders, same number of horses
+        let roman = Army::new(
+            village.id,
+            village.player_id,
+            Tribe::Roman,
+            [0, 0, 0, 0, 100, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        let gaul = Army::new(
+            village.id,
+            village.player_id,
+            Tribe::Gaul,
+            [0, 0, 0, 100, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        );
+        assert_eq!(roman.speed(), 14);
+        assert_eq!(gaul.speed(), 19);
+
+        let roman_time = travel_time_secs(
+            &village.position,
+            &target,
+            roman.speed(),
+            TravelSettings::default(),
+        );
+        let gaul_time = travel_time_secs(
+            &village.position,
+            &target,
+            gaul.speed(),
+            TravelSettings::default(),
+        );
+        assert!(gaul_time < roman_time, "Gaul cavalry arrives sooner");
+
+        // 50 fields at 14 fields per hour
+        assert_eq!(roman_time, 50 * 3600 / 14);
+    }
+
+    #[test]
+    fn test_max_outgoing_movements_by_rally_point_level() {
+        let mut v = new_village();
+        assert_eq!(v.max_outgoing_movements(), 0);
+
+        v.buildings
+            .insert(39, Building::new(BuildingName::RallyPoint));
+        assert_eq!(v.max_outgoing_movements(), 5);
+
+        v.buildings.insert(
+            39,
+            Building::new(BuildingName::RallyPoint)
+                .at_level(20)
+                .unwrap(),
+        );
+        assert_eq!(v.max_outgoing_movements(), 100);
+    }
+
+    #[test]
+    fn test_sendable_troops() {
+        let mut v = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        v.army.units = [100, 20, 0, 5, 0, 0, 0, 0, 0, 0];
+        assert_eq!(v.sendable_troops(), [100, 20, 0, 5, 0, 0, 0, 0, 0, 0]);
+
+        // troops sent away leave the garrison
+        v.army.deploy([30, 0, 0, 5, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(v.sendable_troops(), [70, 20, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        // trapped troops can't be sent
+        v.army.trapped = [0, 15, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(v.sendable_troops(), [70, 5, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(v.army.deploy([0, 6, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        assert_eq!(v.army.units, [70, 20, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_annex_oasis_requires_clearing_animals() {
+        let position = Position { x: 10, y: 20 };
+        let mut v = test_village(&test_player(Tribe::Roman), position.clone());
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(10)
+                .unwrap(),
+        );
+        let mut oasis = Oasis {
+            id: 42,
+            player_id: None,
+            village_id: None,
+            position: Position { x: 11, y: 20 },
+            topology: OasisTopology::Lumber,
+            animals: [3, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+
+        assert!(v.annex_oasis(&mut oasis, WORLD_MAX_SIZE).is_err());
+        assert!(v.oases.is_empty());
+
+        oasis.animals = [0; 10];
+        v.annex_oasis(&mut oasis, WORLD_MAX_SIZE).unwrap();
+        assert_eq!(oasis.village_id, Some(v.id));
+        assert_eq!(v.oases.len(), 1);
+        assert_eq!(v.production.bonus.lumber, 25);
+    }
+
+    // Village with every field at level 10: 800 lumber, clay and iron, 1200 crop.
+    fn producing_village() -> Village {
+        let mut v = new_village();
+        for slot_id in 1..=18 {
+            let field = v.buildings[&slot_id].at_level(10).unwrap();
+            v.buildings.insert(slot_id, field);
+        }
+        v.update_state();
+        v
+    }
+
+    fn oasis(topology: OasisTopology) -> Oasis {
+        Oasis {
+            id: 42,
+            player_id: None,
+            village_id: None,
+            position: Position { x: 11, y: 20 },
+            topology,
+            animals: [0; 10],
+        }
+    }
+
+    #[test]
+    fn test_production_from_base_fields() {
+        let v = producing_village();
+        let production = v.resource_production(&[], Utc::now());
+
+        assert_eq!(production, ResourceGroup::new(800, 800, 800, 1200));
+        assert_eq!(
+            production.crop() as i64 - v.production.upkeep as i64,
+            v.production.effective.crop
+        );
+    }
+
+    #[test]
+    fn test_production_from_processing_buildings() {
+        let mut v = producing_village();
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Sawmill).at_level(5).unwrap(),
+        );
+        v.buildings.insert(
+            21,
+            Building::new(BuildingName::GrainMill).at_level(5).unwrap(),
+        );
+        v.update_state();
+
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(1000, 800, 800, 1500)
+        );
+    }
+
+    #[test]
+    fn test_production_from_boosters_at_various_levels() {
+        let booster = |name: BuildingName, level: u8| Building::new(name).at_level(level).unwrap();
+
+        let mut v = producing_village();
+        v.buildings.insert(20, booster(BuildingName::Sawmill, 1));
+        v.buildings.insert(21, booster(BuildingName::Brickyard, 3));
+        v.buildings
+            .insert(22, booster(BuildingName::IronFoundry, 4));
+        v.update_state();
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(840, 920, 960, 1200)
+        );
+
+        // Grain Mill and Bakery stack on crop only
+        v.buildings.insert(23, booster(BuildingName::GrainMill, 5));
+        v.buildings.insert(24, booster(BuildingName::Bakery, 2));
+        v.update_state();
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(840, 920, 960, 1620)
+        );
+    }
+
+    #[test]
+    fn test_bakery_needs_grain_mill() {
+        let mut v = producing_village();
+        v.buildings
+            .insert(20, Building::new(BuildingName::Bakery).at_level(5).unwrap());
+        v.update_state();
+
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(800, 800, 800, 1200)
+        );
+    }
+
+    #[test]
+    fn test_production_from_oases() {
+        let mut v = producing_village();
+        v.oases.push(oasis(OasisTopology::LumberCrop));
+        v.oases.push(oasis(OasisTopology::Crop50));
+        v.update_state();
+
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(1000, 800, 800, 2100)
+        );
+
+        // oases add up with buildings on the base production, they don't compound
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Sawmill).at_level(5).unwrap(),
+        );
+        v.update_state();
+        assert_eq!(v.production.bonus.lumber, 50);
+        assert_eq!(v.resource_production(&[], Utc::now()).lumber(), 1200);
+        assert_eq!(v.production.effective.lumber, 1200);
+    }
+
+    #[test]
+    fn test_production_from_hero() {
+        let v = producing_village();
+        let hero = ProductionModifier::Hero(ResourceGroup::new(0, 0, 0, 240));
+
+        assert_eq!(
+            v.resource_production(&[hero], Utc::now()),
+            ResourceGroup::new(800, 800, 800, 1440)
+        );
+    }
+
+    #[test]
+    fn test_production_from_timed_effects() {
+        let v = producing_village();
+        let now = Utc::now();
+        let boost = ProductionModifier::Timed {
+            bonus: ProductionBonus {
+                lumber: 25,
+                ..Default::default()
+            },
+            until: now + Duration::hours(1),
+        };
+
+        assert_eq!(
+            v.resource_production(std::slice::from_ref(&boost), now),
+            ResourceGroup::new(1000, 800, 800, 1200)
+        );
+        // expired effects don't count anymore
+        assert_eq!(
+            v.resource_production(&[boost], now + Duration::hours(2)),
+            ResourceGroup::new(800, 800, 800, 1200)
+        );
+    }
+
+    #[test]
+    fn test_village_holding_artifacts() {
+        let mut v = producing_village();
+        v.artifacts.push(Artifact::Boost {
+            size: ArtifactSize::Small,
+            effect: ArtifactEffect::Production,
+        });
+        v.update_state();
+
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(1000, 1000, 1000, 1500)
+        );
+        assert_eq!(v.get_buildings_durability(), 1);
+
+        v.artifacts.push(Artifact::Boost {
+            size: ArtifactSize::Unique,
+            effect: ArtifactEffect::BuildingDurability,
+        });
+        assert_eq!(v.get_buildings_durability(), 5);
+
+        // construction plans unlock the Wonder of the World and nothing else
+        v.artifacts = vec![];
+        v.update_state();
+        assert!(v.add_building(BuildingName::WonderOfTheWorld, 30).is_err());
+        v.artifacts.push(Artifact::ConstructionPlan);
+        assert!(v.add_building(BuildingName::WonderOfTheWorld, 30).is_ok());
+        assert_eq!(
+            v.resource_production(&[], Utc::now()),
+            ResourceGroup::new(800, 800, 800, 1200)
+        );
+    }
+
+    #[test]
+    fn test_production_modifiers_stack_in_order() {
+        let mut v = producing_village();
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::Sawmill).at_level(5).unwrap(),
+        );
+        v.buildings.insert(
+            21,
+            Building::new(BuildingName::GrainMill).at_level(5).unwrap(),
+        );
+        v.oases.push(oasis(OasisTopology::LumberCrop));
+        v.update_state();
+
+        let now = Utc::now();
+        // given out of order: the timed boost still applies last, hero production included
+        let extra = [
+            ProductionModifier::Timed {
+                bonus: ProductionBonus {
+                    lumber: 50,
+                    ..Default::default()
+                },
+                until: now + Duration::hours(1),
+            },
+            ProductionModifier::Hero(ResourceGroup::new(100, 0, 0, 0)),
+        ];
+
+        // lumber: (800 + 25% + 25% of 800 + 100) * 1.5, crop: 1200 + 25% + 25% of 1200
+        assert_eq!(
+            v.resource_production(&extra, now),
+            ResourceGroup::new(1950, 800, 800, 1800)
+        );
+    }
+
+    fn starving_village() -> Village {
+        let mut v = producing_village();
+        v.resources = ResourceGroup::new(800, 800, 800, 0);
+        v
+    }
+
+    #[test]
+    fn test_no_starvation_at_zero_net_crop() {
+        let mut v = starving_village();
+        let net = v.net_crop_production() as u32;
+        v.army.units[0] = net;
+
+        assert!(!v.starve(0));
+        assert_eq!(v.army.units[0], net);
+        assert_eq!(v.net_crop_production(), 0);
+    }
+
+    #[test]
+    fn test_starvation_kills_hungriest_troops_first() {
+        let mut v = starving_village();
+        let net = v.net_crop_production() as u32;
+        // Legionnaires eat 1 crop per hour, Equites Caesaris 4
+        v.army.units[0] = 20;
+        v.army.units[5] = 400;
+
+        assert!(!v.starve(0));
+        assert_eq!(v.army.units[0], 20);
+        assert_eq!(v.army.units[5], (net - 20) / 4);
+        assert!((0..4).contains(&v.net_crop_production()));
+
+        // crop in the granary keeps troops alive
+        let mut v = starving_village();
+        v.resources = ResourceGroup::new(800, 800, 800, 1);
+        v.army.units[5] = 400;
+        assert!(!v.starve(0));
+        assert_eq!(v.army.units[5], 400);
+    }
+
+    #[test]
+    fn test_starvation_spares_troops_away() {
+        let mut v = starving_village();
+        let net = v.net_crop_production() as u32;
+        v.army.units[0] = net + 10;
+        v.update_state();
+        v.army.deploy([10, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+
+        assert!(!v.starve(0));
+        assert_eq!(v.army.units[0], net);
+    }
+
+    #[test]
+    fn test_starvation_reaches_reinforcements() {
+        let mut v = starving_village();
+        let net = v.net_crop_production() as u32;
+        v.army.units[0] = net;
+        v.reinforcements.push(Army::new(
+            1,
+            Uuid::new_v4(),
+            Tribe::Gaul,
+            [10, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0; 10],
+        ));
+
+        assert!(!v.starve(0));
+        assert_eq!(
+            v.army.units[0] + v.reinforcements.iter().map(|r| r.units[0]).sum::<u32>(),
+            net
+        );
+    }
+
+    #[test]
+    fn test_starvation_without_troops() {
+        let mut v = new_village();
+        v.resources = ResourceGroup::new(750, 750, 750, 0);
+        v.set_building_level(19, 20).unwrap();
+        assert!(v.net_crop_production() < 0);
+
+        assert!(v.starve(0));
+        // the allowance of protected players can cover it
+        let allowance = v.net_crop_production().unsigned_abs() as u32;
+        assert!(!v.starve(allowance));
+    }
+
+    fn demolition_village() -> Village {
+        let mut v = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 20 });
+        // slot 19 is the main building
+        v.set_building_level(19, 10).unwrap();
+        v
+    }
+
+    fn warehouse_population(level: u8) -> u32 {
+        Building::new(BuildingName::Warehouse)
+            .at_level(level)
+            .unwrap()
+            .get_cumulative_stats()
+            .0
+    }
+
+    #[test]
+    fn test_demolish_infrastructure() {
+        let mut v = demolition_village();
+        v.buildings
+            .insert(20, Building::new(BuildingName::Warehouse));
+        // refreshes the village stats
+        v.set_building_level(20, 2).unwrap();
+        let (population, culture_points) = (v.population, v.culture_points_production());
+
+        v.validate_demolition(20).unwrap();
+        assert_eq!(v.demolish_building(20).unwrap(), 1);
+        assert_eq!(v.get_building_by_slot_id(20).unwrap().level, 1);
+        assert_eq!(
+            v.population,
+            population - warehouse_population(2) + warehouse_population(1)
+        );
+        assert!(v.culture_points_production() < culture_points);
+
+        // the last level leaves an empty slot
+        assert_eq!(v.demolish_building(20).unwrap(), 0);
+        assert!(v.get_building_by_slot_id(20).is_none());
+        assert_eq!(v.population, population - warehouse_population(2));
+    }
+
+    #[test]
+    fn test_demolish_resource_field() {
+        let mut v = demolition_village();
+        // slot 1 is a woodcutter
+        v.set_building_level(1, 1).unwrap();
+        let production = v.production.lumber;
+
+        assert_eq!(v.demolish_building(1).unwrap(), 0);
+        let woodcutter = v.get_building_by_slot_id(1).unwrap();
+        assert_eq!(woodcutter.name, BuildingName::Woodcutter);
+        assert_eq!(woodcutter.level, 0);
+        assert!(v.production.lumber < production);
+
+        assert!(
+            v.validate_demolition(1).is_err(),
+            "nothing left to demolish"
+        );
+        assert!(v.demolish_building(1).is_err());
+    }
+
+    #[test]
+    fn test_demolition_needs_main_building_level() {
+        let mut v = demolition_village();
+        v.validate_demolition(19).unwrap();
+        assert_eq!(v.demolish_building(19).unwrap(), 9);
+        assert!(v.validate_demolition(1).is_err());
+        assert!(v.validate_demolition(19).is_err());
+
+        // the Main Building never goes below level 1
+        v.set_building_level(19, 1).unwrap();
+        assert!(v.demolish_building(19).is_err());
+        assert_eq!(v.get_building_by_slot_id(19).unwrap().level, 1);
+    }
+
+    #[test]
+    fn test_allowed_villages_by_culture_points() {
+        assert_eq!(allowed_villages(0), 1);
+        assert_eq!(allowed_villages(1999), 1);
+        assert_eq!(allowed_villages(2000), 2);
+        assert_eq!(allowed_villages(7999), 2);
+        assert_eq!(allowed_villages(8000), 3);
+        assert_eq!(allowed_villages(20000), 4);
+    }
+
+    #[test]
+    fn test_annexed_oases_limited_by_hero_mansion() {
+        let mut v = new_village();
+        let mut first = oasis(OasisTopology::Lumber);
+        assert!(
+            v.annex_oasis(&mut first, WORLD_MAX_SIZE).is_err(),
+            "no Hero's Mansion"
+        );
+
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(10)
+                .unwrap(),
+        );
+        v.annex_oasis(&mut first, WORLD_MAX_SIZE).unwrap();
+
+        let mut second = oasis(OasisTopology::Clay);
+        second.id = 43;
+        assert!(
+            v.annex_oasis(&mut second, WORLD_MAX_SIZE).is_err(),
+            "one oasis at level 10"
+        );
+
+        v.set_building_level(20, 15).unwrap();
+        v.annex_oasis(&mut second, WORLD_MAX_SIZE).unwrap();
+        assert_eq!(v.oases.len(), 2);
+        assert_eq!(v.max_oases(), 2);
+
+        v.set_building_level(20, 20).unwrap();
+        assert_eq!(v.max_oases(), 3);
+    }
+
+    #[test]
+    fn test_annexed_oases_must_be_close() {
+        let mut v = new_village();
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(20)
+                .unwrap(),
+        );
+
+        // the village is at (10, 20), oases must be within 3 fields on both axes
+        let mut far = oasis(OasisTopology::Iron);
+        far.position = Position { x: 14, y: 20 };
+        assert!(v.annex_oasis(&mut far, WORLD_MAX_SIZE).is_err());
+        assert_eq!(far.village_id, None);
+
+        let mut corner = oasis(OasisTopology::Iron);
+        corner.position = Position { x: 13, y: 17 };
+        v.annex_oasis(&mut corner, WORLD_MAX_SIZE).unwrap();
+        assert_eq!(corner.village_id, Some(v.id));
+    }
+
+    #[test]
+    fn test_annex_range_wraps_at_the_world_edge() {
+        let mut v = test_village(&test_player(Tribe::Roman), Position { x: 10, y: 0 });
+        v.buildings.insert(
+            20,
+            Building::new(BuildingName::HeroMansion)
+                .at_level(20)
+                .unwrap(),
+        );
+
+        // in a world going from -10 to 10 the east edge is next to the west one
+        let mut across = oasis(OasisTopology::Iron);
+        across.position = Position { x: -10, y: 0 };
+        assert!(v.annex_oasis(&mut across.clone(), WORLD_MAX_SIZE).is_err());
+        v.annex_oasis(&mut across, 10).unwrap();
+        assert_eq!(across.village_id, Some(v.id));
+    }
 }
@@ -1,4 +1,5 @@
 pub mod app;
+pub mod config;
 pub mod db;
 pub mod game;
 pub mod repository;
@@ -1,40 +1,82 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use anyhow::{Error, Result};
+use chrono::Utc;
 
-use parabellum::app::commands::Cmd;
-use parabellum::app::App;
+use parabellum::app::commands::register_player::RegisterPlayerCommand;
+use parabellum::app::{worker::Worker, App};
+use parabellum::config::Config;
 use parabellum::db::repository::Repository;
 use parabellum::game::models::Tribe;
 // use parabellum::repository::Repository as GameRepository;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    // set RUST_LOG=parabellum=trace to follow the produced events
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
     let db = Repository::new_from_env()
         .await
         .expect("failed to create repository");
 
     // TODO: put this into a cli command as part of a reset/setup task
     use parabellum::repository::Repository as GameRepository;
-    db.bootstrap_new_map(100).await?;
+    let mut config = Config::from_env()?;
+    if db.get_world_size().await?.is_none() {
+        db.bootstrap_new_map(config.world_size as u32, config.world_started_at)
+            .await?;
+    }
+    config.check_world_size(db.get_world_size().await?)?;
+    // the world started when its map was generated, restarts must not move it
+    if let Some(started_at) = db.get_world_started_at().await? {
+        config.world_started_at = started_at;
+    }
 
     // for _ in 0..10 {
-    //     let valley = db.get_unoccupied_valley(None).await?;
+    //     let valley = db.get_unoccupied_valley(None, None).await?;
     //     println!("Valley random -> {:?}", valley);
     // }
 
     // let valley = db
-    //     .get_unoccupied_valley(Some(parabellum::game::models::map::Quadrant::NorthEast))
+    //     .get_unoccupied_valley(Some(parabellum::game::models::map::Quadrant::NorthEast), None)
     //     .await?;
     // println!("Valley NorthEast -> {:?}", valley);
 
-    let app = App::new(Arc::new(db.clone()));
+    let repo = Arc::new(db.clone());
+    let job_poll_secs = config.job_poll_secs;
+    let worker = Worker::new(
+        repo.clone(),
+        config.worker_tick_secs,
+        config.server_speed,
+        config.free_upkeep,
+    );
+    let app = App::new(repo.clone(), config);
+    let recovered = app.recover_stuck_jobs(Utc::now()).await?;
+    tracing::info!("Requeued {} jobs left processing", recovered.len());
+
+    match app
+        .execute(RegisterPlayerCommand::new(
+            repo,
+            "pavonz".to_string(),
+            Tribe::Gaul,
+        ))
+        .await
+    {
+        Ok(player) => tracing::info!("Registered player {} ({})", player.username, player.id),
+        Err(err) => tracing::warn!("Player not registered: {}", err),
+    }
 
-    app.command(Cmd::RegisterPlayer {
-        username: "pavonz".to_string(),
-        tribe: Tribe::Gaul,
-    })
-    .await?;
+    tokio::spawn(async move { worker.run().await });
 
-    Ok(())
+    // failed jobs are retried or given up by run_job, only database errors stop the loop
+    let mut interval = tokio::time::interval(Duration::from_secs(job_poll_secs));
+    loop {
+        interval.tick().await;
+        let ran = app.run_due_jobs(Utc::now()).await?;
+        if ran > 0 {
+            tracing::debug!("Ran {} due jobs", ran);
+        }
+    }
 }
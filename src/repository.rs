@@ -1,20 +1,110 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::app::jobs::Job;
 use crate::game::models::{
+    adventure::Adventure,
+    army::Army,
+    audit::AuditEntry,
+    farm_list::FarmList,
+    hero::Hero,
     map::{Oasis, Quadrant, Valley},
+    report::{CombatPoints, Report, ReportFilter, ReportSort},
+    trade_route::TradeRoute,
     village::Village,
     Player, Tribe,
 };
 
 #[async_trait::async_trait]
 pub trait Repository: Send + Sync {
-    async fn bootstrap_new_map(&self, size: u32) -> Result<()>;
+    async fn bootstrap_new_map(&self, size: u32, started_at: DateTime<Utc>) -> Result<()>;
+    async fn get_world_size(&self) -> Result<Option<i32>>;
+    async fn get_world_started_at(&self) -> Result<Option<DateTime<Utc>>>;
     async fn register_player(&self, username: String, tribe: Tribe) -> Result<Player>;
-    async fn get_unoccupied_valley(&self, quadrant: Option<Quadrant>) -> Result<Valley>;
+    async fn get_unoccupied_valley(
+        &self,
+        quadrant: Option<Quadrant>,
+        seed: Option<u64>,
+    ) -> Result<Valley>;
     async fn get_player_by_id(&self, player_id: Uuid) -> Result<Player>;
     async fn get_player_by_username(&self, username: String) -> Result<Player>;
+    async fn update_player(&self, player: Player) -> Result<()>;
+    async fn get_players_with_expired_protection(&self, now: DateTime<Utc>) -> Result<Vec<Player>>;
+    async fn list_players(&self) -> Result<Vec<Player>>;
+    async fn award_culture_points(&self, player_id: Uuid, day: String, amount: u32)
+        -> Result<bool>;
+    async fn count_players(&self) -> Result<u32>;
+    async fn count_villages(&self) -> Result<u32>;
+    // Returns the village with the highest Wonder of the World and its level, if any.
+    async fn get_highest_wonder(&self) -> Result<Option<(u32, u8)>>;
     async fn get_village_by_id(&self, village_id: u32) -> Result<Village>;
+    async fn get_defending_armies(&self, village_id: u32) -> Result<Vec<Army>>;
+    async fn get_villages_by_player_id(&self, player_id: Uuid) -> Result<Vec<Village>>;
+    // Claims the valley and inserts the village, consuming the settlers of the given
+    // village in the same transaction. It fails if the player has no culture points for
+    // one more village.
+    async fn found_village(&self, village: Village, settlers_village_id: Option<u32>)
+        -> Result<()>;
+    async fn update_village(&self, village: Village) -> Result<()>;
+    // Saves a conquered village and hands its valley and oases over to the new owner,
+    // in the same transaction.
+    async fn conquer_village(&self, village: Village) -> Result<()>;
+    async fn delete_village(&self, village_id: u32) -> Result<()>;
+    async fn delete_player(&self, player_id: Uuid) -> Result<()>;
     async fn get_valley_by_id(&self, valley_id: u32) -> Result<Valley>;
     async fn get_oasis_by_id(&self, oasis_id: u32) -> Result<Oasis>;
+    async fn update_oasis(&self, oasis: Oasis) -> Result<()>;
+    async fn get_hero_by_player_id(&self, player_id: Uuid) -> Result<Hero>;
+    async fn update_hero(&self, hero: Hero) -> Result<()>;
+    async fn list_heroes(&self) -> Result<Vec<Hero>>;
+    async fn add_adventure(&self, adventure: Adventure) -> Result<()>;
+    async fn get_adventure_by_id(&self, adventure_id: Uuid) -> Result<Adventure>;
+    async fn get_adventures_by_player_id(&self, player_id: Uuid) -> Result<Vec<Adventure>>;
+    async fn remove_adventure(&self, adventure_id: Uuid) -> Result<()>;
+    async fn add_job(&self, job: Job) -> Result<()>;
+    async fn get_job_by_id(&self, job_id: Uuid) -> Result<Job>;
+    async fn update_job(&self, job: Job) -> Result<()>;
+    async fn remove_job(&self, job_id: Uuid) -> Result<()>;
+    async fn mark_job_done(&self, job_id: Uuid) -> Result<()>;
+    async fn get_done_jobs_by_player_id(&self, player_id: Uuid) -> Result<Vec<Job>>;
+    async fn get_pending_jobs_by_village_id(&self, village_id: u32) -> Result<Vec<Job>>;
+    // Returns the jobs left unfinished by a worker that started them before the given time.
+    async fn get_stuck_jobs(&self, processing_before: DateTime<Utc>) -> Result<Vec<Job>>;
+    // Returns the queued jobs ending by the given time, the earliest first.
+    async fn get_due_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>>;
+    async fn add_report(&self, report: Report) -> Result<()>;
+    async fn get_report_by_id(&self, report_id: Uuid) -> Result<Report>;
+    // Returns the reports found with the given ids, skipping the missing ones.
+    async fn get_reports_by_ids(&self, report_ids: &[Uuid]) -> Result<Vec<Report>>;
+    async fn get_reports_by_player_id(&self, player_id: Uuid) -> Result<Vec<Report>>;
+    // Returns a page of the reports the player can see, along with how many they are.
+    async fn list_reports(
+        &self,
+        player_id: Uuid,
+        filter: &ReportFilter,
+        offset: u32,
+        limit: u32,
+        sort: ReportSort,
+    ) -> Result<(Vec<Report>, u32)>;
+    // Marks the report as read for the given player only, on whichever side they are.
+    async fn mark_report_read(&self, report_id: Uuid, player_id: Uuid) -> Result<()>;
+    // Archiving and deleting work the same way, a report is removed once both sides
+    // deleted it.
+    async fn archive_reports(&self, report_ids: &[Uuid], player_id: Uuid) -> Result<()>;
+    async fn delete_reports(&self, report_ids: &[Uuid], player_id: Uuid) -> Result<()>;
+    async fn add_audit_entry(&self, entry: AuditEntry) -> Result<()>;
+    async fn get_audit_entries_by_village_id(&self, village_id: u32) -> Result<Vec<AuditEntry>>;
+    async fn get_combat_points(&self, player_id: Uuid) -> Result<CombatPoints>;
+    async fn add_trade_route(&self, route: TradeRoute) -> Result<()>;
+    // Returns None once the route has been deleted.
+    async fn get_trade_route_by_id(&self, route_id: Uuid) -> Result<Option<TradeRoute>>;
+    async fn get_trade_routes_by_village_id(&self, village_id: u32) -> Result<Vec<TradeRoute>>;
+    async fn remove_trade_route(&self, route_id: Uuid) -> Result<()>;
+    async fn add_farm_list(&self, list: FarmList) -> Result<()>;
+    // Returns None once the list has been deleted.
+    async fn get_farm_list_by_id(&self, list_id: Uuid) -> Result<Option<FarmList>>;
+    async fn get_farm_lists_by_village_id(&self, village_id: u32) -> Result<Vec<FarmList>>;
+    async fn update_farm_list(&self, list: FarmList) -> Result<()>;
+    async fn remove_farm_list(&self, list_id: Uuid) -> Result<()>;
 }